@@ -0,0 +1,125 @@
+#ifndef APP_INFO_H
+#define APP_INFO_H
+
+/* Generated by cbindgen from capi/src/lib.rs; do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Result of a call.
+ */
+typedef enum AppInfoStatus {
+  /**
+   * The call succeeded and its out-parameters are set.
+   */
+  APP_INFO_STATUS_OK = 0,
+  /**
+   * A required pointer was null or a string was not valid UTF-8.
+   */
+  APP_INFO_STATUS_INVALID_ARGUMENT = 1,
+  /**
+   * The operation failed; see `app_info_last_error_message`.
+   */
+  APP_INFO_STATUS_FAILED = 2,
+  /**
+   * The operation is not supported on this platform.
+   */
+  APP_INFO_STATUS_UNSUPPORTED = 3,
+  /**
+   * The library panicked. It is still safe to use, but the call had no effect.
+   */
+  APP_INFO_STATUS_PANIC = 4,
+} AppInfoStatus;
+
+/**
+ * Icon pixels in RGBA order with straight alpha, `width * height * 4` bytes.
+ */
+typedef struct CIcon {
+  uint32_t width;
+  uint32_t height;
+  uint8_t *pixels;
+  size_t pixels_len;
+} CIcon;
+
+/**
+ * An installed application. Optional strings are null when unknown.
+ */
+typedef struct CAppInfo {
+  char *name;
+  char *version;
+  char *path;
+  char *executable_path;
+  char *identifier;
+  char *publisher;
+  char *install_date;
+  /**
+   * Installed size in bytes, or 0 when unknown
+   */
+  uint64_t install_size;
+  /**
+   * Null when no icon was requested or none could be extracted
+   */
+  struct CIcon *icon;
+} CAppInfo;
+
+/**
+ * Lists the installed applications.
+ *
+ * On success `*out_list` points to `*out_len` entries, which must be released with
+ * `app_info_free_list`. `icon_size` 0 skips icon extraction. On failure the
+ * out-parameters are set to null and 0.
+ *
+ * # Safety
+ *
+ * `out_list` and `out_len` must be valid for writes.
+ */
+enum AppInfoStatus app_info_get_installed(uint16_t icon_size,
+                                          struct CAppInfo **out_list,
+                                          size_t *out_len);
+
+/**
+ * Releases a list returned by `app_info_get_installed`. Null is ignored.
+ *
+ * # Safety
+ *
+ * `list` and `len` must be exactly as returned, and the list must not be used afterwards.
+ */
+void app_info_free_list(struct CAppInfo *list, size_t len);
+
+/**
+ * Gets the icon for a file at `size` x `size` pixels.
+ *
+ * On success `*out_icon` must be released with `app_info_free_icon`. On failure it is set
+ * to null.
+ *
+ * # Safety
+ *
+ * `path_utf8` must be null or a NUL-terminated string, and `out_icon` must be valid for
+ * writes.
+ */
+enum AppInfoStatus app_info_get_file_icon(const char *path_utf8,
+                                          uint16_t size,
+                                          struct CIcon **out_icon);
+
+/**
+ * Releases an icon returned by `app_info_get_file_icon`. Null is ignored.
+ *
+ * # Safety
+ *
+ * `icon` must be exactly as returned and must not be used afterwards.
+ */
+void app_info_free_icon(struct CIcon *icon);
+
+/**
+ * The message of the last failure on the calling thread, or null if there was none.
+ *
+ * The string is owned by the library and stays valid until the next failing call on the
+ * same thread; it must not be freed.
+ */
+const char *app_info_last_error_message(void);
+
+#endif  /* APP_INFO_H */
@@ -0,0 +1,346 @@
+//! C ABI for `app-info`.
+//!
+//! Every function returns an [`AppInfoStatus`]. On failure the reason is available from
+//! [`app_info_last_error_message`] on the same thread.
+//!
+//! Ownership: everything returned through an out-pointer belongs to the caller and must be
+//! released with the matching free function, and with nothing else. Strings are
+//! NUL-terminated UTF-8 copies; interior NUL bytes are dropped. Panics never cross the
+//! boundary; they are reported as [`AppInfoStatus::Panic`].
+
+use app_info::{AppInfo, Icon};
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+/// Result of a call.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppInfoStatus {
+    /// The call succeeded and its out-parameters are set.
+    Ok = 0,
+    /// A required pointer was null or a string was not valid UTF-8.
+    InvalidArgument = 1,
+    /// The operation failed; see `app_info_last_error_message`.
+    Failed = 2,
+    /// The operation is not supported on this platform.
+    Unsupported = 3,
+    /// The library panicked. It is still safe to use, but the call had no effect.
+    Panic = 4,
+}
+
+/// Icon pixels in RGBA order with straight alpha, `width * height * 4` bytes.
+#[repr(C)]
+#[derive(Debug)]
+pub struct CIcon {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: *mut u8,
+    pub pixels_len: usize,
+}
+
+/// An installed application. Optional strings are null when unknown.
+#[repr(C)]
+#[derive(Debug)]
+pub struct CAppInfo {
+    pub name: *mut c_char,
+    pub version: *mut c_char,
+    pub path: *mut c_char,
+    pub executable_path: *mut c_char,
+    pub identifier: *mut c_char,
+    pub publisher: *mut c_char,
+    pub install_date: *mut c_char,
+    /// Installed size in bytes, or 0 when unknown
+    pub install_size: u64,
+    /// Null when no icon was requested or none could be extracted
+    pub icon: *mut CIcon,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: impl Into<String>) {
+    let message = to_c_string(&message.into());
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Runs `body`, recording its error message and turning panics into a status.
+fn guard(body: impl FnOnce() -> Result<(), (AppInfoStatus, String)>) -> AppInfoStatus {
+    match catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(())) => AppInfoStatus::Ok,
+        Ok(Err((status, message))) => {
+            set_last_error(message);
+            status
+        }
+        Err(panic) => {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            set_last_error(format!("panic: {}", message));
+            AppInfoStatus::Panic
+        }
+    }
+}
+
+fn failure(error: app_info::error::AppInfoError) -> (AppInfoStatus, String) {
+    let status = match error {
+        app_info::error::AppInfoError::UnsupportedPlatform => AppInfoStatus::Unsupported,
+        _ => AppInfoStatus::Failed,
+    };
+    (status, error.to_string())
+}
+
+fn to_c_string(value: &str) -> CString {
+    CString::new(value.replace('\0', "")).unwrap_or_default()
+}
+
+fn into_c_string(value: &str) -> *mut c_char {
+    to_c_string(value).into_raw()
+}
+
+fn into_optional_c_string(value: Option<&str>) -> *mut c_char {
+    value.map_or(ptr::null_mut(), into_c_string)
+}
+
+fn into_c_icon(icon: Icon) -> *mut CIcon {
+    let mut pixels = icon.pixels.into_boxed_slice();
+    let c_icon = CIcon {
+        width: icon.width,
+        height: icon.height,
+        pixels: pixels.as_mut_ptr(),
+        pixels_len: pixels.len(),
+    };
+    std::mem::forget(pixels);
+    Box::into_raw(Box::new(c_icon))
+}
+
+fn into_c_app(app: AppInfo) -> CAppInfo {
+    let path = app.path.to_string_lossy();
+    let executable_path = app
+        .executable_path
+        .as_ref()
+        .map(|path| path.to_string_lossy());
+
+    CAppInfo {
+        name: into_c_string(&app.name),
+        version: into_optional_c_string(app.version.as_deref()),
+        path: into_c_string(&path),
+        executable_path: into_optional_c_string(executable_path.as_deref()),
+        identifier: into_optional_c_string(app.identifier.as_deref()),
+        publisher: into_optional_c_string(app.publisher.as_deref()),
+        install_date: into_optional_c_string(app.install_date.as_deref()),
+        install_size: app.install_size.unwrap_or(0),
+        icon: app.icon.map_or(ptr::null_mut(), into_c_icon),
+    }
+}
+
+/// # Safety
+///
+/// `value` must be null or come from [`into_c_string`].
+unsafe fn free_c_string(value: *mut c_char) {
+    if !value.is_null() {
+        drop(CString::from_raw(value));
+    }
+}
+
+/// # Safety
+///
+/// `icon` must be null or come from [`into_c_icon`].
+unsafe fn free_c_icon(icon: *mut CIcon) {
+    if icon.is_null() {
+        return;
+    }
+    let icon = Box::from_raw(icon);
+    if !icon.pixels.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+            icon.pixels,
+            icon.pixels_len,
+        )));
+    }
+}
+
+/// Lists the installed applications.
+///
+/// On success `*out_list` points to `*out_len` entries, which must be released with
+/// `app_info_free_list`. `icon_size` 0 skips icon extraction. On failure the
+/// out-parameters are set to null and 0.
+///
+/// # Safety
+///
+/// `out_list` and `out_len` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn app_info_get_installed(
+    icon_size: u16,
+    out_list: *mut *mut CAppInfo,
+    out_len: *mut usize,
+) -> AppInfoStatus {
+    if out_list.is_null() || out_len.is_null() {
+        set_last_error("out_list and out_len must not be null");
+        return AppInfoStatus::InvalidArgument;
+    }
+    *out_list = ptr::null_mut();
+    *out_len = 0;
+
+    guard(|| {
+        let apps = app_info::get_installed_apps(icon_size).map_err(failure)?;
+        let list: Box<[CAppInfo]> = apps.into_iter().map(into_c_app).collect();
+        *out_len = list.len();
+        *out_list = Box::into_raw(list).cast();
+        Ok(())
+    })
+}
+
+/// Releases a list returned by `app_info_get_installed`. Null is ignored.
+///
+/// # Safety
+///
+/// `list` and `len` must be exactly as returned, and the list must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn app_info_free_list(list: *mut CAppInfo, len: usize) {
+    if list.is_null() {
+        return;
+    }
+    let _ = catch_unwind(AssertUnwindSafe(|| {
+        let list = Box::from_raw(ptr::slice_from_raw_parts_mut(list, len));
+        for app in list.iter() {
+            free_c_string(app.name);
+            free_c_string(app.version);
+            free_c_string(app.path);
+            free_c_string(app.executable_path);
+            free_c_string(app.identifier);
+            free_c_string(app.publisher);
+            free_c_string(app.install_date);
+            free_c_icon(app.icon);
+        }
+    }));
+}
+
+/// Gets the icon for a file at `size` x `size` pixels.
+///
+/// On success `*out_icon` must be released with `app_info_free_icon`. On failure it is set
+/// to null.
+///
+/// # Safety
+///
+/// `path_utf8` must be null or a NUL-terminated string, and `out_icon` must be valid for
+/// writes.
+#[no_mangle]
+pub unsafe extern "C" fn app_info_get_file_icon(
+    path_utf8: *const c_char,
+    size: u16,
+    out_icon: *mut *mut CIcon,
+) -> AppInfoStatus {
+    if out_icon.is_null() {
+        set_last_error("out_icon must not be null");
+        return AppInfoStatus::InvalidArgument;
+    }
+    *out_icon = ptr::null_mut();
+    if path_utf8.is_null() {
+        set_last_error("path_utf8 must not be null");
+        return AppInfoStatus::InvalidArgument;
+    }
+    let Ok(path) = CStr::from_ptr(path_utf8).to_str() else {
+        set_last_error("path_utf8 is not valid UTF-8");
+        return AppInfoStatus::InvalidArgument;
+    };
+
+    guard(|| {
+        let icon = app_info::get_file_icon(path, size).map_err(failure)?;
+        *out_icon = into_c_icon(icon);
+        Ok(())
+    })
+}
+
+/// Releases an icon returned by `app_info_get_file_icon`. Null is ignored.
+///
+/// # Safety
+///
+/// `icon` must be exactly as returned and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn app_info_free_icon(icon: *mut CIcon) {
+    let _ = catch_unwind(AssertUnwindSafe(|| free_c_icon(icon)));
+}
+
+/// The message of the last failure on the calling thread, or null if there was none.
+///
+/// The string is owned by the library and stays valid until the next failing call on the
+/// same thread; it must not be freed.
+#[no_mangle]
+pub extern "C" fn app_info_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last_error() -> String {
+        let message = app_info_last_error_message();
+        assert!(!message.is_null());
+        unsafe { CStr::from_ptr(message) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn test_guard_catches_panics() {
+        let status = guard(|| panic!("scanner exploded"));
+        assert_eq!(status, AppInfoStatus::Panic);
+        assert_eq!(last_error(), "panic: scanner exploded");
+
+        let status = guard(|| Err(failure(app_info::error::AppInfoError::UnsupportedPlatform)));
+        assert_eq!(status, AppInfoStatus::Unsupported);
+        assert_eq!(last_error(), "Unsupported platform");
+    }
+
+    #[test]
+    fn test_app_round_trip() {
+        let app = AppInfo {
+            name: "Nul\0Name".to_string(),
+            path: "/Applications/Nul.app".into(),
+            install_size: Some(42),
+            icon: Some(Icon {
+                width: 1,
+                height: 1,
+                pixels: vec![1, 2, 3, 4],
+            }),
+            ..Default::default()
+        };
+        let list: Box<[CAppInfo]> = vec![into_c_app(app)].into_boxed_slice();
+        let len = list.len();
+        let list: *mut CAppInfo = Box::into_raw(list).cast();
+
+        unsafe {
+            let entry = &*list;
+            assert_eq!(CStr::from_ptr(entry.name).to_str(), Ok("NulName"));
+            assert!(entry.version.is_null());
+            assert_eq!(entry.install_size, 42);
+            let icon = &*entry.icon;
+            assert_eq!(
+                std::slice::from_raw_parts(icon.pixels, icon.pixels_len),
+                [1, 2, 3, 4]
+            );
+            app_info_free_list(list, len);
+        }
+    }
+
+    #[test]
+    fn test_invalid_arguments() {
+        let mut icon = ptr::null_mut();
+        let status = unsafe { app_info_get_file_icon(ptr::null(), 32, &mut icon) };
+        assert_eq!(status, AppInfoStatus::InvalidArgument);
+        assert!(icon.is_null());
+
+        let status = unsafe { app_info_get_installed(0, ptr::null_mut(), ptr::null_mut()) };
+        assert_eq!(status, AppInfoStatus::InvalidArgument);
+        assert!(last_error().contains("must not be null"));
+    }
+}
@@ -0,0 +1,44 @@
+//! Compiles `smoke.c` against the generated header and the shared library, then runs it.
+
+#[test]
+#[cfg(unix)]
+fn test_c_program() {
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    // Cargo places the library next to the test binary
+    let deps_dir = std::env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .to_path_buf();
+    let program = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("app_info_smoke");
+
+    let compiler = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let compiled = Command::new(&compiler)
+        .arg(manifest_dir.join("tests").join("smoke.c"))
+        .arg("-I")
+        .arg(manifest_dir.join("include"))
+        .arg("-L")
+        .arg(&deps_dir)
+        .arg(format!("-Wl,-rpath,{}", deps_dir.display()))
+        .arg("-lapp_info_capi")
+        .arg("-o")
+        .arg(&program)
+        .status();
+    let Ok(compiled) = compiled else {
+        eprintln!("skipping: no C compiler ({})", compiler);
+        return;
+    };
+    assert!(compiled.success(), "Failed to compile smoke.c");
+
+    let output = Command::new(&program)
+        .output()
+        .expect("Failed to run smoke test");
+    assert!(
+        output.status.success(),
+        "smoke test failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
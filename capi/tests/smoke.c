@@ -0,0 +1,42 @@
+/* Exercises the C ABI from C; built and run by tests/c_abi.rs. */
+#include <stdio.h>
+#include <string.h>
+
+#include "app_info.h"
+
+int main(void) {
+    CAppInfo *list = NULL;
+    size_t len = 0;
+    AppInfoStatus status = app_info_get_installed(0, &list, &len);
+    if (status == APP_INFO_STATUS_OK) {
+        for (size_t i = 0; i < len; i++) {
+            if (list[i].name == NULL || list[i].path == NULL || list[i].icon != NULL) {
+                fprintf(stderr, "invalid entry %zu\n", i);
+                return 1;
+            }
+        }
+        app_info_free_list(list, len);
+    } else {
+        const char *message = app_info_last_error_message();
+        if (message == NULL || strlen(message) == 0 || list != NULL || len != 0) {
+            fprintf(stderr, "failure without a message\n");
+            return 2;
+        }
+    }
+
+    CIcon *icon = NULL;
+    status = app_info_get_file_icon("/path/to/non/existent/file", 32, &icon);
+    if (status == APP_INFO_STATUS_OK || icon != NULL) {
+        fprintf(stderr, "icon for a missing file\n");
+        return 3;
+    }
+    if (app_info_get_file_icon(NULL, 32, &icon) != APP_INFO_STATUS_INVALID_ARGUMENT) {
+        fprintf(stderr, "null path accepted\n");
+        return 4;
+    }
+
+    app_info_free_list(NULL, 0);
+    app_info_free_icon(NULL);
+    printf("listed %zu apps\n", len);
+    return 0;
+}
@@ -0,0 +1,23 @@
+use app_info::{find_app_by_name, LaunchOptions};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Use the name given on the command line, or look for Calculator
+    let name = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "Calculator".to_string());
+
+    println!("Looking for {}...", name);
+    let app = find_app_by_name(&name, 0)?;
+    println!("Found {} at {}", app.name, app.path.display());
+
+    if app.is_running()? {
+        println!("{} is already running, bringing it to the front", app.name);
+    }
+
+    let launched = app.launch_with(&[], LaunchOptions::default())?;
+    match launched.pid {
+        Some(pid) => println!("Launched {} (pid {})", app.name, pid),
+        None => println!("Launched {}", app.name),
+    }
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use app_info::error::AppInfoError;
+use app_info::{load_icons, scan_apps, Icon, IconStorage, ScanOptions};
+use image::{imageops, imageops::FilterType, RgbaImage};
+use std::thread;
+
+const ICON_SIZE: u16 = 64;
+const COLUMNS: u32 = 8;
+const PADDING: u32 = 8;
+
+/// Lists the installed applications at once, then draws the icons of the first ones into
+/// a contact sheet on a worker thread.
+///
+/// Usage: `cargo run --example lazy_icons -- [count] [output.png]`
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let count: usize = match args.next() {
+        Some(count) => count.parse()?,
+        None => 48,
+    };
+    let output = args.next().unwrap_or_else(|| "app_icons.png".to_string());
+
+    // Only record where each icon is, which makes the scan as fast as one without icons
+    let report = scan_apps(&ScanOptions {
+        icon_size: ICON_SIZE,
+        icon_storage: IconStorage::Deferred,
+        ..Default::default()
+    })?;
+    let mut apps = report.apps;
+    apps.sort_by_cached_key(|app| app.name.to_lowercase());
+
+    // Start on the icons before printing, so they load while the table is shown
+    let first: Vec<_> = apps.iter().take(count).cloned().collect();
+    let worker = thread::spawn(move || {
+        let icons = load_icons(&first, ICON_SIZE);
+        let loaded = icons.iter().filter(|icon| icon.is_ok()).count();
+        (contact_sheet(&icons), loaded)
+    });
+
+    for app in &apps {
+        println!(
+            "{:<40} {:<16} {}",
+            app.name,
+            app.version.as_deref().unwrap_or("-"),
+            app.path.display()
+        );
+    }
+    println!("Found {} applications, loading icons...", apps.len());
+
+    let (sheet, loaded) = worker.join().expect("icon thread panicked");
+    sheet.save(&output)?;
+    println!(
+        "Saved {} of {} icons to {}",
+        loaded,
+        count.min(apps.len()),
+        output
+    );
+    Ok(())
+}
+
+/// Lays icons out in rows of `COLUMNS`, leaving the cell of a missing icon empty.
+fn contact_sheet(icons: &[Result<Icon, AppInfoError>]) -> RgbaImage {
+    let cell = u32::from(ICON_SIZE) + PADDING;
+    let rows = (icons.len() as u32).div_ceil(COLUMNS).max(1);
+    let mut sheet = RgbaImage::new(COLUMNS * cell + PADDING, rows * cell + PADDING);
+
+    for (index, icon) in icons.iter().enumerate() {
+        let Some(image) = icon
+            .as_ref()
+            .ok()
+            .and_then(|icon| RgbaImage::from_raw(icon.width, icon.height, icon.pixels.clone()))
+        else {
+            continue;
+        };
+        // Stored icons keep their scanned size, which custom sources may not have honored
+        let image = if image.dimensions() == (ICON_SIZE.into(), ICON_SIZE.into()) {
+            image
+        } else {
+            imageops::resize(
+                &image,
+                ICON_SIZE.into(),
+                ICON_SIZE.into(),
+                FilterType::Lanczos3,
+            )
+        };
+        let index = index as u32;
+        let x = PADDING + (index % COLUMNS) * cell;
+        let y = PADDING + (index / COLUMNS) * cell;
+        imageops::overlay(&mut sheet, &image, x.into(), y.into());
+    }
+    sheet
+}
@@ -22,7 +22,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     for app in apps {
         if let Some(ref icon) = app.icon {
-            match save_icon_as_png(&app, &icon, output_dir) {
+            match save_icon_as_png(&app, icon, output_dir) {
                 Ok(filename) => {
                     saved_count += 1;
                     println!("Saved: {}", filename);
@@ -0,0 +1,24 @@
+use app_info::{AppEvent, AppWatcher, WatcherOptions};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let watcher = AppWatcher::new(WatcherOptions::default())?;
+    println!("Watching for application changes, press Ctrl+C to stop...");
+
+    for event in watcher.events() {
+        match event {
+            AppEvent::Installed(app) => println!(
+                "Installed: {} {}",
+                app.name,
+                app.version.as_deref().unwrap_or("")
+            ),
+            AppEvent::Removed(app) => println!("Removed: {}", app.name),
+            AppEvent::Updated { before, after } => println!(
+                "Updated: {} {} -> {}",
+                after.name,
+                before.version.as_deref().unwrap_or("?"),
+                after.version.as_deref().unwrap_or("?")
+            ),
+        }
+    }
+    Ok(())
+}
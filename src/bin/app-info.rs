@@ -0,0 +1,205 @@
+//! Command-line tool for listing and inspecting installed applications.
+//!
+//! Exit codes: 0 on success, 1 when the application or file was not found, and 2 for any
+//! other error, including invalid arguments.
+
+use app_info::error::{AppInfoError, FileIconError};
+use app_info::{export_json, AppInfo, ExportOptions, MatchMode};
+use clap::{Parser, Subcommand};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(
+    name = "app-info",
+    version,
+    about = "List and inspect installed applications"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List the installed applications
+    List {
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+        /// Save each application's icon as a PNG in this directory
+        #[arg(long, value_name = "DIR")]
+        icons: Option<PathBuf>,
+        /// Icon size in pixels
+        #[arg(long, default_value_t = 64)]
+        size: u16,
+    },
+    /// Find an installed application by name
+    Find {
+        /// Application name, compared case-insensitively
+        name: String,
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Save the icon of a file or application as a PNG
+    Icon {
+        /// File or application to take the icon from
+        path: PathBuf,
+        /// Icon size in pixels
+        #[arg(long, default_value_t = 64)]
+        size: u16,
+        /// Where to write the PNG
+        #[arg(long, value_name = "FILE")]
+        out: PathBuf,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Command::List { json, icons, size } => list(json, icons.as_deref(), size),
+        Command::Find { name, json } => find(&name, json),
+        Command::Icon { path, size, out } => icon(&path, size, &out),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("app-info: {}", err);
+            if is_not_found(&err) {
+                ExitCode::from(1)
+            } else {
+                ExitCode::from(2)
+            }
+        }
+    }
+}
+
+fn is_not_found(err: &AppInfoError) -> bool {
+    matches!(
+        err,
+        AppInfoError::AppNotFound { .. }
+            | AppInfoError::FileIconError(FileIconError::PathDoesNotExist)
+    )
+}
+
+fn list(json: bool, icons: Option<&Path>, size: u16) -> Result<(), AppInfoError> {
+    let icon_size = if icons.is_some() { size } else { 0 };
+    let mut apps = app_info::get_installed_apps(icon_size)?;
+    apps.sort_by_cached_key(|app| app.name.to_lowercase());
+
+    if let Some(dir) = icons {
+        save_icons(&apps, dir)?;
+    }
+    print_apps(&apps, json)
+}
+
+fn find(name: &str, json: bool) -> Result<(), AppInfoError> {
+    match app_info::find_app_by_name(name, 0) {
+        Ok(app) => print_apps(&[app], json),
+        Err(err @ AppInfoError::AppNotFound { .. }) => {
+            // Suggest near misses before reporting the failure
+            let similar: Vec<String> = app_info::find_apps_by_name(name, MatchMode::Contains, 0)?
+                .into_iter()
+                .map(|app| app.name)
+                .collect();
+            if !similar.is_empty() {
+                eprintln!("Similar names: {}", similar.join(", "));
+            }
+            Err(err)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+fn icon(path: &Path, size: u16, out: &Path) -> Result<(), AppInfoError> {
+    let icon = app_info::get_file_icon(path, size)?;
+    std::fs::write(out, icon.to_png()?)?;
+    println!(
+        "Saved {}x{} icon to {}",
+        icon.width,
+        icon.height,
+        out.display()
+    );
+    Ok(())
+}
+
+fn print_apps(apps: &[AppInfo], json: bool) -> Result<(), AppInfoError> {
+    if json {
+        return export_json(apps, std::io::stdout().lock(), ExportOptions::default());
+    }
+
+    let rows: Vec<[&str; 3]> = apps
+        .iter()
+        .map(|app| {
+            [
+                app.name.as_str(),
+                app.version.as_deref().unwrap_or("-"),
+                app.publisher.as_deref().unwrap_or("-"),
+            ]
+        })
+        .collect();
+    let header = ["NAME", "VERSION", "PUBLISHER"];
+    let widths: Vec<usize> = (0..2)
+        .map(|column| {
+            rows.iter()
+                .map(|row| row[column].chars().count())
+                .chain([header[column].len()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    for row in std::iter::once(header).chain(rows) {
+        println!(
+            "{:name$}  {:version$}  {}",
+            row[0],
+            row[1],
+            row[2],
+            name = widths[0],
+            version = widths[1]
+        );
+    }
+    Ok(())
+}
+
+/// Writes each app's icon to `dir` as `<name>.png`, numbering repeated names.
+fn save_icons(apps: &[AppInfo], dir: &Path) -> Result<(), AppInfoError> {
+    std::fs::create_dir_all(dir)?;
+    let mut used = HashSet::new();
+
+    for app in apps {
+        let Some(icon) = &app.icon else {
+            continue;
+        };
+        let stem = file_stem(&app.name);
+        let mut file_name = format!("{}.png", stem);
+        let mut counter = 2;
+        while !used.insert(file_name.to_lowercase()) {
+            file_name = format!("{} ({}).png", stem, counter);
+            counter += 1;
+        }
+        std::fs::write(dir.join(file_name), icon.to_png()?)?;
+    }
+    Ok(())
+}
+
+/// Replaces characters that are not allowed in file names on either platform.
+fn file_stem(name: &str) -> String {
+    let stem: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let stem = stem.trim_matches(|c: char| c == '.' || c.is_whitespace());
+    if stem.is_empty() {
+        "app".to_string()
+    } else {
+        stem.to_string()
+    }
+}
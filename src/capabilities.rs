@@ -0,0 +1,71 @@
+/// What the current build can do, as reported by [`capabilities`].
+///
+/// A `false` flag means the functions it covers return
+/// [`AppInfoError::UnsupportedPlatform`](crate::error::AppInfoError::UnsupportedPlatform)
+/// (or [`FileIconError::FeatureDisabled`](crate::error::FileIconError::FeatureDisabled)
+/// for icons left out of the build), so cross-platform code can check a flag instead of
+/// the target. Flags are added as the crate grows, which is why the struct cannot be
+/// built outside the crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct Capabilities {
+    /// Listing and searching installed applications:
+    /// [`get_installed_apps`](crate::get_installed_apps), the built-in sources of
+    /// [`scan_apps`](crate::scan_apps), the `find_*` functions and
+    /// [`lookup_app`](crate::lookup_app)
+    pub installed_apps: bool,
+    /// Reading a single application with [`get_app_info`](crate::get_app_info)
+    pub app_info: bool,
+    /// Extracting icons with [`get_file_icon`](crate::get_file_icon) and the other icon
+    /// functions, and filling in [`AppInfo::icon`](crate::AppInfo::icon)
+    pub file_icons: bool,
+    /// Listing running applications, the frontmost one and the one behind a process,
+    /// and [`AppInfo::is_running`](crate::AppInfo::is_running)
+    pub running_apps: bool,
+    /// Starting applications with [`AppInfo::launch`](crate::AppInfo::launch)
+    pub launch: bool,
+    /// Removing applications with [`AppInfo::uninstall`](crate::AppInfo::uninstall)
+    pub uninstall: bool,
+    /// Watching for installs and removals with [`AppWatcher`](crate::AppWatcher)
+    pub watch: bool,
+    /// Listing login items with [`get_startup_apps`](crate::get_startup_apps)
+    pub startup_apps: bool,
+    /// Reading usage with [`get_app_usage`](crate::get_app_usage)
+    pub usage: bool,
+    /// Finding the applications for a URL scheme
+    pub url_schemes: bool,
+    /// Finding the applications for a file, and the file types an application opens
+    pub file_associations: bool,
+    /// Checking code signatures with [`get_signature_info`](crate::get_signature_info)
+    pub signatures: bool,
+    /// Attaching package manager entries with
+    /// [`attach_winget_packages`](crate::attach_winget_packages). The package manager
+    /// itself may still be missing at run time.
+    pub package_managers: bool,
+    /// Reading installed programs through `WmiSource`, which needs the `wmi` feature
+    pub wmi: bool,
+}
+
+/// Reports what the current build supports, from the target it was built for and the
+/// features it was built with.
+pub fn capabilities() -> Capabilities {
+    let desktop = cfg!(any(target_os = "macos", target_os = "windows"));
+    let windows = cfg!(target_os = "windows");
+    Capabilities {
+        installed_apps: desktop,
+        app_info: desktop,
+        file_icons: desktop && cfg!(feature = "icons"),
+        running_apps: desktop,
+        launch: desktop,
+        uninstall: desktop,
+        watch: desktop,
+        startup_apps: desktop,
+        usage: desktop,
+        url_schemes: desktop,
+        file_associations: desktop,
+        signatures: windows,
+        package_managers: windows,
+        wmi: windows && cfg!(feature = "wmi"),
+    }
+}
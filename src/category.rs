@@ -0,0 +1,209 @@
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+
+/// The kind of application, as declared by the application itself.
+///
+/// On macOS this comes from `LSApplicationCategoryType` in `Info.plist`. Windows installers
+/// do not record a category, so apps found there have none. Values that have no variant
+/// of their own are kept as [`AppCategory::Other`].
+///
+/// With the `serde` feature a category is written as its display name, such as
+/// `"Developer Tools"`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", from = "String")
+)]
+pub enum AppCategory {
+    Business,
+    DeveloperTools,
+    Education,
+    Entertainment,
+    Finance,
+    /// Games, including every `public.app-category.*-games` subcategory
+    Games,
+    GraphicsDesign,
+    HealthcareFitness,
+    Lifestyle,
+    Medical,
+    Music,
+    News,
+    Photography,
+    Productivity,
+    Reference,
+    SocialNetworking,
+    Sports,
+    Travel,
+    Utilities,
+    Video,
+    Weather,
+    /// A category with no variant of its own, as it was found
+    Other(String),
+}
+
+/// Each category with its UTI suffix and display name.
+const CATEGORIES: &[(AppCategory, &str, &str)] = &[
+    (AppCategory::Business, "business", "Business"),
+    (
+        AppCategory::DeveloperTools,
+        "developer-tools",
+        "Developer Tools",
+    ),
+    (AppCategory::Education, "education", "Education"),
+    (AppCategory::Entertainment, "entertainment", "Entertainment"),
+    (AppCategory::Finance, "finance", "Finance"),
+    (AppCategory::Games, "games", "Games"),
+    (
+        AppCategory::GraphicsDesign,
+        "graphics-design",
+        "Graphics & Design",
+    ),
+    (
+        AppCategory::HealthcareFitness,
+        "healthcare-fitness",
+        "Health & Fitness",
+    ),
+    (AppCategory::Lifestyle, "lifestyle", "Lifestyle"),
+    (AppCategory::Medical, "medical", "Medical"),
+    (AppCategory::Music, "music", "Music"),
+    (AppCategory::News, "news", "News"),
+    (AppCategory::Photography, "photography", "Photography"),
+    (AppCategory::Productivity, "productivity", "Productivity"),
+    (AppCategory::Reference, "reference", "Reference"),
+    (
+        AppCategory::SocialNetworking,
+        "social-networking",
+        "Social Networking",
+    ),
+    (AppCategory::Sports, "sports", "Sports"),
+    (AppCategory::Travel, "travel", "Travel"),
+    (AppCategory::Utilities, "utilities", "Utilities"),
+    (AppCategory::Video, "video", "Video"),
+    (AppCategory::Weather, "weather", "Weather"),
+];
+
+const UTI_PREFIX: &str = "public.app-category.";
+
+impl AppCategory {
+    /// Parses a macOS category UTI such as `public.app-category.developer-tools`, or a
+    /// display name such as `Developer Tools`, ignoring case. Anything else becomes
+    /// [`AppCategory::Other`].
+    pub fn parse(value: &str) -> AppCategory {
+        let trimmed = value.trim();
+        let lower = trimmed.to_lowercase();
+
+        if let Some(suffix) = lower.strip_prefix(UTI_PREFIX) {
+            if suffix.ends_with("-games") {
+                return AppCategory::Games;
+            }
+            if let Some((category, _, _)) = CATEGORIES.iter().find(|(_, uti, _)| *uti == suffix) {
+                return category.clone();
+            }
+            return AppCategory::Other(trimmed.to_string());
+        }
+
+        // Accept "Graphics and Design" and "graphics-design" as well as the display name
+        let normalized = lower.replace(" and ", " & ").replace('-', " ");
+        CATEGORIES
+            .iter()
+            .find(|(_, uti, name)| {
+                name.to_lowercase() == normalized || uti.replace('-', " ") == normalized
+            })
+            .map(|(category, _, _)| category.clone())
+            .unwrap_or_else(|| AppCategory::Other(trimmed.to_string()))
+    }
+
+    /// The macOS UTI for the category, or `None` for [`AppCategory::Other`].
+    pub fn uti(&self) -> Option<String> {
+        self.entry()
+            .map(|(_, uti, _)| format!("{}{}", UTI_PREFIX, uti))
+    }
+
+    fn entry(&self) -> Option<&'static (AppCategory, &'static str, &'static str)> {
+        CATEGORIES.iter().find(|(category, _, _)| category == self)
+    }
+}
+
+impl fmt::Display for AppCategory {
+    /// Writes the display name, or an [`AppCategory::Other`] value as it was found.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self, self.entry()) {
+            (AppCategory::Other(value), _) => f.write_str(value),
+            (_, Some((_, _, name))) => f.write_str(name),
+            (_, None) => Ok(()),
+        }
+    }
+}
+
+impl FromStr for AppCategory {
+    type Err = Infallible;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(AppCategory::parse(value))
+    }
+}
+
+impl From<String> for AppCategory {
+    fn from(value: String) -> Self {
+        AppCategory::parse(&value)
+    }
+}
+
+impl From<AppCategory> for String {
+    fn from(category: AppCategory) -> Self {
+        category.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_category_mapping() {
+        for (category, uti, name) in CATEGORIES {
+            let full_uti = format!("public.app-category.{}", uti);
+            assert_eq!(AppCategory::parse(&full_uti), *category);
+            assert_eq!(AppCategory::parse(name), *category);
+            assert_eq!(AppCategory::parse(&name.to_uppercase()), *category);
+            assert_eq!(category.uti().as_deref(), Some(full_uti.as_str()));
+            assert_eq!(category.to_string(), *name);
+            assert_eq!(AppCategory::parse(&category.to_string()), *category);
+        }
+
+        assert_eq!(
+            AppCategory::parse("public.app-category.puzzle-games"),
+            AppCategory::Games
+        );
+        assert_eq!(
+            AppCategory::parse("PUBLIC.APP-CATEGORY.DEVELOPER-TOOLS"),
+            AppCategory::DeveloperTools
+        );
+        assert_eq!(
+            AppCategory::parse(" Graphics and Design "),
+            AppCategory::GraphicsDesign
+        );
+        assert_eq!(
+            AppCategory::parse("social-networking"),
+            AppCategory::SocialNetworking
+        );
+        assert_eq!("Games".parse(), Ok(AppCategory::Games));
+    }
+
+    #[test]
+    fn test_other_category() {
+        let unmapped = AppCategory::parse("public.app-category.kids");
+        assert_eq!(
+            unmapped,
+            AppCategory::Other("public.app-category.kids".to_string())
+        );
+        assert_eq!(unmapped.uti(), None);
+        assert_eq!(unmapped.to_string(), "public.app-category.kids");
+
+        let custom = AppCategory::parse("Accessories");
+        assert_eq!(custom, AppCategory::Other("Accessories".to_string()));
+        assert_eq!(AppCategory::parse(&custom.to_string()), custom);
+    }
+}
@@ -0,0 +1,254 @@
+#[cfg(any(target_os = "windows", test))]
+use crate::InstallScope;
+use crate::{AppInfo, AppSource, Result, ScanContext};
+#[cfg(any(target_os = "windows", test))]
+use std::path::PathBuf;
+
+/// A source for the Control Panel applets registered on Windows, which do not appear
+/// among the installed applications.
+///
+/// Applets are read from the `Control Panel\Cpls` keys of the machine and the current
+/// user. Each becomes an app with the `.cpl` file as its path and an identifier like
+/// `cpl:<registration name>`; the name, publisher and version come from the file's
+/// version resource, and the icon from the shell. Keys that are missing or cannot be read
+/// are skipped. Other platforms yield no apps.
+///
+/// The source is not part of the built-in ones; add it to [`crate::ScanOptions::sources`].
+#[derive(Debug, Clone, Default)]
+pub struct ControlPanelSource;
+
+impl ControlPanelSource {
+    /// Creates a source for the registered Control Panel applets.
+    pub fn new() -> Self {
+        ControlPanelSource
+    }
+}
+
+impl AppSource for ControlPanelSource {
+    fn name(&self) -> &str {
+        "control-panel"
+    }
+
+    #[cfg(target_os = "windows")]
+    fn scan(&self, ctx: &ScanContext) -> Result<Vec<AppInfo>> {
+        enter_span!(DEBUG, "control_panel_scan");
+        let registrations = crate::window::cpl_registrations();
+        let mut apps = cpl_apps(registrations, |name| std::env::var(name).ok());
+        for (found, app) in apps.iter_mut().enumerate() {
+            if ctx.is_cancelled() {
+                apps.truncate(found);
+                break;
+            }
+            crate::window::apply_file_details(app, ctx);
+            ctx.report_progress(found + 1);
+        }
+        Ok(apps)
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    fn scan(&self, ctx: &ScanContext) -> Result<Vec<AppInfo>> {
+        let _ = ctx;
+        Ok(Vec::new())
+    }
+}
+
+/// A source for the optional Windows features that are turned on, as listed under "Turn
+/// Windows features on or off".
+///
+/// Features are read from the selections Component Based Servicing records for them, and
+/// each enabled one becomes an app named after the feature, such as
+/// `Microsoft-Windows-Subsystem-Linux`, with an identifier like `feature:<name>`.
+/// Features have no path or icon. Keys that cannot be read are skipped. Other platforms
+/// yield no apps.
+///
+/// The source is not part of the built-in ones; add it to [`crate::ScanOptions::sources`].
+#[derive(Debug, Clone, Default)]
+pub struct OptionalFeatureSource;
+
+impl OptionalFeatureSource {
+    /// Creates a source for the enabled optional features.
+    pub fn new() -> Self {
+        OptionalFeatureSource
+    }
+}
+
+impl AppSource for OptionalFeatureSource {
+    fn name(&self) -> &str {
+        "optional-features"
+    }
+
+    #[cfg(target_os = "windows")]
+    fn scan(&self, ctx: &ScanContext) -> Result<Vec<AppInfo>> {
+        enter_span!(DEBUG, "optional_feature_scan");
+        let apps = enabled_features(crate::window::optional_feature_selections());
+        ctx.report_progress(apps.len());
+        Ok(apps)
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    fn scan(&self, ctx: &ScanContext) -> Result<Vec<AppInfo>> {
+        let _ = ctx;
+        Ok(Vec::new())
+    }
+}
+
+/// Builds the apps for the values of the `Cpls` keys, given as (hive scope, value name,
+/// value data). The same file registered twice is reported once, for the first scope.
+#[cfg(any(target_os = "windows", test))]
+fn cpl_apps(
+    registrations: Vec<(InstallScope, String, String)>,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Vec<AppInfo> {
+    let mut apps: Vec<AppInfo> = Vec::new();
+    for (scope, name, data) in registrations {
+        let data = crate::window::unquote_path(&data);
+        let path = crate::window::expand_environment_strings(data, &lookup);
+        if name.is_empty() || path.is_empty() {
+            continue;
+        }
+
+        let path = PathBuf::from(path);
+        let known = apps.iter().any(|app| {
+            app.path.to_string_lossy().to_lowercase() == path.to_string_lossy().to_lowercase()
+        });
+        if known {
+            continue;
+        }
+        apps.push(AppInfo {
+            name: name.clone(),
+            path,
+            identifier: Some(format!("cpl:{}", name)),
+            install_scope: Some(scope),
+            ..Default::default()
+        });
+    }
+    apps
+}
+
+/// Builds the apps for the features whose `Selection` is set, given as (feature name,
+/// selection), sorted by name. Features are machine-wide.
+#[cfg(any(target_os = "windows", test))]
+fn enabled_features(selections: Vec<(String, Option<bool>)>) -> Vec<AppInfo> {
+    let mut apps: Vec<AppInfo> = selections
+        .into_iter()
+        .filter(|(name, selected)| !name.is_empty() && *selected == Some(true))
+        .map(|(name, _)| AppInfo {
+            identifier: Some(format!("feature:{}", name)),
+            name,
+            install_scope: Some(InstallScope::Machine),
+            ..Default::default()
+        })
+        .collect();
+    apps.sort_by_key(|app| app.name.to_lowercase());
+    apps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registration(scope: InstallScope, name: &str, data: &str) -> (InstallScope, String, String) {
+        (scope, name.to_string(), data.to_string())
+    }
+
+    #[test]
+    fn test_cpl_apps() {
+        let lookup = |name: &str| match name {
+            "SystemRoot" => Some("C:\\Windows".to_string()),
+            _ => None,
+        };
+        let apps = cpl_apps(
+            vec![
+                registration(
+                    InstallScope::Machine,
+                    "Java",
+                    "\"C:\\Program Files\\Java\\jre-1.8\\bin\\javacpl.cpl\"",
+                ),
+                registration(
+                    InstallScope::Machine,
+                    "NvidiaCpl",
+                    "%SystemRoot%\\System32\\nvcpl.cpl",
+                ),
+                registration(InstallScope::Machine, "Empty", ""),
+                registration(
+                    InstallScope::User,
+                    "JavaAgain",
+                    "c:\\program files\\java\\jre-1.8\\bin\\JAVACPL.CPL",
+                ),
+                registration(InstallScope::User, "Mail", "%Unknown%\\mlcfg32.cpl"),
+            ],
+            lookup,
+        );
+
+        let summary: Vec<(&str, String, Option<&str>, Option<InstallScope>)> = apps
+            .iter()
+            .map(|app| {
+                (
+                    app.name.as_str(),
+                    app.path.to_string_lossy().into_owned(),
+                    app.identifier.as_deref(),
+                    app.install_scope,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (
+                    "Java",
+                    "C:\\Program Files\\Java\\jre-1.8\\bin\\javacpl.cpl".to_string(),
+                    Some("cpl:Java"),
+                    Some(InstallScope::Machine)
+                ),
+                (
+                    "NvidiaCpl",
+                    "C:\\Windows\\System32\\nvcpl.cpl".to_string(),
+                    Some("cpl:NvidiaCpl"),
+                    Some(InstallScope::Machine)
+                ),
+                (
+                    "Mail",
+                    "%Unknown%\\mlcfg32.cpl".to_string(),
+                    Some("cpl:Mail"),
+                    Some(InstallScope::User)
+                ),
+            ]
+        );
+        assert!(apps.iter().all(|app| app.icon.is_none()));
+    }
+
+    #[test]
+    fn test_enabled_features() {
+        let selection = |name: &str, selected: Option<bool>| (name.to_string(), selected);
+        let apps = enabled_features(vec![
+            selection("VirtualMachinePlatform", Some(true)),
+            selection("Microsoft-Hyper-V-All", Some(false)),
+            selection("Microsoft-Windows-Subsystem-Linux", Some(true)),
+            selection("TelnetClient", None),
+            selection("", Some(true)),
+        ]);
+
+        let names: Vec<&str> = apps.iter().map(|app| app.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "Microsoft-Windows-Subsystem-Linux",
+                "VirtualMachinePlatform"
+            ]
+        );
+        assert_eq!(
+            apps[0].identifier.as_deref(),
+            Some("feature:Microsoft-Windows-Subsystem-Linux")
+        );
+        assert_eq!(apps[0].install_scope, Some(InstallScope::Machine));
+        assert_eq!(apps[0].path, PathBuf::new());
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn test_sources_elsewhere() {
+        let ctx = ScanContext::new(32);
+        assert!(ControlPanelSource::new().scan(&ctx).unwrap().is_empty());
+        assert!(OptionalFeatureSource::new().scan(&ctx).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,454 @@
+use crate::error::AppInfoError;
+use std::fmt;
+use std::str::FromStr;
+
+/// A calendar date read from an installer's [`AppInfo::install_date`](crate::AppInfo::install_date).
+///
+/// Installers write that value in whatever form they like: `20240320` as Windows Installer
+/// does, `2024-03-20`, or the short date of the machine they ran on, such as `20.03.2024`,
+/// `3/20/2024` or `20-Mär-2024`. [`InstallDate::parse`] reads all of these. Dates compare
+/// in calendar order and display as `2024-03-20`, which is also their serde form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", try_from = "String")
+)]
+pub struct InstallDate {
+    pub year: u16,
+    /// 1 to 12
+    pub month: u8,
+    /// 1 to 31
+    pub day: u8,
+}
+
+/// How to read a date whose day and month could be either way round, such as `03/04/2024`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DateOrder {
+    /// Follow the current user's short date format. Only Windows has one to consult;
+    /// elsewhere, and when it does not say, such dates are left unread.
+    #[default]
+    Auto,
+    /// Day before month, as in most of Europe: `03/04/2024` is 3 April
+    DayFirst,
+    /// Month before day, as in the United States: `03/04/2024` is 4 March
+    MonthFirst,
+}
+
+/// What [`parse_date`] made of a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DateParse {
+    Date(InstallDate),
+    /// A valid date either way round, with no preference to pick one
+    Ambiguous,
+    Invalid,
+}
+
+/// Month names installers write in the languages they are most often run in, full or
+/// cut short. Any prefix of three letters or more counts, as do the listed short forms.
+const MONTH_NAMES: &[(&str, u8)] = &[
+    ("january", 1),
+    ("januar", 1),
+    ("janvier", 1),
+    ("enero", 1),
+    ("february", 2),
+    ("februar", 2),
+    ("février", 2),
+    ("febrero", 2),
+    ("march", 3),
+    ("märz", 3),
+    ("mrz", 3),
+    ("mars", 3),
+    ("marzo", 3),
+    ("april", 4),
+    ("avril", 4),
+    ("abril", 4),
+    ("may", 5),
+    ("mai", 5),
+    ("mayo", 5),
+    ("june", 6),
+    ("juni", 6),
+    ("juin", 6),
+    ("junio", 6),
+    ("july", 7),
+    ("juli", 7),
+    ("juillet", 7),
+    ("julio", 7),
+    ("august", 8),
+    ("août", 8),
+    ("agosto", 8),
+    ("september", 9),
+    ("septembre", 9),
+    ("septiembre", 9),
+    ("october", 10),
+    ("oktober", 10),
+    ("octobre", 10),
+    ("octubre", 10),
+    ("november", 11),
+    ("novembre", 11),
+    ("noviembre", 11),
+    ("december", 12),
+    ("dezember", 12),
+    ("décembre", 12),
+    ("diciembre", 12),
+];
+
+impl InstallDate {
+    /// Reads an installer date string.
+    ///
+    /// Forms that say which part is which are read first: `YYYYMMDD`, ISO 8601 with or
+    /// without a time, year-first dates such as `2024/03/20`, and dates with a month name
+    /// such as `20-Mär-2024` or `Mar 20, 2024`. Otherwise the value is taken for a day and
+    /// month followed by the year, separated by `/`, `-` or `.`; a part above 12 decides
+    /// which is the day, and only when neither is does `order` decide. Two-digit years are
+    /// read as 1970 to 2069. Returns `None` for anything else, for dates that do not
+    /// exist, and for ambiguous dates that `order` cannot settle.
+    pub fn parse(value: &str, order: DateOrder) -> Option<InstallDate> {
+        match parse_date(value, preference(order)) {
+            DateParse::Date(date) => Some(date),
+            DateParse::Ambiguous | DateParse::Invalid => None,
+        }
+    }
+}
+
+/// The order to read ambiguous dates in, looking up the user's for [`DateOrder::Auto`].
+fn preference(order: DateOrder) -> Option<DateOrder> {
+    match order {
+        #[cfg(target_os = "windows")]
+        DateOrder::Auto => crate::window::user_date_order(),
+        #[cfg(not(target_os = "windows"))]
+        DateOrder::Auto => None,
+        order => Some(order),
+    }
+}
+
+/// Reads a date string as [`InstallDate::parse`] describes, with `order` as the
+/// preference for ambiguous dates.
+pub(crate) fn parse_date(value: &str, order: Option<DateOrder>) -> DateParse {
+    // Leave out a time, which starts with the number before the first colon
+    let value = match value.find(':') {
+        Some(colon) => value[..colon]
+            .trim_end_matches(|c: char| c.is_ascii_digit())
+            .trim_end_matches(|c: char| c == 'T' || c.is_whitespace()),
+        None => value,
+    };
+    let tokens = date_tokens(value);
+    let number = |token: &str| token.parse::<u32>().ok();
+
+    match tokens.as_slice() {
+        [digits] if digits.len() == 8 && digits.bytes().all(|b| b.is_ascii_digit()) => {
+            date_from_parts(
+                number(&digits[..4]),
+                number(&digits[4..6]),
+                number(&digits[6..]),
+            )
+        }
+        [first, second, third] => {
+            let is_number = |token: &str| token.bytes().all(|b| b.is_ascii_digit());
+            let month_name = |token: &str| (!is_number(token)).then(|| month_from_name(token));
+            match (month_name(first), month_name(second), month_name(third)) {
+                // Year, month and day
+                (None, None, None) if first.len() == 4 => {
+                    date_from_parts(number(first), number(second), number(third))
+                }
+                (None, None, None) => {
+                    day_month_date(number(first), number(second), year(third), order)
+                }
+                // 20-Mär-2024 and 2024-Mar-20
+                (None, Some(month), None) if first.len() == 4 => {
+                    date_from_parts(number(first), month, number(third))
+                }
+                (None, Some(month), None) => date_from_parts(year(third), month, number(first)),
+                // Mar 20, 2024
+                (Some(month), None, None) => date_from_parts(year(third), month, number(second)),
+                _ => DateParse::Invalid,
+            }
+        }
+        _ => DateParse::Invalid,
+    }
+}
+
+/// Splits a date into its runs of digits and of letters, dropping separators.
+fn date_tokens(value: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (index, c) in value.char_indices() {
+        let continues = start.is_some_and(|start: usize| {
+            let previous = value[start..].chars().next().unwrap();
+            c.is_alphanumeric() && previous.is_ascii_digit() == c.is_ascii_digit()
+        });
+        if continues {
+            continue;
+        }
+        if let Some(start) = start.take() {
+            tokens.push(&value[start..index]);
+        }
+        if c.is_alphanumeric() {
+            start = Some(index);
+        }
+    }
+    if let Some(start) = start {
+        tokens.push(&value[start..]);
+    }
+    tokens
+}
+
+/// The month a name gives, when every name it could be short for agrees.
+fn month_from_name(token: &str) -> Option<u32> {
+    let token = token.to_lowercase();
+    if token.chars().count() < 3 {
+        return None;
+    }
+    let mut months = MONTH_NAMES
+        .iter()
+        .filter(|(name, _)| name.starts_with(&token))
+        .map(|&(_, month)| u32::from(month));
+    let month = months.next()?;
+    months.all(|other| other == month).then_some(month)
+}
+
+/// Reads a year of four digits, or of two as 1970 to 2069.
+fn year(token: &str) -> Option<u32> {
+    let year = token.parse::<u32>().ok()?;
+    match token.len() {
+        4 => Some(year),
+        2 if year < 70 => Some(2000 + year),
+        2 => Some(1900 + year),
+        _ => None,
+    }
+}
+
+/// Reads a day and month in either order, as their values decide or else `order`.
+fn day_month_date(
+    first: Option<u32>,
+    second: Option<u32>,
+    year: Option<u32>,
+    order: Option<DateOrder>,
+) -> DateParse {
+    let (Some(first), Some(second)) = (first, second) else {
+        return DateParse::Invalid;
+    };
+    let day_first = date_from_parts(year, Some(second), Some(first));
+    let month_first = date_from_parts(year, Some(first), Some(second));
+    match (day_first, month_first) {
+        (DateParse::Date(a), DateParse::Date(b)) if a != b => match order {
+            Some(DateOrder::DayFirst) => day_first,
+            Some(DateOrder::MonthFirst) => month_first,
+            Some(DateOrder::Auto) | None => DateParse::Ambiguous,
+        },
+        (DateParse::Date(_), _) => day_first,
+        (_, month_first) => month_first,
+    }
+}
+
+/// A date from its parts, when it exists and falls in a plausible range of years.
+fn date_from_parts(year: Option<u32>, month: Option<u32>, day: Option<u32>) -> DateParse {
+    let (Some(year), Some(month), Some(day)) = (year, month, day) else {
+        return DateParse::Invalid;
+    };
+    if !(1900..=2100).contains(&year) || !(1..=12).contains(&month) {
+        return DateParse::Invalid;
+    }
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    };
+    if !(1..=days).contains(&day) {
+        return DateParse::Invalid;
+    }
+    DateParse::Date(InstallDate {
+        year: year as u16,
+        month: month as u8,
+        day: day as u8,
+    })
+}
+
+/// Fills in [`AppInfo::parsed_install_date`](crate::AppInfo::parsed_install_date) and
+/// [`AppInfo::install_date_ambiguous`](crate::AppInfo::install_date_ambiguous) from the
+/// raw install date.
+pub(crate) fn read_install_date(app: &mut crate::AppInfo, order: DateOrder) {
+    let Some(value) = app.install_date.as_deref() else {
+        return;
+    };
+    match parse_date(value, preference(order)) {
+        DateParse::Date(date) => app.parsed_install_date = Some(date),
+        DateParse::Ambiguous => app.install_date_ambiguous = true,
+        DateParse::Invalid => {}
+    }
+}
+
+impl fmt::Display for InstallDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// Reads the `YYYY-MM-DD` form [`InstallDate`] displays as. Use [`InstallDate::parse`]
+/// for installer strings.
+impl FromStr for InstallDate {
+    type Err = AppInfoError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut parts = value.splitn(3, '-');
+        let mut part = |len: usize| {
+            parts
+                .next()
+                .filter(|part| part.len() == len && part.bytes().all(|b| b.is_ascii_digit()))
+                .and_then(|part| part.parse().ok())
+        };
+        match date_from_parts(part(4), part(2), part(2)) {
+            DateParse::Date(date) => Ok(date),
+            _ => Err(AppInfoError::InvalidDate {
+                value: value.to_string(),
+            }),
+        }
+    }
+}
+
+impl TryFrom<String> for InstallDate {
+    type Error = AppInfoError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<InstallDate> for String {
+    fn from(date: InstallDate) -> Self {
+        date.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: u16, month: u8, day: u8) -> DateParse {
+        DateParse::Date(InstallDate { year, month, day })
+    }
+
+    #[test]
+    fn test_parse_date() {
+        // (value, read with no preference, day first, month first)
+        let cases = [
+            ("20240320", date(2024, 3, 20), None, None),
+            ("2024-03-20", date(2024, 3, 20), None, None),
+            ("2024-03-20T14:05:09Z", date(2024, 3, 20), None, None),
+            ("2024/3/5", date(2024, 3, 5), None, None),
+            ("2024.03.20", date(2024, 3, 20), None, None),
+            // A day above 12 decides the order whatever the preference
+            ("20.03.2024", date(2024, 3, 20), None, None),
+            ("20/03/2024 14:05", date(2024, 3, 20), None, None),
+            ("3/20/2024", date(2024, 3, 20), None, None),
+            ("03-20-2024 2:05:09 PM", date(2024, 3, 20), None, None),
+            ("20.03.24", date(2024, 3, 20), None, None),
+            ("12/31/99", date(1999, 12, 31), None, None),
+            // Either way round
+            (
+                "03/04/2024",
+                DateParse::Ambiguous,
+                Some(date(2024, 4, 3)),
+                Some(date(2024, 3, 4)),
+            ),
+            (
+                "4.3.24",
+                DateParse::Ambiguous,
+                Some(date(2024, 3, 4)),
+                Some(date(2024, 4, 3)),
+            ),
+            ("07/07/2024", date(2024, 7, 7), None, None),
+            // Only one way round is a date
+            ("02/30/2024", DateParse::Invalid, None, None),
+            ("30/02/2024", DateParse::Invalid, None, None),
+            ("29/02/2024", date(2024, 2, 29), None, None),
+            // Month names
+            ("20-Mär-2024", date(2024, 3, 20), None, None),
+            ("20-Mar-2024", date(2024, 3, 20), None, None),
+            ("Mar 20, 2024", date(2024, 3, 20), None, None),
+            ("March 5 2024", date(2024, 3, 5), None, None),
+            ("5. März 2024", date(2024, 3, 5), None, None),
+            ("5 mrz 24", date(2024, 3, 5), None, None),
+            ("12 déc. 2023", date(2023, 12, 12), None, None),
+            ("1-août-2023", date(2023, 8, 1), None, None),
+            ("3 de abril de 2024", DateParse::Invalid, None, None),
+            ("3-abr-2024", date(2024, 4, 3), None, None),
+            ("2024-Oct-05", date(2024, 10, 5), None, None),
+            ("Mai 5 2024", date(2024, 5, 5), None, None),
+            ("20 Ma 2024", DateParse::Invalid, None, None),
+            // Not dates
+            ("", DateParse::Invalid, None, None),
+            ("   ", DateParse::Invalid, None, None),
+            ("00000000", DateParse::Invalid, None, None),
+            ("20241332", DateParse::Invalid, None, None),
+            ("2024031", DateParse::Invalid, None, None),
+            ("13/13/2024", DateParse::Invalid, None, None),
+            ("03/04/124", DateParse::Invalid, None, None),
+            ("1.2.3.4", DateParse::Invalid, None, None),
+            ("unknown", DateParse::Invalid, None, None),
+            ("1710892800", DateParse::Invalid, None, None),
+        ];
+        for (value, expected, day_first, month_first) in cases {
+            assert_eq!(parse_date(value, None), expected, "{}", value);
+            assert_eq!(
+                parse_date(value, Some(DateOrder::Auto)),
+                expected,
+                "{}",
+                value
+            );
+            assert_eq!(
+                parse_date(value, Some(DateOrder::DayFirst)),
+                day_first.unwrap_or(expected),
+                "{} day first",
+                value
+            );
+            assert_eq!(
+                parse_date(value, Some(DateOrder::MonthFirst)),
+                month_first.unwrap_or(expected),
+                "{} month first",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_install_date() {
+        let parse = |value: &str| InstallDate::parse(value, DateOrder::DayFirst);
+        let date = parse("03/04/2024").unwrap();
+        assert_eq!(date.to_string(), "2024-04-03");
+        assert_eq!("2024-04-03".parse::<InstallDate>().ok(), Some(date));
+        assert!(parse("20240320").unwrap() < date);
+        assert_eq!(
+            InstallDate::parse("03/04/2024", DateOrder::MonthFirst),
+            Some(InstallDate {
+                year: 2024,
+                month: 3,
+                day: 4
+            })
+        );
+        for invalid in ["2024-4-03", "20240403", "2024-02-30", "2024-04-03T10:00"] {
+            assert!(invalid.parse::<InstallDate>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_read_install_date() {
+        let mut app = crate::AppInfo {
+            install_date: Some("05/06/2023".to_string()),
+            ..Default::default()
+        };
+        read_install_date(&mut app, DateOrder::MonthFirst);
+        assert_eq!(app.parsed_install_date.unwrap().to_string(), "2023-05-06");
+        assert!(!app.install_date_ambiguous);
+
+        let mut app = crate::AppInfo {
+            install_date: Some("garbage".to_string()),
+            ..Default::default()
+        };
+        read_install_date(&mut app, DateOrder::DayFirst);
+        assert_eq!(app.parsed_install_date, None);
+        assert!(!app.install_date_ambiguous);
+    }
+}
@@ -0,0 +1,315 @@
+use crate::glob::fold_case;
+use crate::{compare_versions, normalize_publisher, AppInfo, DateOrder, InstallDate};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+/// Why the entries of a [`DuplicateGroup`] are taken to be the same product.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DuplicateReason {
+    /// Every entry has the same identifier
+    SameIdentifier,
+    /// The entries share a name and publisher, though some may also share an identifier
+    SameNameAndPublisher,
+}
+
+/// Installations that look like copies of the same product, as reported by
+/// [`find_duplicate_installs`].
+#[derive(Debug, Clone)]
+pub struct DuplicateGroup<'a> {
+    /// The installations, in the order they were given
+    pub apps: Vec<&'a AppInfo>,
+    /// How the installations were matched
+    pub reason: DuplicateReason,
+    /// Index into [`DuplicateGroup::apps`] of the installation that looks newest, by
+    /// version, then install date, then modification time of its path. `None` when no
+    /// single one stands out.
+    pub newest: Option<usize>,
+}
+
+/// Finds installations that look like copies of the same product, such as a per-machine
+/// and a per-user install, or an old version left behind in another directory.
+///
+/// Entries are grouped when they share an identifier, ignoring case, or a name and a
+/// publisher in the form [`normalize_publisher`] gives it. The app's own version is left
+/// out of the name, so "7-Zip 23.01" and "7-Zip 22.00" match. Entries without a publisher
+/// only match by identifier, so unrelated apps that share a generic name such as "Setup"
+/// are not reported. Entries that are the same installation seen twice, with the same
+/// path, name, version and scope, count once, and groups need at least two distinct
+/// installations. The apps are only reported, never changed or merged.
+pub fn find_duplicate_installs(apps: &[AppInfo]) -> Vec<DuplicateGroup<'_>> {
+    // Connect entries sharing a key, then report the connected sets
+    let mut parent: Vec<usize> = (0..apps.len()).collect();
+    let keys: [fn(&AppInfo) -> Option<String>; 2] = [identifier_key, name_key];
+    for key in keys {
+        let mut first_with_key: HashMap<String, usize> = HashMap::new();
+        for (index, app) in apps.iter().enumerate() {
+            let Some(key) = key(app) else {
+                continue;
+            };
+            let first = *first_with_key.entry(key).or_insert(index);
+            let (a, b) = (root(&mut parent, first), root(&mut parent, index));
+            parent[a.max(b)] = a.min(b);
+        }
+    }
+
+    let mut sets: Vec<Vec<usize>> = Vec::new();
+    let mut set_of_root = HashMap::new();
+    for index in 0..apps.len() {
+        let root = root(&mut parent, index);
+        let set = *set_of_root.entry(root).or_insert_with(|| {
+            sets.push(Vec::new());
+            sets.len() - 1
+        });
+        sets[set].push(index);
+    }
+
+    sets.into_iter()
+        .filter_map(|set| {
+            let mut members: Vec<&AppInfo> = Vec::new();
+            for &index in &set {
+                let app = &apps[index];
+                if !members.iter().any(|member| same_installation(member, app)) {
+                    members.push(app);
+                }
+            }
+            if members.len() < 2 {
+                return None;
+            }
+
+            let identifier = identifier_key(members[0]);
+            let reason = if identifier.is_some()
+                && members.iter().all(|app| identifier_key(app) == identifier)
+            {
+                DuplicateReason::SameIdentifier
+            } else {
+                DuplicateReason::SameNameAndPublisher
+            };
+            let newest = newest_index(&members);
+            Some(DuplicateGroup {
+                apps: members,
+                reason,
+                newest,
+            })
+        })
+        .collect()
+}
+
+/// Follows `parent` links up to the representative of an entry's set.
+fn root(parent: &mut [usize], mut index: usize) -> usize {
+    while parent[index] != index {
+        parent[index] = parent[parent[index]];
+        index = parent[index];
+    }
+    index
+}
+
+fn identifier_key(app: &AppInfo) -> Option<String> {
+    let identifier = app.identifier.as_deref()?.trim();
+    (!identifier.is_empty()).then(|| fold_case(identifier))
+}
+
+/// The folded name without the app's own version, and the normalized publisher.
+fn name_key(app: &AppInfo) -> Option<String> {
+    let publisher = normalize_publisher(app.publisher.as_deref()?);
+    let mut name = fold_case(&app.name);
+    if let Some(version) = app.version.as_deref().map(str::trim) {
+        if !version.is_empty() {
+            name = name.replace(&fold_case(version), " ");
+        }
+    }
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    if name.is_empty() || publisher.is_empty() {
+        return None;
+    }
+    Some(format!("{}\0{}", name, publisher))
+}
+
+/// Whether two entries describe one installation: the same app, in the same place, at the
+/// same version.
+fn same_installation(a: &AppInfo, b: &AppInfo) -> bool {
+    crate::scan::installation_key(a) == crate::scan::installation_key(b) && a.version == b.version
+}
+
+/// The index of the entry that is newer than every other one, if there is one.
+fn newest_index(apps: &[&AppInfo]) -> Option<usize> {
+    let modified: Vec<Option<SystemTime>> = apps
+        .iter()
+        .map(|app| {
+            std::fs::metadata(&app.path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        })
+        .collect();
+    let compare = |a: usize, b: usize| -> Ordering {
+        let versions = apps[a]
+            .version
+            .as_deref()
+            .zip(apps[b].version.as_deref())
+            .map(|(left, right)| compare_versions(left, right));
+        let dates = install_date(apps[a])
+            .zip(install_date(apps[b]))
+            .map(|(left, right)| left.cmp(&right));
+        let times = modified[a]
+            .zip(modified[b])
+            .map(|(left, right)| left.cmp(&right));
+        [versions, dates, times]
+            .into_iter()
+            .flatten()
+            .find(|ordering| *ordering != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    };
+
+    (0..apps.len()).find(|&candidate| {
+        (0..apps.len())
+            .filter(|&other| other != candidate)
+            .all(|other| compare(candidate, other) == Ordering::Greater)
+    })
+}
+
+/// The install date, as the scan read it or else as [`InstallDate::parse`] reads it.
+fn install_date(app: &AppInfo) -> Option<InstallDate> {
+    app.parsed_install_date.or_else(|| {
+        app.install_date
+            .as_deref()
+            .and_then(|date| InstallDate::parse(date, DateOrder::Auto))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InstallScope;
+    use std::path::PathBuf;
+
+    fn app(name: &str, version: &str, path: &str) -> AppInfo {
+        AppInfo {
+            name: name.to_string(),
+            version: Some(version.to_string()),
+            path: PathBuf::from(path),
+            ..Default::default()
+        }
+    }
+
+    fn names<'a>(group: &DuplicateGroup<'a>) -> Vec<(&'a str, Option<&'a str>)> {
+        group
+            .apps
+            .iter()
+            .map(|app| (app.name.as_str(), app.version.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn test_same_identifier_different_path() {
+        let machine = AppInfo {
+            identifier: Some("{EA457B21-F73E-494C-ACAB-524FDE069978}_is1".to_string()),
+            install_scope: Some(InstallScope::Machine),
+            ..app(
+                "Microsoft Visual Studio Code",
+                "1.89.0",
+                "C:\\Program Files\\Microsoft VS Code",
+            )
+        };
+        let user = AppInfo {
+            identifier: Some("{ea457b21-f73e-494c-acab-524fde069978}_IS1".to_string()),
+            install_scope: Some(InstallScope::User),
+            ..app(
+                "Microsoft Visual Studio Code",
+                "1.90.2",
+                "C:\\Users\\me\\AppData\\Local\\Programs\\Microsoft VS Code",
+            )
+        };
+        let other = AppInfo {
+            identifier: Some("Git_is1".to_string()),
+            ..app("Git", "2.45.1", "C:\\Program Files\\Git")
+        };
+
+        let apps = vec![machine, other, user];
+        let groups = find_duplicate_installs(&apps);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].reason, DuplicateReason::SameIdentifier);
+        assert_eq!(
+            names(&groups[0]),
+            [
+                ("Microsoft Visual Studio Code", Some("1.89.0")),
+                ("Microsoft Visual Studio Code", Some("1.90.2"))
+            ]
+        );
+        assert_eq!(groups[0].newest, Some(1));
+    }
+
+    #[test]
+    fn test_same_name_different_version() {
+        let with_publisher = |name: &str, version: &str, path: &str, publisher: &str| AppInfo {
+            publisher: Some(publisher.to_string()),
+            ..app(name, version, path)
+        };
+        let apps = vec![
+            with_publisher("7-Zip 22.00 (x64)", "22.00", "C:\\7-Zip", "Igor Pavlov"),
+            with_publisher(
+                "7-Zip 23.01 (x64)",
+                "23.01",
+                "D:\\Tools\\7-Zip",
+                "Igor Pavlov ",
+            ),
+            with_publisher(
+                "Zoom",
+                "5.17.5",
+                "C:\\Zoom",
+                "Zoom Video Communications, Inc.",
+            ),
+            with_publisher(
+                "Zoom",
+                "5.17.5",
+                "C:\\Zoom\\bin",
+                "Zoom Video Communications",
+            ),
+        ];
+
+        let groups = find_duplicate_installs(&apps);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].reason, DuplicateReason::SameNameAndPublisher);
+        assert_eq!(groups[0].newest, Some(1));
+        // Same version and no dates to go by, so neither is newer
+        assert_eq!(groups[1].apps.len(), 2);
+        assert_eq!(groups[1].newest, None);
+    }
+
+    #[test]
+    fn test_install_date_breaks_version_ties() {
+        let dated = |path: &str, date: &str| AppInfo {
+            identifier: Some("Tool".to_string()),
+            install_date: Some(date.to_string()),
+            ..app("Tool", "1.0", path)
+        };
+        let apps = vec![dated("C:\\Tool", "20240102"), dated("D:\\Tool", "20231130")];
+        let groups = find_duplicate_installs(&apps);
+        assert_eq!(groups[0].newest, Some(0));
+    }
+
+    #[test]
+    fn test_duplicate_false_positives() {
+        // Two unrelated installers that share a generic name
+        let apps = vec![
+            AppInfo {
+                publisher: Some("Contoso Ltd.".to_string()),
+                ..app("Setup", "1.0", "C:\\Contoso")
+            },
+            AppInfo {
+                publisher: Some("Fabrikam, Inc.".to_string()),
+                ..app("Setup", "2.0", "C:\\Fabrikam")
+            },
+            app("Setup", "3.0", "C:\\Unknown"),
+            app("Setup", "4.0", "C:\\Unknown2"),
+        ];
+        assert!(find_duplicate_installs(&apps).is_empty());
+
+        // One installation listed twice, such as in both registry views
+        let twice = AppInfo {
+            identifier: Some("Tool".to_string()),
+            ..app("Tool", "1.0", "C:\\Tool")
+        };
+        assert!(find_duplicate_installs(&[twice.clone(), twice]).is_empty());
+        assert!(find_duplicate_installs(&[]).is_empty());
+    }
+}
@@ -1,9 +1,21 @@
 use thiserror::Error;
 
+/// Errors reported by this crate.
+///
+/// With the `serde` feature the error serializes, so failures can be sent across a
+/// process boundary. I/O errors serialize as their message.
 #[derive(Error, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum AppInfoError {
     #[error("Failed to read applications directory: {0}")]
-    DirectoryReadError(#[from] std::io::Error),
+    DirectoryReadError(
+        #[from]
+        #[cfg_attr(
+            feature = "serde",
+            serde(serialize_with = "crate::serde_support::serialize_io_error")
+        )]
+        std::io::Error,
+    ),
     
     #[error("Failed to parse application bundle: {path}")]
     BundleParseError { path: String },
@@ -22,9 +34,94 @@ pub enum AppInfoError {
     
     #[error("Failed to get file icon: {0}")]
     FileIconError(#[from] FileIconError),
+    
+    #[error("Failed to encode icon: {0}")]
+    IconEncodeError(String),
+    
+    #[error("No application handles URL scheme: {scheme}")]
+    UrlSchemeNotHandled { scheme: String },
+    
+    #[error("No application is in the foreground")]
+    NoForegroundApp,
+    
+    #[error("Process not found or not accessible: {pid}")]
+    ProcessNotFound { pid: u32 },
+    
+    #[error("Application executable not found: {path}")]
+    ExecutableNotFound { path: String },
+    
+    #[error("Launch was cancelled by the user")]
+    LaunchCancelled,
+    
+    #[error("Failed to launch application: {0}")]
+    LaunchFailed(String),
+    
+    #[error("Failed to watch for application changes: {0}")]
+    WatcherError(String),
+    
+    #[error("Failed to write export: {0}")]
+    ExportError(String),
+    
+    #[error("Invalid pattern {pattern:?}: {reason}")]
+    InvalidPattern { pattern: String, reason: String },
+    
+    #[error("Package manager failed: {0}")]
+    PackageManagerError(String),
+    
+    #[error("WMI query failed: {0}")]
+    WmiError(String),
+    
+    #[error("Failed to convert icon: {0}")]
+    IconConversionError(String),
+
+    #[error("Invalid date {value:?}, expected YYYY-MM-DD")]
+    InvalidDate { value: String },
+
+    #[error("Invalid stable ID {value:?}")]
+    InvalidStableId { value: String },
+
+    #[error("Failed to uninstall application: {0}")]
+    UninstallFailed(String),
 }
 
-#[derive(Error, Debug)]
+impl AppInfoError {
+    /// An equivalent error, for handing one failure to several callers. I/O errors keep
+    /// their kind and message but not their source.
+    pub(crate) fn duplicate(&self) -> AppInfoError {
+        use AppInfoError::*;
+        match self {
+            DirectoryReadError(e) => DirectoryReadError(std::io::Error::new(e.kind(), e.to_string())),
+            BundleParseError { path } => BundleParseError { path: path.clone() },
+            PlistError(message) => PlistError(message.clone()),
+            RegistryError(message) => RegistryError(message.clone()),
+            AppNotFound { name } => AppNotFound { name: name.clone() },
+            UnsupportedPlatform => UnsupportedPlatform,
+            FileIconError(e) => FileIconError(e.clone()),
+            IconEncodeError(message) => IconEncodeError(message.clone()),
+            UrlSchemeNotHandled { scheme } => UrlSchemeNotHandled { scheme: scheme.clone() },
+            NoForegroundApp => NoForegroundApp,
+            ProcessNotFound { pid } => ProcessNotFound { pid: *pid },
+            ExecutableNotFound { path } => ExecutableNotFound { path: path.clone() },
+            LaunchCancelled => LaunchCancelled,
+            LaunchFailed(message) => LaunchFailed(message.clone()),
+            WatcherError(message) => WatcherError(message.clone()),
+            ExportError(message) => ExportError(message.clone()),
+            InvalidPattern { pattern, reason } => InvalidPattern {
+                pattern: pattern.clone(),
+                reason: reason.clone(),
+            },
+            PackageManagerError(message) => PackageManagerError(message.clone()),
+            WmiError(message) => WmiError(message.clone()),
+            IconConversionError(message) => IconConversionError(message.clone()),
+            InvalidDate { value } => InvalidDate { value: value.clone() },
+            InvalidStableId { value } => InvalidStableId { value: value.clone() },
+            UninstallFailed(message) => UninstallFailed(message.clone()),
+        }
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum FileIconError {
     #[error("Path does not exist")]
     PathDoesNotExist,
@@ -35,8 +132,15 @@ pub enum FileIconError {
     #[error("Failed to extract icon")]
     Failed,
     
+    /// No longer returned: unsupported platforms give [`AppInfoError::UnsupportedPlatform`]
     #[error("Platform not supported")]
     PlatformNotSupported,
+    
+    #[error("Icon support is not compiled in; enable the `icons` feature")]
+    FeatureDisabled,
+    
+    #[error("No icon at index {index}")]
+    IconIndexOutOfRange { index: i32 },
 }
 
 pub type Result<T> = std::result::Result<T, AppInfoError>;
@@ -0,0 +1,572 @@
+use crate::{
+    error::AppInfoError, AppInfo, AppKind, AssociationRole, InstallScope, RegistryView, Result,
+    SignatureStatus,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::fmt::Write as _;
+use std::io::Write;
+
+/// A column of [`export_csv`] output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportColumn {
+    /// [`AppInfo::name`]
+    Name,
+    /// [`AppInfo::version`]
+    Version,
+    /// [`AppInfo::publisher`]
+    Publisher,
+    /// [`AppInfo::identifier`]
+    Identifier,
+    /// [`AppInfo::path`]
+    Path,
+    /// [`AppInfo::executable_path`]
+    ExecutablePath,
+    /// [`AppInfo::install_date`]
+    InstallDate,
+    /// [`AppInfo::install_size`], in bytes
+    InstallSize,
+    /// [`AppInfo::category`], as its display name
+    Category,
+    /// [`AppInfo::kind`], as the variant name such as `Application`
+    Kind,
+    /// [`AppInfo::stable_id`]
+    StableId,
+}
+
+impl ExportColumn {
+    /// The column's header in CSV output.
+    pub fn header(self) -> &'static str {
+        match self {
+            ExportColumn::Name => "name",
+            ExportColumn::Version => "version",
+            ExportColumn::Publisher => "publisher",
+            ExportColumn::Identifier => "identifier",
+            ExportColumn::Path => "path",
+            ExportColumn::ExecutablePath => "executable_path",
+            ExportColumn::InstallDate => "install_date",
+            ExportColumn::InstallSize => "install_size",
+            ExportColumn::Category => "category",
+            ExportColumn::Kind => "kind",
+            ExportColumn::StableId => "stable_id",
+        }
+    }
+
+    fn value(self, app: &AppInfo) -> String {
+        match self {
+            ExportColumn::Name => app.name.clone(),
+            ExportColumn::Version => app.version.clone().unwrap_or_default(),
+            ExportColumn::Publisher => app.publisher.clone().unwrap_or_default(),
+            ExportColumn::Identifier => app.identifier.clone().unwrap_or_default(),
+            ExportColumn::Path => app.path.to_string_lossy().into_owned(),
+            ExportColumn::ExecutablePath => app
+                .executable_path
+                .as_ref()
+                .map(|path| path.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            ExportColumn::InstallDate => app.install_date.clone().unwrap_or_default(),
+            ExportColumn::InstallSize => app
+                .install_size
+                .map(|size| size.to_string())
+                .unwrap_or_default(),
+            ExportColumn::Category => app
+                .category
+                .as_ref()
+                .map(|category| category.to_string())
+                .unwrap_or_default(),
+            ExportColumn::Kind => kind_name(app.kind).unwrap_or_default().to_string(),
+            ExportColumn::StableId => app.stable_id(),
+        }
+    }
+}
+
+fn kind_name(kind: Option<AppKind>) -> Option<&'static str> {
+    kind.map(|kind| match kind {
+        AppKind::Application => "Application",
+        AppKind::CommandLineTool => "CommandLineTool",
+        AppKind::Background => "Background",
+        AppKind::Runtime => "Runtime",
+    })
+}
+
+/// Options for [`export_json`] and [`export_csv`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportOptions {
+    /// Include each app's icon in JSON output, as a base64 PNG. CSV output never has icons.
+    pub include_icons: bool,
+    /// The CSV columns, in order. Defaults to name, version, publisher, identifier, path and
+    /// install date.
+    pub columns: Vec<ExportColumn>,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions {
+            include_icons: false,
+            columns: vec![
+                ExportColumn::Name,
+                ExportColumn::Version,
+                ExportColumn::Publisher,
+                ExportColumn::Identifier,
+                ExportColumn::Path,
+                ExportColumn::InstallDate,
+            ],
+        }
+    }
+}
+
+/// Writes apps as a JSON array of objects, one per line.
+///
+/// Every object has the fields of [`AppInfo`] and its `stable_id`, with `null` for missing
+/// values and the category as its display name. Paths are written as UTF-8 with invalid sequences replaced
+/// by U+FFFD, so a path that is not valid UTF-8 cannot be recovered from the output. When
+/// [`ExportOptions::include_icons`] is set, apps with an icon, as pixels or compressed, get
+/// an `icon` object with `width`, `height` and a base64 `png`.
+pub fn export_json(apps: &[AppInfo], mut writer: impl Write, opts: ExportOptions) -> Result<()> {
+    write_all(&mut writer, "[")?;
+    for (index, app) in apps.iter().enumerate() {
+        let mut line = String::from(if index == 0 { "\n" } else { ",\n" });
+        json_object(&mut line, app, opts.include_icons)?;
+        write_all(&mut writer, &line)?;
+    }
+    write_all(&mut writer, if apps.is_empty() { "]\n" } else { "\n]\n" })?;
+    writer
+        .flush()
+        .map_err(|err| AppInfoError::ExportError(err.to_string()))
+}
+
+/// Writes apps as CSV with a header row, following RFC 4180.
+///
+/// Fields containing commas, quotes or line breaks are quoted, and missing values are
+/// left empty. Paths are written as UTF-8 with invalid sequences replaced by U+FFFD, so a
+/// path that is not valid UTF-8 cannot be recovered from the output.
+pub fn export_csv(apps: &[AppInfo], mut writer: impl Write, opts: ExportOptions) -> Result<()> {
+    let header: Vec<&str> = opts.columns.iter().map(|column| column.header()).collect();
+    write_all(&mut writer, &csv_record(header))?;
+    for app in apps {
+        let values: Vec<String> = opts
+            .columns
+            .iter()
+            .map(|column| column.value(app))
+            .collect();
+        write_all(&mut writer, &csv_record(values.iter().map(String::as_str)))?;
+    }
+    writer
+        .flush()
+        .map_err(|err| AppInfoError::ExportError(err.to_string()))
+}
+
+fn write_all(writer: &mut impl Write, text: &str) -> Result<()> {
+    writer
+        .write_all(text.as_bytes())
+        .map_err(|err| AppInfoError::ExportError(err.to_string()))
+}
+
+fn json_object(out: &mut String, app: &AppInfo, include_icon: bool) -> Result<()> {
+    let path = app.path.to_string_lossy();
+    let executable_path = app
+        .executable_path
+        .as_ref()
+        .map(|path| path.to_string_lossy());
+
+    out.push_str("{\"name\":");
+    json_string(out, &app.name);
+    out.push_str(",\"stable_id\":");
+    json_string(out, &app.stable_id());
+    out.push_str(",\"version\":");
+    json_optional(out, app.version.as_deref());
+    out.push_str(",\"path\":");
+    json_string(out, &path);
+    out.push_str(",\"executable_path\":");
+    json_optional(out, executable_path.as_deref());
+    out.push_str(",\"identifier\":");
+    json_optional(out, app.identifier.as_deref());
+    out.push_str(",\"publisher\":");
+    json_optional(out, app.publisher.as_deref());
+    out.push_str(",\"install_date\":");
+    json_optional(out, app.install_date.as_deref());
+    out.push_str(",\"parsed_install_date\":");
+    let parsed_install_date = app.parsed_install_date.map(|date| date.to_string());
+    json_optional(out, parsed_install_date.as_deref());
+    let _ = write!(
+        out,
+        ",\"install_date_ambiguous\":{}",
+        app.install_date_ambiguous
+    );
+    out.push_str(",\"install_size\":");
+    match app.install_size {
+        Some(size) => {
+            let _ = write!(out, "{}", size);
+        }
+        None => out.push_str("null"),
+    }
+    out.push_str(",\"category\":");
+    let category = app.category.as_ref().map(|category| category.to_string());
+    json_optional(out, category.as_deref());
+    out.push_str(",\"kind\":");
+    json_optional(out, kind_name(app.kind));
+    out.push_str(",\"package_manager\":");
+    match &app.package_manager {
+        Some(package) => {
+            out.push_str("{\"manager\":");
+            json_string(out, &package.manager);
+            out.push_str(",\"id\":");
+            json_string(out, &package.id);
+            out.push_str(",\"available_version\":");
+            json_optional(out, package.available_version.as_deref());
+            out.push('}');
+        }
+        None => out.push_str("null"),
+    }
+    out.push_str(",\"install_scope\":");
+    json_optional(
+        out,
+        app.install_scope.map(|scope| match scope {
+            InstallScope::Machine => "Machine",
+            InstallScope::User => "User",
+        }),
+    );
+    out.push_str(",\"registry_view\":");
+    json_optional(
+        out,
+        app.registry_view.map(|view| match view {
+            RegistryView::Native => "Native",
+            RegistryView::Wow64 => "Wow64",
+        }),
+    );
+    out.push_str(",\"owner\":");
+    match &app.owner {
+        Some(owner) => {
+            out.push_str("{\"sid\":");
+            json_string(out, &owner.sid);
+            out.push_str(",\"name\":");
+            json_optional(out, owner.name.as_deref());
+            out.push('}');
+        }
+        None => out.push_str("null"),
+    }
+
+    for (key, value) in [
+        ("can_uninstall", app.can_uninstall),
+        ("can_modify", app.can_modify),
+        ("can_repair", app.can_repair),
+    ] {
+        let _ = write!(out, ",\"{}\":", key);
+        match value {
+            Some(value) => {
+                let _ = write!(out, "{}", value);
+            }
+            None => out.push_str("null"),
+        }
+    }
+
+    out.push_str(",\"declared_url_schemes\":[");
+    for (i, scheme) in app.declared_url_schemes.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        json_string(out, scheme);
+    }
+    out.push_str("],\"declared_file_types\":[");
+    for (i, association) in app.declared_file_types.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str("{\"extension\":");
+        json_optional(out, association.extension.as_deref());
+        out.push_str(",\"uti\":");
+        json_optional(out, association.uti.as_deref());
+        out.push_str(",\"role\":");
+        json_string(
+            out,
+            match association.role {
+                AssociationRole::Editor => "Editor",
+                AssociationRole::Viewer => "Viewer",
+                AssociationRole::Shell => "Shell",
+                AssociationRole::None => "None",
+            },
+        );
+        out.push('}');
+    }
+    out.push(']');
+
+    out.push_str(",\"signature\":");
+    match &app.signature {
+        Some(signature) => {
+            out.push_str("{\"status\":");
+            json_string(
+                out,
+                match signature.status {
+                    SignatureStatus::Valid => "Valid",
+                    SignatureStatus::Invalid => "Invalid",
+                    SignatureStatus::CatalogSigned => "CatalogSigned",
+                    SignatureStatus::Unsigned => "Unsigned",
+                },
+            );
+            out.push_str(",\"signer\":");
+            json_optional(out, signature.signer.as_deref());
+            out.push('}');
+        }
+        None => out.push_str("null"),
+    }
+    let _ = write!(out, ",\"name_inferred\":{}", app.name_inferred);
+
+    if include_icon {
+        out.push_str(",\"icon\":");
+        // A compressed icon is already PNG, so it is written without decoding it
+        let png = match (&app.icon, &app.compressed_icon) {
+            (Some(icon), _) => Some((icon.width, icon.height, icon.to_base64_png()?)),
+            (None, Some(icon)) => Some((icon.width, icon.height, STANDARD.encode(&icon.png))),
+            (None, None) => None,
+        };
+        match png {
+            Some((width, height, png)) => {
+                let _ = write!(
+                    out,
+                    "{{\"width\":{},\"height\":{},\"png\":\"{}\"}}",
+                    width, height, png
+                );
+            }
+            None => out.push_str("null"),
+        }
+    }
+
+    out.push('}');
+    Ok(())
+}
+
+fn json_optional(out: &mut String, value: Option<&str>) {
+    match value {
+        Some(value) => json_string(out, value),
+        None => out.push_str("null"),
+    }
+}
+
+fn json_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Formats one CSV record, terminated by CRLF.
+fn csv_record<'a>(fields: impl IntoIterator<Item = &'a str>) -> String {
+    let mut record = String::new();
+    for (index, field) in fields.into_iter().enumerate() {
+        if index > 0 {
+            record.push(',');
+        }
+        if field.contains([',', '"', '\r', '\n']) {
+            record.push('"');
+            record.push_str(&field.replace('"', "\"\""));
+            record.push('"');
+        } else {
+            record.push_str(field);
+        }
+    }
+    record.push_str("\r\n");
+    record
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AppCategory, FileAssociation, Icon, SignatureInfo, UserAccount};
+    use std::path::PathBuf;
+
+    fn apps() -> Vec<AppInfo> {
+        vec![
+            AppInfo {
+                name: "Tricky, \"Quoted\"\nApp".to_string(),
+                version: Some("1.0".to_string()),
+                path: PathBuf::from("C:\\Program Files\\Tricky"),
+                icon: Some(Icon {
+                    width: 1,
+                    height: 1,
+                    pixels: vec![255, 0, 0, 255],
+                }),
+                publisher: Some("Acme\tCorp".to_string()),
+                install_date: Some("20240301".to_string()),
+                parsed_install_date: "2024-03-01".parse().ok(),
+                install_size: Some(4096),
+                category: Some(AppCategory::Utilities),
+                kind: Some(AppKind::CommandLineTool),
+                install_scope: Some(InstallScope::User),
+                registry_view: Some(RegistryView::Wow64),
+                owner: Some(UserAccount {
+                    sid: "S-1-5-21-1-2-3-1001".to_string(),
+                    name: Some("HOST\\alice".to_string()),
+                }),
+                can_uninstall: Some(false),
+                declared_url_schemes: vec!["tricky".to_string(), "tricky-beta".to_string()],
+                declared_file_types: vec![FileAssociation {
+                    extension: Some("trk".to_string()),
+                    uti: None,
+                    role: AssociationRole::Editor,
+                }],
+                signature: Some(SignatureInfo {
+                    status: SignatureStatus::Valid,
+                    signer: Some("Acme Corp".to_string()),
+                }),
+                ..Default::default()
+            },
+            AppInfo {
+                name: "Plain".to_string(),
+                path: PathBuf::from("/Applications/Plain.app"),
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn test_export_json() {
+        let mut out = Vec::new();
+        let opts = ExportOptions {
+            include_icons: true,
+            ..Default::default()
+        };
+        export_json(&apps(), &mut out, opts).expect("Failed to export");
+
+        let value: serde_json::Value = serde_json::from_slice(&out).expect("Invalid JSON");
+        let entries = value.as_array().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["name"], "Tricky, \"Quoted\"\nApp");
+        assert_eq!(entries[0]["publisher"], "Acme\tCorp");
+        assert_eq!(entries[0]["stable_id"], apps()[0].stable_id());
+        assert_eq!(entries[0]["path"], "C:\\Program Files\\Tricky");
+        assert_eq!(entries[0]["install_size"], 4096);
+        assert_eq!(entries[0]["category"], "Utilities");
+        assert!(entries[1]["category"].is_null());
+        assert_eq!(entries[0]["kind"], "CommandLineTool");
+        assert!(entries[1]["kind"].is_null());
+        assert!(entries[1]["package_manager"].is_null());
+        assert_eq!(entries[0]["install_scope"], "User");
+        assert_eq!(entries[0]["registry_view"], "Wow64");
+        assert!(entries[1]["registry_view"].is_null());
+        assert_eq!(entries[0]["owner"]["sid"], "S-1-5-21-1-2-3-1001");
+        assert_eq!(entries[0]["owner"]["name"], "HOST\\alice");
+        assert!(entries[1]["owner"].is_null());
+        assert_eq!(entries[0]["can_uninstall"], false);
+        assert!(entries[0]["can_modify"].is_null());
+        assert_eq!(
+            entries[0]["declared_url_schemes"],
+            serde_json::json!(["tricky", "tricky-beta"])
+        );
+        assert_eq!(
+            entries[0]["declared_file_types"],
+            serde_json::json!([{"extension": "trk", "uti": null, "role": "Editor"}])
+        );
+        assert_eq!(entries[1]["declared_url_schemes"], serde_json::json!([]));
+        assert_eq!(entries[0]["signature"]["status"], "Valid");
+        assert_eq!(entries[0]["signature"]["signer"], "Acme Corp");
+        assert!(entries[1]["signature"].is_null());
+        assert_eq!(entries[0]["name_inferred"], false);
+        assert_eq!(entries[0]["parsed_install_date"], "2024-03-01");
+        assert!(entries[1]["parsed_install_date"].is_null());
+        assert_eq!(entries[0]["install_date_ambiguous"], false);
+        assert_eq!(entries[0]["icon"]["width"], 1);
+        assert!(entries[0]["icon"]["png"].as_str().is_some());
+        assert!(entries[1]["version"].is_null());
+        assert!(entries[1]["icon"].is_null());
+
+        let mut out = Vec::new();
+        export_json(&apps(), &mut out, ExportOptions::default()).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert!(value[0].get("icon").is_none());
+
+        // A compressed icon is written as it is stored
+        let compressed = AppInfo {
+            compressed_icon: apps()[0].icon.as_ref().map(|icon| icon.compress().unwrap()),
+            ..apps()[1].clone()
+        };
+        let mut out = Vec::new();
+        let opts = ExportOptions {
+            include_icons: true,
+            ..Default::default()
+        };
+        export_json(std::slice::from_ref(&compressed), &mut out, opts).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(value[0]["icon"]["width"], 1);
+        assert_eq!(
+            value[0]["icon"]["png"],
+            STANDARD.encode(compressed.compressed_icon.unwrap().png)
+        );
+
+        let mut out = Vec::new();
+        export_json(&[], &mut out, ExportOptions::default()).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&out).unwrap(),
+            serde_json::json!([])
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_export_json_round_trip() {
+        // Without icons the output reads back as `AppInfo`
+        let mut out = Vec::new();
+        export_json(&apps(), &mut out, ExportOptions::default()).unwrap();
+        let decoded: Vec<AppInfo> = serde_json::from_slice(&out).expect("Failed to deserialize");
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[0].name, "Tricky, \"Quoted\"\nApp");
+        assert_eq!(decoded[0].path, PathBuf::from("C:\\Program Files\\Tricky"));
+        assert_eq!(decoded[0].install_size, Some(4096));
+        assert_eq!(decoded[0].category, Some(AppCategory::Utilities));
+        assert_eq!(decoded[0].kind, Some(AppKind::CommandLineTool));
+        assert_eq!(decoded[0].install_scope, Some(InstallScope::User));
+        assert!(decoded[0].icon.is_none());
+        assert_eq!(decoded[1].version, None);
+    }
+
+    #[test]
+    fn test_export_csv() {
+        let mut out = Vec::new();
+        export_csv(&apps(), &mut out, ExportOptions::default()).expect("Failed to export");
+
+        let mut reader = csv::Reader::from_reader(out.as_slice());
+        let headers: Vec<String> = reader.headers().unwrap().iter().map(String::from).collect();
+        assert_eq!(
+            headers,
+            [
+                "name",
+                "version",
+                "publisher",
+                "identifier",
+                "path",
+                "install_date"
+            ]
+        );
+
+        let records: Vec<csv::StringRecord> = reader.records().map(|r| r.unwrap()).collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(&records[0][0], "Tricky, \"Quoted\"\nApp");
+        assert_eq!(&records[0][2], "Acme\tCorp");
+        assert_eq!(&records[0][4], "C:\\Program Files\\Tricky");
+        assert_eq!(&records[1][1], "");
+
+        let mut out = Vec::new();
+        let opts = ExportOptions {
+            columns: vec![
+                ExportColumn::InstallSize,
+                ExportColumn::Name,
+                ExportColumn::Kind,
+            ],
+            ..Default::default()
+        };
+        export_csv(&apps(), &mut out, opts).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.starts_with(
+            "install_size,name,kind\r\n4096,\"Tricky, \"\"Quoted\"\"\nApp\",CommandLineTool\r\n,Plain,\r\n"
+        ));
+    }
+}
@@ -0,0 +1,241 @@
+use crate::{error::AppInfoError, Result};
+
+/// A shell-style wildcard pattern, matched case-insensitively against whole strings.
+///
+/// `*` matches any run of characters, `?` exactly one, and `[...]` one character from a
+/// set such as `[abc]` or `[a-z]`, with `[!...]` or `[^...]` for its complement. A `]`
+/// right after the opening bracket is part of the set, and `\` makes the next character
+/// literal.
+#[derive(Debug, Clone)]
+pub(crate) struct Glob {
+    tokens: Vec<Token>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Literal(char),
+    AnyChar,
+    AnyRun,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl Glob {
+    pub(crate) fn new(pattern: &str) -> Result<Self> {
+        let invalid = |reason: &str| AppInfoError::InvalidPattern {
+            pattern: pattern.to_string(),
+            reason: reason.to_string(),
+        };
+
+        // Fold up front so pattern and text are folded the same way, even where folding
+        // changes the number of characters
+        let folded = fold_case(pattern);
+        let mut chars = folded.chars().peekable();
+        let mut tokens = Vec::new();
+
+        while let Some(c) = chars.next() {
+            let token = match c {
+                '*' => {
+                    // Consecutive stars match the same as one
+                    if tokens.last() == Some(&Token::AnyRun) {
+                        continue;
+                    }
+                    Token::AnyRun
+                }
+                '?' => Token::AnyChar,
+                '\\' => Token::Literal(
+                    chars
+                        .next()
+                        .ok_or_else(|| invalid("trailing escape character"))?,
+                ),
+                '[' => {
+                    let negated = chars.next_if(|c| *c == '!' || *c == '^').is_some();
+                    let mut ranges = Vec::new();
+                    let mut first = true;
+                    loop {
+                        let start = match chars.next() {
+                            None => return Err(invalid("unterminated character class")),
+                            Some(']') if !first => break,
+                            Some('\\') => chars
+                                .next()
+                                .ok_or_else(|| invalid("unterminated character class"))?,
+                            Some(c) => c,
+                        };
+                        first = false;
+
+                        let is_range = chars.peek() == Some(&'-')
+                            && chars.clone().nth(1).is_some_and(|next| next != ']');
+                        if !is_range {
+                            ranges.push((start, start));
+                            continue;
+                        }
+                        chars.next();
+                        let end = match chars.next() {
+                            Some('\\') => chars
+                                .next()
+                                .ok_or_else(|| invalid("unterminated character class"))?,
+                            Some(c) => c,
+                            None => return Err(invalid("unterminated character class")),
+                        };
+                        if end < start {
+                            return Err(invalid("character range is out of order"));
+                        }
+                        ranges.push((start, end));
+                    }
+                    Token::Class { negated, ranges }
+                }
+                c => Token::Literal(c),
+            };
+            tokens.push(token);
+        }
+
+        Ok(Glob { tokens })
+    }
+
+    /// Whether the pattern matches all of `text`.
+    pub(crate) fn matches(&self, text: &str) -> bool {
+        let text: Vec<char> = fold_case(text).chars().collect();
+        let (mut t, mut p) = (0, 0);
+        // Where to resume after the most recent `*` when the rest fails to match
+        let mut backtrack: Option<(usize, usize)> = None;
+
+        while t < text.len() {
+            match self.tokens.get(p) {
+                Some(Token::AnyRun) => {
+                    backtrack = Some((p, t));
+                    p += 1;
+                    continue;
+                }
+                Some(token) if token_matches(token, text[t]) => {
+                    t += 1;
+                    p += 1;
+                    continue;
+                }
+                _ => {}
+            }
+            match backtrack {
+                Some((star, start)) => {
+                    p = star + 1;
+                    t = start + 1;
+                    backtrack = Some((star, start + 1));
+                }
+                None => return false,
+            }
+        }
+
+        self.tokens[p..].iter().all(|token| *token == Token::AnyRun)
+    }
+}
+
+/// Lowercases `text`, and applies the full case foldings that lowercasing leaves out, so
+/// that `Straße` and `STRASSE` compare equal.
+pub(crate) fn fold_case(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    for c in text.to_lowercase().chars() {
+        match c {
+            'ß' => folded.push_str("ss"),
+            'ς' => folded.push('σ'),
+            'ſ' => folded.push('s'),
+            c => folded.push(c),
+        }
+    }
+    folded
+}
+
+fn token_matches(token: &Token, c: char) -> bool {
+    match token {
+        Token::Literal(literal) => *literal == c,
+        Token::AnyChar => true,
+        Token::AnyRun => false,
+        Token::Class { negated, ranges } => {
+            ranges
+                .iter()
+                .any(|(start, end)| (*start..=*end).contains(&c))
+                != *negated
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NAMES: &[&str] = &[
+        "Microsoft Edge",
+        "Microsoft Visual C++ 2015-2022 Redistributable (x64)",
+        "Adobe Acrobat",
+        "Adobe Reader",
+        "Adobe Photoshop 2024",
+        "ÉDITEUR Straße",
+        "[Beta] Tool",
+    ];
+
+    fn matching(pattern: &str) -> Vec<&'static str> {
+        let glob = Glob::new(pattern).expect("Invalid pattern");
+        NAMES
+            .iter()
+            .copied()
+            .filter(|name| glob.matches(name))
+            .collect()
+    }
+
+    #[test]
+    fn test_glob_wildcards() {
+        assert_eq!(
+            matching("Microsoft*"),
+            [
+                "Microsoft Edge",
+                "Microsoft Visual C++ 2015-2022 Redistributable (x64)"
+            ]
+        );
+        assert_eq!(
+            matching("*visual c++*"),
+            ["Microsoft Visual C++ 2015-2022 Redistributable (x64)"]
+        );
+        assert_eq!(matching("Adobe ??????"), ["Adobe Reader"]);
+        assert_eq!(matching("Adobe *20[0-9][0-9]"), ["Adobe Photoshop 2024"]);
+        assert_eq!(
+            matching("adobe [!r]*"),
+            ["Adobe Acrobat", "Adobe Photoshop 2024"]
+        );
+        assert!(matching("Edge").is_empty());
+        assert_eq!(matching("**").len(), NAMES.len());
+        assert!(Glob::new("").unwrap().matches(""));
+        assert!(!Glob::new("").unwrap().matches("a"));
+    }
+
+    #[test]
+    fn test_glob_case_folding() {
+        assert_eq!(matching("éditeur STRASSE"), ["ÉDITEUR Straße"]);
+        assert_eq!(matching("éditeur straße"), ["ÉDITEUR Straße"]);
+        assert_eq!(matching("*stra?e"), Vec::<&str>::new());
+        assert_eq!(matching("[é]diteur*"), ["ÉDITEUR Straße"]);
+    }
+
+    #[test]
+    fn test_glob_escapes_and_classes() {
+        assert_eq!(matching("\\[Beta]*"), ["[Beta] Tool"]);
+        assert_eq!(matching("[[]beta[]] tool"), ["[Beta] Tool"]);
+        assert!(Glob::new("[a-]").unwrap().matches("-"));
+        assert!(Glob::new("a\\*").unwrap().matches("a*"));
+        assert!(!Glob::new("a\\*").unwrap().matches("ab"));
+    }
+
+    #[test]
+    fn test_glob_invalid() {
+        for pattern in ["Adobe [", "[abc", "[z-a]", "trailing\\", "[a-\\"] {
+            match Glob::new(pattern) {
+                Err(AppInfoError::InvalidPattern {
+                    pattern: reported,
+                    reason,
+                }) => {
+                    assert_eq!(reported, pattern);
+                    assert!(!reason.is_empty());
+                }
+                other => panic!("{:?} should be invalid, got {:?}", pattern, other),
+            }
+        }
+    }
+}
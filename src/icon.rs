@@ -0,0 +1,1027 @@
+use crate::{error::AppInfoError, CompressedIcon, FitMode, Icon, IconSource, Result};
+use base64::{engine::general_purpose::STANDARD, write::EncoderStringWriter};
+use std::io::Write;
+
+/// Prefix of a PNG data URI.
+const DATA_URI_PREFIX: &str = "data:image/png;base64,";
+
+/// Width and height of the grayscale thumbnail used by [`Icon::dhash`].
+const DHASH_WIDTH: usize = 9;
+const DHASH_HEIGHT: usize = 8;
+
+/// Maximum number of pixels sampled by [`Icon::dominant_colors`].
+const MAX_COLOR_SAMPLES: usize = 4096;
+
+/// Number of k-means refinement passes run by [`Icon::dominant_colors`].
+const KMEANS_ITERATIONS: usize = 10;
+
+/// Pixels at or above this alpha are considered opaque when clustering colors.
+const OPAQUE_ALPHA: u8 = 128;
+
+/// 64-bit FNV-1a parameters.
+pub(crate) const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Computes the 64-bit FNV-1a hash of `bytes`, continuing from `state`.
+///
+/// Unlike `std`'s `DefaultHasher`, the output is fixed and safe to persist. Content hashes
+/// and [`StableId`](crate::StableId) hashes are made with it.
+pub(crate) fn fnv1a_64(state: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(state, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+impl Icon {
+    /// Encodes the icon as a PNG image.
+    pub fn to_png(&self) -> Result<Vec<u8>> {
+        let mut png = Vec::new();
+        self.write_png(&mut png)?;
+        Ok(png)
+    }
+
+    /// Encodes the icon as PNG and returns it as a `data:image/png;base64,` URI,
+    /// suitable for use as the `src` of an `<img>` element.
+    ///
+    /// The payload is not line-wrapped.
+    pub fn to_data_uri(&self) -> Result<String> {
+        self.encode_base64_png(DATA_URI_PREFIX.to_string())
+    }
+
+    /// Encodes the icon as PNG and returns the base64 payload without the data URI prefix.
+    pub fn to_base64_png(&self) -> Result<String> {
+        self.encode_base64_png(String::new())
+    }
+
+    /// Encodes the icon as PNG for keeping in memory, see [`CompressedIcon`].
+    pub fn compress(&self) -> Result<CompressedIcon> {
+        Ok(CompressedIcon {
+            width: self.width,
+            height: self.height,
+            png: self.to_png()?,
+        })
+    }
+
+    /// Returns a copy of the pixel data in BGRA channel order with straight alpha.
+    pub fn to_bgra(&self) -> Vec<u8> {
+        let mut pixels = self.pixels.clone();
+        swap_red_blue(&mut pixels);
+        pixels
+    }
+
+    /// Multiplies the color channels by alpha in place.
+    ///
+    /// Icons returned by this crate use straight alpha; call this before handing the buffer
+    /// to APIs that expect premultiplied data (Direct2D, wgpu, Core Graphics). Note that the
+    /// conversion is lossy for nearly transparent pixels, so a subsequent
+    /// [`Icon::unpremultiply_alpha`] only restores colors within ±1 where alpha is high.
+    pub fn premultiply_alpha(&mut self) {
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            let alpha = pixel[3] as u32;
+            for channel in &mut pixel[..3] {
+                *channel = ((*channel as u32 * alpha + 127) / 255) as u8;
+            }
+        }
+    }
+
+    /// Divides the color channels by alpha in place, converting premultiplied pixel data
+    /// back to straight alpha. Fully transparent pixels become transparent black.
+    pub fn unpremultiply_alpha(&mut self) {
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            let alpha = pixel[3] as u32;
+            for channel in &mut pixel[..3] {
+                *channel = match alpha {
+                    0 => 0,
+                    255 => *channel,
+                    _ => ((*channel as u32 * 255 + alpha / 2) / alpha).min(255) as u8,
+                };
+            }
+        }
+    }
+
+    /// Returns a copy of the icon resampled to `width` x `height` pixels.
+    ///
+    /// `fit` decides how the aspect ratio is handled. Colors are interpolated with
+    /// premultiplied alpha so transparent pixels do not bleed dark fringes into the edges.
+    pub fn resized(&self, width: u32, height: u32, fit: FitMode) -> Icon {
+        let mut pixels = vec![0u8; width as usize * height as usize * 4];
+        let (src_width, src_height) = (self.width as usize, self.height as usize);
+        if src_width == 0 || src_height == 0 || self.pixels.len() < src_width * src_height * 4 {
+            return Icon {
+                width,
+                height,
+                pixels,
+            };
+        }
+
+        let (x, y, fit_width, fit_height) = fit_rect(self.width, self.height, width, height, fit);
+        let columns = axis_weights(x, fit_width, width, self.width);
+        let rows = axis_weights(y, fit_height, height, self.height);
+
+        for (dst_y, row_weights) in rows.iter().enumerate() {
+            for (dst_x, column_weights) in columns.iter().enumerate() {
+                let mut sum = [0f64; 4];
+                for &(src_y, weight_y) in row_weights {
+                    for &(src_x, weight_x) in column_weights {
+                        let offset = (src_y * src_width + src_x) * 4;
+                        let weight = weight_x * weight_y;
+                        let alpha = self.pixels[offset + 3] as f64;
+                        for (total, &channel) in sum[..3].iter_mut().zip(&self.pixels[offset..]) {
+                            *total += channel as f64 * alpha * weight;
+                        }
+                        sum[3] += alpha * weight;
+                    }
+                }
+
+                let offset = (dst_y * width as usize + dst_x) * 4;
+                if sum[3] > 0.0 {
+                    for (channel, total) in pixels[offset..offset + 3].iter_mut().zip(&sum) {
+                        *channel = (total / sum[3]).round().clamp(0.0, 255.0) as u8;
+                    }
+                    pixels[offset + 3] = sum[3].round().clamp(0.0, 255.0) as u8;
+                }
+            }
+        }
+
+        Icon {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Returns a stable hash of the icon's dimensions and pixel data.
+    ///
+    /// The hash uses 64-bit FNV-1a, so it is identical across runs, platforms and Rust
+    /// versions and can be stored in a cache. It only detects exact matches: any change
+    /// to a single pixel, or rendering the same icon at another size, changes the hash.
+    /// Use [`Icon::dhash`] to compare icons that may have been rescaled.
+    pub fn content_hash(&self) -> u64 {
+        let hash = fnv1a_64(FNV_OFFSET_BASIS, &self.width.to_le_bytes());
+        let hash = fnv1a_64(hash, &self.height.to_le_bytes());
+        fnv1a_64(hash, &self.pixels)
+    }
+
+    /// Returns a perceptual difference hash (dHash) of the icon.
+    ///
+    /// The icon is downscaled to a 9x8 grayscale thumbnail (transparent pixels count as
+    /// black) and each bit records whether a pixel is brighter than its right-hand
+    /// neighbour. Because it only captures the coarse structure, the hash tolerates
+    /// rescaling and minor re-encoding; compare two hashes with [`Icon::dhash_distance`].
+    pub fn dhash(&self) -> u64 {
+        let thumbnail = self.grayscale_thumbnail();
+
+        let mut hash = 0u64;
+        for row in thumbnail.chunks_exact(DHASH_WIDTH) {
+            for pair in row.windows(2) {
+                hash = (hash << 1) | (pair[0] > pair[1]) as u64;
+            }
+        }
+        hash
+    }
+
+    /// Returns the number of differing bits between two [`Icon::dhash`] values.
+    ///
+    /// 0 means the icons look the same; values up to about 10 usually indicate the same
+    /// image at a different size, while unrelated icons land around 32.
+    pub fn dhash_distance(a: u64, b: u64) -> u32 {
+        (a ^ b).count_ones()
+    }
+
+    /// Returns the average color of the icon as RGBA.
+    ///
+    /// Each pixel is weighted by its alpha, so transparent padding around the artwork
+    /// does not darken the result. A fully transparent icon yields transparent black.
+    pub fn average_color(&self) -> [u8; 4] {
+        let mut sums = [0u64; 4];
+        let mut total_alpha = 0u64;
+        for pixel in self.pixels.chunks_exact(4) {
+            let alpha = pixel[3] as u64;
+            for (sum, &channel) in sums.iter_mut().zip(pixel) {
+                *sum += channel as u64 * alpha;
+            }
+            total_alpha += alpha;
+        }
+
+        if total_alpha == 0 {
+            return [0; 4];
+        }
+        sums.map(|sum| ((sum + total_alpha / 2) / total_alpha) as u8)
+    }
+
+    /// Returns up to `k` dominant colors of the icon as RGBA, most common first.
+    ///
+    /// The colors are found by k-means clustering over a subsample of the opaque pixels,
+    /// seeded deterministically so the same icon always produces the same palette. Fewer
+    /// than `k` colors are returned when the icon does not contain that many distinct
+    /// colors, and a fully transparent icon yields a single transparent black entry.
+    pub fn dominant_colors(&self, k: usize) -> Vec<[u8; 4]> {
+        if k == 0 {
+            return Vec::new();
+        }
+
+        let samples = self.color_samples();
+        if samples.is_empty() {
+            return vec![[0; 4]];
+        }
+
+        let mut centroids = initial_centroids(&samples, k);
+        let mut assignments = vec![0; samples.len()];
+        for _ in 0..KMEANS_ITERATIONS {
+            for (assignment, sample) in assignments.iter_mut().zip(&samples) {
+                *assignment = nearest_centroid(&centroids, sample);
+            }
+
+            let mut sums = vec![([0f64; 4], 0usize); centroids.len()];
+            for (&assignment, sample) in assignments.iter().zip(&samples) {
+                let (sum, count) = &mut sums[assignment];
+                for (total, channel) in sum.iter_mut().zip(sample) {
+                    *total += channel;
+                }
+                *count += 1;
+            }
+            for (centroid, (sum, count)) in centroids.iter_mut().zip(&sums) {
+                if *count > 0 {
+                    *centroid = sum.map(|total| total / *count as f64);
+                }
+            }
+        }
+
+        let mut counts = vec![0usize; centroids.len()];
+        for (assignment, sample) in assignments.iter_mut().zip(&samples) {
+            *assignment = nearest_centroid(&centroids, sample);
+            counts[*assignment] += 1;
+        }
+
+        let mut clusters: Vec<_> = centroids
+            .into_iter()
+            .zip(counts)
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        // Stable sort keeps ties in seeding order, which is itself deterministic
+        clusters.sort_by_key(|cluster| std::cmp::Reverse(cluster.1));
+        clusters
+            .into_iter()
+            .map(|(centroid, _)| centroid.map(|channel| channel.round().clamp(0.0, 255.0) as u8))
+            .collect()
+    }
+
+    /// Collects an evenly strided subsample of the opaque pixels, falling back to any
+    /// visible pixel for icons that are entirely translucent.
+    fn color_samples(&self) -> Vec<[f64; 4]> {
+        let pixels = || self.pixels.chunks_exact(4);
+        let threshold = if pixels().any(|pixel| pixel[3] >= OPAQUE_ALPHA) {
+            OPAQUE_ALPHA
+        } else {
+            1
+        };
+
+        let candidates = pixels().filter(|pixel| pixel[3] >= threshold).count();
+        let stride = candidates.div_ceil(MAX_COLOR_SAMPLES).max(1);
+        pixels()
+            .filter(|pixel| pixel[3] >= threshold)
+            .step_by(stride)
+            .map(|pixel| [0, 1, 2, 3].map(|i| pixel[i] as f64))
+            .collect()
+    }
+
+    /// Box-filters the icon down to a [`DHASH_WIDTH`] x [`DHASH_HEIGHT`] luminance grid.
+    fn grayscale_thumbnail(&self) -> [f32; DHASH_WIDTH * DHASH_HEIGHT] {
+        let mut thumbnail = [0.0; DHASH_WIDTH * DHASH_HEIGHT];
+        let (width, height) = (self.width as usize, self.height as usize);
+        if width == 0 || height == 0 || self.pixels.len() < width * height * 4 {
+            return thumbnail;
+        }
+
+        for (cell_y, row) in thumbnail.chunks_exact_mut(DHASH_WIDTH).enumerate() {
+            let (y0, y1) = cell_bounds(cell_y, DHASH_HEIGHT, height);
+            for (cell_x, cell) in row.iter_mut().enumerate() {
+                let (x0, x1) = cell_bounds(cell_x, DHASH_WIDTH, width);
+
+                let mut sum = 0.0;
+                for y in y0..y1 {
+                    for x in x0..x1 {
+                        let offset = (y * width + x) * 4;
+                        let [r, g, b, a] = [0, 1, 2, 3].map(|i| self.pixels[offset + i] as f32);
+                        sum += (0.299 * r + 0.587 * g + 0.114 * b) * a / 255.0;
+                    }
+                }
+                *cell = sum / ((y1 - y0) * (x1 - x0)) as f32;
+            }
+        }
+        thumbnail
+    }
+
+    /// Streams the PNG encoding straight into the base64 encoder, appending to `prefix`.
+    fn encode_base64_png(&self, prefix: String) -> Result<String> {
+        let mut writer = EncoderStringWriter::from_consumer(prefix, &STANDARD);
+        self.write_png(&mut writer)?;
+        Ok(writer.into_inner())
+    }
+
+    /// Writes the icon as a PNG image to the given writer.
+    fn write_png<W: Write>(&self, writer: W) -> Result<()> {
+        let expected_len = self.width as usize * self.height as usize * 4;
+        if self.pixels.len() != expected_len {
+            return Err(AppInfoError::IconEncodeError(format!(
+                "expected {} bytes of RGBA data for a {}x{} icon, found {}",
+                expected_len,
+                self.width,
+                self.height,
+                self.pixels.len()
+            )));
+        }
+
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        // Picking a filter per row costs little and shrinks typical icons by a quarter
+        encoder.set_adaptive_filter(png::AdaptiveFilterType::Adaptive);
+
+        let mut png_writer = encoder
+            .write_header()
+            .map_err(|e| AppInfoError::IconEncodeError(e.to_string()))?;
+        png_writer
+            .write_image_data(&self.pixels)
+            .map_err(|e| AppInfoError::IconEncodeError(e.to_string()))?;
+        png_writer
+            .finish()
+            .map_err(|e| AppInfoError::IconEncodeError(e.to_string()))
+    }
+}
+
+impl CompressedIcon {
+    /// Decodes the PNG back into straight-alpha RGBA pixels.
+    ///
+    /// Grayscale, RGB and palette images are converted to RGBA, and 16-bit channels
+    /// are reduced to 8 bits. Returns [`AppInfoError::IconConversionError`] when the data
+    /// is not a PNG or its size differs from `width` and `height`.
+    pub fn decode(&self) -> Result<Icon> {
+        let invalid = |err: png::DecodingError| AppInfoError::IconConversionError(err.to_string());
+
+        let mut decoder = png::Decoder::new(self.png.as_slice());
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let mut reader = decoder.read_info().map_err(invalid)?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer).map_err(invalid)?;
+        buffer.truncate(info.buffer_size());
+
+        if (info.width, info.height) != (self.width, self.height) {
+            return Err(AppInfoError::IconConversionError(format!(
+                "expected a {}x{} image, found {}x{}",
+                self.width, self.height, info.width, info.height
+            )));
+        }
+
+        let pixels = match info.color_type {
+            png::ColorType::Rgba => buffer,
+            png::ColorType::Rgb => buffer
+                .chunks_exact(3)
+                .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+                .collect(),
+            png::ColorType::GrayscaleAlpha => buffer
+                .chunks_exact(2)
+                .flat_map(|ga| [ga[0], ga[0], ga[0], ga[1]])
+                .collect(),
+            png::ColorType::Grayscale => buffer.iter().flat_map(|&g| [g, g, g, 255]).collect(),
+            png::ColorType::Indexed => {
+                return Err(AppInfoError::IconConversionError(
+                    "palette was not expanded".to_string(),
+                ))
+            }
+        };
+        Ok(Icon {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    }
+}
+
+impl IconSource {
+    /// Reads the icon at `size` x `size` pixels.
+    ///
+    /// [`IconSource::Resource`] only reads on Windows and [`IconSource::Image`] on macOS
+    /// and Windows; elsewhere they return [`AppInfoError::UnsupportedPlatform`].
+    pub fn load(&self, size: u16) -> Result<Icon> {
+        if size == 0 {
+            return Err(AppInfoError::FileIconError(
+                crate::error::FileIconError::NullIconSize,
+            ));
+        }
+        match self {
+            IconSource::File(path) => crate::get_file_icon(path, size),
+            IconSource::Resource { path, index } => {
+                #[cfg(target_os = "windows")]
+                return crate::window::icon_at_index(path, *index, size);
+
+                #[cfg(not(target_os = "windows"))]
+                {
+                    let _ = (path, index);
+                    Err(AppInfoError::UnsupportedPlatform)
+                }
+            }
+            IconSource::Image(path) => {
+                #[cfg(target_os = "macos")]
+                return crate::macos::load_image_icon(path, size);
+
+                #[cfg(target_os = "windows")]
+                return crate::window::load_image_icon(path, size);
+
+                #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+                {
+                    let _ = path;
+                    Err(AppInfoError::UnsupportedPlatform)
+                }
+            }
+        }
+    }
+}
+
+/// The error the icon functions return when the `icons` feature is disabled.
+#[cfg(all(
+    any(target_os = "macos", target_os = "windows"),
+    not(feature = "icons")
+))]
+pub(crate) fn icons_disabled() -> AppInfoError {
+    AppInfoError::FileIconError(crate::error::FileIconError::FeatureDisabled)
+}
+
+/// The pixel size of each `.icns` element type that holds an image. Masks and metadata
+/// such as `TOC ` and `icnV` are left out.
+#[cfg(any(all(target_os = "macos", feature = "icons"), test))]
+const ICNS_IMAGE_TYPES: &[(&[u8; 4], u32)] = &[
+    (b"icm#", 16),
+    (b"icm4", 16),
+    (b"icm8", 16),
+    (b"ics#", 16),
+    (b"ics4", 16),
+    (b"ics8", 16),
+    (b"is32", 16),
+    (b"icp4", 16),
+    (b"ic04", 16),
+    (b"ICON", 32),
+    (b"ICN#", 32),
+    (b"icl4", 32),
+    (b"icl8", 32),
+    (b"il32", 32),
+    (b"icp5", 32),
+    (b"ic05", 32),
+    (b"ic11", 32),
+    (b"ich#", 48),
+    (b"ich4", 48),
+    (b"ich8", 48),
+    (b"ih32", 48),
+    (b"icp6", 64),
+    (b"ic12", 64),
+    (b"it32", 128),
+    (b"ic07", 128),
+    (b"ic08", 256),
+    (b"ic13", 256),
+    (b"ic09", 512),
+    (b"ic14", 512),
+    (b"ic10", 1024),
+];
+
+/// Lists the pixel sizes of the images in an `.icns` file, in file order. Returns `None`
+/// if the data is not an icon family.
+#[cfg(any(all(target_os = "macos", feature = "icons"), test))]
+pub(crate) fn icns_sizes(data: &[u8]) -> Option<Vec<(u32, u32)>> {
+    let read_u32 = |offset: usize| -> Option<usize> {
+        Some(u32::from_be_bytes(data.get(offset..offset + 4)?.try_into().ok()?) as usize)
+    };
+    if data.get(..4)? != b"icns" {
+        return None;
+    }
+    let end = read_u32(4)?.min(data.len());
+
+    // Each element is a four-character type and a length that includes this header
+    let mut sizes = Vec::new();
+    let mut offset = 8;
+    while offset + 8 <= end {
+        let kind = &data[offset..offset + 4];
+        let length = read_u32(offset + 4)?;
+        if length < 8 || length > end - offset {
+            break;
+        }
+        if let Some((_, size)) = ICNS_IMAGE_TYPES.iter().find(|(known, _)| *known == kind) {
+            sizes.push((*size, *size));
+        }
+        offset += length;
+    }
+    Some(sizes)
+}
+
+/// Computes where a `src_width` x `src_height` image lands inside a `dst_width` x
+/// `dst_height` canvas, as `(x, y, width, height)` in whole destination pixels. The
+/// rectangle extends past the canvas for [`FitMode::Cover`].
+pub(crate) fn fit_rect(
+    src_width: u32,
+    src_height: u32,
+    dst_width: u32,
+    dst_height: u32,
+    fit: FitMode,
+) -> (i64, i64, u32, u32) {
+    let scale_x = dst_width as f64 / src_width.max(1) as f64;
+    let scale_y = dst_height as f64 / src_height.max(1) as f64;
+    let scale = match fit {
+        FitMode::Stretch => return (0, 0, dst_width, dst_height),
+        FitMode::Contain => scale_x.min(scale_y),
+        FitMode::Cover => scale_x.max(scale_y),
+    };
+
+    let width = ((src_width as f64 * scale).round() as u32).max(1);
+    let height = ((src_height as f64 * scale).round() as u32).max(1);
+    let x = (dst_width as i64 - width as i64) / 2;
+    let y = (dst_height as i64 - height as i64) / 2;
+    (x, y, width, height)
+}
+
+/// For every destination pixel along one axis, lists the source pixels it covers and the
+/// fraction of the destination pixel each one accounts for. Pixels outside the fitted
+/// span of `len` pixels starting at `start` get no samples and stay transparent.
+fn axis_weights(start: i64, len: u32, dst_len: u32, src_len: u32) -> Vec<Vec<(usize, f64)>> {
+    let scale = src_len as f64 / len as f64;
+    (0..dst_len as i64)
+        .map(|dst| {
+            let offset = dst - start;
+            if offset < 0 || offset >= len as i64 {
+                return Vec::new();
+            }
+
+            let begin = offset as f64 * scale;
+            let end = (offset + 1) as f64 * scale;
+            let first = begin.floor() as usize;
+            let last = (end.ceil() as usize).min(src_len as usize);
+            (first..last)
+                .map(|src| {
+                    let covered = end.min(src as f64 + 1.0) - begin.max(src as f64);
+                    (src, covered / scale)
+                })
+                .filter(|&(_, weight)| weight > 0.0)
+                .collect()
+        })
+        .collect()
+}
+
+/// Swaps the red and blue channels of a 4-bytes-per-pixel buffer in place,
+/// converting between RGBA and BGRA.
+pub(crate) fn swap_red_blue(pixels: &mut [u8]) {
+    for pixel in pixels.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+    }
+}
+
+/// Picks up to `k` starting centroids by maximin seeding: the sample closest to the mean
+/// first, then repeatedly the sample farthest from every centroid chosen so far.
+fn initial_centroids(samples: &[[f64; 4]], k: usize) -> Vec<[f64; 4]> {
+    let mut mean = [0.0; 4];
+    for sample in samples {
+        for (total, channel) in mean.iter_mut().zip(sample) {
+            *total += channel;
+        }
+    }
+    let mean = mean.map(|total| total / samples.len() as f64);
+
+    let first = samples[nearest_centroid(samples, &mean)];
+    let mut centroids = vec![first];
+    let mut distances: Vec<f64> = samples.iter().map(|s| distance(s, &first)).collect();
+
+    while centroids.len() < k {
+        let (farthest, &max_distance) = distances.iter().enumerate().fold(
+            (0, &0.0),
+            |best, item| if item.1 > best.1 { item } else { best },
+        );
+        if max_distance == 0.0 {
+            // Every sample already coincides with a centroid
+            break;
+        }
+
+        let centroid = samples[farthest];
+        for (current, sample) in distances.iter_mut().zip(samples) {
+            *current = current.min(distance(sample, &centroid));
+        }
+        centroids.push(centroid);
+    }
+    centroids
+}
+
+/// Returns the index of the centroid closest to `color`.
+fn nearest_centroid(centroids: &[[f64; 4]], color: &[f64; 4]) -> usize {
+    let mut nearest = 0;
+    let mut nearest_distance = f64::INFINITY;
+    for (index, centroid) in centroids.iter().enumerate() {
+        let current = distance(centroid, color);
+        if current < nearest_distance {
+            nearest = index;
+            nearest_distance = current;
+        }
+    }
+    nearest
+}
+
+/// Squared euclidean distance between two RGBA colors.
+fn distance(a: &[f64; 4], b: &[f64; 4]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()
+}
+
+/// Returns the source pixel range covered by thumbnail cell `index` out of `cells`,
+/// always covering at least one pixel.
+fn cell_bounds(index: usize, cells: usize, len: usize) -> (usize, usize) {
+    let start = (index * len / cells).min(len - 1);
+    let end = ((index + 1) * len / cells).clamp(start + 1, len);
+    (start, end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::Engine;
+
+    const ICNS_FIXTURE: &[u8] = include_bytes!("../tests/fixtures/app.icns");
+
+    fn gradient_icon(width: u32, height: u32) -> Icon {
+        let pixels = (0..width * height)
+            .flat_map(|i| {
+                let (x, y) = (i % width, i / width);
+                [x as u8, y as u8, (x ^ y) as u8, 255]
+            })
+            .collect();
+        Icon {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    fn pattern_icon(size: u32, pattern: impl Fn(f64, f64) -> f64) -> Icon {
+        let pixels = (0..size * size)
+            .flat_map(|i| {
+                let u = (i % size) as f64 / size as f64;
+                let v = (i / size) as f64 / size as f64;
+                let value = (127.5 + 127.5 * pattern(u, v)) as u8;
+                [value, value, value, 255]
+            })
+            .collect();
+        Icon {
+            width: size,
+            height: size,
+            pixels,
+        }
+    }
+
+    fn waves(u: f64, v: f64) -> f64 {
+        (std::f64::consts::TAU * u * 1.5).sin() * (std::f64::consts::TAU * v).cos()
+    }
+
+    fn ripples(u: f64, v: f64) -> f64 {
+        (std::f64::consts::TAU * (u * 2.5 + 0.3)).cos() * (std::f64::consts::PI * v * 3.0).sin()
+    }
+
+    /// A 16x16 icon with a transparent border around a 12x12 area that is
+    /// three quarters red and one quarter blue.
+    fn two_color_icon() -> Icon {
+        let mut pixels = Vec::new();
+        for y in 0..16 {
+            for x in 0..16 {
+                pixels.extend_from_slice(match (x, y) {
+                    (2..=10, 2..=13) => &[255, 0, 0, 255],
+                    (11..=13, 2..=13) => &[0, 0, 255, 255],
+                    _ => &[0, 0, 0, 0],
+                });
+            }
+        }
+        Icon {
+            width: 16,
+            height: 16,
+            pixels,
+        }
+    }
+
+    /// An 8x4 icon made of four 2px wide vertical stripes.
+    fn striped_icon() -> Icon {
+        let stripes: [[u8; 4]; 4] = [
+            [255, 0, 0, 255],
+            [0, 255, 0, 255],
+            [0, 0, 255, 255],
+            [255, 255, 0, 255],
+        ];
+        let pixels = (0..32).flat_map(|i| stripes[(i % 8) / 2]).collect();
+        Icon {
+            width: 8,
+            height: 4,
+            pixels,
+        }
+    }
+
+    fn pixel(icon: &Icon, x: u32, y: u32) -> [u8; 4] {
+        let offset = ((y * icon.width + x) * 4) as usize;
+        icon.pixels[offset..offset + 4].try_into().unwrap()
+    }
+
+    fn decode_png(data: &[u8]) -> (u32, u32, Vec<u8>) {
+        let decoder = png::Decoder::new(data);
+        let mut reader = decoder.read_info().expect("Invalid PNG header");
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer).expect("Invalid PNG data");
+        buffer.truncate(info.buffer_size());
+        (info.width, info.height, buffer)
+    }
+
+    #[test]
+    fn test_to_data_uri_round_trip() {
+        let icon = gradient_icon(48, 32);
+        let uri = icon.to_data_uri().expect("Failed to encode icon");
+
+        let payload = uri
+            .strip_prefix("data:image/png;base64,")
+            .expect("Missing data URI prefix");
+        assert!(!payload.contains(['\r', '\n']));
+
+        let png = STANDARD.decode(payload).expect("Invalid base64 payload");
+        let (width, height, pixels) = decode_png(&png);
+        assert_eq!((width, height), (48, 32));
+        assert_eq!(pixels, icon.pixels);
+
+        assert_eq!(icon.to_base64_png().unwrap(), payload);
+    }
+
+    #[test]
+    fn test_compressed_icon() {
+        let icon = gradient_icon(48, 32);
+        let compressed = icon.compress().expect("Failed to compress icon");
+        assert_eq!((compressed.width, compressed.height), (48, 32));
+        assert_eq!(compressed.png, icon.to_png().unwrap());
+        assert_eq!(compressed.decode().unwrap().pixels, icon.pixels);
+
+        // Other color types come back as RGBA
+        let mut gray = Vec::new();
+        let mut encoder = png::Encoder::new(&mut gray, 2, 1);
+        encoder.set_color(png::ColorType::GrayscaleAlpha);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[10, 255, 200, 128]).unwrap();
+        writer.finish().unwrap();
+        let gray = CompressedIcon {
+            width: 2,
+            height: 1,
+            png: gray,
+        };
+        assert_eq!(
+            gray.decode().unwrap().pixels,
+            [10, 10, 10, 255, 200, 200, 200, 128]
+        );
+
+        let resized = CompressedIcon {
+            width: 4,
+            ..gray.clone()
+        };
+        assert!(matches!(
+            resized.decode(),
+            Err(AppInfoError::IconConversionError(_))
+        ));
+        let garbage = CompressedIcon {
+            png: vec![1, 2, 3],
+            ..gray
+        };
+        assert!(garbage.decode().is_err());
+    }
+
+    #[test]
+    fn test_to_png_rejects_mismatched_buffer() {
+        let icon = Icon {
+            width: 4,
+            height: 4,
+            pixels: vec![0; 10],
+        };
+        assert!(matches!(
+            icon.to_data_uri(),
+            Err(AppInfoError::IconEncodeError(_))
+        ));
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let icon = gradient_icon(16, 16);
+        assert_eq!(icon.content_hash(), icon.clone().content_hash());
+
+        let mut changed = icon.clone();
+        changed.pixels[5] ^= 1;
+        assert_ne!(icon.content_hash(), changed.content_hash());
+
+        // Same bytes with different dimensions must not collide
+        let reshaped = Icon {
+            width: 8,
+            height: 32,
+            pixels: icon.pixels.clone(),
+        };
+        assert_ne!(icon.content_hash(), reshaped.content_hash());
+
+        // The hash must be stable across runs and releases
+        let empty = Icon {
+            width: 0,
+            height: 0,
+            pixels: Vec::new(),
+        };
+        assert_eq!(empty.content_hash(), 0xa8c7_f832_281a_39c5);
+    }
+
+    #[test]
+    fn test_dhash() {
+        let icon = pattern_icon(64, waves);
+        assert_eq!(icon.dhash(), icon.clone().dhash());
+
+        let resized = pattern_icon(32, waves);
+        let resized_distance = Icon::dhash_distance(icon.dhash(), resized.dhash());
+        assert!(resized_distance <= 6, "distance was {}", resized_distance);
+
+        let unrelated = pattern_icon(64, ripples);
+        let unrelated_distance = Icon::dhash_distance(icon.dhash(), unrelated.dhash());
+        assert!(
+            unrelated_distance >= 16,
+            "distance was {}",
+            unrelated_distance
+        );
+
+        // Icons smaller than the thumbnail must not panic
+        let tiny = gradient_icon(3, 2);
+        let _ = tiny.dhash();
+    }
+
+    #[test]
+    fn test_average_color() {
+        let icon = two_color_icon();
+        assert_eq!(icon.average_color(), [191, 0, 64, 255]);
+
+        let transparent = Icon {
+            width: 4,
+            height: 4,
+            pixels: vec![0; 64],
+        };
+        assert_eq!(transparent.average_color(), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_dominant_colors() {
+        let icon = two_color_icon();
+        let colors = icon.dominant_colors(2);
+        assert_eq!(colors, vec![[255, 0, 0, 255], [0, 0, 255, 255]]);
+
+        // Asking for more clusters than there are colors returns only the real ones
+        assert_eq!(icon.dominant_colors(5), colors);
+        assert_eq!(icon.dominant_colors(1).len(), 1);
+        assert!(icon.dominant_colors(0).is_empty());
+
+        // The result must be deterministic
+        let icon = gradient_icon(64, 64);
+        assert_eq!(icon.dominant_colors(4), icon.dominant_colors(4));
+
+        let transparent = Icon {
+            width: 4,
+            height: 4,
+            pixels: vec![0; 64],
+        };
+        assert_eq!(transparent.dominant_colors(3), vec![[0, 0, 0, 0]]);
+    }
+
+    #[test]
+    fn test_to_bgra() {
+        let icon = Icon {
+            width: 2,
+            height: 1,
+            pixels: vec![10, 20, 30, 40, 50, 60, 70, 80],
+        };
+        let bgra = icon.to_bgra();
+        assert_eq!(bgra, vec![30, 20, 10, 40, 70, 60, 50, 80]);
+
+        let mut round_trip = bgra;
+        swap_red_blue(&mut round_trip);
+        assert_eq!(round_trip, icon.pixels);
+    }
+
+    #[test]
+    fn test_premultiply_alpha() {
+        let mut icon = Icon {
+            width: 4,
+            height: 1,
+            pixels: vec![
+                200, 100, 50, 255, // opaque
+                200, 100, 50, 0, // fully transparent
+                200, 100, 50, 128, // half transparent
+                255, 255, 255, 51, // white at 20%
+            ],
+        };
+        icon.premultiply_alpha();
+        assert_eq!(
+            icon.pixels,
+            vec![200, 100, 50, 255, 0, 0, 0, 0, 100, 50, 25, 128, 51, 51, 51, 51]
+        );
+
+        icon.unpremultiply_alpha();
+        assert_eq!(
+            icon.pixels,
+            vec![200, 100, 50, 255, 0, 0, 0, 0, 199, 100, 50, 128, 255, 255, 255, 51]
+        );
+    }
+
+    #[test]
+    fn test_premultiplied_round_trip() {
+        // Every valid premultiplied value must survive unpremultiply -> premultiply
+        // within one step per channel
+        for alpha in 0..=255u8 {
+            for channel in 0..=alpha {
+                let mut icon = Icon {
+                    width: 1,
+                    height: 1,
+                    pixels: vec![channel, channel, channel, alpha],
+                };
+                icon.unpremultiply_alpha();
+                icon.premultiply_alpha();
+                assert!(
+                    icon.pixels[0].abs_diff(channel) <= 1,
+                    "channel {} alpha {} became {}",
+                    channel,
+                    alpha,
+                    icon.pixels[0]
+                );
+                assert_eq!(icon.pixels[3], alpha);
+            }
+        }
+    }
+
+    #[test]
+    fn test_resized_stretch() {
+        let icon = striped_icon().resized(4, 4, FitMode::Stretch);
+        assert_eq!((icon.width, icon.height), (4, 4));
+        assert_eq!(icon.pixels.len(), 4 * 4 * 4);
+        let source = striped_icon();
+        for y in 0..4 {
+            for x in 0..4 {
+                assert_eq!(pixel(&icon, x, y), pixel(&source, x * 2, 0));
+            }
+        }
+    }
+
+    #[test]
+    fn test_resized_contain() {
+        let icon = striped_icon().resized(4, 4, FitMode::Contain);
+        assert_eq!((icon.width, icon.height), (4, 4));
+        let source = striped_icon();
+        for x in 0..4 {
+            // Letterboxed: the source is squeezed into the middle two rows
+            assert_eq!(pixel(&icon, x, 0), [0, 0, 0, 0]);
+            assert_eq!(pixel(&icon, x, 1), pixel(&source, x * 2, 0));
+            assert_eq!(pixel(&icon, x, 2), pixel(&source, x * 2, 0));
+            assert_eq!(pixel(&icon, x, 3), [0, 0, 0, 0]);
+        }
+    }
+
+    #[test]
+    fn test_resized_cover() {
+        let icon = striped_icon().resized(4, 4, FitMode::Cover);
+        assert_eq!((icon.width, icon.height), (4, 4));
+        let source = striped_icon();
+        for y in 0..4 {
+            // Center crop: only the middle two stripes remain, at full resolution
+            for x in 0..4 {
+                assert_eq!(pixel(&icon, x, y), pixel(&source, x + 2, y));
+            }
+        }
+    }
+
+    #[test]
+    fn test_resized_blends_with_premultiplied_alpha() {
+        let icon = Icon {
+            width: 2,
+            height: 1,
+            pixels: vec![255, 255, 255, 255, 0, 0, 0, 0],
+        };
+        // The transparent neighbour must not darken the white pixel
+        let resized = icon.resized(1, 1, FitMode::Stretch);
+        assert_eq!(resized.pixels, vec![255, 255, 255, 128]);
+    }
+
+    #[test]
+    fn test_icns_sizes() {
+        // Masks, the table of contents and the version element are not images
+        assert_eq!(
+            icns_sizes(ICNS_FIXTURE),
+            Some(vec![
+                (16, 16),
+                (32, 32),
+                (128, 128),
+                (256, 256),
+                (256, 256),
+                (1024, 1024)
+            ])
+        );
+
+        // An element running past the end of the data is left out
+        let truncated = icns_sizes(&ICNS_FIXTURE[..ICNS_FIXTURE.len() / 2]).unwrap();
+        assert!(!truncated.is_empty());
+        assert!(truncated.len() < 6);
+
+        assert_eq!(icns_sizes(b"icns\0\0\0\x08"), Some(Vec::new()));
+        assert_eq!(icns_sizes(b"\x89PNG\r\n\x1a\n"), None);
+        assert_eq!(icns_sizes(&[]), None);
+    }
+}
@@ -0,0 +1,430 @@
+use crate::error::AppInfoError;
+use crate::icon::{fnv1a_64, FNV_OFFSET_BASIS};
+use crate::{AppInfo, InstallScope, RegistryView};
+use std::fmt;
+use std::str::FromStr;
+
+/// The platform stable IDs made on this machine start with.
+const PLATFORM: &str = std::env::consts::OS;
+
+/// An identity for an installed application that stays the same from one scan to the
+/// next, as returned by [`AppInfo::stable_id`].
+///
+/// The string form is `<platform>[:user[@<sid>]][:wow64]:<kind>:<value>`, where the
+/// platform is the operating system that made the ID (`macos`, `windows`, `linux`...),
+/// `user` marks per-user installs ([`InstallScope::User`]) and carries the SID of their
+/// [`AppInfo::owner`] when there is one, `wow64` marks entries from the 32-bit registry
+/// view ([`RegistryView::Wow64`]), and the kind is the first of these that applies:
+///
+/// - `bundle`, on macOS: the bundle identifier, as in `macos:bundle:com.apple.Safari`.
+///   Identifiers with a source prefix, such as `steam:440`, are not bundle identifiers
+///   and get the `id` kind.
+/// - `productcode`, on Windows: a Windows Installer product code in upper case, as in
+///   `windows:productcode:{23170F69-40C1-2702-2301-000001000000}`
+/// - `id`: any other [`AppInfo::identifier`] as written, as in `windows:id:Git_is1` or
+///   `windows:id:steam:440`
+/// - `path`: the 64-bit FNV-1a hash of the path as 16 lower-case hex digits, as in
+///   `macos:path:6eed032b9c223bf7` for `/Applications/Safari.app`. The path is hashed
+///   as UTF-8 in lower case, with trailing separators removed.
+/// - `name`: the hash of the trimmed name in lower case, for apps with neither an
+///   identifier nor a path
+///
+/// This is version [`StableId::FORMAT_VERSION`] of the format. A change to the ID any
+/// app gets is a new version.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StableId {
+    /// The operating system the ID was made on
+    pub platform: String,
+    /// Whether the install is for the current user only
+    pub per_user: bool,
+    /// The SID of the user a per-user install belongs to, for apps with an
+    /// [`AppInfo::owner`]. Only written when `per_user` is set.
+    pub owner: Option<String>,
+    /// Whether the entry is from the 32-bit registry view of 64-bit Windows
+    pub wow64: bool,
+    /// What the value is made from
+    pub kind: StableIdKind,
+    /// The identifier, or the hash as 16 lower-case hex digits
+    pub value: String,
+}
+
+/// What a [`StableId`] is made from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StableIdKind {
+    /// A macOS bundle identifier
+    Bundle,
+    /// A Windows Installer product code
+    ProductCode,
+    /// Any other identifier
+    Id,
+    /// A hash of the path
+    Path,
+    /// A hash of the name
+    Name,
+}
+
+impl StableIdKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            StableIdKind::Bundle => "bundle",
+            StableIdKind::ProductCode => "productcode",
+            StableIdKind::Id => "id",
+            StableIdKind::Path => "path",
+            StableIdKind::Name => "name",
+        }
+    }
+}
+
+impl StableId {
+    /// The version of the string format described on [`StableId`].
+    pub const FORMAT_VERSION: u32 = 1;
+
+    /// The stable ID of an app found on this machine.
+    pub fn of(app: &AppInfo) -> StableId {
+        stable_id_on(app, PLATFORM)
+    }
+
+    /// Reads a stable ID back from its string form.
+    pub fn parse(value: &str) -> Result<StableId, AppInfoError> {
+        let invalid = || AppInfoError::InvalidStableId {
+            value: value.to_string(),
+        };
+        let (platform, rest) = value.split_once(':').ok_or_else(invalid)?;
+        let (per_user, owner, rest) = match rest.strip_prefix("user") {
+            Some(rest) if rest.starts_with(':') => (true, None, &rest[1..]),
+            Some(rest) if rest.starts_with('@') => {
+                let (owner, rest) = rest[1..].split_once(':').ok_or_else(invalid)?;
+                if owner.is_empty() {
+                    return Err(invalid());
+                }
+                (true, Some(owner.to_string()), rest)
+            }
+            _ => (false, None, rest),
+        };
+        let (wow64, rest) = match rest.strip_prefix("wow64:") {
+            Some(rest) => (true, rest),
+            None => (false, rest),
+        };
+        let (kind, id) = rest.split_once(':').ok_or_else(invalid)?;
+        let kind = match kind {
+            "bundle" => StableIdKind::Bundle,
+            "productcode" => StableIdKind::ProductCode,
+            "id" => StableIdKind::Id,
+            "path" => StableIdKind::Path,
+            "name" => StableIdKind::Name,
+            _ => return Err(invalid()),
+        };
+        let is_hash = matches!(kind, StableIdKind::Path | StableIdKind::Name);
+        let valid_hash =
+            id.len() == 16 && id.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'));
+        if platform.is_empty() || id.is_empty() || (is_hash && !valid_hash) {
+            return Err(invalid());
+        }
+        Ok(StableId {
+            platform: platform.to_string(),
+            per_user,
+            owner,
+            wow64,
+            kind,
+            value: id.to_string(),
+        })
+    }
+}
+
+/// The stable ID `app` gets on `platform`.
+fn stable_id_on(app: &AppInfo, platform: &str) -> StableId {
+    let identifier = app
+        .identifier
+        .as_deref()
+        .map(str::trim)
+        .filter(|identifier| !identifier.is_empty());
+    let (kind, value) = match identifier {
+        Some(identifier) if platform == "macos" && !identifier.contains(':') => {
+            (StableIdKind::Bundle, identifier.to_string())
+        }
+        Some(identifier) if platform == "windows" && is_product_code(identifier) => {
+            (StableIdKind::ProductCode, identifier.to_ascii_uppercase())
+        }
+        Some(identifier) => (StableIdKind::Id, identifier.to_string()),
+        None => {
+            let path = app.path.to_string_lossy().to_lowercase();
+            let path = path.trim_end_matches(['/', '\\']);
+            if path.is_empty() {
+                let name = app.name.trim().to_lowercase();
+                (
+                    StableIdKind::Name,
+                    format!("{:016x}", fnv1a_64(FNV_OFFSET_BASIS, name.as_bytes())),
+                )
+            } else {
+                (
+                    StableIdKind::Path,
+                    format!("{:016x}", fnv1a_64(FNV_OFFSET_BASIS, path.as_bytes())),
+                )
+            }
+        }
+    };
+    let per_user = app.install_scope == Some(InstallScope::User);
+    StableId {
+        platform: platform.to_string(),
+        per_user,
+        owner: app
+            .owner
+            .as_ref()
+            .filter(|_| per_user)
+            .map(|owner| owner.sid.clone()),
+        wow64: app.registry_view == Some(RegistryView::Wow64),
+        kind,
+        value,
+    }
+}
+
+/// Whether an identifier is a GUID in braces, as Windows Installer product codes are.
+pub(crate) fn is_product_code(identifier: &str) -> bool {
+    let Some(guid) = identifier
+        .strip_prefix('{')
+        .and_then(|rest| rest.strip_suffix('}'))
+    else {
+        return false;
+    };
+    let groups: Vec<&str> = guid.split('-').collect();
+    groups.iter().map(|group| group.len()).eq([8, 4, 4, 4, 12])
+        && groups
+            .iter()
+            .all(|group| group.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+impl fmt::Display for StableId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.platform)?;
+        if self.per_user {
+            f.write_str(":user")?;
+            if let Some(owner) = &self.owner {
+                write!(f, "@{}", owner)?;
+            }
+        }
+        if self.wow64 {
+            f.write_str(":wow64")?;
+        }
+        write!(f, ":{}:{}", self.kind.as_str(), self.value)
+    }
+}
+
+impl FromStr for StableId {
+    type Err = AppInfoError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        StableId::parse(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UserAccount;
+    use std::path::PathBuf;
+
+    fn app(name: &str, path: &str, identifier: Option<&str>) -> AppInfo {
+        AppInfo {
+            name: name.to_string(),
+            path: PathBuf::from(path),
+            identifier: identifier.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a_64(FNV_OFFSET_BASIS, b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a_64(FNV_OFFSET_BASIS, b"a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(fnv1a_64(FNV_OFFSET_BASIS, b"foobar"), 0x85944171f73967e8);
+    }
+
+    #[test]
+    fn test_stable_id() {
+        let user = |app: AppInfo| AppInfo {
+            install_scope: Some(InstallScope::User),
+            ..app
+        };
+        let cases = [
+            (
+                "macos",
+                app(
+                    "Safari",
+                    "/Applications/Safari.app",
+                    Some("com.apple.Safari"),
+                ),
+                "macos:bundle:com.apple.Safari",
+            ),
+            (
+                "windows",
+                app(
+                    "7-Zip",
+                    "C:\\Program Files\\7-Zip",
+                    Some("{23170f69-40c1-2702-2301-000001000000}"),
+                ),
+                "windows:productcode:{23170F69-40C1-2702-2301-000001000000}",
+            ),
+            (
+                "windows",
+                user(app(
+                    "Code",
+                    "C:\\Users\\me\\Code",
+                    Some("{771FD6B0-FA20-440A-A002-3B3BAC16DC50}_is1"),
+                )),
+                "windows:user:id:{771FD6B0-FA20-440A-A002-3B3BAC16DC50}_is1",
+            ),
+            (
+                "windows",
+                app("Git", "", Some(" Git_is1 ")),
+                "windows:id:Git_is1",
+            ),
+            (
+                "windows",
+                AppInfo {
+                    registry_view: Some(RegistryView::Wow64),
+                    ..app("Git", "", Some("Git_is1"))
+                },
+                "windows:wow64:id:Git_is1",
+            ),
+            (
+                "windows",
+                AppInfo {
+                    registry_view: Some(RegistryView::Native),
+                    ..app("Git", "", Some("Git_is1"))
+                },
+                "windows:id:Git_is1",
+            ),
+            (
+                "linux",
+                app("Team Fortress 2", "", Some("steam:440")),
+                "linux:id:steam:440",
+            ),
+            // Only bundle identifiers are bundles
+            (
+                "macos",
+                app("Team Fortress 2", "", Some("steam:440")),
+                "macos:id:steam:440",
+            ),
+            // Machine-wide installs have no owner to write
+            (
+                "windows",
+                AppInfo {
+                    install_scope: Some(InstallScope::Machine),
+                    owner: Some(UserAccount {
+                        sid: "S-1-5-21-1-2-3-1001".to_string(),
+                        name: None,
+                    }),
+                    ..app("Tool", "", Some("tool"))
+                },
+                "windows:id:tool",
+            ),
+            (
+                "linux",
+                app(
+                    "Product",
+                    "",
+                    Some("{23170F69-40C1-2702-2301-000001000000}"),
+                ),
+                "linux:id:{23170F69-40C1-2702-2301-000001000000}",
+            ),
+            (
+                "macos",
+                app("Tool", "/opt/tool", None),
+                "macos:path:99362c7e2cf2d1c2",
+            ),
+            // Case and trailing separators do not matter
+            (
+                "macos",
+                app("Tool", "/OPT/Tool/", None),
+                "macos:path:99362c7e2cf2d1c2",
+            ),
+            (
+                "windows",
+                user(app("Tool", "C:\\Tools\\tool.exe", Some(""))),
+                "windows:user:path:d7252542aaebb765",
+            ),
+            // Another user's install of the same app gets an ID of its own
+            (
+                "windows",
+                AppInfo {
+                    owner: Some(UserAccount {
+                        sid: "S-1-5-21-1-2-3-1001".to_string(),
+                        name: Some("HOST\\alice".to_string()),
+                    }),
+                    ..user(app("Slack", "", Some("slack")))
+                },
+                "windows:user@S-1-5-21-1-2-3-1001:id:slack",
+            ),
+            (
+                "windows",
+                app(" Runtime ", "", None),
+                "windows:name:754a0a8c81da6c6b",
+            ),
+        ];
+        for (platform, app, expected) in cases {
+            let id = stable_id_on(&app, platform);
+            assert_eq!(id.to_string(), expected, "{}", app.name);
+            assert_eq!(StableId::parse(expected).unwrap(), id);
+        }
+    }
+
+    #[test]
+    fn test_parse_stable_id() {
+        let id = StableId::parse("windows:user:id:steam:440").unwrap();
+        assert!(id.per_user);
+        assert!(!id.wow64);
+        assert_eq!(id.kind, StableIdKind::Id);
+        assert_eq!(id.value, "steam:440");
+        assert_eq!(id.owner, None);
+        let id = StableId::parse("windows:user@S-1-5-21-1-2-3-1001:wow64:id:a").unwrap();
+        assert!(id.per_user && id.wow64);
+        assert_eq!(id.owner.as_deref(), Some("S-1-5-21-1-2-3-1001"));
+        assert_eq!(
+            "macos:bundle:a".parse::<StableId>().unwrap().kind,
+            StableIdKind::Bundle
+        );
+
+        // Display writes what FromStr reads back
+        for id in [
+            "windows:user@S-1-5-21-1-2-3-1001:wow64:id:a",
+            "macos:user:bundle:com.apple.Safari",
+            "windows:wow64:productcode:{23170F69-40C1-2702-2301-000001000000}",
+        ] {
+            assert_eq!(id.parse::<StableId>().unwrap().to_string(), id);
+        }
+        let ownerless = StableId {
+            platform: "windows".to_string(),
+            per_user: false,
+            owner: Some("S-1-5-21-1-2-3-1001".to_string()),
+            wow64: false,
+            kind: StableIdKind::Id,
+            value: "a".to_string(),
+        };
+        assert_eq!(ownerless.to_string(), "windows:id:a");
+        assert_eq!(
+            ownerless.to_string().parse::<StableId>().unwrap(),
+            StableId {
+                owner: None,
+                ..ownerless
+            }
+        );
+
+        for invalid in [
+            "",
+            "macos",
+            "macos:bundle",
+            "macos:bundle:",
+            ":bundle:a",
+            "macos:user:bundle",
+            "windows:user@:id:a",
+            "windows:user@S-1-5-18",
+            "windows:users:id:a",
+            "windows:wow64:user:id:a",
+            "macos:app:a",
+            "macos:path:99362C7E2CF2D1C2",
+            "macos:name:1234",
+        ] {
+            assert!(StableId::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use crate::{error::AppInfoError, Icon, Result};
+
+/// Checks that the pixel buffer holds exactly `width` x `height` RGBA pixels and that the
+/// dimensions fit the `u32` pixel counts the windowing crates compute, which they would
+/// otherwise overflow on.
+fn validate(icon: &Icon) -> Result<()> {
+    let invalid = |reason: String| Err(AppInfoError::IconConversionError(reason));
+    if icon.width == 0 || icon.height == 0 {
+        return invalid(format!("icon is {}x{}", icon.width, icon.height));
+    }
+    let pixel_count = icon.width as u64 * icon.height as u64;
+    if pixel_count > u32::MAX as u64 {
+        return invalid(format!(
+            "icon is too large at {}x{}",
+            icon.width, icon.height
+        ));
+    }
+    if icon.pixels.len() as u64 != pixel_count * 4 {
+        return invalid(format!(
+            "icon is {}x{} but has {} bytes of pixels",
+            icon.width,
+            icon.height,
+            icon.pixels.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Hands the pixel buffer over to the tray icon without copying it.
+#[cfg(feature = "tray-icon")]
+impl TryFrom<Icon> for tray_icon::Icon {
+    type Error = AppInfoError;
+
+    fn try_from(icon: Icon) -> Result<Self> {
+        validate(&icon)?;
+        tray_icon::Icon::from_rgba(icon.pixels, icon.width, icon.height)
+            .map_err(|err| AppInfoError::IconConversionError(err.to_string()))
+    }
+}
+
+/// Hands the pixel buffer over to the window icon without copying it.
+#[cfg(feature = "winit")]
+impl TryFrom<Icon> for winit::window::Icon {
+    type Error = AppInfoError;
+
+    fn try_from(icon: Icon) -> Result<Self> {
+        validate(&icon)?;
+        winit::window::Icon::from_rgba(icon.pixels, icon.width, icon.height)
+            .map_err(|err| AppInfoError::IconConversionError(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn icon(width: u32, height: u32, pixel_bytes: usize) -> Icon {
+        Icon {
+            width,
+            height,
+            pixels: (0..pixel_bytes).map(|i| i as u8).collect(),
+        }
+    }
+
+    fn invalid_icons() -> Vec<Icon> {
+        vec![
+            // Dimensions whose pixel count overflows u32
+            icon(100_000, 100_000, 16),
+            icon(4, 4, 60),
+            icon(0, 0, 0),
+        ]
+    }
+
+    #[test]
+    #[cfg(feature = "tray-icon")]
+    fn test_tray_icon_conversion() {
+        assert!(tray_icon::Icon::try_from(icon(16, 16, 16 * 16 * 4)).is_ok());
+        for invalid in invalid_icons() {
+            assert!(matches!(
+                tray_icon::Icon::try_from(invalid),
+                Err(AppInfoError::IconConversionError(_))
+            ));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "winit")]
+    fn test_winit_icon_conversion() {
+        assert!(winit::window::Icon::try_from(icon(16, 16, 16 * 16 * 4)).is_ok());
+        for invalid in invalid_icons() {
+            assert!(matches!(
+                winit::window::Icon::try_from(invalid),
+                Err(AppInfoError::IconConversionError(_))
+            ));
+        }
+    }
+}
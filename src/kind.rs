@@ -0,0 +1,334 @@
+/// What an installed entry is for, as worked out from what the scanners can see of it.
+///
+/// The classification is conservative: an entry whose signals disagree, or that gives
+/// none, has no kind rather than a guessed one. On Windows it follows the main
+/// executable's PE subsystem, driver install locations and redistributable names; on
+/// macOS the bundle's `CFBundlePackageType`, `LSUIElement`, `LSBackgroundOnly` and
+/// whether it declares an icon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AppKind {
+    /// An application the user works with through its windows, including menu bar apps
+    Application,
+    /// A program run from a terminal
+    CommandLineTool,
+    /// A service, agent or driver that runs without a user interface
+    Background,
+    /// A runtime, framework or redistributable that other programs depend on
+    Runtime,
+}
+
+/// The subsystem a Windows executable declares in its PE header.
+#[cfg(any(target_os = "windows", test))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PeSubsystem {
+    /// Opens windows
+    Gui,
+    /// Runs in a console
+    Console,
+    /// Runs without the Windows API, as drivers do
+    Native,
+    /// EFI, boot and other subsystems
+    Other,
+}
+
+/// What the Windows scanner knows about an entry, for [`windows_kind`].
+#[cfg(any(target_os = "windows", test))]
+#[derive(Debug, Default)]
+pub(crate) struct WindowsSignals<'a> {
+    pub name: &'a str,
+    pub path: &'a str,
+    pub install_location: Option<&'a str>,
+    /// Whether the entry sets `SystemComponent`, which hides it from Add/Remove Programs
+    pub system_component: bool,
+    /// The subsystem of the main executable, when there is one
+    pub subsystem: Option<PeSubsystem>,
+}
+
+/// Words that name a runtime on their own, such as in
+/// `Microsoft Visual C++ 2015-2022 Redistributable (x64)`.
+#[cfg(any(target_os = "windows", test))]
+const RUNTIME_WORDS: &[&str] = &[
+    "redistributable",
+    "redist",
+    "vcredist",
+    "runtime",
+    "runtimes",
+];
+
+/// Words that name a runtime in entries hidden from Add/Remove Programs, which are parts
+/// of something else, such as `Microsoft ASP.NET Core 8.0.1 Shared Framework (x64)`.
+#[cfg(any(target_os = "windows", test))]
+const COMPONENT_WORDS: &[&str] = &["framework", "library", "libraries", "sdk", "host"];
+
+/// Classifies a Windows entry: runtimes by name first, then drivers by location, then
+/// the main executable's subsystem.
+#[cfg(any(target_os = "windows", test))]
+pub(crate) fn windows_kind(signals: &WindowsSignals) -> Option<AppKind> {
+    let name = signals.name.to_lowercase();
+    let words: Vec<&str> = name
+        .split(|c: char| !c.is_alphanumeric() && c != '+')
+        .filter(|word| !word.is_empty())
+        .collect();
+    let has_word = |list: &[&str]| words.iter().any(|word| list.contains(word));
+    if has_word(RUNTIME_WORDS) || name.contains(".net framework") {
+        return Some(AppKind::Runtime);
+    }
+    if signals.system_component && (has_word(COMPONENT_WORDS) || name.contains("targeting pack")) {
+        return Some(AppKind::Runtime);
+    }
+
+    let is_driver_path = |path: &str| {
+        let path = path.to_lowercase();
+        path.ends_with(".sys")
+            || path
+                .split(['\\', '/'])
+                .any(|component| component == "drivers" || component == "driverstore")
+    };
+    if is_driver_path(signals.path) || signals.install_location.is_some_and(is_driver_path) {
+        return Some(AppKind::Background);
+    }
+
+    match signals.subsystem? {
+        PeSubsystem::Gui => Some(AppKind::Application),
+        PeSubsystem::Console => Some(AppKind::CommandLineTool),
+        PeSubsystem::Native => Some(AppKind::Background),
+        PeSubsystem::Other => None,
+    }
+}
+
+/// What a bundle's `Info.plist` says about it, for [`macos_kind`].
+#[cfg(any(target_os = "macos", test))]
+#[derive(Debug, Default)]
+pub(crate) struct BundleSignals<'a> {
+    /// `CFBundlePackageType`, such as `APPL` or `FMWK`
+    pub package_type: Option<&'a str>,
+    /// `LSUIElement`: no Dock icon or menu bar, as for menu bar apps and agents
+    pub ui_element: bool,
+    /// `LSBackgroundOnly`: no user interface at all
+    pub background_only: bool,
+    /// Whether the bundle declares an icon
+    pub has_icon: bool,
+}
+
+/// Classifies a macOS bundle. Agents with an icon are taken for menu bar apps; an
+/// application bundle without an icon is left unclassified, since helpers look the same.
+#[cfg(any(target_os = "macos", test))]
+pub(crate) fn macos_kind(signals: &BundleSignals) -> Option<AppKind> {
+    match signals.package_type.map(str::trim) {
+        Some("FMWK") => return Some(AppKind::Runtime),
+        Some("XPC!") => return Some(AppKind::Background),
+        Some("APPL") | None => {}
+        Some(_) => return None,
+    }
+    if signals.background_only {
+        return Some(AppKind::Background);
+    }
+    match (signals.ui_element, signals.has_icon) {
+        (true, false) => Some(AppKind::Background),
+        (_, true) => Some(AppKind::Application),
+        (false, false) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_windows_kind() {
+        let cases = [
+            (
+                WindowsSignals {
+                    name: "Microsoft Visual C++ 2015-2022 Redistributable (x64) - 14.38.33130",
+                    ..Default::default()
+                },
+                Some(AppKind::Runtime),
+            ),
+            (
+                WindowsSignals {
+                    name: "Microsoft Windows Desktop Runtime - 8.0.1 (x64)",
+                    subsystem: Some(PeSubsystem::Gui),
+                    ..Default::default()
+                },
+                Some(AppKind::Runtime),
+            ),
+            (
+                WindowsSignals {
+                    name: "Microsoft .NET Framework 4.8.1 Targeting Pack",
+                    ..Default::default()
+                },
+                Some(AppKind::Runtime),
+            ),
+            (
+                WindowsSignals {
+                    name: "Microsoft ASP.NET Core 8.0.1 Shared Framework (x64)",
+                    system_component: true,
+                    ..Default::default()
+                },
+                Some(AppKind::Runtime),
+            ),
+            // Only hidden entries are taken for components by these words
+            (
+                WindowsSignals {
+                    name: "Windows Software Development Kit - Windows 10.0.22621.2428",
+                    subsystem: Some(PeSubsystem::Gui),
+                    ..Default::default()
+                },
+                Some(AppKind::Application),
+            ),
+            (
+                WindowsSignals {
+                    name: "Fabrikam Host",
+                    subsystem: Some(PeSubsystem::Console),
+                    ..Default::default()
+                },
+                Some(AppKind::CommandLineTool),
+            ),
+            (
+                WindowsSignals {
+                    name: "Realtek Card Reader",
+                    path: r"C:\Windows\System32\drivers\RtsPer.sys",
+                    ..Default::default()
+                },
+                Some(AppKind::Background),
+            ),
+            (
+                WindowsSignals {
+                    name: "Intel(R) Chipset Device Software",
+                    path: r"C:\Program Files (x86)\Intel\Intel(R) Chipset\SetupChipset.exe",
+                    install_location: Some(r"C:\Windows\System32\DriverStore\FileRepository\"),
+                    subsystem: Some(PeSubsystem::Gui),
+                    ..Default::default()
+                },
+                Some(AppKind::Background),
+            ),
+            (
+                WindowsSignals {
+                    name: "Mozilla Firefox (x64 en-US)",
+                    path: r"C:\Program Files\Mozilla Firefox\firefox.exe",
+                    subsystem: Some(PeSubsystem::Gui),
+                    ..Default::default()
+                },
+                Some(AppKind::Application),
+            ),
+            (
+                WindowsSignals {
+                    name: "Git",
+                    path: r"C:\Program Files\Git\cmd\git.exe",
+                    subsystem: Some(PeSubsystem::Console),
+                    ..Default::default()
+                },
+                Some(AppKind::CommandLineTool),
+            ),
+            (
+                WindowsSignals {
+                    name: "Contoso Filter",
+                    subsystem: Some(PeSubsystem::Native),
+                    ..Default::default()
+                },
+                Some(AppKind::Background),
+            ),
+            (
+                WindowsSignals {
+                    name: "Contoso Boot Manager",
+                    subsystem: Some(PeSubsystem::Other),
+                    ..Default::default()
+                },
+                None,
+            ),
+            (
+                WindowsSignals {
+                    name: "Contoso Suite",
+                    install_location: Some(r"C:\Program Files\Contoso"),
+                    ..Default::default()
+                },
+                None,
+            ),
+        ];
+        for (signals, expected) in cases {
+            assert_eq!(windows_kind(&signals), expected, "{}", signals.name);
+        }
+    }
+
+    #[test]
+    fn test_macos_kind() {
+        let cases = [
+            (
+                BundleSignals {
+                    package_type: Some("APPL"),
+                    has_icon: true,
+                    ..Default::default()
+                },
+                Some(AppKind::Application),
+            ),
+            (
+                BundleSignals {
+                    has_icon: true,
+                    ..Default::default()
+                },
+                Some(AppKind::Application),
+            ),
+            // A menu bar app
+            (
+                BundleSignals {
+                    package_type: Some("APPL"),
+                    ui_element: true,
+                    has_icon: true,
+                    ..Default::default()
+                },
+                Some(AppKind::Application),
+            ),
+            (
+                BundleSignals {
+                    package_type: Some("APPL"),
+                    ui_element: true,
+                    ..Default::default()
+                },
+                Some(AppKind::Background),
+            ),
+            (
+                BundleSignals {
+                    package_type: Some("APPL"),
+                    background_only: true,
+                    has_icon: true,
+                    ..Default::default()
+                },
+                Some(AppKind::Background),
+            ),
+            (
+                BundleSignals {
+                    package_type: Some("XPC!"),
+                    ..Default::default()
+                },
+                Some(AppKind::Background),
+            ),
+            (
+                BundleSignals {
+                    package_type: Some("FMWK"),
+                    has_icon: true,
+                    ..Default::default()
+                },
+                Some(AppKind::Runtime),
+            ),
+            (
+                BundleSignals {
+                    package_type: Some("BNDL"),
+                    has_icon: true,
+                    ..Default::default()
+                },
+                None,
+            ),
+            (
+                BundleSignals {
+                    package_type: Some("APPL"),
+                    ..Default::default()
+                },
+                None,
+            ),
+        ];
+        for (signals, expected) in cases {
+            assert_eq!(macos_kind(&signals), expected, "{:?}", signals);
+        }
+    }
+}
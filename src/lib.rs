@@ -1,40 +1,579 @@
+#[macro_use]
+mod trace;
+
+mod capabilities;
+mod category;
+mod control_panel;
+mod date;
+mod duplicates;
 pub mod error;
+mod export;
+mod glob;
+mod icon;
+mod identity;
+#[cfg(all(
+    any(feature = "tray-icon", feature = "winit"),
+    any(target_os = "macos", target_os = "windows")
+))]
+mod interop;
+mod kind;
 pub mod macos;
+mod mock;
+mod publisher;
+#[cfg(feature = "regex")]
+mod regex_search;
+mod scan;
+#[cfg(feature = "serde")]
+mod serde_support;
+mod steam;
+mod uninstall;
+mod version;
+mod watcher;
 pub mod window;
+#[cfg(any(target_os = "windows", test))]
+mod winget;
+#[cfg(feature = "wmi")]
+mod wmi;
 
 use error::{AppInfoError, Result};
+use std::ffi::OsString;
 use std::path::PathBuf;
+use std::time::SystemTime;
+
+pub use capabilities::{capabilities, Capabilities};
+pub use category::AppCategory;
+pub use control_panel::{ControlPanelSource, OptionalFeatureSource};
+pub use date::{DateOrder, InstallDate};
+pub use duplicates::{find_duplicate_installs, DuplicateGroup, DuplicateReason};
+pub use export::{export_csv, export_json, ExportColumn, ExportOptions};
+pub use identity::{StableId, StableIdKind};
+pub use kind::AppKind;
+pub use mock::MockSource;
+pub use publisher::{group_by_publisher, normalize_publisher};
+#[cfg(feature = "regex")]
+pub use regex_search::{find_apps_matching, MatchFields};
+pub use scan::{
+    default_sources, scan_apps, AppSource, CancellationToken, IconStorage, LocationStatus,
+    ProgressSink, ScanContext, ScanLocation, ScanOptions, ScanPhase, ScanProgress, ScanReport,
+    ScanStats, SourceError, SourceStats, UserProfiles, VisitedLocation,
+};
+pub use steam::SteamSource;
+pub use uninstall::{UninstallCommand, UninstallOptions, UninstallOutcome, UninstallPlan};
+pub use version::compare_versions;
+pub use watcher::{AppEvent, AppWatcher, WatcherOptions};
+#[cfg(feature = "wmi")]
+pub use wmi::WmiSource;
 
 /// Application information
-#[derive(Debug, Clone)]
+///
+/// With the `serde` feature it can be serialized. Paths that are valid UTF-8 are written
+/// as plain strings; other paths use serde's `OsString` representation, which only reads
+/// back on the same kind of platform (Unix or Windows) that wrote it.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AppInfo {
     /// Application name
     pub name: String,
     /// Application version
     pub version: Option<String>,
     /// Application path
+    #[cfg_attr(feature = "serde", serde(with = "serde_support::path"))]
     pub path: PathBuf,
-    /// Application icon (RGBA format)
+    /// Path of the binary that runs when the application starts (inside the bundle on
+    /// macOS), when it can be determined
+    #[cfg_attr(feature = "serde", serde(with = "serde_support::option_path"))]
+    pub executable_path: Option<PathBuf>,
+    /// Application icon (RGBA format). Always `None` without the `icons` feature, and
+    /// when a scan stores icons some other way, see [`ScanOptions::icon_storage`].
     pub icon: Option<Icon>,
+    /// The icon encoded as PNG, in place of [`AppInfo::icon`] for scans with
+    /// [`IconStorage::Png`]. [`CompressedIcon::decode`] turns it back into pixels.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub compressed_icon: Option<CompressedIcon>,
+    /// Where the icon comes from, for loading it later with [`AppInfo::load_icon`]. The
+    /// built-in sources fill it in whether or not they extract the icon.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub icon_source: Option<IconSource>,
     /// Application bundle identifier (macOS) or ProductCode (Windows)
     pub identifier: Option<String>,
     /// Developer/Publisher
     pub publisher: Option<String>,
-    /// Installation date
+    /// Installation date, as the installer wrote it
     pub install_date: Option<String>,
+    /// [`AppInfo::install_date`] read as a date, see [`InstallDate::parse`]. Scans read it
+    /// with [`ScanOptions::date_order`].
+    pub parsed_install_date: Option<InstallDate>,
+    /// Whether [`AppInfo::install_date`] could be read with the day and month either way
+    /// round and no preference decided, which leaves [`AppInfo::parsed_install_date`]
+    /// unset.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub install_date_ambiguous: bool,
+    /// Installed size in bytes, when the installer records it
+    pub install_size: Option<u64>,
+    /// What kind of application it is, when it declares one
+    pub category: Option<AppCategory>,
+    /// Whether it is an application, a command-line tool, a background component or a
+    /// runtime, when that can be told, see [`AppKind`]
+    pub kind: Option<AppKind>,
+    /// The package manager entry for the application. Only set by
+    /// [`attach_winget_packages`].
+    pub package_manager: Option<PackageRef>,
+    /// Whether the application is installed for every user or only the current one. Set
+    /// on Windows from the registry hive the entry was found in.
+    pub install_scope: Option<InstallScope>,
+    /// The registry view a Windows Uninstall entry was read from. `None` for other apps
+    /// and for keys both views share, such as those under `HKEY_CURRENT_USER`. See
+    /// [`AppInfo::is_32bit_on_64bit_os`].
+    pub registry_view: Option<RegistryView>,
+    /// The user a per-user install belongs to. Only set for entries read from every
+    /// user's registry hive, see [`ScanOptions::user_profiles`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub owner: Option<UserAccount>,
+    /// Whether the application may be uninstalled, as its Windows Uninstall entry declares
+    /// with `NoRemove`. `None` when the entry does not say, which Add/Remove Programs
+    /// treats as allowed.
+    pub can_uninstall: Option<bool>,
+    /// Whether the installation may be modified, as declared with `NoModify`. `None` when
+    /// not declared, which means allowed.
+    pub can_modify: Option<bool>,
+    /// Whether the installation may be repaired, as declared with `NoRepair`. `None` when
+    /// not declared, which means allowed.
+    pub can_repair: Option<bool>,
+    /// The URL schemes the application declares it handles, lowercased: the
+    /// `CFBundleURLTypes` of its `Info.plist` on macOS, and the `URLAssociations` of its
+    /// registered capabilities on Windows. These are what the application claims, not what
+    /// the system routes to it; see [`get_default_app_for_url_scheme`] for that. Only read
+    /// when [`ScanOptions::read_declarations`] asks for it.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub declared_url_schemes: Vec<String>,
+    /// The file types the application declares it opens, from `CFBundleDocumentTypes` on
+    /// macOS and the `FileAssociations` of its registered capabilities on Windows. Like
+    /// [`AppInfo::declared_url_schemes`], these are claims rather than the system's
+    /// defaults, and only read when [`ScanOptions::read_declarations`] asks for it.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub declared_file_types: Vec<FileAssociation>,
+    /// The code signature of the main executable. Only checked on Windows, and only when
+    /// [`ScanOptions::check_signatures`] asks for it.
+    pub signature: Option<SignatureInfo>,
+    /// Whether [`AppInfo::name`] was worked out rather than declared, as for Windows
+    /// Uninstall entries without a `DisplayName`. Consumers that only want names the
+    /// installer chose can leave these out.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub name_inferred: bool,
+}
+
+/// Who an installation is for, which decides whether changing it needs elevation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InstallScope {
+    /// Installed for every user of the machine, such as under `HKEY_LOCAL_MACHINE`
+    Machine,
+    /// Installed for the current user only, such as under `HKEY_CURRENT_USER`
+    User,
+}
+
+/// A user account, as named in [`AppInfo::owner`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UserAccount {
+    /// The account's security identifier, such as `S-1-5-21-1004336348-...-1001`
+    pub sid: String,
+    /// The account name as `DOMAIN\user`, when the SID could be resolved
+    pub name: Option<String>,
+}
+
+/// Which view of the Windows registry an entry was read from. On 64-bit Windows, 32-bit
+/// programs get their own copy of `HKEY_LOCAL_MACHINE\SOFTWARE` under `WOW6432Node`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RegistryView {
+    /// The view of the operating system's own architecture
+    Native,
+    /// The view 32-bit programs see on 64-bit Windows
+    Wow64,
+}
+
+/// An application's entry in a package manager.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PackageRef {
+    /// The package manager, such as `"winget"`
+    pub manager: String,
+    /// The package identifier, such as `Mozilla.Firefox`
+    pub id: String,
+    /// A newer version the package manager offers, if any
+    pub available_version: Option<String>,
+}
+
+/// The code signature of a file, as reported by [`get_signature_info`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SignatureInfo {
+    /// Whether the file is signed, and whether the signature holds
+    pub status: SignatureStatus,
+    /// The common name in the signing certificate's subject, such as
+    /// `Mozilla Corporation`. Only known for files with an embedded signature.
+    pub signer: Option<String>,
+}
+
+/// How a file is signed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SignatureStatus {
+    /// Signed, and the signature and its certificate chain verify
+    Valid,
+    /// Signed, but the signature does not verify: the file changed after signing, or the
+    /// certificate is untrusted, expired or revoked
+    Invalid,
+    /// Not signed itself, but listed in a signed system catalog, as most Windows
+    /// components are
+    CatalogSigned,
+    /// Not signed
+    Unsigned,
 }
 
 /// Icon data
+///
+/// With the `serde` feature it can be serialized, with the pixels written as a standard
+/// base64 string rather than an array of numbers.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Icon {
     /// Icon width in pixels
     pub width: u32,
     /// Icon height in pixels
     pub height: u32,
-    /// Pixel data in RGBA format
+    /// Pixel data in RGBA format with straight (non-premultiplied) alpha
+    #[cfg_attr(feature = "serde", serde(with = "serde_support::base64_bytes"))]
     pub pixels: Vec<u8>,
 }
 
+/// An icon encoded as PNG, which takes a fraction of the memory of its pixels.
+///
+/// With the `serde` feature it can be serialized, with the PNG written as a standard
+/// base64 string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CompressedIcon {
+    /// Icon width in pixels
+    pub width: u32,
+    /// Icon height in pixels
+    pub height: u32,
+    /// The PNG file
+    #[cfg_attr(feature = "serde", serde(with = "serde_support::base64_bytes"))]
+    pub png: Vec<u8>,
+}
+
+/// Where an application's icon can be read from, see [`AppInfo::icon_source`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum IconSource {
+    /// The icon the system shows for a file or bundle, as [`get_file_icon`] reads it
+    File(#[cfg_attr(feature = "serde", serde(with = "serde_support::path"))] PathBuf),
+    /// An icon resource in a Windows executable or DLL, numbered as in a `DisplayIcon`
+    /// value: from zero, or negative for a resource ID
+    Resource {
+        #[cfg_attr(feature = "serde", serde(with = "serde_support::path"))]
+        path: PathBuf,
+        index: i32,
+    },
+    /// An image file such as a PNG or JPEG, as Steam caches for games
+    Image(#[cfg_attr(feature = "serde", serde(with = "serde_support::path"))] PathBuf),
+}
+
+/// An application that is currently running.
+#[derive(Debug, Clone)]
+pub struct RunningApp {
+    /// The application the process belongs to
+    pub app: AppInfo,
+    /// Process identifier
+    pub pid: u32,
+    /// Whether the application currently has focus
+    pub is_active: bool,
+    /// Whether the application is hidden (macOS) or has no visible top-level window (Windows)
+    pub is_hidden: bool,
+}
+
+/// Which processes [`get_running_apps_filtered`] reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RunningAppFilter {
+    /// Only regular applications: those shown in the Dock on macOS, or owning a visible
+    /// top-level window on Windows.
+    #[default]
+    Regular,
+    /// Every process that can be inspected, including background agents and services.
+    All,
+}
+
+/// Options for [`AppInfo::launch_with`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LaunchOptions {
+    /// Bring the application to the front once it starts. Defaults to `true`.
+    pub activate: bool,
+    /// Start the application without showing its windows.
+    pub hidden: bool,
+    /// Ask for administrator rights through the `runas` verb (Windows only).
+    pub elevated: bool,
+    /// Working directory for the new process. Windows defaults to the executable's own
+    /// directory; macOS bundles are always started by LaunchServices and ignore it.
+    pub working_directory: Option<PathBuf>,
+}
+
+impl Default for LaunchOptions {
+    fn default() -> Self {
+        LaunchOptions {
+            activate: true,
+            hidden: false,
+            elevated: false,
+            working_directory: None,
+        }
+    }
+}
+
+/// An application started by [`AppInfo::launch`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LaunchedApp {
+    /// Process identifier of the launched application, when the platform reports it
+    pub pid: Option<u32>,
+}
+
+/// A file type an application declares it can open.
+///
+/// Declarations name a type either by extension or, on macOS, by Uniform Type Identifier;
+/// each association carries one of the two.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FileAssociation {
+    /// File extension without the leading dot, lowercased
+    pub extension: Option<String>,
+    /// Uniform Type Identifier such as `public.png` (macOS)
+    pub uti: Option<String>,
+    /// What the application does with files of this type
+    pub role: AssociationRole,
+}
+
+/// The role an application declares for a file type it handles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AssociationRole {
+    /// The application can read and modify files of this type.
+    Editor,
+    /// The application can open files of this type but not save them.
+    #[default]
+    Viewer,
+    /// The application provides runtime services for the type (macOS `Shell` role).
+    Shell,
+    /// The application declares the type without claiming to open it.
+    None,
+}
+
+/// How an application is configured to start automatically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StartupMechanism {
+    /// A macOS login item, as listed in System Settings.
+    LoginItem,
+    /// A macOS launch agent plist in a `LaunchAgents` directory.
+    LaunchAgent,
+    /// A value under a Windows `Run` registry key.
+    Run,
+    /// A value under a Windows `RunOnce` registry key, run at the next login only.
+    RunOnce,
+    /// A file or shortcut in a Windows Startup folder.
+    StartupFolder,
+}
+
+/// An application configured to start at login.
+#[derive(Debug, Clone)]
+pub struct StartupApp {
+    /// The application that starts, synthesized from the target when it is not installed
+    pub app: AppInfo,
+    /// Where the entry is configured
+    pub mechanism: StartupMechanism,
+    /// Name of the entry: the registry value, shortcut file or launch agent label
+    pub name: String,
+    /// The configured command line, when the mechanism stores one
+    pub command: Option<String>,
+    /// Whether the entry applies to every user rather than only the current one
+    pub all_users: bool,
+    /// Whether the entry is enabled; entries can be switched off without being removed
+    pub enabled: bool,
+}
+
+/// How often and how recently an application has been used, as recorded by the system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppUsage {
+    /// The bundle path on macOS; on Windows the executable path, or the AppUserModelID of
+    /// packaged and shell applications
+    pub identifier_or_path: String,
+    /// When the application was last used
+    pub last_used: Option<SystemTime>,
+    /// How many times the application has been launched
+    pub launch_count: Option<u32>,
+}
+
+impl AppInfo {
+    /// An ID for this installation that stays the same from one scan to the next, such as
+    /// `macos:bundle:com.apple.Safari` or `windows:productcode:{GUID}`. See [`StableId`]
+    /// for the format. Scans and [`AppWatcher`] tell installations apart by it and their
+    /// path, since copies of one macOS bundle in several places share an ID. Each copy is
+    /// kept, and [`find_duplicate_installs`] reports them together.
+    pub fn stable_id(&self) -> String {
+        StableId::of(self).to_string()
+    }
+
+    /// Gets the icon however the scan stored it: [`AppInfo::icon`] as it is,
+    /// [`AppInfo::compressed_icon`] decoded, or, failing both, a fresh read of
+    /// [`AppInfo::icon_source`] at `size`. Stored icons keep the size they were scanned
+    /// at.
+    ///
+    /// Returns [`FileIconError::Failed`](error::FileIconError::Failed) when the app has no
+    /// icon and no source.
+    pub fn load_icon(&self, size: u16) -> Result<Icon> {
+        if let Some(icon) = &self.icon {
+            return Ok(icon.clone());
+        }
+        if let Some(icon) = &self.compressed_icon {
+            return icon.decode();
+        }
+        match &self.icon_source {
+            Some(source) => source.load(size),
+            None => Err(AppInfoError::FileIconError(error::FileIconError::Failed)),
+        }
+    }
+
+    /// Lists the file types this application declares it can open.
+    ///
+    /// On macOS this reads `CFBundleDocumentTypes` from the bundle's `Info.plist`; on
+    /// Windows it collects the extensions whose ProgIds open with this executable, plus
+    /// those listed in the application's registered `Capabilities\FileAssociations`.
+    /// Applications that declare no types return an empty vector.
+    pub fn file_associations(&self) -> Result<Vec<FileAssociation>> {
+        #[cfg(target_os = "macos")]
+        return macos::file_associations(&self.path);
+
+        #[cfg(target_os = "windows")]
+        return window::file_associations(app_executable(self).unwrap_or(&self.path));
+
+        #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+        Err(AppInfoError::UnsupportedPlatform)
+    }
+
+    /// Checks whether this application currently has a running process.
+    ///
+    /// On macOS running applications are matched by bundle `identifier`, then by bundle
+    /// location, then by executable. On Windows every process's executable is compared
+    /// with `executable_path` (or `path` when it is an `.exe`), ignoring case and short
+    /// name differences. An application with nothing to match against is reported as not
+    /// running (`Ok(false)`).
+    pub fn is_running(&self) -> Result<bool> {
+        #[cfg(target_os = "macos")]
+        return macos::is_running(self);
+
+        #[cfg(target_os = "windows")]
+        return window::is_running(self);
+
+        #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+        Err(AppInfoError::UnsupportedPlatform)
+    }
+
+    /// Whether this is a 32-bit application running on 64-bit Windows.
+    ///
+    /// Combines the [`AppInfo::registry_view`] the entry came from, whether the PE header
+    /// of [`AppInfo::executable_path`] is for a 32- or 64-bit image, and the architecture
+    /// of Windows itself.
+    /// Returns `None` when neither the view nor the executable tells, and when they
+    /// disagree, as for a 32-bit program registered by a 64-bit installer. Always `None`
+    /// outside Windows.
+    pub fn is_32bit_on_64bit_os(&self) -> Option<bool> {
+        #[cfg(target_os = "windows")]
+        return window::is_32bit_on_64bit_os(self);
+
+        #[cfg(not(target_os = "windows"))]
+        None
+    }
+
+    /// Launches the application with default [`LaunchOptions`] and no arguments.
+    pub fn launch(&self) -> Result<LaunchedApp> {
+        self.launch_with(&[], LaunchOptions::default())
+    }
+
+    /// Launches the application with command-line arguments.
+    ///
+    /// On macOS bundles are opened through `NSWorkspace`, so they launch properly even
+    /// without an `executable_path`; bare executables are spawned directly. On Windows
+    /// the executable is started with `ShellExecuteExW`, which honors elevation
+    /// manifests, falling back to spawning it directly.
+    ///
+    /// Returns [`AppInfoError::ExecutableNotFound`] when there is nothing to launch,
+    /// [`AppInfoError::LaunchCancelled`] when the user declines an elevation prompt, and
+    /// [`AppInfoError::LaunchFailed`] for other failures.
+    pub fn launch_with(&self, args: &[OsString], options: LaunchOptions) -> Result<LaunchedApp> {
+        #[cfg(target_os = "macos")]
+        return macos::launch(self, args, &options);
+
+        #[cfg(target_os = "windows")]
+        return window::launch(self, args, &options);
+
+        #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+        {
+            let _ = (args, options);
+            Err(AppInfoError::UnsupportedPlatform)
+        }
+    }
+
+    /// Uninstalls the application and waits for the uninstaller to finish.
+    ///
+    /// On Windows this runs the command the application's Uninstall entry registers, read
+    /// again from the registry. Windows Installer products are removed with
+    /// `msiexec /x` and their product code instead, and machine-wide installs ask for
+    /// elevation when the process is not elevated already. On macOS, where applications
+    /// have no uninstaller, the bundle is moved to the Trash, which
+    /// [`UninstallOutcome::MovedToTrash`] tells apart from a real uninstall.
+    ///
+    /// With [`UninstallOptions::dry_run`] nothing runs, and the outcome is the plan. Returns
+    /// [`AppInfoError::UninstallFailed`] when there is no way to uninstall the application,
+    /// as for entries that set `NoRemove` or apps no Uninstall entry lists, and when the
+    /// uninstaller cannot be started.
+    pub fn uninstall(&self, options: UninstallOptions) -> Result<UninstallOutcome> {
+        #[cfg(target_os = "macos")]
+        return macos::uninstall(self, &options);
+
+        #[cfg(target_os = "windows")]
+        return window::uninstall(self, &options);
+
+        #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+        {
+            let _ = options;
+            Err(AppInfoError::UnsupportedPlatform)
+        }
+    }
+}
+
+/// How an icon is fitted into an output size whose aspect ratio differs from the source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FitMode {
+    /// Scale to fill the output exactly, distorting the aspect ratio if necessary.
+    #[default]
+    Stretch,
+    /// Scale to fit entirely inside the output, padding the rest with transparent pixels.
+    Contain,
+    /// Scale to cover the whole output, cropping the overflow around the center.
+    Cover,
+}
+
+/// How [`find_apps_by_name`] compares names. Every mode ignores case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatchMode {
+    /// The whole name matches.
+    #[default]
+    Exact,
+    /// The name starts with the query.
+    Prefix,
+    /// The name contains the query anywhere.
+    Contains,
+}
+
 /// Gets all installed applications.
 ///
 /// # Arguments
@@ -44,7 +583,14 @@ pub struct Icon {
 /// # Returns
 ///
 /// A vector containing information about all installed applications.
+///
+/// Every icon is extracted before this returns, which takes a while at large sizes. To
+/// show the list first and the icons as they load, scan with
+/// [`IconStorage::Deferred`] and read the icons with [`load_icons`] on another thread, as
+/// `examples/lazy_icons.rs` does.
 pub fn get_installed_apps(icon_size: u16) -> Result<Vec<AppInfo>> {
+    enter_span!(INFO, "get_installed_apps", icon_size);
+
     #[cfg(target_os = "macos")]
     return macos::get_installed_apps(icon_size);
 
@@ -52,11 +598,71 @@ pub fn get_installed_apps(icon_size: u16) -> Result<Vec<AppInfo>> {
     return window::get_installed_apps(icon_size);
 
     #[cfg(not(any(target_os = "macos", target_os = "windows")))]
-    Err(AppInfoError::UnsupportedPlatform)
+    {
+        let _ = icon_size;
+        Err(AppInfoError::UnsupportedPlatform)
+    }
+}
+
+/// Attaches winget package identifiers to apps from a Windows scan.
+///
+/// Runs `winget list --source winget`, which takes several seconds, so it is never done as
+/// part of a scan. Each package winget reports as installed sets
+/// [`AppInfo::package_manager`] on one app:
+///
+/// - Recent versions of winget name, with `--details`, the Uninstall entry they matched each
+///   package to, and the package goes to the app read from that entry. Its identifier is
+///   the entry's key name, which for Windows Installer products is the product code.
+/// - Other packages go to the app with the same name, ignoring case and punctuation, whose
+///   publisher matches after [`normalize_publisher`] when both have one. Of several such
+///   apps the one with the same version is chosen. Without one, the package is left
+///   unattached unless the apps all have one publisher.
+///
+/// Older versions of winget print only a table. Its columns are located by their position
+/// under the header, so names with double-width characters may not match. Packages whose
+/// ID is cut off to fit the table are skipped.
+///
+/// Returns how many apps got a package. Without winget, and on other platforms, nothing
+/// changes and 0 is returned; [`AppInfoError::PackageManagerError`] is returned if winget
+/// is installed but fails.
+pub fn attach_winget_packages(apps: &mut [AppInfo]) -> Result<usize> {
+    #[cfg(target_os = "windows")]
+    return winget::attach_winget_packages(apps);
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = apps;
+        Ok(0)
+    }
+}
+
+/// Checks the code signature of a file.
+///
+/// On Windows the embedded Authenticode signature is verified with `WinVerifyTrust`,
+/// without going online for revocation lists, and the signer is read from the signing
+/// certificate. Files with no embedded signature are looked up in the system catalogs.
+/// Unsigned files are reported as [`SignatureStatus::Unsigned`] rather than as an error.
+/// A check takes tens of milliseconds, which is why scans skip it unless
+/// [`ScanOptions::check_signatures`] is set.
+///
+/// Returns [`AppInfoError::ExecutableNotFound`] when there is no file at `path`, and
+/// [`AppInfoError::UnsupportedPlatform`] on other platforms.
+pub fn get_signature_info(path: impl AsRef<std::path::Path>) -> Result<SignatureInfo> {
+    #[cfg(target_os = "windows")]
+    return window::get_signature_info(path.as_ref());
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = path;
+        Err(AppInfoError::UnsupportedPlatform)
+    }
 }
 
 /// Finds a specific application by its name.
 ///
+/// The name is compared ignoring case. When several applications share the name, the
+/// first one [`find_apps_by_name`] returns with [`MatchMode::Exact`] is used.
+///
 /// # Arguments
 ///
 /// * `name` - The name of the application to find.
@@ -66,132 +672,1629 @@ pub fn get_installed_apps(icon_size: u16) -> Result<Vec<AppInfo>> {
 ///
 /// Information about the matched application.
 pub fn find_app_by_name(name: &str, icon_size: u16) -> Result<AppInfo> {
-    let apps = get_installed_apps(icon_size)?;
-    apps.into_iter()
-        .find(|app| app.name.eq_ignore_ascii_case(name))
+    find_apps_by_name(name, MatchMode::Exact, icon_size)?
+        .into_iter()
+        .next()
         .ok_or_else(|| AppInfoError::AppNotFound {
             name: name.to_string(),
         })
 }
 
-/// Gets the icon for a given file path.
-pub fn get_file_icon(path: impl AsRef<std::path::Path>, size: u16) -> Result<Icon> {
-    let path = path.as_ref();
-    if !path.exists() {
-        return Err(AppInfoError::FileIconError(
-            error::FileIconError::PathDoesNotExist,
-        ));
-    }
+/// Finds every installed application whose name matches `name`.
+///
+/// Names are compared ignoring case, including for non-ASCII letters. Matches are sorted
+/// by name, then version and path, so the order does not depend on scan order. An empty
+/// or all-whitespace `name` matches nothing in every mode, rather than everything with
+/// [`MatchMode::Prefix`] or [`MatchMode::Contains`]. Returns an empty vector when nothing
+/// matches.
+///
+/// # Arguments
+///
+/// * `name` - The name, or part of a name, to look for.
+/// * `mode` - How `name` is compared against application names.
+/// * `icon_size` - The desired icon size. If 0, no icon will be fetched.
+pub fn find_apps_by_name(name: &str, mode: MatchMode, icon_size: u16) -> Result<Vec<AppInfo>> {
+    Ok(apps_matching_name(
+        get_installed_apps(icon_size)?,
+        name,
+        mode,
+    ))
+}
 
-    if size == 0 {
-        return Err(AppInfoError::FileIconError(
-            error::FileIconError::NullIconSize,
-        ));
+fn apps_matching_name(apps: Vec<AppInfo>, name: &str, mode: MatchMode) -> Vec<AppInfo> {
+    if name.trim().is_empty() {
+        return Vec::new();
     }
 
-    #[cfg(target_os = "macos")]
-    return macos::get_file_icon(path, size);
+    let query = glob::fold_case(name);
+    let mut matches: Vec<AppInfo> = apps
+        .into_iter()
+        .filter(|app| {
+            let candidate = glob::fold_case(&app.name);
+            match mode {
+                MatchMode::Exact => candidate == query,
+                MatchMode::Prefix => candidate.starts_with(&query),
+                MatchMode::Contains => candidate.contains(&query),
+            }
+        })
+        .collect();
+    matches.sort_by_cached_key(|app| {
+        (
+            glob::fold_case(&app.name),
+            app.name.clone(),
+            app.version.clone(),
+            app.path.clone(),
+        )
+    });
+    matches
+}
 
-    #[cfg(target_os = "windows")]
-    return window::get_file_icon(path, size);
+/// Finds the installed applications whose publisher is `publisher`, ignoring case.
+///
+/// Applications without a publisher are left out. Use [`find_apps_by_publisher_with`] to
+/// also match other spellings of the same vendor, such as "Microsoft Corp." for
+/// "Microsoft Corporation". Returns an empty vector when nothing matches.
+///
+/// # Arguments
+///
+/// * `publisher` - The publisher to look for.
+/// * `icon_size` - The desired icon size. If 0, no icon will be fetched.
+pub fn find_apps_by_publisher(publisher: &str, icon_size: u16) -> Result<Vec<AppInfo>> {
+    find_apps_by_publisher_with(publisher, icon_size, false)
+}
 
-    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
-    Err(AppInfoError::FileIconError(
-        error::FileIconError::PlatformNotSupported,
+/// [`find_apps_by_publisher`] that can compare publishers in the form
+/// [`normalize_publisher`] gives them, so legal-entity suffixes and spacing do not matter.
+pub fn find_apps_by_publisher_with(
+    publisher: &str,
+    icon_size: u16,
+    normalized: bool,
+) -> Result<Vec<AppInfo>> {
+    Ok(apps_matching_publisher(
+        get_installed_apps(icon_size)?,
+        publisher,
+        normalized,
     ))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_get_installed_apps() {
-        // Test without icons
-        let apps = get_installed_apps(0).expect("Failed to get installed apps");
-        assert!(!apps.is_empty(), "Should find at least one application");
-        println!("Applications found: {:?}", apps);
-
-        // Test with icons
-        let apps_with_icons =
-            get_installed_apps(32).expect("Failed to get installed apps with icons");
-        assert!(!apps_with_icons.is_empty());
-
-        // On supported platforms, at least one app should have an icon
-        #[cfg(any(target_os = "macos", target_os = "windows"))]
-        {
-            let app_with_icon = apps_with_icons.iter().find(|app| app.icon.is_some());
-            assert!(
-                app_with_icon.is_some(),
-                "At least one app should have an icon"
-            );
-
-            if let Some(app) = app_with_icon {
-                let icon = app.icon.as_ref().unwrap();
-                assert_eq!(icon.width, 32);
-                assert_eq!(icon.height, 32);
-                assert!(!icon.pixels.is_empty());
-                assert_eq!(icon.pixels.len(), (32 * 32 * 4) as usize);
-            }
+fn apps_matching_publisher(apps: Vec<AppInfo>, publisher: &str, normalized: bool) -> Vec<AppInfo> {
+    let fold = |value: &str| {
+        if normalized {
+            normalize_publisher(value)
+        } else {
+            glob::fold_case(value.trim())
         }
+    };
+    let query = fold(publisher);
+    if query.is_empty() {
+        return Vec::new();
     }
+    apps.into_iter()
+        .filter(|app| {
+            app.publisher
+                .as_deref()
+                .is_some_and(|value| fold(value) == query)
+        })
+        .collect()
+}
 
-    #[test]
-    fn test_find_app_by_name() {
-        let apps = get_installed_apps(0).unwrap();
-        if apps.is_empty() {
-            // Skip this test if no apps are installed
-            return;
+/// Finds the installed applications that declare `category`.
+///
+/// Applications without a category are left out. Only macOS bundles declare one, in
+/// `LSApplicationCategoryType`. Categories are not available on Windows, where neither
+/// Uninstall entries nor MSIX packages record one, so there the result is always empty.
+/// An [`AppCategory::Other`] value matches apps whose category parses to the same value.
+/// Apps are matched before their icons are loaded, so only matches pay for icon
+/// extraction.
+///
+/// # Arguments
+///
+/// * `category` - The category to look for.
+/// * `icon_size` - The desired icon size. If 0, no icon will be fetched.
+pub fn find_apps_by_category(category: AppCategory, icon_size: u16) -> Result<Vec<AppInfo>> {
+    let mut apps: Vec<AppInfo> = get_installed_apps(0)?
+        .into_iter()
+        .filter(|app| app.category.as_ref() == Some(&category))
+        .collect();
+    if icon_size > 0 {
+        let icons = load_icons(&apps, icon_size);
+        for (app, icon) in apps.iter_mut().zip(icons) {
+            app.icon = icon.ok();
         }
+    }
+    Ok(apps)
+}
 
-        // Test with the first app from the list
-        let first_app_name = &apps[0].name;
-        let found_app = find_app_by_name(first_app_name, 0).unwrap();
-        assert_eq!(found_app.name, *first_app_name);
+/// Finds the installed applications whose name matches a wildcard pattern.
+///
+/// `*` matches any run of characters, `?` exactly one, and `[...]` one character from a
+/// set such as `[abc]` or `[0-9]`, with `[!...]` for its complement; `\` makes the next
+/// character literal. The pattern must match the whole name, ignoring case, so
+/// `Microsoft*` finds "Microsoft Edge" but `Edge` does not. Invalid patterns, such as an
+/// unclosed `[`, return [`AppInfoError::InvalidPattern`].
+///
+/// # Arguments
+///
+/// * `pattern` - The wildcard pattern.
+/// * `icon_size` - The desired icon size. If 0, no icon will be fetched.
+pub fn find_apps_matching_glob(pattern: &str, icon_size: u16) -> Result<Vec<AppInfo>> {
+    find_apps_matching_glob_with(pattern, icon_size, false)
+}
 
-        // Test case-insensitivity
-        let found_app_lower = find_app_by_name(&first_app_name.to_lowercase(), 0).unwrap();
-        assert_eq!(found_app_lower.name, *first_app_name);
+/// [`find_apps_matching_glob`] that can also match the pattern against
+/// [`AppInfo::identifier`], so `com.adobe.*` finds every Adobe bundle on macOS.
+pub fn find_apps_matching_glob_with(
+    pattern: &str,
+    icon_size: u16,
+    match_identifier: bool,
+) -> Result<Vec<AppInfo>> {
+    let glob = glob::Glob::new(pattern)?;
+    Ok(get_installed_apps(icon_size)?
+        .into_iter()
+        .filter(|app| {
+            glob.matches(&app.name)
+                || (match_identifier
+                    && app.identifier.as_deref().is_some_and(|id| glob.matches(id)))
+        })
+        .collect())
+}
 
-        // Test for an app that doesn't exist
-        let not_found_result = find_app_by_name("ThisAppSurelyDoesNotExist12345", 0);
-        assert!(matches!(
-            not_found_result,
-            Err(AppInfoError::AppNotFound { .. })
-        ));
+/// Finds the installed applications whose main executable is named `exe_name`.
+///
+/// The name may be given with or without a `.exe` suffix, so `Code.exe` and `Code` are
+/// equivalent. It is compared case-insensitively on Windows and case-sensitively on macOS;
+/// use [`find_app_by_executable_with`] to change that on macOS. Several applications can
+/// ship an executable of the same name, so all matches are returned, the most likely main
+/// application first: ones whose name matches the executable, then ones whose executable
+/// is not nested inside another bundle or deep in the installation. Returns an empty
+/// vector when nothing matches.
+///
+/// # Arguments
+///
+/// * `exe_name` - The executable's file name, as reported by a process list or telemetry.
+/// * `icon_size` - The desired icon size. If 0, no icon will be fetched.
+pub fn find_app_by_executable(exe_name: &str, icon_size: u16) -> Result<Vec<AppInfo>> {
+    find_app_by_executable_with(exe_name, icon_size, cfg!(target_os = "macos"))
+}
+
+/// [`find_app_by_executable`] with explicit case sensitivity.
+///
+/// `case_sensitive` is ignored on Windows, where file names never differ by case alone.
+pub fn find_app_by_executable_with(
+    exe_name: &str,
+    icon_size: u16,
+    case_sensitive: bool,
+) -> Result<Vec<AppInfo>> {
+    let case_sensitive = case_sensitive && !cfg!(target_os = "windows");
+    let stem = executable_stem(exe_name.trim());
+    if stem.is_empty() {
+        return Ok(Vec::new());
     }
 
-    #[test]
-    fn test_get_file_icon() {
-        // Choose a path that is likely to exist on different platforms
-        let path_to_test = if cfg!(target_os = "macos") {
-            "/System/Applications/Calculator.app"
-        } else if cfg!(target_os = "windows") {
-            "C:\\Windows\\System32\\notepad.exe"
-        } else {
-            // On other OS, we don't have a default path, so skip the rest of the test
-            return;
-        };
+    let mut apps: Vec<AppInfo> = get_installed_apps(icon_size)?
+        .into_iter()
+        .filter(|app| executable_matches(app, stem, case_sensitive))
+        .collect();
+    apps.sort_by_cached_key(|app| executable_rank(app, stem));
+    Ok(apps)
+}
 
-        let path = std::path::Path::new(path_to_test);
+/// The executable an app runs: its `executable_path`, or its path when that is an `.exe`.
+fn app_executable(app: &AppInfo) -> Option<&std::path::Path> {
+    app.executable_path.as_deref().or_else(|| {
+        app.path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("exe"))
+            .then_some(app.path.as_path())
+    })
+}
 
-        // Test with size 0, should return an error
-        let result = get_file_icon(path, 0);
-        assert!(matches!(
-            result,
-            Err(AppInfoError::FileIconError(
-                error::FileIconError::NullIconSize
-            ))
-        ));
+/// Strips a trailing `.exe`, in any case, from an executable's file name.
+fn executable_stem(name: &str) -> &str {
+    match name.len().checked_sub(4) {
+        Some(split)
+            if name.is_char_boundary(split) && name[split..].eq_ignore_ascii_case(".exe") =>
+        {
+            &name[..split]
+        }
+        _ => name,
+    }
+}
 
-        // Test with a non-existent path
-        let result = get_file_icon("/path/to/non/existent/file", 32);
-        assert!(matches!(
-            result,
-            Err(AppInfoError::FileIconError(
-                error::FileIconError::PathDoesNotExist
-            ))
-        ));
+fn executable_matches(app: &AppInfo, stem: &str, case_sensitive: bool) -> bool {
+    let Some(file_name) = app_executable(app)
+        .and_then(|path| path.file_name())
+        .and_then(|name| name.to_str())
+    else {
+        return false;
+    };
+    let candidate = executable_stem(file_name);
+    if case_sensitive {
+        candidate == stem
+    } else {
+        candidate.to_lowercase() == stem.to_lowercase()
+    }
+}
+
+/// Orders matches for [`find_app_by_executable`]; lower is more likely the main app.
+fn executable_rank(app: &AppInfo, stem: &str) -> (bool, u8, usize) {
+    let path = app_executable(app).unwrap_or(&app.path);
+    // Helpers live inside another application's bundle, e.g. `Foo.app/.../Foo Helper.app`
+    let bundles = path
+        .components()
+        .filter(|component| {
+            std::path::Path::new(component.as_os_str())
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("app"))
+        })
+        .count();
+    let name = app.name.to_lowercase();
+    let stem = stem.to_lowercase();
+    let name_match = if name == stem {
+        0
+    } else if name.contains(&stem) {
+        1
+    } else {
+        2
+    };
+    (bundles > 1, name_match, path.components().count())
+}
+
+/// Gets information about a single application without scanning everything.
+///
+/// On macOS `path` must be an `.app` bundle. On Windows it may be an executable or an
+/// installation directory; the matching Uninstall registry entry is used when there is
+/// one, and the executable's version resource otherwise. Paths that are not applications
+/// return [`AppInfoError::BundleParseError`].
+///
+/// # Arguments
+///
+/// * `path` - The application bundle, executable or installation directory.
+/// * `icon_size` - The desired icon size. If 0, no icon will be fetched.
+pub fn get_app_info(path: impl AsRef<std::path::Path>, icon_size: u16) -> Result<AppInfo> {
+    #[cfg(target_os = "macos")]
+    return macos::get_app_info(path.as_ref(), icon_size);
+
+    #[cfg(target_os = "windows")]
+    return window::get_app_info(path.as_ref(), icon_size);
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let _ = (path, icon_size);
+        Err(AppInfoError::UnsupportedPlatform)
+    }
+}
+
+/// Looks up one installed application by its identifier without scanning everything.
+///
+/// On macOS `identifier` is a bundle identifier such as `com.apple.Safari`, and the
+/// installation LaunchServices considers primary is returned. On Windows it is the name
+/// of the application's Uninstall registry key, which is the product code `{GUID}` for
+/// MSI installs; scans report the same value in [`AppInfo::identifier`]. Unknown
+/// identifiers return [`AppInfoError::AppNotFound`].
+///
+/// # Arguments
+///
+/// * `identifier` - The bundle identifier or Uninstall key name.
+/// * `icon_size` - The desired icon size. If 0, no icon will be fetched.
+pub fn lookup_app(identifier: &str, icon_size: u16) -> Result<AppInfo> {
+    #[cfg(target_os = "macos")]
+    return macos::lookup_app(identifier, icon_size);
+
+    #[cfg(target_os = "windows")]
+    return window::lookup_app(identifier, icon_size);
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let _ = (identifier, icon_size);
+        Err(AppInfoError::UnsupportedPlatform)
+    }
+}
+
+/// Looks up every installation of an application by its identifier, primary first.
+///
+/// Several copies of a bundle can be installed on macOS; on Windows the same key can
+/// exist in both registry views and in the per-user hive. See [`lookup_app`] for what
+/// `identifier` is. An unknown identifier returns an empty vector.
+///
+/// # Arguments
+///
+/// * `identifier` - The bundle identifier or Uninstall key name.
+/// * `icon_size` - The desired icon size. If 0, no icon will be fetched.
+pub fn lookup_all_apps(identifier: &str, icon_size: u16) -> Result<Vec<AppInfo>> {
+    #[cfg(target_os = "macos")]
+    return macos::lookup_all_apps(identifier, icon_size);
+
+    #[cfg(target_os = "windows")]
+    return window::lookup_all_apps(identifier, icon_size);
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let _ = (identifier, icon_size);
+        Err(AppInfoError::UnsupportedPlatform)
+    }
+}
+
+/// Gets the regular applications that are currently running.
+///
+/// Equivalent to [`get_running_apps_filtered`] with [`RunningAppFilter::Regular`].
+///
+/// # Arguments
+///
+/// * `icon_size` - The desired icon size. If 0, no icon will be fetched.
+pub fn get_running_apps(icon_size: u16) -> Result<Vec<RunningApp>> {
+    get_running_apps_filtered(icon_size, RunningAppFilter::Regular)
+}
+
+/// Gets the currently running applications, one entry per process.
+///
+/// Each process is described with the same [`AppInfo`] the installed scan produces where
+/// its executable belongs to an installed application. Processes the caller lacks
+/// permission to inspect are skipped rather than failing the call.
+///
+/// # Arguments
+///
+/// * `icon_size` - The desired icon size. If 0, no icon will be fetched.
+/// * `filter` - Whether to include background processes.
+pub fn get_running_apps_filtered(
+    icon_size: u16,
+    filter: RunningAppFilter,
+) -> Result<Vec<RunningApp>> {
+    #[cfg(target_os = "macos")]
+    return macos::get_running_apps(icon_size, filter);
+
+    #[cfg(target_os = "windows")]
+    return window::get_running_apps(icon_size, filter);
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let _ = (icon_size, filter);
+        Err(AppInfoError::UnsupportedPlatform)
+    }
+}
+
+/// Gets the application that currently has focus.
+///
+/// This is cheap enough to poll several times a second, especially with an `icon_size`
+/// of 0. Returns [`AppInfoError::NoForegroundApp`] when nothing is focused, when the
+/// desktop itself has focus, or when the focused process cannot be inspected.
+///
+/// # Arguments
+///
+/// * `icon_size` - The desired icon size. If 0, no icon will be fetched.
+pub fn get_frontmost_app(icon_size: u16) -> Result<AppInfo> {
+    #[cfg(target_os = "macos")]
+    return macos::get_frontmost_app(icon_size);
+
+    #[cfg(target_os = "windows")]
+    return window::get_frontmost_app(icon_size);
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let _ = icon_size;
+        Err(AppInfoError::UnsupportedPlatform)
+    }
+}
+
+/// Gets the application a process belongs to.
+///
+/// The process's executable is matched against installed applications (its bundle on
+/// macOS) and otherwise described from the executable itself. Returns
+/// [`AppInfoError::ProcessNotFound`] when the process has exited or cannot be inspected.
+///
+/// # Arguments
+///
+/// * `pid` - The process identifier.
+/// * `icon_size` - The desired icon size. If 0, no icon will be fetched.
+pub fn get_app_for_pid(pid: u32, icon_size: u16) -> Result<AppInfo> {
+    #[cfg(target_os = "macos")]
+    return macos::get_app_for_pid(pid, icon_size);
+
+    #[cfg(target_os = "windows")]
+    return window::get_app_for_pid(pid, icon_size);
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let _ = (pid, icon_size);
+        Err(AppInfoError::UnsupportedPlatform)
+    }
+}
+
+/// Gets the icon of a running process.
+///
+/// On macOS applications give the icon `NSRunningApplication` holds, which follows a Dock
+/// icon changed at runtime and is there even when the bundle on disk cannot be read;
+/// other processes, and every process on Windows, give their executable's icon. Returns
+/// [`AppInfoError::ProcessNotFound`] when the process has exited or cannot be inspected.
+///
+/// # Arguments
+///
+/// * `pid` - The process identifier.
+/// * `size` - The desired icon size.
+pub fn get_icon_for_pid(pid: u32, size: u16) -> Result<Icon> {
+    if size == 0 {
+        return Err(AppInfoError::FileIconError(
+            error::FileIconError::NullIconSize,
+        ));
+    }
+
+    #[cfg(target_os = "macos")]
+    return macos::get_icon_for_pid(pid, size);
+
+    #[cfg(target_os = "windows")]
+    return window::get_icon_for_pid(pid, size);
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let _ = pid;
+        Err(AppInfoError::UnsupportedPlatform)
+    }
+}
+
+/// Gets the applications configured to start when the user logs in.
+///
+/// On macOS this covers login items (when System Events may be queried) and the user and
+/// system launch agents; on Windows the `Run` and `RunOnce` keys of both hives and the
+/// Startup folders. Entries whose target is not an installed application are described
+/// from the target itself rather than dropped.
+///
+/// # Arguments
+///
+/// * `icon_size` - The desired icon size. If 0, no icon will be fetched.
+pub fn get_startup_apps(icon_size: u16) -> Result<Vec<StartupApp>> {
+    #[cfg(target_os = "macos")]
+    return macos::get_startup_apps(icon_size);
+
+    #[cfg(target_os = "windows")]
+    return window::get_startup_apps(icon_size);
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let _ = icon_size;
+        Err(AppInfoError::UnsupportedPlatform)
+    }
+}
+
+/// Gets the application usage the system has recorded, for sorting by "most used".
+///
+/// On Windows this decodes the current user's UserAssist data, which covers programs
+/// started through Explorer and the Start menu. On macOS it reads the Spotlight
+/// `kMDItemLastUsedDate` and `kMDItemUseCount` attributes of every installed bundle.
+/// Data that is missing or cannot be read shows up as `None` rather than as an error.
+pub fn get_app_usage() -> Result<Vec<AppUsage>> {
+    #[cfg(target_os = "macos")]
+    return macos::get_app_usage();
+
+    #[cfg(target_os = "windows")]
+    return window::get_app_usage();
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    Err(AppInfoError::UnsupportedPlatform)
+}
+
+/// Gets the recorded usage of each of `apps`, matched by bundle path on macOS and by
+/// executable path on Windows.
+///
+/// The result is aligned with `apps`, with `None` for applications that have no recorded
+/// usage.
+pub fn get_usage_for_apps(apps: &[AppInfo]) -> Result<Vec<Option<AppUsage>>> {
+    #[cfg(target_os = "macos")]
+    return macos::get_usage_for_apps(apps);
+
+    #[cfg(target_os = "windows")]
+    return window::get_usage_for_apps(apps);
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let _ = apps;
+        Err(AppInfoError::UnsupportedPlatform)
+    }
+}
+
+/// Gets every application registered to open URLs with the given scheme.
+///
+/// `scheme` is matched case-insensitively and may be given with or without its trailing
+/// `:` or `://`, so `"mailto"`, `"MAILTO:"` and `"slack://"` all work. The default handler,
+/// when there is one, comes first. An unregistered scheme returns an empty vector.
+///
+/// # Arguments
+///
+/// * `scheme` - The URL scheme to look up.
+/// * `icon_size` - The desired icon size. If 0, no icon will be fetched.
+pub fn get_apps_for_url_scheme(scheme: &str, icon_size: u16) -> Result<Vec<AppInfo>> {
+    let scheme = normalize_url_scheme(scheme);
+    if scheme.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    #[cfg(target_os = "macos")]
+    return macos::get_apps_for_url_scheme(&scheme, icon_size);
+
+    #[cfg(target_os = "windows")]
+    return window::get_apps_for_url_scheme(&scheme, icon_size);
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let _ = icon_size;
+        Err(AppInfoError::UnsupportedPlatform)
+    }
+}
+
+/// Gets the application that opens URLs with the given scheme by default.
+///
+/// `scheme` is normalized as in [`get_apps_for_url_scheme`]. Returns
+/// [`AppInfoError::UrlSchemeNotHandled`] when no application is registered for it.
+pub fn get_default_app_for_url_scheme(scheme: &str, icon_size: u16) -> Result<AppInfo> {
+    let scheme = normalize_url_scheme(scheme);
+    if scheme.is_empty() {
+        return Err(AppInfoError::UrlSchemeNotHandled { scheme });
+    }
+
+    #[cfg(target_os = "macos")]
+    return macos::get_default_app_for_url_scheme(&scheme, icon_size);
+
+    #[cfg(target_os = "windows")]
+    return window::get_default_app_for_url_scheme(&scheme, icon_size);
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let _ = icon_size;
+        Err(AppInfoError::UnsupportedPlatform)
+    }
+}
+
+/// Gets every application that can open a file, as offered in an "Open with" menu.
+///
+/// The default application for the file comes first. On macOS the candidates come from
+/// LaunchServices. On Windows they are the handlers registered for the file's extension:
+/// the user's choice, the extension's ProgIds, the "Open with" lists and the
+/// applications whose capabilities declare the extension, each resolved to the
+/// executable its open command runs. A file nothing can open returns an empty vector.
+///
+/// # Arguments
+///
+/// * `path` - The file to open; it must exist.
+/// * `icon_size` - The desired icon size. If 0, no icon will be fetched.
+pub fn get_apps_for_file(
+    path: impl AsRef<std::path::Path>,
+    icon_size: u16,
+) -> Result<Vec<AppInfo>> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Err(AppInfoError::FileIconError(
+            error::FileIconError::PathDoesNotExist,
+        ));
+    }
+
+    #[cfg(target_os = "macos")]
+    return macos::get_apps_for_file(path, icon_size);
+
+    #[cfg(target_os = "windows")]
+    return window::get_apps_for_file(path, icon_size);
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let _ = icon_size;
+        Err(AppInfoError::UnsupportedPlatform)
+    }
+}
+
+/// Lowercases a URL scheme and strips surrounding whitespace and a trailing `:` or `://`.
+fn normalize_url_scheme(scheme: &str) -> String {
+    let scheme = scheme.trim();
+    let scheme = scheme
+        .strip_suffix("://")
+        .or_else(|| scheme.strip_suffix(':'))
+        .unwrap_or(scheme);
+    scheme.to_lowercase()
+}
+
+/// Gets the icon for a given file path.
+pub fn get_file_icon(path: impl AsRef<std::path::Path>, size: u16) -> Result<Icon> {
+    get_file_icon_sized(path, size, size, FitMode::Stretch)
+}
+
+/// Gets the generic icon the system shows for files with the given extension, without
+/// needing an actual file.
+///
+/// `extension` is accepted with or without the leading dot and case-insensitively, so
+/// `"pdf"`, `".PDF"` and `"Pdf"` are equivalent. Unknown extensions return the generic
+/// document icon rather than an error.
+pub fn get_icon_for_extension(extension: &str, size: u16) -> Result<Icon> {
+    if size == 0 {
+        return Err(AppInfoError::FileIconError(
+            error::FileIconError::NullIconSize,
+        ));
+    }
+
+    let extension = extension.trim();
+    let extension = extension
+        .strip_prefix('.')
+        .unwrap_or(extension)
+        .to_lowercase();
+
+    #[cfg(target_os = "macos")]
+    return macos::get_icon_for_extension(&extension, size);
+
+    #[cfg(target_os = "windows")]
+    return window::get_icon_for_extension(&extension, size);
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let _ = extension;
+        Err(AppInfoError::UnsupportedPlatform)
+    }
+}
+
+/// Gets the icons for many file paths at once.
+///
+/// Per-call setup (COM and the WIC factory on Windows, the shared workspace on macOS) is
+/// shared across the whole batch, and paths that resolve to the same file are only
+/// extracted once. The output has one entry per input path, in the same order; a path
+/// that fails yields an `Err` in its own slot without affecting the others.
+pub fn get_file_icons<P: AsRef<std::path::Path>>(paths: &[P], size: u16) -> Vec<Result<Icon>> {
+    if size == 0 {
+        return paths
+            .iter()
+            .map(|_| {
+                Err(AppInfoError::FileIconError(
+                    error::FileIconError::NullIconSize,
+                ))
+            })
+            .collect();
+    }
+
+    // Map every input slot to a unique canonical file, keeping the first spelling seen
+    let mut unique_paths: Vec<&std::path::Path> = Vec::new();
+    let mut canonical_paths: std::collections::HashMap<PathBuf, usize> =
+        std::collections::HashMap::new();
+    let slots: Vec<Option<usize>> = paths
+        .iter()
+        .map(|path| {
+            let path = path.as_ref();
+            let canonical = path.canonicalize().ok()?;
+            let index = *canonical_paths.entry(canonical).or_insert_with(|| {
+                unique_paths.push(path);
+                unique_paths.len() - 1
+            });
+            Some(index)
+        })
+        .collect();
+
+    #[cfg(target_os = "macos")]
+    let icons = macos::get_file_icons(&unique_paths, size);
+
+    #[cfg(target_os = "windows")]
+    let icons = window::get_file_icons(&unique_paths, size);
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let icons: Vec<Result<Icon>> = unique_paths
+        .iter()
+        .map(|_| Err(AppInfoError::UnsupportedPlatform))
+        .collect();
+
+    fan_out_icons(&slots, icons)
+}
+
+/// Gives every input slot of [`get_file_icons`] the result for its file, `None` standing
+/// for a missing file. The first slot of a file gets the result itself and later ones an
+/// equivalent copy, so every slot of a file fails the same way.
+fn fan_out_icons(slots: &[Option<usize>], mut icons: Vec<Result<Icon>>) -> Vec<Result<Icon>> {
+    let mut first_slot = vec![usize::MAX; icons.len()];
+    for (slot, index) in slots.iter().enumerate().rev() {
+        if let Some(index) = index {
+            first_slot[*index] = slot;
+        }
+    }
+
+    // Going backwards, the copies are made before the first slot takes the result
+    let mut results: Vec<Result<Icon>> = slots
+        .iter()
+        .enumerate()
+        .rev()
+        .map(|(slot, index)| match *index {
+            None => Err(AppInfoError::FileIconError(
+                error::FileIconError::PathDoesNotExist,
+            )),
+            Some(index) if first_slot[index] == slot => std::mem::replace(
+                &mut icons[index],
+                Err(AppInfoError::FileIconError(error::FileIconError::Failed)),
+            ),
+            Some(index) => match &icons[index] {
+                Ok(icon) => Ok(icon.clone()),
+                Err(e) => Err(e.duplicate()),
+            },
+        })
+        .collect();
+    results.reverse();
+    results
+}
+
+/// Gets the icons of many apps at once, the way [`AppInfo::load_icon`] gets each one.
+///
+/// Stored icons are returned as they are and compressed ones decoded. Apps whose
+/// [`AppInfo::icon_source`] is a file are read together through [`get_file_icons`], so
+/// they share its setup and its handling of duplicates. The output has one entry per app,
+/// in the same order. Apps and icons are `Send`, so this can run on a worker thread while
+/// the list is already on screen; see `examples/lazy_icons.rs`.
+pub fn load_icons(apps: &[AppInfo], size: u16) -> Vec<Result<Icon>> {
+    // The file each app's icon still has to be read from, when it is one
+    fn pending_file(app: &AppInfo) -> Option<&std::path::Path> {
+        match (&app.icon, &app.compressed_icon, &app.icon_source) {
+            (None, None, Some(IconSource::File(path))) => Some(path),
+            _ => None,
+        }
+    }
+
+    let files: Vec<&std::path::Path> = apps.iter().filter_map(pending_file).collect();
+    let mut file_icons = get_file_icons(&files, size).into_iter();
+    apps.iter()
+        .map(|app| match pending_file(app) {
+            Some(_) => file_icons.next().expect("one icon per file source"),
+            None => app.load_icon(size),
+        })
+        .collect()
+}
+
+/// Gets the icon for a given file path at its native resolution.
+///
+/// Returns the largest genuine representation available (e.g. the biggest image in an
+/// `.icns` or `.ico`) without any upscaling, so `width` and `height` reflect the source and
+/// may be anything from 16 to 1024 pixels.
+pub fn get_file_icon_native(path: impl AsRef<std::path::Path>) -> Result<Icon> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Err(AppInfoError::FileIconError(
+            error::FileIconError::PathDoesNotExist,
+        ));
+    }
+
+    #[cfg(target_os = "macos")]
+    return macos::get_file_icon_native(path);
+
+    #[cfg(target_os = "windows")]
+    return window::get_file_icon_native(path);
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    Err(AppInfoError::UnsupportedPlatform)
+}
+
+/// Lists the sizes, as `(width, height)` in pixels, of the images stored in the icon of a
+/// file or app, sorted ascending and without duplicates.
+///
+/// On macOS these are the elements of the bundle's `.icns` file, or of an `.icns` file
+/// itself; apps whose icon lives in an asset catalog report the Finder icon's bitmap
+/// representations instead. On Windows they are the frames of an `.ico` file or of the
+/// first icon group in an executable or DLL, and other files report the shell's image
+/// list tiers. Pick a size from the list to avoid upscaling. A file with no icon of its
+/// own gives an empty vector.
+pub fn get_available_icon_sizes(path: impl AsRef<std::path::Path>) -> Result<Vec<(u32, u32)>> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Err(AppInfoError::FileIconError(
+            error::FileIconError::PathDoesNotExist,
+        ));
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    {
+        #[cfg(target_os = "macos")]
+        let mut sizes = macos::get_available_icon_sizes(path)?;
+        #[cfg(target_os = "windows")]
+        let mut sizes = window::get_available_icon_sizes(path)?;
+        sizes.sort_unstable();
+        sizes.dedup();
+        Ok(sizes)
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    Err(AppInfoError::UnsupportedPlatform)
+}
+
+/// Gets the icon for a given file path at an arbitrary, possibly non-square, size.
+///
+/// The returned icon is always exactly `width` x `height` pixels; `fit` decides how the
+/// source image is scaled into that area.
+pub fn get_file_icon_sized(
+    path: impl AsRef<std::path::Path>,
+    width: u16,
+    height: u16,
+    fit: FitMode,
+) -> Result<Icon> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Err(AppInfoError::FileIconError(
+            error::FileIconError::PathDoesNotExist,
+        ));
+    }
+
+    if width == 0 || height == 0 {
+        return Err(AppInfoError::FileIconError(
+            error::FileIconError::NullIconSize,
+        ));
+    }
+
+    #[cfg(target_os = "macos")]
+    return macos::get_file_icon_sized(path, width, height, fit);
+
+    #[cfg(target_os = "windows")]
+    return window::get_file_icon_sized(path, width, height, fit);
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let _ = fit;
+        Err(AppInfoError::UnsupportedPlatform)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    fn test_get_installed_apps() {
+        // Test without icons
+        let apps = get_installed_apps(0).expect("Failed to get installed apps");
+        assert!(!apps.is_empty(), "Should find at least one application");
+        println!("Applications found: {:?}", apps);
+
+        // Test with icons
+        let apps_with_icons =
+            get_installed_apps(32).expect("Failed to get installed apps with icons");
+        assert!(!apps_with_icons.is_empty());
+
+        // On supported platforms, at least one app should have an icon
+        #[cfg(feature = "icons")]
+        {
+            let app_with_icon = apps_with_icons.iter().find(|app| app.icon.is_some());
+            assert!(
+                app_with_icon.is_some(),
+                "At least one app should have an icon"
+            );
+
+            if let Some(app) = app_with_icon {
+                let icon = app.icon.as_ref().unwrap();
+                assert_eq!(icon.width, 32);
+                assert_eq!(icon.height, 32);
+                assert!(!icon.pixels.is_empty());
+                assert_eq!(icon.pixels.len(), (32 * 32 * 4) as usize);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    fn test_scan_stats() {
+        let report = scan_apps(&ScanOptions {
+            icon_size: 32,
+            ..Default::default()
+        })
+        .expect("Failed to scan");
+        // The built-in source reports its counts even when it fails on this machine
+        assert_eq!(report.stats.sources.len(), 1);
+
+        let stats = &report.stats.sources[0];
+        if report.errors.is_empty() {
+            assert_eq!(stats.apps + stats.filtered + stats.failed, stats.enumerated);
+            assert_eq!(stats.apps - stats.deduplicated, report.apps.len());
+        }
+        assert!(stats.icons <= stats.apps);
+        assert!(stats.enumeration + stats.metadata + stats.icon_extraction <= stats.duration);
+        assert!(stats.duration <= report.stats.duration);
+        println!("Scan statistics: {:?}", report.stats);
+    }
+
+    #[test]
+    #[cfg(all(any(target_os = "macos", target_os = "windows"), feature = "icons"))]
+    fn test_scan_compressed_icons() {
+        let report = scan_apps(&ScanOptions {
+            icon_size: 256,
+            icon_storage: IconStorage::Png,
+            ..Default::default()
+        })
+        .expect("Failed to scan");
+        let compressed: Vec<&CompressedIcon> = report
+            .apps
+            .iter()
+            .filter_map(|app| app.compressed_icon.as_ref())
+            .collect();
+        assert!(
+            !compressed.is_empty(),
+            "At least one app should have an icon"
+        );
+        assert!(report.apps.iter().all(|app| app.icon.is_none()));
+
+        let raw = compressed.len() * 256 * 256 * 4;
+        let stored: usize = compressed.iter().map(|icon| icon.png.len()).sum();
+        println!("{} bytes of PNG for {} bytes of pixels", stored, raw);
+        assert!(stored * 2 < raw);
+
+        let icon = compressed[0].decode().expect("Failed to decode icon");
+        assert_eq!((icon.width, icon.height), (256, 256));
+        assert_eq!(icon.pixels.len(), 256 * 256 * 4);
+    }
+
+    #[test]
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    fn test_scan_locations() {
+        let missing = std::env::temp_dir().join("app-info-missing-applications");
+        let report = scan_apps(&ScanOptions {
+            extra_directories: vec![missing.clone()],
+            ..Default::default()
+        })
+        .expect("Failed to scan");
+        let status = |wanted: &ScanLocation| {
+            report
+                .locations()
+                .find(|visited| visited.location == *wanted)
+                .map(|visited| visited.status.clone())
+        };
+
+        for location in default_sources() {
+            assert!(status(&location).is_some(), "{} not reported", location);
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            assert!(macos::default_scan_directories().contains(&PathBuf::from("/Applications")));
+            let applications = ScanLocation::Directory {
+                path: PathBuf::from("/Applications"),
+                scope: Some(InstallScope::Machine),
+            };
+            assert_eq!(status(&applications), Some(LocationStatus::Scanned));
+            let extra = ScanLocation::Directory {
+                path: missing,
+                scope: None,
+            };
+            assert_eq!(status(&extra), Some(LocationStatus::NotPresent));
+        }
+
+        #[cfg(target_os = "windows")]
+        assert!(default_sources()
+            .iter()
+            .any(|location| location.scope() == Some(InstallScope::User)));
+    }
+
+    #[test]
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    fn test_lookup_app() {
+        let scan_started = std::time::Instant::now();
+        let apps = get_installed_apps(0).unwrap();
+        let scan_time = scan_started.elapsed();
+
+        let Some(expected) = apps.iter().find(|app| app.identifier.is_some()) else {
+            return;
+        };
+        let identifier = expected.identifier.as_deref().unwrap();
+
+        let lookup_started = std::time::Instant::now();
+        let found = lookup_app(identifier, 0).expect("Failed to look up app");
+        let lookup_time = lookup_started.elapsed();
+        assert_eq!(found.identifier.as_deref(), Some(identifier));
+        assert!(
+            lookup_time <= scan_time,
+            "lookup took {:?}, a full scan {:?}",
+            lookup_time,
+            scan_time
+        );
+
+        let all = lookup_all_apps(identifier, 0).unwrap();
+        assert!(!all.is_empty());
+        assert_eq!(all[0].path, found.path);
+
+        assert!(matches!(
+            lookup_app("com.example.this-app-surely-does-not-exist", 0),
+            Err(AppInfoError::AppNotFound { .. })
+        ));
+        assert!(
+            lookup_all_apps("com.example.this-app-surely-does-not-exist", 0)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    /// A uniquely named scratch directory under the system temp directory.
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    fn scratch_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("app-info-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).expect("Failed to create scratch directory");
+        dir
+    }
+
+    #[test]
+    #[cfg(target_os = "macos")]
+    fn test_get_app_info() {
+        let dir = scratch_dir("get-app-info");
+        let bundle = dir.join("Synthetic.app");
+        std::fs::create_dir_all(bundle.join("Contents")).unwrap();
+        std::fs::write(
+            bundle.join("Contents/Info.plist"),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+    <key>CFBundleName</key>
+    <string>Synthetic</string>
+    <key>CFBundleIdentifier</key>
+    <string>com.example.synthetic</string>
+    <key>CFBundleShortVersionString</key>
+    <string>1.2.3</string>
+</dict>
+</plist>"#,
+        )
+        .unwrap();
+
+        let app = get_app_info(&bundle, 0).expect("Failed to parse synthetic bundle");
+        assert_eq!(app.name, "Synthetic");
+        assert_eq!(app.version.as_deref(), Some("1.2.3"));
+        assert_eq!(app.identifier.as_deref(), Some("com.example.synthetic"));
+        assert_eq!(app.path, bundle);
+        assert!(app.icon.is_none());
+
+        // Directories that are not bundles, and bundles without an Info.plist
+        let empty_bundle = dir.join("Empty.app");
+        std::fs::create_dir_all(&empty_bundle).unwrap();
+        for path in [dir.clone(), empty_bundle, dir.join("Missing.app")] {
+            assert!(matches!(
+                get_app_info(&path, 0),
+                Err(AppInfoError::BundleParseError { .. })
+            ));
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    #[cfg(target_os = "windows")]
+    fn test_get_app_info() {
+        let current_exe = std::env::current_exe().expect("Failed to get current executable");
+        let app = get_app_info(&current_exe, 0).expect("Failed to describe own executable");
+        assert!(!app.name.is_empty());
+        assert!(app.executable_path.is_some());
+
+        // A directory without executables and a file that is not one
+        let dir = scratch_dir("get-app-info");
+        let text_file = dir.join("readme.txt");
+        std::fs::write(&text_file, "not an application").unwrap();
+        for path in [dir.clone(), text_file, dir.join("missing.exe")] {
+            assert!(matches!(
+                get_app_info(&path, 0),
+                Err(AppInfoError::BundleParseError { .. })
+            ));
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    fn test_find_app_by_name() {
+        let apps = get_installed_apps(0).unwrap();
+        if apps.is_empty() {
+            // Skip this test if no apps are installed
+            return;
+        }
+
+        // Test with the first app from the list
+        let first_app_name = &apps[0].name;
+        let found_app = find_app_by_name(first_app_name, 0).unwrap();
+        assert_eq!(found_app.name, *first_app_name);
+
+        // Test case-insensitivity
+        let found_app_lower = find_app_by_name(&first_app_name.to_lowercase(), 0).unwrap();
+        assert_eq!(found_app_lower.name, *first_app_name);
+
+        // Test for an app that doesn't exist
+        let not_found_result = find_app_by_name("ThisAppSurelyDoesNotExist12345", 0);
+        assert!(matches!(
+            not_found_result,
+            Err(AppInfoError::AppNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_find_apps_matching_glob() {
+        // The pattern is checked before anything is scanned, on every platform
+        assert!(matches!(
+            find_apps_matching_glob("Adobe [", 0),
+            Err(AppInfoError::InvalidPattern { .. })
+        ));
+
+        #[cfg(any(target_os = "macos", target_os = "windows"))]
+        {
+            let apps = get_installed_apps(0).unwrap();
+            let Some(first) = apps.first() else {
+                return;
+            };
+            let prefix: String = first.name.chars().take(3).collect();
+            let pattern = format!("{}*", prefix.replace(['*', '?', '[', '\\'], "?"));
+            let found = find_apps_matching_glob(&pattern.to_uppercase(), 0).unwrap();
+            assert!(found.iter().any(|app| app.name == first.name));
+        }
+    }
+
+    #[test]
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    fn test_find_apps_by_category() {
+        let apps = get_installed_apps(0).unwrap();
+        let mut groups: std::collections::HashMap<AppCategory, usize> =
+            std::collections::HashMap::new();
+        for category in apps.iter().filter_map(|app| app.category.clone()) {
+            *groups.entry(category).or_default() += 1;
+        }
+
+        // Every categorized app lands in exactly one group
+        let categorized = apps.iter().filter(|app| app.category.is_some()).count();
+        assert_eq!(groups.values().sum::<usize>(), categorized);
+        for (category, count) in groups {
+            let found = find_apps_by_category(category.clone(), 0).unwrap();
+            assert_eq!(found.len(), count, "{}", category);
+        }
+
+        let unused = AppCategory::Other("ThisCategorySurelyDoesNotExist12345".to_string());
+        assert!(find_apps_by_category(unused, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_attach_winget_packages() {
+        let mut apps = get_installed_apps(0).unwrap_or_default();
+        let attached = match attach_winget_packages(&mut apps) {
+            Ok(attached) => attached,
+            // winget is installed but unusable, e.g. without network access for its source
+            Err(AppInfoError::PackageManagerError(message)) if cfg!(target_os = "windows") => {
+                println!("winget failed: {}", message);
+                return;
+            }
+            Err(err) => panic!("Failed to attach winget packages: {}", err),
+        };
+        let with_package = apps
+            .iter()
+            .filter(|app| app.package_manager.is_some())
+            .count();
+        assert_eq!(attached, with_package);
+
+        #[cfg(not(target_os = "windows"))]
+        assert_eq!(attached, 0);
+    }
+
+    #[test]
+    fn test_executable_matching() {
+        let app = |name: &str, executable: &str| AppInfo {
+            name: name.to_string(),
+            path: PathBuf::from("/opt"),
+            executable_path: Some(PathBuf::from(executable)),
+            ..Default::default()
+        };
+        let code = app("Visual Studio Code", "C:/Programs/VS Code/Code.exe");
+        assert_eq!(executable_stem("Code.EXE"), "Code");
+        assert_eq!(executable_stem("Electron"), "Electron");
+        assert_eq!(executable_stem(".exe"), "");
+        assert!(executable_matches(&code, "Code", true));
+        assert!(executable_matches(&code, "code", false));
+        assert!(!executable_matches(&code, "code", true));
+        assert!(!executable_matches(&code, "Cod", false));
+
+        // Registry entries often only know the executable as their path
+        let installer_exe = AppInfo {
+            name: "Tool".to_string(),
+            path: PathBuf::from("C:/Tools/tool.exe"),
+            ..Default::default()
+        };
+        assert!(executable_matches(&installer_exe, "tool", false));
+
+        let mut apps = [
+            app(
+                "Slack Helper",
+                "/Applications/Slack.app/Contents/Frameworks/Slack Helper.app/Contents/MacOS/Electron",
+            ),
+            app("Notes Tool", "/Applications/Notes Tool.app/Contents/MacOS/Electron"),
+            app("Electron", "/Applications/Electron.app/Contents/MacOS/Electron"),
+        ];
+        apps.sort_by_cached_key(|app| executable_rank(app, "Electron"));
+        let names: Vec<&str> = apps.iter().map(|app| app.name.as_str()).collect();
+        assert_eq!(names, ["Electron", "Notes Tool", "Slack Helper"]);
+    }
+
+    #[test]
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    fn test_find_app_by_executable() {
+        let apps = get_installed_apps(0).unwrap();
+        let Some((expected, file_name)) = apps.iter().find_map(|app| {
+            let file_name = app_executable(app)?.file_name()?.to_str()?;
+            Some((app, file_name.to_string()))
+        }) else {
+            return;
+        };
+
+        let found = find_app_by_executable(&file_name, 0).expect("Failed to find app");
+        assert!(found
+            .iter()
+            .any(|app| app.name == expected.name && app.path == expected.path));
+
+        let stem = executable_stem(&file_name);
+        let found = find_app_by_executable_with(&stem.to_uppercase(), 0, false).unwrap();
+        assert!(found.iter().any(|app| app.name == expected.name));
+
+        let missing = find_app_by_executable("ThisExecutableSurelyDoesNotExist12345.exe", 0);
+        assert!(missing.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_apps_matching_name() {
+        let app = |name: &str, version: &str, path: &str| AppInfo {
+            name: name.to_string(),
+            version: Some(version.to_string()),
+            path: PathBuf::from(path),
+            ..Default::default()
+        };
+        let apps = || {
+            vec![
+                app("Python", "3.12", "/opt/python3.12"),
+                app("Docker Desktop", "4.30", "/opt/docker-desktop"),
+                app("python", "3.11", "/opt/python3.11"),
+                app("Docker", "24.0", "/opt/docker"),
+                app("ÉDITEUR", "1.0", "/opt/editeur"),
+            ]
+        };
+        let found = |name: &str, mode: MatchMode| -> Vec<(String, Option<String>)> {
+            apps_matching_name(apps(), name, mode)
+                .into_iter()
+                .map(|app| (app.name, app.version))
+                .collect()
+        };
+        let entry = |name: &str, version: &str| (name.to_string(), Some(version.to_string()));
+
+        // Duplicate names are all returned, in a stable order
+        assert_eq!(
+            found("PYTHON", MatchMode::Exact),
+            [entry("Python", "3.12"), entry("python", "3.11")]
+        );
+        assert_eq!(found("docker", MatchMode::Exact), [entry("Docker", "24.0")]);
+        assert_eq!(
+            found("Dock", MatchMode::Prefix),
+            [entry("Docker", "24.0"), entry("Docker Desktop", "4.30")]
+        );
+        assert_eq!(
+            found("desk", MatchMode::Contains),
+            [entry("Docker Desktop", "4.30")]
+        );
+        assert_eq!(
+            found("éditeur", MatchMode::Exact),
+            [entry("ÉDITEUR", "1.0")]
+        );
+        assert!(found("Desk", MatchMode::Prefix).is_empty());
+
+        for mode in [MatchMode::Exact, MatchMode::Prefix, MatchMode::Contains] {
+            assert!(found("", mode).is_empty());
+            assert!(found("  ", mode).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_apps_matching_publisher() {
+        let app = |name: &str, publisher: Option<&str>| AppInfo {
+            name: name.to_string(),
+            publisher: publisher.map(str::to_string),
+            ..Default::default()
+        };
+        let apps = || {
+            vec![
+                app("Edge", Some("Microsoft Corporation")),
+                app("Teams", Some("Microsoft Corp.")),
+                app("Terminal", Some(" microsoft corporation ")),
+                app("Chrome", Some("Google LLC")),
+                app("Unknown", None),
+            ]
+        };
+        let found = |publisher: &str, normalized: bool| -> Vec<String> {
+            apps_matching_publisher(apps(), publisher, normalized)
+                .into_iter()
+                .map(|app| app.name)
+                .collect()
+        };
+
+        assert_eq!(found("MICROSOFT CORPORATION", false), ["Edge", "Terminal"]);
+        assert_eq!(found("Microsoft", false), Vec::<String>::new());
+        assert_eq!(found("Microsoft", true), ["Edge", "Teams", "Terminal"]);
+        assert_eq!(found("Google Inc.", true), ["Chrome"]);
+        assert!(found("", true).is_empty());
+        assert!(found(" ", false).is_empty());
+    }
+
+    #[test]
+    #[cfg(target_os = "macos")]
+    fn test_find_apps_by_name_prefix() {
+        let found = find_apps_by_name("Calc", MatchMode::Prefix, 0).unwrap();
+        assert!(found
+            .iter()
+            .all(|app| app.name.to_lowercase().starts_with("calc")));
+        assert!(found.iter().any(|app| app.name == "Calculator"));
+    }
+
+    #[test]
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    fn test_get_running_apps() {
+        let all = get_running_apps_filtered(0, RunningAppFilter::All)
+            .expect("Failed to get running apps");
+        assert!(
+            !all.is_empty(),
+            "At least this test process should be running"
+        );
+        assert!(all.iter().all(|running| running.app.icon.is_none()));
+
+        // Regular apps are a subset of all inspectable processes
+        let regular = get_running_apps(0).expect("Failed to get running apps");
+        assert!(regular.len() <= all.len());
+        assert!(regular
+            .iter()
+            .all(|running| all.iter().any(|other| other.pid == running.pid)));
+    }
+
+    #[test]
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    fn test_get_frontmost_app() {
+        match get_frontmost_app(0) {
+            Ok(app) => {
+                assert!(!app.name.is_empty());
+                assert!(app.icon.is_none());
+            }
+            // Headless sessions (CI services, SSH logins) have nothing in the foreground
+            Err(AppInfoError::NoForegroundApp) => {}
+            Err(e) => panic!("Failed to get frontmost app: {}", e),
+        }
+    }
+
+    #[test]
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    fn test_get_startup_apps() {
+        let startup_apps = get_startup_apps(0).expect("Failed to get startup apps");
+        for startup_app in &startup_apps {
+            assert!(!startup_app.name.is_empty());
+            assert!(!startup_app.app.name.is_empty());
+            assert!(startup_app.app.icon.is_none());
+        }
+    }
+
+    #[test]
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    fn test_get_usage_for_apps() {
+        let apps = get_installed_apps(0).expect("Failed to get installed apps");
+        let usage = get_usage_for_apps(&apps).expect("Failed to get app usage");
+        assert_eq!(usage.len(), apps.len());
+
+        let recorded = get_app_usage().expect("Failed to get app usage");
+        for entry in &recorded {
+            assert!(!entry.identifier_or_path.is_empty());
+        }
+    }
+
+    #[test]
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    fn test_get_app_for_pid() {
+        let app = get_app_for_pid(std::process::id(), 0).expect("Failed to resolve own pid");
+        let current_exe = std::env::current_exe()
+            .and_then(|path| path.canonicalize())
+            .expect("Failed to get current executable");
+        assert_eq!(app.path.canonicalize().ok(), Some(current_exe));
+
+        assert!(matches!(
+            get_app_for_pid(u32::MAX - 1, 0),
+            Err(AppInfoError::ProcessNotFound { pid }) if pid == u32::MAX - 1
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "icons")]
+    fn test_get_icon_for_pid() {
+        assert!(matches!(
+            get_icon_for_pid(std::process::id(), 0),
+            Err(AppInfoError::FileIconError(
+                error::FileIconError::NullIconSize
+            ))
+        ));
+
+        #[cfg(any(target_os = "macos", target_os = "windows"))]
+        {
+            let icon = get_icon_for_pid(std::process::id(), 32).expect("Failed to get own icon");
+            assert_eq!((icon.width, icon.height), (32, 32));
+            assert_eq!(icon.pixels.len(), 32 * 32 * 4);
+
+            assert!(matches!(
+                get_icon_for_pid(u32::MAX - 1, 32),
+                Err(AppInfoError::ProcessNotFound { pid }) if pid == u32::MAX - 1
+            ));
+        }
+    }
+
+    #[test]
+    #[cfg(all(target_os = "macos", feature = "icons"))]
+    fn test_get_icon_for_pid_finder() {
+        let finder = get_running_apps(0)
+            .unwrap()
+            .into_iter()
+            .find(|running| running.app.identifier.as_deref() == Some("com.apple.finder"));
+        // Finder is not running in every session, such as over SSH
+        if let Some(finder) = finder {
+            let icon = get_icon_for_pid(finder.pid, 64).expect("Failed to get Finder's icon");
+            assert_eq!((icon.width, icon.height), (64, 64));
+            assert!(icon.pixels.chunks_exact(4).any(|pixel| pixel[3] > 0));
+        }
+    }
+
+    #[test]
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    fn test_is_running() {
+        let current_exe = std::env::current_exe().expect("Failed to get current executable");
+        let this_process = AppInfo {
+            name: "Test runner".to_string(),
+            path: current_exe.clone(),
+            executable_path: Some(current_exe),
+            ..Default::default()
+        };
+        assert!(this_process.is_running().expect("Failed to check process"));
+
+        let missing = AppInfo {
+            name: "Missing".to_string(),
+            path: PathBuf::from("/path/to/non/existent/file"),
+            ..Default::default()
+        };
+        assert!(!missing.is_running().expect("Failed to check process"));
+        assert!(!AppInfo::default()
+            .is_running()
+            .expect("Failed to check process"));
+    }
+
+    #[test]
+    fn test_launch_missing_app() {
+        let missing = AppInfo {
+            name: "Missing".to_string(),
+            path: PathBuf::from("/path/to/non/existent/file"),
+            ..Default::default()
+        };
+        let result = missing.launch();
+
+        #[cfg(any(target_os = "macos", target_os = "windows"))]
+        assert!(matches!(
+            result,
+            Err(AppInfoError::ExecutableNotFound { .. })
+        ));
+
+        #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+        assert!(matches!(result, Err(AppInfoError::UnsupportedPlatform)));
+    }
+
+    #[test]
+    fn test_normalize_url_scheme() {
+        assert_eq!(normalize_url_scheme("mailto"), "mailto");
+        assert_eq!(normalize_url_scheme("MAILTO:"), "mailto");
+        assert_eq!(normalize_url_scheme(" Slack:// "), "slack");
+        assert_eq!(normalize_url_scheme("://"), "");
+    }
+
+    #[test]
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    fn test_get_apps_for_url_scheme() {
+        let unregistered = "thisschemesurelydoesnotexist12345://";
+        let apps = get_apps_for_url_scheme(unregistered, 0).expect("Failed to query scheme");
+        assert!(apps.is_empty());
+        assert!(matches!(
+            get_default_app_for_url_scheme(unregistered, 0),
+            Err(AppInfoError::UrlSchemeNotHandled { scheme }) if scheme == "thisschemesurelydoesnotexist12345"
+        ));
+
+        // When a default handler exists it is also listed first
+        if let Ok(default_app) = get_default_app_for_url_scheme("HTTPS://", 0) {
+            let apps = get_apps_for_url_scheme("https", 0).expect("Failed to query scheme");
+            assert_eq!(apps.first().map(|app| &app.path), Some(&default_app.path));
+        }
+    }
+
+    #[test]
+    fn test_get_apps_for_file() {
+        let missing = std::env::temp_dir().join("app-info-no-such-file-12345.txt");
+        assert!(matches!(
+            get_apps_for_file(&missing, 0),
+            Err(AppInfoError::FileIconError(
+                error::FileIconError::PathDoesNotExist
+            ))
+        ));
+
+        let text_file =
+            std::env::temp_dir().join(format!("app-info-open-with-{}.txt", std::process::id()));
+        std::fs::write(&text_file, "hello").unwrap();
+        let apps = get_apps_for_file(&text_file, 0);
+        let _ = std::fs::remove_file(&text_file);
+
+        if cfg!(any(target_os = "macos", target_os = "windows")) {
+            assert!(!apps.expect("Failed to query handlers").is_empty());
+        } else {
+            assert!(matches!(apps, Err(AppInfoError::UnsupportedPlatform)));
+        }
+    }
+
+    #[test]
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    fn test_file_associations() {
+        let path_to_test = if cfg!(target_os = "macos") {
+            "/System/Applications/TextEdit.app"
+        } else {
+            "C:\\Windows\\System32\\notepad.exe"
+        };
+        if !std::path::Path::new(path_to_test).exists() {
+            return;
+        }
+
+        let app = AppInfo {
+            name: "Test".to_string(),
+            path: PathBuf::from(path_to_test),
+            ..Default::default()
+        };
+        let associations = app
+            .file_associations()
+            .expect("Failed to read file associations");
+        assert!(associations
+            .iter()
+            .all(|association| association.extension.is_some() != association.uti.is_some()));
+    }
+
+    #[test]
+    #[cfg(all(
+        any(target_os = "macos", target_os = "windows"),
+        not(feature = "icons")
+    ))]
+    fn test_icons_disabled() {
+        let disabled = |result: Result<Icon>| {
+            matches!(
+                result,
+                Err(AppInfoError::FileIconError(
+                    error::FileIconError::FeatureDisabled
+                ))
+            )
+        };
+        let path = env!("CARGO_MANIFEST_DIR");
+        assert!(disabled(get_file_icon(path, 32)));
+        assert!(disabled(get_file_icon_native(path)));
+        assert!(disabled(get_icon_for_extension("pdf", 32)));
+        assert!(get_file_icons(&[path], 32).into_iter().all(disabled));
+
+        // Scans still work and simply carry no icons
+        let apps = get_installed_apps(32).expect("Failed to get installed apps");
+        assert!(apps.iter().all(|app| app.icon.is_none()));
+    }
+
+    #[test]
+    #[cfg(feature = "icons")]
+    fn test_get_file_icon() {
+        // Choose a path that is likely to exist on different platforms
+        let path_to_test = if cfg!(target_os = "macos") {
+            "/System/Applications/Calculator.app"
+        } else if cfg!(target_os = "windows") {
+            "C:\\Windows\\System32\\notepad.exe"
+        } else {
+            // On other OS, we don't have a default path, so skip the rest of the test
+            return;
+        };
+
+        let path = std::path::Path::new(path_to_test);
+
+        // Test with size 0, should return an error
+        let result = get_file_icon(path, 0);
+        assert!(matches!(
+            result,
+            Err(AppInfoError::FileIconError(
+                error::FileIconError::NullIconSize
+            ))
+        ));
+
+        // Test with a non-existent path
+        let result = get_file_icon("/path/to/non/existent/file", 32);
+        assert!(matches!(
+            result,
+            Err(AppInfoError::FileIconError(
+                error::FileIconError::PathDoesNotExist
+            ))
+        ));
 
         // If the test path exists, test getting a real icon
         if path.exists() {
@@ -202,4 +2305,329 @@ mod tests {
             assert_eq!(icon.pixels.len(), (64 * 64 * 4) as usize);
         }
     }
+
+    #[test]
+    #[cfg(feature = "icons")]
+    fn test_get_file_icon_sized() {
+        let path_to_test = if cfg!(target_os = "macos") {
+            "/System/Applications/Calculator.app"
+        } else if cfg!(target_os = "windows") {
+            "C:\\Windows\\System32\\notepad.exe"
+        } else {
+            return;
+        };
+
+        let result = get_file_icon_sized(path_to_test, 64, 0, FitMode::Contain);
+        assert!(matches!(
+            result,
+            Err(AppInfoError::FileIconError(
+                error::FileIconError::NullIconSize
+            ))
+        ));
+
+        if std::path::Path::new(path_to_test).exists() {
+            for fit in [FitMode::Stretch, FitMode::Contain, FitMode::Cover] {
+                let icon = get_file_icon_sized(path_to_test, 64, 48, fit)
+                    .expect("Failed to get sized file icon");
+                assert_eq!((icon.width, icon.height), (64, 48));
+                assert_eq!(icon.pixels.len(), 64 * 48 * 4);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "icons")]
+    fn test_get_file_icon_native() {
+        let path_to_test = if cfg!(target_os = "macos") {
+            "/System/Applications/Calculator.app"
+        } else if cfg!(target_os = "windows") {
+            "C:\\Windows\\System32\\notepad.exe"
+        } else {
+            return;
+        };
+
+        let result = get_file_icon_native("/path/to/non/existent/file");
+        assert!(matches!(
+            result,
+            Err(AppInfoError::FileIconError(
+                error::FileIconError::PathDoesNotExist
+            ))
+        ));
+
+        if std::path::Path::new(path_to_test).exists() {
+            let icon = get_file_icon_native(path_to_test).expect("Failed to get native icon");
+            assert!(icon.width >= 128, "native icon was only {}px", icon.width);
+            assert_eq!(icon.width, icon.height);
+            assert_eq!(icon.pixels.len(), (icon.width * icon.height * 4) as usize);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "icons")]
+    fn test_get_available_icon_sizes() {
+        assert!(matches!(
+            get_available_icon_sizes("/path/to/non/existent/file"),
+            Err(AppInfoError::FileIconError(
+                error::FileIconError::PathDoesNotExist
+            ))
+        ));
+
+        let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        if cfg!(target_os = "macos") {
+            let sizes = get_available_icon_sizes(fixtures.join("app.icns")).unwrap();
+            assert_eq!(
+                sizes,
+                [(16, 16), (32, 32), (128, 128), (256, 256), (1024, 1024)]
+            );
+        } else if cfg!(target_os = "windows") {
+            let sizes = get_available_icon_sizes(fixtures.join("app.ico")).unwrap();
+            assert_eq!(
+                sizes,
+                [(16, 16), (32, 32), (48, 48), (256, 256), (512, 512)]
+            );
+            let notepad = get_available_icon_sizes("C:\\Windows\\System32\\notepad.exe");
+            assert!(notepad.is_ok_and(|sizes| sizes.windows(2).all(|pair| pair[0] < pair[1])));
+        } else {
+            assert!(matches!(
+                get_available_icon_sizes(fixtures.join("app.ico")),
+                Err(AppInfoError::UnsupportedPlatform)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_load_icons() {
+        fn assert_send<T: Send + Sync>() {}
+        assert_send::<AppInfo>();
+        assert_send::<Icon>();
+
+        let icon = |width: u32| Icon {
+            width,
+            height: width,
+            pixels: vec![255; (width * width * 4) as usize],
+        };
+        let apps = [
+            AppInfo {
+                icon: Some(icon(4)),
+                ..Default::default()
+            },
+            AppInfo {
+                icon_source: Some(IconSource::File(PathBuf::from(
+                    "/path/to/non/existent/file",
+                ))),
+                ..Default::default()
+            },
+            AppInfo::default(),
+            AppInfo {
+                compressed_icon: Some(icon(2).compress().unwrap()),
+                icon_source: Some(IconSource::File(PathBuf::from("/also/missing"))),
+                ..Default::default()
+            },
+        ];
+
+        let icons = load_icons(&apps, 32);
+        assert_eq!(icons.len(), apps.len());
+        assert_eq!(icons[0].as_ref().unwrap().width, 4);
+        assert!(matches!(
+            icons[1],
+            Err(AppInfoError::FileIconError(
+                error::FileIconError::PathDoesNotExist
+            ))
+        ));
+        assert!(matches!(
+            icons[2],
+            Err(AppInfoError::FileIconError(error::FileIconError::Failed))
+        ));
+        assert_eq!(icons[3].as_ref().unwrap().pixels, icon(2).pixels);
+    }
+
+    #[test]
+    #[cfg(feature = "icons")]
+    fn test_get_file_icons() {
+        // The crate directory exists on every platform and always has a folder icon
+        let existing = env!("CARGO_MANIFEST_DIR");
+        let missing = "/path/to/non/existent/file";
+        let paths = [existing, missing, existing, existing, missing];
+
+        let icons = get_file_icons(&paths, 32);
+        assert_eq!(icons.len(), paths.len());
+        for slot in [1, 4] {
+            assert!(matches!(
+                icons[slot],
+                Err(AppInfoError::FileIconError(
+                    error::FileIconError::PathDoesNotExist
+                ))
+            ));
+        }
+        // Where icons cannot be read, each copy of the file fails like the first
+        for slot in [2, 3] {
+            if let (Err(first), Err(copy)) = (&icons[0], &icons[slot]) {
+                assert_eq!(copy.to_string(), first.to_string());
+            }
+        }
+
+        #[cfg(any(target_os = "macos", target_os = "windows"))]
+        {
+            let first = icons[0].as_ref().expect("Failed to get batch icon");
+            assert_eq!((first.width, first.height), (32, 32));
+            for icon in [&icons[2], &icons[3]] {
+                assert_eq!(icon.as_ref().unwrap().pixels, first.pixels);
+            }
+        }
+
+        let zero_sized = get_file_icons(&paths, 0);
+        assert!(zero_sized.iter().all(|icon| matches!(
+            icon,
+            Err(AppInfoError::FileIconError(
+                error::FileIconError::NullIconSize
+            ))
+        )));
+    }
+
+    #[test]
+    fn test_fan_out_icons() {
+        let icon = Icon {
+            width: 1,
+            height: 1,
+            pixels: vec![0, 0, 0, 255],
+        };
+        let io_error = AppInfoError::DirectoryReadError(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            "denied",
+        ));
+        let slots = [Some(1), Some(0), None, Some(1), Some(0)];
+        let icons = fan_out_icons(&slots, vec![Ok(icon.clone()), Err(io_error)]);
+        assert_eq!(icons.len(), slots.len());
+
+        // Every slot of a file fails the same way
+        for slot in [0, 3] {
+            assert!(matches!(
+                &icons[slot],
+                Err(AppInfoError::DirectoryReadError(e))
+                    if e.kind() == std::io::ErrorKind::PermissionDenied && e.to_string() == "denied"
+            ));
+        }
+        for slot in [1, 4] {
+            assert_eq!(icons[slot].as_ref().unwrap().pixels, icon.pixels);
+        }
+        assert!(matches!(
+            icons[2],
+            Err(AppInfoError::FileIconError(
+                error::FileIconError::PathDoesNotExist
+            ))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "icons")]
+    fn test_get_icon_for_extension() {
+        let result = get_icon_for_extension("pdf", 0);
+        assert!(matches!(
+            result,
+            Err(AppInfoError::FileIconError(
+                error::FileIconError::NullIconSize
+            ))
+        ));
+
+        #[cfg(any(target_os = "macos", target_os = "windows"))]
+        {
+            for extension in ["pdf", "zip", ".PDF", "thisextensiondoesnotexist"] {
+                let icon =
+                    get_icon_for_extension(extension, 32).expect("Failed to get extension icon");
+                assert_eq!((icon.width, icon.height), (32, 32));
+                assert_eq!(icon.pixels.len(), 32 * 32 * 4);
+                assert!(icon.pixels.chunks_exact(4).any(|pixel| pixel[3] > 0));
+            }
+        }
+    }
+
+    #[test]
+    fn test_capabilities() {
+        fn supported<T>(result: Result<T>) -> bool {
+            !matches!(result, Err(AppInfoError::UnsupportedPlatform))
+        }
+
+        let caps = capabilities();
+        let crate_dir = env!("CARGO_MANIFEST_DIR");
+        let missing = AppInfo {
+            path: PathBuf::from("/path/to/non/existent/file"),
+            ..Default::default()
+        };
+        assert_eq!(supported(get_installed_apps(0)), caps.installed_apps);
+        assert_eq!(
+            supported(find_app_by_name("app-info", 0)),
+            caps.installed_apps
+        );
+        assert_eq!(supported(get_app_info(crate_dir, 0)), caps.app_info);
+        // The crate directory has a folder icon wherever icons work
+        assert_eq!(get_file_icon(crate_dir, 16).is_ok(), caps.file_icons);
+        assert_eq!(supported(get_running_apps(0)), caps.running_apps);
+        assert_eq!(supported(missing.launch()), caps.launch);
+        let dry_run = UninstallOptions {
+            dry_run: true,
+            ..Default::default()
+        };
+        assert_eq!(supported(missing.uninstall(dry_run)), caps.uninstall);
+        assert_eq!(
+            supported(AppWatcher::new(WatcherOptions::default())),
+            caps.watch
+        );
+        assert_eq!(supported(get_startup_apps(0)), caps.startup_apps);
+        assert_eq!(supported(get_app_usage()), caps.usage);
+        assert_eq!(
+            supported(get_apps_for_url_scheme("https", 0)),
+            caps.url_schemes
+        );
+        assert_eq!(
+            supported(missing.file_associations()),
+            caps.file_associations
+        );
+        assert_eq!(
+            supported(get_signature_info(std::env::current_exe().unwrap())),
+            caps.signatures
+        );
+        assert!(!caps.wmi || caps.signatures);
+
+        // Every stub reports the same error, icons included
+        #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+        {
+            assert_eq!(
+                caps,
+                Capabilities {
+                    installed_apps: false,
+                    app_info: false,
+                    file_icons: false,
+                    running_apps: false,
+                    launch: false,
+                    uninstall: false,
+                    watch: false,
+                    startup_apps: false,
+                    usage: false,
+                    url_schemes: false,
+                    file_associations: false,
+                    signatures: false,
+                    package_managers: false,
+                    wmi: false,
+                }
+            );
+            let unsupported =
+                |result: Result<Icon>| matches!(result, Err(AppInfoError::UnsupportedPlatform));
+            assert!(unsupported(get_file_icon(crate_dir, 16)));
+            assert!(unsupported(get_file_icon_native(crate_dir)));
+            assert!(unsupported(get_icon_for_extension("txt", 16)));
+            assert!(unsupported(get_icon_for_pid(std::process::id(), 16)));
+            assert!(get_file_icons(&[crate_dir], 16)
+                .into_iter()
+                .all(unsupported));
+            assert!(matches!(
+                get_available_icon_sizes(crate_dir),
+                Err(AppInfoError::UnsupportedPlatform)
+            ));
+            assert!(!supported(get_frontmost_app(0)));
+            assert!(!supported(get_app_for_pid(std::process::id(), 0)));
+            assert!(!supported(lookup_app("app-info", 0)));
+            assert!(!supported(missing.is_running()));
+            assert!(!supported(get_usage_for_apps(&[])));
+        }
+    }
 }
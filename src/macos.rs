@@ -1,16 +1,37 @@
 #[cfg(target_os = "macos")]
-use crate::{error::AppInfoError, AppInfo, Icon, Result};
+use crate::kind::{macos_kind, BundleSignals};
+#[cfg(target_os = "macos")]
+use crate::{
+    error::AppInfoError, normalize_url_scheme, AppCategory, AppInfo, AppSource, AppUsage,
+    AssociationRole, FileAssociation, FitMode, Icon, IconSource, InstallScope, LaunchOptions,
+    LaunchedApp, LocationStatus, Result, RunningApp, RunningAppFilter, ScanContext, ScanLocation,
+    ScanPhase, StartupApp, StartupMechanism, UninstallOptions, UninstallOutcome, UninstallPlan,
+};
+#[cfg(target_os = "macos")]
+use block2::RcBlock;
 #[cfg(target_os = "macos")]
 use objc2::{
-    class, msg_send_id,
-    rc::{Allocated, Id},
+    class, msg_send, msg_send_id,
+    rc::{autoreleasepool, Allocated, Id},
+    runtime::NSObjectProtocol,
+    sel,
 };
 #[cfg(target_os = "macos")]
 use objc2_app_kit::{
-    NSBitmapImageRep, NSCompositingOperation, NSGraphicsContext, NSImage, NSWorkspace,
+    NSApplicationActivationPolicy, NSRunningApplication, NSWorkspace, NSWorkspaceOpenConfiguration,
+};
+#[cfg(all(target_os = "macos", feature = "icons"))]
+use objc2_app_kit::{
+    NSBitmapImageRep, NSCompositingOperation, NSGraphicsContext, NSImage, NSRectFillUsingOperation,
+};
+#[cfg(all(target_os = "macos", feature = "icons"))]
+use objc2_foundation::{CGFloat, CGPoint, CGRect, CGSize};
+#[cfg(target_os = "macos")]
+use objc2_foundation::{
+    NSArray, NSError, NSFileManager, NSMetadataItem, NSString, NSUserCancelledError, NSURL,
 };
 #[cfg(target_os = "macos")]
-use objc2_foundation::{CGFloat, CGPoint, CGRect, CGSize, NSString};
+use std::ffi::OsString;
 #[cfg(target_os = "macos")]
 use std::fs;
 #[cfg(target_os = "macos")]
@@ -19,43 +40,270 @@ use std::path::{Path, PathBuf};
 /// Gets all installed applications on macOS by scanning standard application directories.
 #[cfg(target_os = "macos")]
 pub fn get_installed_apps(icon_size: u16) -> Result<Vec<AppInfo>> {
-    let mut apps = Vec::new();
+    ApplicationDirectoriesSource.scan(&ScanContext::new(icon_size))
+}
+
+/// The built-in source that scans the standard application directories for bundles.
+#[cfg(target_os = "macos")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ApplicationDirectoriesSource;
 
-    // Search the /Applications directory
-    let applications_dir = Path::new("/Applications");
-    if applications_dir.exists() {
-        apps.extend(scan_directory(applications_dir, icon_size)?);
+#[cfg(target_os = "macos")]
+impl AppSource for ApplicationDirectoriesSource {
+    fn name(&self) -> &str {
+        "macos-applications"
     }
 
-    // Search the /System/Applications directory
-    let system_apps_dir = Path::new("/System/Applications");
-    if system_apps_dir.exists() {
-        apps.extend(scan_directory(system_apps_dir, icon_size)?);
+    fn scan(&self, ctx: &ScanContext) -> Result<Vec<AppInfo>> {
+        let mut apps = Vec::new();
+
+        let extra = ctx
+            .extra_directories()
+            .iter()
+            .map(|path| ScanLocation::Directory {
+                path: path.clone(),
+                scope: None,
+            });
+        for location in default_scan_locations().into_iter().chain(extra) {
+            if ctx.is_cancelled() {
+                break;
+            }
+            let ScanLocation::Directory { path, .. } = &location else {
+                continue;
+            };
+            match scan_directory(path, ctx, apps.len()) {
+                Ok(found) => {
+                    apps.extend(found);
+                    ctx.record_location(location, LocationStatus::Scanned);
+                }
+                Err(error) => {
+                    debug_event!(directory = %path.display(), error = %error, "skipping directory");
+                    ctx.record_location(location, LocationStatus::from_io_error(&error));
+                }
+            }
+        }
+
+        Ok(apps)
     }
+}
+
+/// The directories the built-in source searches for application bundles, in scan order:
+/// `/Applications` and `/System/Applications` for the whole machine, then the user's
+/// `~/Applications`. [`default_sources`](crate::default_sources) tells them apart.
+#[cfg(target_os = "macos")]
+pub fn default_scan_directories() -> Vec<PathBuf> {
+    default_scan_locations()
+        .into_iter()
+        .filter_map(|location| match location {
+            ScanLocation::Directory { path, .. } => Some(path),
+            _ => None,
+        })
+        .collect()
+}
 
-    // Search the user's Applications directory
+/// [`default_scan_directories`] with the scope of each.
+#[cfg(target_os = "macos")]
+pub(crate) fn default_scan_locations() -> Vec<ScanLocation> {
+    let machine = |path: &str| ScanLocation::Directory {
+        path: PathBuf::from(path),
+        scope: Some(InstallScope::Machine),
+    };
+    let mut locations = vec![machine("/Applications"), machine("/System/Applications")];
+
+    // The user's Applications directory
     if let Some(home_dir) = std::env::var_os("HOME") {
-        let user_apps = PathBuf::from(home_dir).join("Applications");
-        if user_apps.exists() {
-            apps.extend(scan_directory(&user_apps, icon_size)?);
+        locations.push(ScanLocation::Directory {
+            path: PathBuf::from(home_dir).join("Applications"),
+            scope: Some(InstallScope::User),
+        });
+    }
+
+    locations
+}
+
+/// Number of independently watched scopes (one per application directory) for the app
+/// watcher.
+#[cfg(target_os = "macos")]
+pub(crate) fn watched_scope_count() -> usize {
+    default_scan_directories().len()
+}
+
+/// Rescans a single application directory for the app watcher. Bundles already in
+/// `previous` whose Info.plist has not changed since `since` are reused rather than
+/// parsed again.
+#[cfg(target_os = "macos")]
+pub(crate) fn scan_scope(
+    scope: usize,
+    previous: &[AppInfo],
+    since: std::time::SystemTime,
+) -> Vec<AppInfo> {
+    let Some(directory) = default_scan_directories().into_iter().nth(scope) else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(&directory) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|s| s.to_str()) == Some("app"))
+        .filter_map(|path| {
+            let modified = fs::metadata(path.join("Contents/Info.plist"))
+                .and_then(|metadata| metadata.modified())
+                .ok();
+            let unchanged = modified.is_some_and(|modified| modified < since);
+
+            match previous.iter().find(|app| app.path == path) {
+                Some(app) if unchanged => Some(app.clone()),
+                _ => parse_app_bundle(&path, 0).ok(),
+            }
+        })
+        .collect()
+}
+
+/// Waits for changes to the application directories with kqueue.
+///
+/// Only the directories themselves are watched, so bundles being added, removed or
+/// renamed are noticed; an in-place update is picked up once its installer replaces the
+/// bundle.
+#[cfg(target_os = "macos")]
+pub(crate) struct ChangeMonitor {
+    queue: libc::c_int,
+    descriptors: Vec<libc::c_int>,
+}
+
+#[cfg(target_os = "macos")]
+impl ChangeMonitor {
+    pub(crate) fn new() -> Result<Self> {
+        use std::os::unix::ffi::OsStrExt;
+
+        let queue = unsafe { libc::kqueue() };
+        if queue < 0 {
+            return Err(AppInfoError::WatcherError(
+                std::io::Error::last_os_error().to_string(),
+            ));
+        }
+
+        let mut monitor = ChangeMonitor {
+            queue,
+            descriptors: Vec::new(),
+        };
+
+        for (scope, directory) in default_scan_directories().iter().enumerate() {
+            let Ok(path) = std::ffi::CString::new(directory.as_os_str().as_bytes()) else {
+                continue;
+            };
+            let descriptor = unsafe { libc::open(path.as_ptr(), libc::O_EVTONLY) };
+            if descriptor < 0 {
+                continue;
+            }
+
+            let change = libc::kevent {
+                ident: descriptor as libc::uintptr_t,
+                filter: libc::EVFILT_VNODE,
+                flags: libc::EV_ADD | libc::EV_CLEAR,
+                fflags: libc::NOTE_WRITE
+                    | libc::NOTE_EXTEND
+                    | libc::NOTE_DELETE
+                    | libc::NOTE_RENAME,
+                data: 0,
+                udata: scope as *mut libc::c_void,
+            };
+            let registered = unsafe {
+                libc::kevent(queue, &change, 1, std::ptr::null_mut(), 0, std::ptr::null())
+            };
+
+            if registered < 0 {
+                unsafe { libc::close(descriptor) };
+                continue;
+            }
+            monitor.descriptors.push(descriptor);
         }
+
+        if monitor.descriptors.is_empty() {
+            return Err(AppInfoError::WatcherError(
+                "No application directory could be watched".to_string(),
+            ));
+        }
+
+        Ok(monitor)
     }
 
-    Ok(apps)
+    /// Blocks for up to `timeout` and returns the scopes that changed.
+    pub(crate) fn wait(&mut self, timeout: std::time::Duration) -> Vec<usize> {
+        let timeout = libc::timespec {
+            tv_sec: timeout.as_secs() as libc::time_t,
+            tv_nsec: timeout.subsec_nanos() as libc::c_long,
+        };
+        let mut events: [libc::kevent; 8] = unsafe { std::mem::zeroed() };
+
+        let count = unsafe {
+            libc::kevent(
+                self.queue,
+                std::ptr::null(),
+                0,
+                events.as_mut_ptr(),
+                events.len() as libc::c_int,
+                &timeout,
+            )
+        };
+        if count <= 0 {
+            return Vec::new();
+        }
+
+        let mut scopes: Vec<usize> = events[..count as usize]
+            .iter()
+            .map(|event| event.udata as usize)
+            .collect();
+        scopes.sort_unstable();
+        scopes.dedup();
+        scopes
+    }
 }
 
-/// Scans a directory for .app bundles and parses them.
 #[cfg(target_os = "macos")]
-fn scan_directory(dir: &Path, icon_size: u16) -> Result<Vec<AppInfo>> {
+impl Drop for ChangeMonitor {
+    fn drop(&mut self) {
+        unsafe {
+            for &descriptor in &self.descriptors {
+                libc::close(descriptor);
+            }
+            libc::close(self.queue);
+        }
+    }
+}
+
+/// Scans a directory for .app bundles and parses them. `found_before` counts the apps
+/// earlier directories contributed, for progress reports.
+#[cfg(target_os = "macos")]
+fn scan_directory(
+    dir: &Path,
+    ctx: &ScanContext,
+    found_before: usize,
+) -> std::io::Result<Vec<AppInfo>> {
     let mut apps = Vec::new();
 
-    for entry in fs::read_dir(dir)? {
-        let entry = entry?;
+    let entries = ctx.time(ScanPhase::Enumeration, || {
+        fs::read_dir(dir)?.collect::<std::io::Result<Vec<_>>>()
+    })?;
+    for entry in entries {
+        if ctx.is_cancelled() {
+            break;
+        }
         let path = entry.path();
 
         if path.extension().and_then(|s| s.to_str()) == Some("app") {
-            if let Ok(app_info) = parse_app_bundle(&path, icon_size) {
-                apps.push(app_info);
+            ctx.count_enumerated(1);
+            match ctx.time(ScanPhase::Metadata, || read_app_bundle(&path, ctx)) {
+                Ok(app_info) => {
+                    apps.push(app_info);
+                    ctx.report_progress(found_before + apps.len());
+                }
+                Err(_err) => {
+                    ctx.count_failed();
+                    debug_event!(path = %path.display(), error = %_err, "skipping bundle");
+                }
             }
         }
     }
@@ -63,25 +311,78 @@ fn scan_directory(dir: &Path, icon_size: u16) -> Result<Vec<AppInfo>> {
     Ok(apps)
 }
 
-/// Parses an application bundle (.app) to extract its information.
+/// Looks up the application LaunchServices prefers for a bundle identifier on macOS.
 #[cfg(target_os = "macos")]
-fn parse_app_bundle(app_path: &Path, icon_size: u16) -> Result<AppInfo> {
-    let info_plist_path = app_path.join("Contents/Info.plist");
+pub fn lookup_app(identifier: &str, icon_size: u16) -> Result<AppInfo> {
+    let not_found = || AppInfoError::AppNotFound {
+        name: identifier.to_string(),
+    };
 
-    if !info_plist_path.exists() {
+    let bundle_identifier = NSString::from_str(identifier);
+    let app_url = unsafe {
+        NSWorkspace::sharedWorkspace().URLForApplicationWithBundleIdentifier(&bundle_identifier)
+    }
+    .ok_or_else(not_found)?;
+    let path = file_url_path(&app_url).ok_or_else(not_found)?;
+    parse_app_bundle(&path, icon_size)
+}
+
+/// Looks up every application LaunchServices knows for a bundle identifier on macOS,
+/// the preferred one first.
+#[cfg(target_os = "macos")]
+pub fn lookup_all_apps(identifier: &str, icon_size: u16) -> Result<Vec<AppInfo>> {
+    let workspace = unsafe { NSWorkspace::sharedWorkspace() };
+    let bundle_identifier = NSString::from_str(identifier);
+
+    let mut paths: Vec<PathBuf> =
+        unsafe { workspace.URLForApplicationWithBundleIdentifier(&bundle_identifier) }
+            .and_then(|app_url| file_url_path(&app_url))
+            .into_iter()
+            .collect();
+
+    // Listing every installation needs macOS 12
+    if workspace.respondsToSelector(sel!(URLsForApplicationsWithBundleIdentifier:)) {
+        let app_urls =
+            unsafe { workspace.URLsForApplicationsWithBundleIdentifier(&bundle_identifier) };
+        for index in 0..app_urls.count() {
+            let app_url = unsafe { app_urls.objectAtIndex(index) };
+            if let Some(path) = file_url_path(&app_url) {
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+    }
+
+    Ok(paths
+        .iter()
+        .filter_map(|path| parse_app_bundle(path, icon_size).ok())
+        .collect())
+}
+
+/// Parses a single application bundle on macOS.
+#[cfg(target_os = "macos")]
+pub fn get_app_info(path: &Path, icon_size: u16) -> Result<AppInfo> {
+    let is_bundle = path.extension().and_then(|s| s.to_str()) == Some("app") && path.is_dir();
+    if !is_bundle {
         return Err(AppInfoError::BundleParseError {
-            path: app_path.display().to_string(),
+            path: path.display().to_string(),
         });
     }
+    parse_app_bundle(path, icon_size)
+}
 
-    // Read Info.plist
-    let plist_data = fs::read(&info_plist_path)?;
-    let plist: plist::Value =
-        plist::from_bytes(&plist_data).map_err(|e| AppInfoError::PlistError(e.to_string()))?;
+/// Parses an application bundle (.app) to extract its information.
+#[cfg(target_os = "macos")]
+fn parse_app_bundle(app_path: &Path, icon_size: u16) -> Result<AppInfo> {
+    read_app_bundle(app_path, &ScanContext::new(icon_size))
+}
 
-    let dict = plist
-        .as_dictionary()
-        .ok_or_else(|| AppInfoError::PlistError("Invalid plist format".to_string()))?;
+/// Parses an application bundle, timing its icon through `ctx`.
+#[cfg(target_os = "macos")]
+fn read_app_bundle(app_path: &Path, ctx: &ScanContext) -> Result<AppInfo> {
+    enter_span!(DEBUG, "parse_app_bundle", path = %app_path.display());
+    let dict = read_info_plist(app_path)?;
 
     // Extract application information
     let name = dict
@@ -107,27 +408,1111 @@ fn parse_app_bundle(app_path: &Path, icon_size: u16) -> Result<AppInfo> {
         .and_then(|v| v.as_string())
         .map(|s| s.to_string());
 
+    let executable_path = dict
+        .get("CFBundleExecutable")
+        .and_then(|v| v.as_string())
+        .map(|executable| app_path.join("Contents/MacOS").join(executable))
+        .filter(|path| path.exists());
+
+    let category = dict
+        .get("LSApplicationCategoryType")
+        .and_then(|v| v.as_string())
+        .filter(|category| !category.is_empty())
+        .map(AppCategory::parse);
+
+    let kind = macos_kind(&BundleSignals {
+        package_type: dict.get("CFBundlePackageType").and_then(|v| v.as_string()),
+        ui_element: plist_flag(&dict, "LSUIElement"),
+        background_only: plist_flag(&dict, "LSBackgroundOnly"),
+        has_icon: ["CFBundleIconFile", "CFBundleIconName"].iter().any(|key| {
+            dict.get(key)
+                .and_then(|v| v.as_string())
+                .is_some_and(|name| !name.is_empty())
+        }) || dict.contains_key("CFBundleIcons"),
+    });
+
+    let (declared_url_schemes, declared_file_types) = if ctx.read_declarations() {
+        (parse_url_types(&dict), parse_document_types(&dict))
+    } else {
+        (Vec::new(), Vec::new())
+    };
+
     // Get the icon
-    let icon = if icon_size > 0 {
-        get_file_icon(app_path, icon_size).ok()
+    let icon_size = ctx.icon_size();
+    let icon = if ctx.extracts_icons() {
+        ctx.time(ScanPhase::Icons, || get_file_icon(app_path, icon_size).ok())
     } else {
         None
     };
 
-    Ok(AppInfo {
+    let mut app = AppInfo {
         name,
         version,
         path: app_path.to_path_buf(),
-        icon,
+        executable_path,
+        icon: None,
+        compressed_icon: None,
+        icon_source: Some(IconSource::File(app_path.to_path_buf())),
         identifier,
         publisher: None, // Publisher info is not typically stored in Info.plist on macOS
         install_date: None, // Can be obtained from the file system, but requires extra implementation
+        parsed_install_date: None,
+        install_date_ambiguous: false,
+        install_size: None,
+        category,
+        kind,
+        package_manager: None,
+        install_scope: None,
+        registry_view: None,
+        owner: None,
+        can_uninstall: None,
+        can_modify: None,
+        can_repair: None,
+        declared_url_schemes,
+        declared_file_types,
+        signature: None,
+        name_inferred: false,
+    };
+    ctx.store_icon(&mut app, icon);
+    Ok(app)
+}
+
+/// Gets the running applications on macOS from `NSWorkspace.runningApplications`.
+#[cfg(target_os = "macos")]
+pub fn get_running_apps(icon_size: u16, filter: RunningAppFilter) -> Result<Vec<RunningApp>> {
+    autoreleasepool(|_| {
+        let workspace = unsafe { NSWorkspace::sharedWorkspace() };
+        let applications = unsafe { workspace.runningApplications() };
+
+        let mut running = Vec::new();
+        for index in 0..applications.count() {
+            let application = unsafe { applications.objectAtIndex(index) };
+            let is_regular =
+                unsafe { application.activationPolicy() } == NSApplicationActivationPolicy::Regular;
+            if filter == RunningAppFilter::Regular && !is_regular {
+                continue;
+            }
+
+            // Applications that are still launching have no process yet
+            let pid = unsafe { application.processIdentifier() };
+            if pid <= 0 || unsafe { application.isTerminated() } {
+                continue;
+            }
+
+            if let Some(app) = running_application_info(&application, icon_size) {
+                running.push(RunningApp {
+                    app,
+                    pid: pid as u32,
+                    is_active: unsafe { application.isActive() },
+                    is_hidden: unsafe { application.isHidden() },
+                });
+            }
+        }
+        Ok(running)
     })
 }
 
-/// Gets the icon for a given file path on macOS.
+/// Gets the application that currently has focus on macOS.
+#[cfg(target_os = "macos")]
+pub fn get_frontmost_app(icon_size: u16) -> Result<AppInfo> {
+    autoreleasepool(|_| {
+        let workspace = unsafe { NSWorkspace::sharedWorkspace() };
+        let application =
+            unsafe { workspace.frontmostApplication() }.ok_or(AppInfoError::NoForegroundApp)?;
+        running_application_info(&application, icon_size).ok_or(AppInfoError::NoForegroundApp)
+    })
+}
+
+/// Gets the application a process belongs to on macOS.
+///
+/// GUI applications are resolved through `NSRunningApplication`; other processes are
+/// described from their executable, or from the `.app` bundle that contains it.
+#[cfg(target_os = "macos")]
+pub fn get_app_for_pid(pid: u32, icon_size: u16) -> Result<AppInfo> {
+    let not_found = || AppInfoError::ProcessNotFound { pid };
+    let pid_t = libc::pid_t::try_from(pid).map_err(|_| not_found())?;
+
+    let from_application = autoreleasepool(|_| {
+        unsafe { NSRunningApplication::runningApplicationWithProcessIdentifier(pid_t) }
+            .and_then(|application| running_application_info(&application, icon_size))
+    });
+    if let Some(app) = from_application {
+        return Ok(app);
+    }
+
+    let executable = process_executable_path(pid_t).ok_or_else(not_found)?;
+    if let Some(app) = enclosing_app_bundle(&executable)
+        .and_then(|bundle| parse_app_bundle(&bundle, icon_size).ok())
+    {
+        return Ok(app);
+    }
+
+    let icon = if icon_size > 0 {
+        get_file_icon(&executable, icon_size).ok()
+    } else {
+        None
+    };
+    Ok(AppInfo {
+        name: executable
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or("Unknown")
+            .to_string(),
+        version: None,
+        path: executable.clone(),
+        executable_path: Some(executable),
+        icon,
+        compressed_icon: None,
+        icon_source: None,
+        identifier: None,
+        publisher: None,
+        install_date: None,
+        parsed_install_date: None,
+        install_date_ambiguous: false,
+        install_size: None,
+        category: None,
+        kind: None,
+        package_manager: None,
+        install_scope: None,
+        registry_view: None,
+        owner: None,
+        can_uninstall: None,
+        can_modify: None,
+        can_repair: None,
+        declared_url_schemes: Vec::new(),
+        declared_file_types: Vec::new(),
+        signature: None,
+        name_inferred: false,
+    })
+}
+
+/// Checks whether an application is running on macOS: by bundle identifier first, then
+/// by bundle location, then by comparing every process's executable.
+#[cfg(target_os = "macos")]
+pub fn is_running(app: &AppInfo) -> Result<bool> {
+    let found = autoreleasepool(|_| unsafe {
+        if let Some(identifier) = &app.identifier {
+            let identifier = NSString::from_str(identifier);
+            if NSRunningApplication::runningApplicationsWithBundleIdentifier(&identifier).count()
+                > 0
+            {
+                return true;
+            }
+        }
+
+        // Copies of a bundle that share an identifier are told apart by location
+        let Some(bundle_path) = app.path.canonicalize().ok().filter(|path| path.is_dir()) else {
+            return false;
+        };
+        let applications = NSWorkspace::sharedWorkspace().runningApplications();
+        (0..applications.count()).any(|index| {
+            applications
+                .objectAtIndex(index)
+                .bundleURL()
+                .and_then(|url| file_url_path(&url))
+                .and_then(|path| path.canonicalize().ok())
+                .is_some_and(|path| path == bundle_path)
+        })
+    });
+    if found {
+        return Ok(true);
+    }
+
+    // Processes without a bundle only show up in the process table
+    let executable = app
+        .executable_path
+        .as_deref()
+        .or_else(|| Some(app.path.as_path()).filter(|path| path.is_file()))
+        .and_then(|path| path.canonicalize().ok());
+    let Some(executable) = executable else {
+        return Ok(false);
+    };
+    Ok(all_process_ids().into_iter().any(|pid| {
+        process_executable_path(pid)
+            .and_then(|path| path.canonicalize().ok())
+            .is_some_and(|path| path == executable)
+    }))
+}
+
+/// Lists the IDs of all processes on the system.
+#[cfg(target_os = "macos")]
+fn all_process_ids() -> Vec<libc::pid_t> {
+    // A null buffer returns the current process count; leave room for new processes
+    let count = unsafe { libc::proc_listallpids(std::ptr::null_mut(), 0) };
+    if count <= 0 {
+        return Vec::new();
+    }
+
+    let mut pids: Vec<libc::pid_t> = vec![0; count as usize + 64];
+    let size = (pids.len() * std::mem::size_of::<libc::pid_t>()) as libc::c_int;
+    let count = unsafe { libc::proc_listallpids(pids.as_mut_ptr().cast(), size) };
+    pids.truncate(count.max(0) as usize);
+    pids
+}
+
+/// How long to wait for LaunchServices to report that an application has started.
+#[cfg(target_os = "macos")]
+const LAUNCH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Launches an application on macOS. Bundles go through `NSWorkspace`; anything else is
+/// spawned as a plain executable.
+#[cfg(target_os = "macos")]
+pub fn launch(app: &AppInfo, args: &[OsString], options: &LaunchOptions) -> Result<LaunchedApp> {
+    let is_bundle = app.path.extension().and_then(|s| s.to_str()) == Some("app");
+    if is_bundle && app.path.is_dir() {
+        return open_application(&app.path, args, options);
+    }
+
+    let executable = app
+        .executable_path
+        .as_deref()
+        .or_else(|| Some(app.path.as_path()).filter(|path| path.is_file()))
+        .filter(|path| path.is_file())
+        .ok_or_else(|| AppInfoError::ExecutableNotFound {
+            path: app.path.display().to_string(),
+        })?;
+    spawn_executable(executable, args, options)
+}
+
+/// Opens an application bundle through `NSWorkspace` and waits for it to start.
+#[cfg(target_os = "macos")]
+fn open_application(
+    bundle_path: &Path,
+    args: &[OsString],
+    options: &LaunchOptions,
+) -> Result<LaunchedApp> {
+    use std::sync::mpsc;
+
+    let bundle_string = bundle_path
+        .to_str()
+        .ok_or_else(|| AppInfoError::ExecutableNotFound {
+            path: bundle_path.display().to_string(),
+        })?;
+
+    let (sender, receiver) = mpsc::channel::<Result<LaunchedApp>>();
+    let handler = RcBlock::new(
+        move |application: *mut NSRunningApplication, error: *mut NSError| {
+            let result = match unsafe { (application.as_ref(), error.as_ref()) } {
+                (Some(application), _) => Ok(LaunchedApp {
+                    pid: u32::try_from(unsafe { application.processIdentifier() })
+                        .ok()
+                        .filter(|&pid| pid > 0),
+                }),
+                (None, Some(error)) if error.code() == NSUserCancelledError => {
+                    Err(AppInfoError::LaunchCancelled)
+                }
+                (None, Some(error)) => Err(AppInfoError::LaunchFailed(
+                    error.localizedDescription().to_string(),
+                )),
+                (None, None) => Err(AppInfoError::LaunchFailed(
+                    "No application was launched".to_string(),
+                )),
+            };
+            let _ = sender.send(result);
+        },
+    );
+
+    unsafe {
+        let url = NSURL::fileURLWithPath(&NSString::from_str(bundle_string));
+        let configuration = NSWorkspaceOpenConfiguration::configuration();
+        configuration.setActivates(options.activate && !options.hidden);
+        configuration.setHides(options.hidden);
+        let arguments: Vec<Id<NSString>> = args
+            .iter()
+            .map(|arg| NSString::from_str(&arg.to_string_lossy()))
+            .collect();
+        configuration.setArguments(&NSArray::from_vec(arguments));
+
+        NSWorkspace::sharedWorkspace().openApplicationAtURL_configuration_completionHandler(
+            &url,
+            &configuration,
+            Some(&handler),
+        );
+    }
+
+    // The completion handler runs on a background queue, so blocking here is safe
+    receiver.recv_timeout(LAUNCH_TIMEOUT).unwrap_or_else(|_| {
+        Err(AppInfoError::LaunchFailed(
+            "Timed out waiting for the application to start".to_string(),
+        ))
+    })
+}
+
+/// Spawns a plain executable, reaping it in the background once it exits.
+#[cfg(target_os = "macos")]
+fn spawn_executable(
+    executable: &Path,
+    args: &[OsString],
+    options: &LaunchOptions,
+) -> Result<LaunchedApp> {
+    let mut command = std::process::Command::new(executable);
+    command.args(args);
+    if let Some(working_directory) = &options.working_directory {
+        command.current_dir(working_directory);
+    }
+
+    let mut child = command.spawn().map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => AppInfoError::ExecutableNotFound {
+            path: executable.display().to_string(),
+        },
+        _ => AppInfoError::LaunchFailed(e.to_string()),
+    })?;
+
+    let pid = child.id();
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(LaunchedApp { pid: Some(pid) })
+}
+
+/// Uninstalls an application on macOS by moving its bundle to the Trash, see
+/// [`AppInfo::uninstall`].
+#[cfg(target_os = "macos")]
+pub fn uninstall(app: &AppInfo, options: &UninstallOptions) -> Result<UninstallOutcome> {
+    let is_bundle = app.path.extension().and_then(|s| s.to_str()) == Some("app");
+    if !is_bundle || !app.path.is_dir() {
+        return Err(AppInfoError::UninstallFailed(format!(
+            "{} is not an application bundle",
+            app.path.display()
+        )));
+    }
+    if options.dry_run {
+        return Ok(UninstallOutcome::DryRun(UninstallPlan::MoveToTrash(
+            app.path.clone(),
+        )));
+    }
+
+    let bundle_string = app.path.to_str().ok_or_else(|| {
+        AppInfoError::UninstallFailed(format!("{} is not valid UTF-8", app.path.display()))
+    })?;
+    // NSFileManager trashes synchronously, unlike NSWorkspace's recycle, whose completion
+    // handler needs a run loop the caller may not have
+    let trashed = autoreleasepool(|_| unsafe {
+        let url = NSURL::fileURLWithPath(&NSString::from_str(bundle_string));
+        let mut resulting_url = None;
+        NSFileManager::defaultManager()
+            .trashItemAtURL_resultingItemURL_error(&url, Some(&mut resulting_url))
+            .map(|()| resulting_url.as_deref().and_then(file_url_path))
+            .map_err(|error| {
+                AppInfoError::UninstallFailed(error.localizedDescription().to_string())
+            })
+    })?;
+    Ok(UninstallOutcome::MovedToTrash {
+        path: trashed.unwrap_or_else(|| app.path.clone()),
+    })
+}
+
+/// Gets the executable path of any process the caller may inspect.
+#[cfg(target_os = "macos")]
+fn process_executable_path(pid: libc::pid_t) -> Option<PathBuf> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut buffer = vec![0u8; libc::PROC_PIDPATHINFO_MAXSIZE as usize];
+    let length =
+        unsafe { libc::proc_pidpath(pid, buffer.as_mut_ptr().cast(), buffer.len() as u32) };
+    if length <= 0 {
+        return None;
+    }
+    Some(PathBuf::from(std::ffi::OsStr::from_bytes(
+        &buffer[..length as usize],
+    )))
+}
+
+/// Finds the innermost `.app` bundle that contains a path.
+#[cfg(target_os = "macos")]
+fn enclosing_app_bundle(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .find(|ancestor| {
+            ancestor.extension().and_then(|s| s.to_str()) == Some("app")
+                && ancestor.join("Contents/Info.plist").exists()
+        })
+        .map(Path::to_path_buf)
+}
+
+/// Describes a running application from its bundle, falling back to its executable for
+/// processes that have no readable bundle.
+#[cfg(target_os = "macos")]
+fn running_application_info(application: &NSRunningApplication, icon_size: u16) -> Option<AppInfo> {
+    let bundle_path = unsafe { application.bundleURL() }.and_then(|url| file_url_path(&url));
+    if let Some(mut app) = bundle_path.and_then(|path| parse_app_bundle(&path, icon_size).ok()) {
+        if icon_size > 0 && app.icon.is_none() {
+            app.icon = running_application_icon(application, icon_size);
+        }
+        return Some(app);
+    }
+
+    let executable = unsafe { application.executableURL() }.and_then(|url| file_url_path(&url))?;
+    let name = unsafe { application.localizedName() }
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| {
+            executable
+                .file_name()
+                .and_then(|s| s.to_str())
+                .unwrap_or("Unknown")
+                .to_string()
+        });
+    let icon = if icon_size > 0 {
+        running_application_icon(application, icon_size)
+            .or_else(|| get_file_icon(&executable, icon_size).ok())
+    } else {
+        None
+    };
+
+    Some(AppInfo {
+        name,
+        version: None,
+        path: executable.clone(),
+        executable_path: Some(executable),
+        icon,
+        compressed_icon: None,
+        icon_source: None,
+        identifier: unsafe { application.bundleIdentifier() }.map(|id| id.to_string()),
+        publisher: None,
+        install_date: None,
+        parsed_install_date: None,
+        install_date_ambiguous: false,
+        install_size: None,
+        category: None,
+        kind: None,
+        package_manager: None,
+        install_scope: None,
+        registry_view: None,
+        owner: None,
+        can_uninstall: None,
+        can_modify: None,
+        can_repair: None,
+        declared_url_schemes: Vec::new(),
+        declared_file_types: Vec::new(),
+        signature: None,
+        name_inferred: false,
+    })
+}
+
+/// Gets the applications configured to start at login on macOS: the user's login items
+/// and the launch agents of the user and of the system.
+#[cfg(target_os = "macos")]
+pub fn get_startup_apps(icon_size: u16) -> Result<Vec<StartupApp>> {
+    let mut startup_apps = Vec::new();
+
+    for path in login_item_paths() {
+        let name = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("Unknown")
+            .to_string();
+        startup_apps.push(StartupApp {
+            app: startup_target_info(&path, &name, icon_size),
+            mechanism: StartupMechanism::LoginItem,
+            name,
+            command: None,
+            all_users: false,
+            enabled: true,
+        });
+    }
+
+    let mut agent_directories = Vec::new();
+    if let Some(home_dir) = std::env::var_os("HOME") {
+        agent_directories.push((PathBuf::from(home_dir).join("Library/LaunchAgents"), false));
+    }
+    agent_directories.push((PathBuf::from("/Library/LaunchAgents"), true));
+
+    for (directory, all_users) in agent_directories {
+        let Ok(entries) = fs::read_dir(&directory) else {
+            continue;
+        };
+        let mut plist_paths: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().and_then(|s| s.to_str()) == Some("plist"))
+            .collect();
+        plist_paths.sort();
+
+        for plist_path in plist_paths {
+            let Some(agent) = read_plist_dictionary(&plist_path)
+                .ok()
+                .and_then(|plist| parse_launch_agent(&plist))
+            else {
+                continue;
+            };
+
+            startup_apps.push(StartupApp {
+                app: startup_target_info(&agent.program, &agent.label, icon_size),
+                mechanism: StartupMechanism::LaunchAgent,
+                command: Some(agent.command_line()),
+                name: agent.label,
+                all_users,
+                enabled: !agent.disabled,
+            });
+        }
+    }
+
+    Ok(startup_apps)
+}
+
+/// Lists the user's login items through System Events. Returns nothing when the caller
+/// is not allowed to automate System Events.
+#[cfg(target_os = "macos")]
+fn login_item_paths() -> Vec<PathBuf> {
+    const SCRIPT: &str = r#"set output to ""
+tell application "System Events"
+    repeat with loginItem in login items
+        set output to output & (path of loginItem) & linefeed
+    end repeat
+end tell
+return output"#;
+
+    let output = std::process::Command::new("/usr/bin/osascript")
+        .args(["-e", SCRIPT])
+        .stderr(std::process::Stdio::null())
+        .output();
+
+    match output {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(PathBuf::from)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Describes what a startup entry launches: the enclosing application bundle when there
+/// is one, otherwise the target itself.
+#[cfg(target_os = "macos")]
+fn startup_target_info(target: &Path, name: &str, icon_size: u16) -> AppInfo {
+    if let Some(app) =
+        enclosing_app_bundle(target).and_then(|bundle| parse_app_bundle(&bundle, icon_size).ok())
+    {
+        return app;
+    }
+
+    let icon = if icon_size > 0 {
+        get_file_icon(target, icon_size).ok()
+    } else {
+        None
+    };
+
+    AppInfo {
+        name: name.to_string(),
+        version: None,
+        path: target.to_path_buf(),
+        executable_path: Some(target.to_path_buf()),
+        icon,
+        compressed_icon: None,
+        icon_source: Some(IconSource::File(target.to_path_buf())),
+        identifier: None,
+        publisher: None,
+        install_date: None,
+        parsed_install_date: None,
+        install_date_ambiguous: false,
+        install_size: None,
+        category: None,
+        kind: None,
+        package_manager: None,
+        install_scope: None,
+        registry_view: None,
+        owner: None,
+        can_uninstall: None,
+        can_modify: None,
+        can_repair: None,
+        declared_url_schemes: Vec::new(),
+        declared_file_types: Vec::new(),
+        signature: None,
+        name_inferred: false,
+    }
+}
+
+/// The parts of a launchd property list that identify what it runs.
+#[cfg(target_os = "macos")]
+#[derive(Debug, PartialEq)]
+struct LaunchAgent {
+    label: String,
+    program: PathBuf,
+    arguments: Vec<String>,
+    disabled: bool,
+}
+
+#[cfg(target_os = "macos")]
+impl LaunchAgent {
+    /// The command as launchd runs it.
+    fn command_line(&self) -> String {
+        if self.arguments.is_empty() {
+            self.program.display().to_string()
+        } else {
+            self.arguments.join(" ")
+        }
+    }
+}
+
+/// Parses a launch agent property list. `Program` takes precedence over the first
+/// `ProgramArguments` entry, as it does for launchd.
+#[cfg(target_os = "macos")]
+fn parse_launch_agent(plist: &plist::Dictionary) -> Option<LaunchAgent> {
+    let label = plist.get("Label")?.as_string()?.to_string();
+    let arguments: Vec<String> = plist
+        .get("ProgramArguments")
+        .and_then(|value| value.as_array())
+        .map(|arguments| {
+            arguments
+                .iter()
+                .filter_map(|argument| argument.as_string().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    let program = plist
+        .get("Program")
+        .and_then(|value| value.as_string())
+        .map(str::to_string)
+        .or_else(|| arguments.first().cloned())?;
+
+    Some(LaunchAgent {
+        label,
+        program: PathBuf::from(program),
+        arguments,
+        disabled: plist
+            .get("Disabled")
+            .and_then(|value| value.as_boolean())
+            .unwrap_or(false),
+    })
+}
+
+/// Gets the Spotlight usage data of every installed bundle on macOS.
+#[cfg(target_os = "macos")]
+pub fn get_app_usage() -> Result<Vec<AppUsage>> {
+    let mut bundles = Vec::new();
+    for directory in default_scan_directories() {
+        let Ok(entries) = fs::read_dir(&directory) else {
+            continue;
+        };
+        bundles.extend(
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().and_then(|s| s.to_str()) == Some("app")),
+        );
+    }
+
+    Ok(bundle_usage(&bundles).into_iter().flatten().collect())
+}
+
+/// Gets the Spotlight usage data of each app's bundle on macOS.
+#[cfg(target_os = "macos")]
+pub fn get_usage_for_apps(apps: &[AppInfo]) -> Result<Vec<Option<AppUsage>>> {
+    let bundles: Vec<PathBuf> = apps.iter().map(|app| app.path.clone()).collect();
+    Ok(bundle_usage(&bundles))
+}
+
+/// Reads `kMDItemLastUsedDate` and `kMDItemUseCount` for a batch of bundles. Bundles
+/// Spotlight has not indexed, or has no usage for, yield `None`.
+#[cfg(target_os = "macos")]
+fn bundle_usage(bundles: &[PathBuf]) -> Vec<Option<AppUsage>> {
+    autoreleasepool(|_| {
+        let last_used_key = NSString::from_str("kMDItemLastUsedDate");
+        let use_count_key = NSString::from_str("kMDItemUseCount");
+
+        bundles
+            .iter()
+            .map(|bundle| {
+                let bundle_string = bundle.to_str()?;
+                let url = unsafe { NSURL::fileURLWithPath(&NSString::from_str(bundle_string)) };
+                let allocated: Allocated<NSMetadataItem> =
+                    unsafe { msg_send_id![class!(NSMetadataItem), alloc] };
+                let item = unsafe { NSMetadataItem::initWithURL(allocated, &url) }?;
+
+                let last_used = unsafe { item.valueForAttribute(&last_used_key) }
+                    .filter(|value| unsafe { msg_send![&**value, isKindOfClass: class!(NSDate)] })
+                    .map(|date| {
+                        let seconds: f64 = unsafe { msg_send![&*date, timeIntervalSince1970] };
+                        std::time::UNIX_EPOCH + std::time::Duration::from_secs_f64(seconds.max(0.0))
+                    });
+                let launch_count = unsafe { item.valueForAttribute(&use_count_key) }
+                    .filter(|value| unsafe { msg_send![&**value, isKindOfClass: class!(NSNumber)] })
+                    .map(|count| unsafe { msg_send![&*count, unsignedIntValue] });
+
+                if last_used.is_none() && launch_count.is_none() {
+                    return None;
+                }
+                Some(AppUsage {
+                    identifier_or_path: bundle_string.to_string(),
+                    last_used,
+                    launch_count,
+                })
+            })
+            .collect()
+    })
+}
+
+/// Reads and parses the `Info.plist` of an application bundle.
+#[cfg(target_os = "macos")]
+fn read_info_plist(app_path: &Path) -> Result<plist::Dictionary> {
+    let info_plist_path = app_path.join("Contents/Info.plist");
+
+    if !info_plist_path.exists() {
+        return Err(AppInfoError::BundleParseError {
+            path: app_path.display().to_string(),
+        });
+    }
+
+    read_plist_dictionary(&info_plist_path)
+}
+
+/// Reads a boolean `Info.plist` key, which bundles also write as the string `"1"` or
+/// `"YES"` or as an integer.
+#[cfg(target_os = "macos")]
+fn plist_flag(dict: &plist::Dictionary, key: &str) -> bool {
+    match dict.get(key) {
+        Some(plist::Value::Boolean(value)) => *value,
+        Some(plist::Value::Integer(value)) => value.as_signed().is_some_and(|value| value != 0),
+        Some(plist::Value::String(value)) => {
+            ["1", "yes", "true"].contains(&value.trim().to_ascii_lowercase().as_str())
+        }
+        _ => false,
+    }
+}
+
+/// Reads a property list file whose root is a dictionary.
+#[cfg(target_os = "macos")]
+fn read_plist_dictionary(path: &Path) -> Result<plist::Dictionary> {
+    let plist_data = fs::read(path)?;
+    let plist: plist::Value =
+        plist::from_bytes(&plist_data).map_err(|e| AppInfoError::PlistError(e.to_string()))?;
+
+    plist
+        .into_dictionary()
+        .ok_or_else(|| AppInfoError::PlistError("Invalid plist format".to_string()))
+}
+
+/// Lists the document types an application bundle declares in its `Info.plist`.
+#[cfg(target_os = "macos")]
+pub fn file_associations(app_path: &Path) -> Result<Vec<FileAssociation>> {
+    let dict = read_info_plist(app_path)?;
+    Ok(parse_document_types(&dict))
+}
+
+/// Collects the schemes of every entry in `CFBundleURLTypes`, lowercased and without
+/// repeats.
+#[cfg(target_os = "macos")]
+fn parse_url_types(dict: &plist::Dictionary) -> Vec<String> {
+    let Some(url_types) = dict.get("CFBundleURLTypes").and_then(|v| v.as_array()) else {
+        return Vec::new();
+    };
+
+    let mut schemes: Vec<String> = Vec::new();
+    for url_type in url_types.iter().filter_map(|v| v.as_dictionary()) {
+        let declared = url_type
+            .get("CFBundleURLSchemes")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_string())
+            .map(normalize_url_scheme)
+            .filter(|scheme| !scheme.is_empty());
+        for scheme in declared {
+            if !schemes.contains(&scheme) {
+                schemes.push(scheme);
+            }
+        }
+    }
+    schemes
+}
+
+/// Flattens `CFBundleDocumentTypes` into one association per declared extension and
+/// per declared UTI. Wildcard extensions are skipped, as they name no concrete type.
+#[cfg(target_os = "macos")]
+fn parse_document_types(dict: &plist::Dictionary) -> Vec<FileAssociation> {
+    let Some(document_types) = dict.get("CFBundleDocumentTypes").and_then(|v| v.as_array()) else {
+        return Vec::new();
+    };
+
+    let strings = |document_type: &plist::Dictionary, key: &str| -> Vec<String> {
+        document_type
+            .get(key)
+            .and_then(|v| v.as_array())
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(|v| v.as_string())
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let mut associations: Vec<FileAssociation> = Vec::new();
+    for document_type in document_types.iter().filter_map(|v| v.as_dictionary()) {
+        let role = match document_type
+            .get("CFBundleTypeRole")
+            .and_then(|v| v.as_string())
+        {
+            Some("Editor") => AssociationRole::Editor,
+            Some("Shell") => AssociationRole::Shell,
+            Some("None") => AssociationRole::None,
+            _ => AssociationRole::Viewer,
+        };
+
+        let extensions = strings(document_type, "CFBundleTypeExtensions")
+            .into_iter()
+            .filter(|extension| extension != "*")
+            .map(|extension| FileAssociation {
+                extension: Some(extension.trim_start_matches('.').to_lowercase()),
+                uti: None,
+                role,
+            });
+        let utis = strings(document_type, "LSItemContentTypes")
+            .into_iter()
+            .map(|uti| FileAssociation {
+                extension: None,
+                uti: Some(uti),
+                role,
+            });
+
+        for association in extensions.chain(utis) {
+            if !associations.contains(&association) {
+                associations.push(association);
+            }
+        }
+    }
+    associations
+}
+
+/// Gets every application LaunchServices has registered for a URL scheme, default
+/// handler first. `scheme` is expected to be normalized (lowercase, no `://`).
+#[cfg(target_os = "macos")]
+pub fn get_apps_for_url_scheme(scheme: &str, icon_size: u16) -> Result<Vec<AppInfo>> {
+    let Some(url) = scheme_url(scheme) else {
+        return Ok(Vec::new());
+    };
+    Ok(apps_for_url(&url, icon_size))
+}
+
+/// Gets every application LaunchServices would offer to open a file with, default
+/// handler first.
+#[cfg(target_os = "macos")]
+pub fn get_apps_for_file(path: &Path, icon_size: u16) -> Result<Vec<AppInfo>> {
+    let path_string = path.to_string_lossy();
+    let url = unsafe { NSURL::fileURLWithPath(&NSString::from_str(&path_string)) };
+    Ok(apps_for_url(&url, icon_size))
+}
+
+/// Parses the applications that can open `url`, putting the default one first.
+#[cfg(target_os = "macos")]
+fn apps_for_url(url: &NSURL, icon_size: u16) -> Vec<AppInfo> {
+    let workspace = unsafe { NSWorkspace::sharedWorkspace() };
+    let default_path = unsafe { workspace.URLForApplicationToOpenURL(url) }
+        .and_then(|app_url| file_url_path(&app_url));
+    let handlers = unsafe { workspace.URLsForApplicationsToOpenURL(url) };
+
+    let mut paths: Vec<PathBuf> = default_path.into_iter().collect();
+    for index in 0..handlers.count() {
+        let handler = unsafe { handlers.objectAtIndex(index) };
+        if let Some(path) = file_url_path(&handler) {
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+    }
+
+    paths
+        .iter()
+        .filter_map(|path| parse_app_bundle(path, icon_size).ok())
+        .collect()
+}
+
+/// Gets the application LaunchServices opens a URL scheme with by default.
+/// `scheme` is expected to be normalized (lowercase, no `://`).
 #[cfg(target_os = "macos")]
+pub fn get_default_app_for_url_scheme(scheme: &str, icon_size: u16) -> Result<AppInfo> {
+    let not_handled = || AppInfoError::UrlSchemeNotHandled {
+        scheme: scheme.to_string(),
+    };
+
+    let url = scheme_url(scheme).ok_or_else(not_handled)?;
+    let app_url = unsafe { NSWorkspace::sharedWorkspace().URLForApplicationToOpenURL(&url) }
+        .ok_or_else(not_handled)?;
+    let path = file_url_path(&app_url).ok_or_else(not_handled)?;
+    parse_app_bundle(&path, icon_size)
+}
+
+/// Builds a bare `scheme:` URL to ask LaunchServices about.
+#[cfg(target_os = "macos")]
+fn scheme_url(scheme: &str) -> Option<Id<NSURL>> {
+    unsafe { NSURL::URLWithString(&NSString::from_str(&format!("{}:", scheme))) }
+}
+
+/// Converts a `file://` URL into a filesystem path.
+#[cfg(target_os = "macos")]
+fn file_url_path(url: &NSURL) -> Option<PathBuf> {
+    unsafe { url.path() }.map(|path| PathBuf::from(path.to_string()))
+}
+
+/// Gets the icon for a given file path on macOS.
+#[cfg(all(target_os = "macos", feature = "icons"))]
 pub fn get_file_icon(path: &Path, size: u16) -> Result<Icon> {
+    get_file_icon_sized(path, size, size, FitMode::Stretch)
+}
+
+/// Gets the icon for a given file path on macOS at an arbitrary size.
+#[cfg(all(target_os = "macos", feature = "icons"))]
+pub fn get_file_icon_sized(path: &Path, width: u16, height: u16, fit: FitMode) -> Result<Icon> {
+    enter_span!(DEBUG, "get_file_icon", path = %path.display(), width, height);
+    let image = icon_image_for_file(path)?;
+    render_image(&image, width, height, fit)
+}
+
+/// Gets the icons for several files on macOS inside a single autorelease pool, so the
+/// intermediate images are released as soon as the batch is done.
+#[cfg(all(target_os = "macos", feature = "icons"))]
+pub fn get_file_icons(paths: &[&Path], size: u16) -> Vec<Result<Icon>> {
+    enter_span!(DEBUG, "get_file_icons", count = paths.len(), size);
+    autoreleasepool(|_| {
+        let workspace = unsafe { NSWorkspace::sharedWorkspace() };
+        paths
+            .iter()
+            .map(|path| {
+                let image = icon_image_with_workspace(&workspace, path)?;
+                render_image(&image, size, size, FitMode::Stretch)
+            })
+            .collect()
+    })
+}
+
+/// Gets the icon of a running process on macOS: the one `NSRunningApplication` holds for
+/// applications, and the executable's icon for other processes.
+#[cfg(all(target_os = "macos", feature = "icons"))]
+pub fn get_icon_for_pid(pid: u32, size: u16) -> Result<Icon> {
+    enter_span!(DEBUG, "get_icon_for_pid", pid, size);
+    let not_found = || AppInfoError::ProcessNotFound { pid };
+    let pid_t = libc::pid_t::try_from(pid).map_err(|_| not_found())?;
+
+    let from_application = autoreleasepool(|_| {
+        unsafe { NSRunningApplication::runningApplicationWithProcessIdentifier(pid_t) }
+            .and_then(|application| running_application_icon(&application, size))
+    });
+    if let Some(icon) = from_application {
+        return Ok(icon);
+    }
+
+    let executable = process_executable_path(pid_t).ok_or_else(not_found)?;
+    get_file_icon(&executable, size)
+}
+
+/// Renders the icon an application has while it runs, which can differ from the one in
+/// its bundle. `None` when it has none.
+#[cfg(all(target_os = "macos", feature = "icons"))]
+fn running_application_icon(application: &NSRunningApplication, size: u16) -> Option<Icon> {
+    let image = unsafe { application.icon() }?;
+    render_image(&image, size, size, FitMode::Stretch).ok()
+}
+
+/// Gets the generic icon Finder shows for files with the given extension. Unknown
+/// extensions get the generic document icon. `extension` is expected to be normalized
+/// (no leading dot).
+#[cfg(all(target_os = "macos", feature = "icons"))]
+pub fn get_icon_for_extension(extension: &str, size: u16) -> Result<Icon> {
+    enter_span!(DEBUG, "get_icon_for_extension", extension, size);
+    let file_type = NSString::from_str(extension);
+    // `iconForContentType:` needs a `UTType` from UniformTypeIdentifiers (macOS 11+);
+    // the extension-based lookup still works everywhere and maps unknown types itself.
+    #[allow(deprecated)]
+    let image = unsafe { NSWorkspace::sharedWorkspace().iconForFileType(&file_type) };
+    render_image(&image, size, size, FitMode::Stretch)
+}
+
+/// Gets the largest icon representation available for a file on macOS, rendered at its
+/// own pixel size without any scaling.
+#[cfg(all(target_os = "macos", feature = "icons"))]
+pub fn get_file_icon_native(path: &Path) -> Result<Icon> {
+    enter_span!(DEBUG, "get_file_icon_native", path = %path.display());
+    let image = icon_image_for_file(path)?;
+    let (width, height) = largest_representation_size(&image);
+    render_image(&image, width, height, FitMode::Stretch)
+}
+
+/// Lists the sizes of the images in a bundle's `.icns` file, or in an `.icns` file itself,
+/// in file order. Bundles whose icon lives in an asset catalog, and other files, get the
+/// sizes of the Finder icon's bitmap representations instead.
+#[cfg(all(target_os = "macos", feature = "icons"))]
+pub fn get_available_icon_sizes(path: &Path) -> Result<Vec<(u32, u32)>> {
+    enter_span!(DEBUG, "get_available_icon_sizes", path = %path.display());
+    let sizes = icns_path(path)
+        .and_then(|icns| fs::read(icns).ok())
+        .and_then(|data| crate::icon::icns_sizes(&data))
+        .filter(|sizes| !sizes.is_empty());
+    if let Some(sizes) = sizes {
+        return Ok(sizes);
+    }
+
+    let Ok(image) = icon_image_for_file(path) else {
+        return Ok(Vec::new());
+    };
+    Ok(representation_sizes(&image)
+        .into_iter()
+        .map(|(width, height)| (width as u32, height as u32))
+        .collect())
+}
+
+/// The `.icns` file a path names, or the one its bundle's `CFBundleIconFile` names.
+#[cfg(all(target_os = "macos", feature = "icons"))]
+fn icns_path(path: &Path) -> Option<PathBuf> {
+    if path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("icns"))
+    {
+        return Some(path.to_path_buf());
+    }
+    let info = read_info_plist(path).ok()?;
+    let file_name = icns_file_name(&info)?;
+    Some(path.join("Contents/Resources").join(file_name))
+}
+
+/// The file name of a bundle's `CFBundleIconFile`, which may leave out the extension.
+#[cfg(all(target_os = "macos", feature = "icons"))]
+fn icns_file_name(info: &plist::Dictionary) -> Option<String> {
+    let name = info.get("CFBundleIconFile")?.as_string()?.trim();
+    if name.is_empty() {
+        return None;
+    }
+    Some(if Path::new(name).extension().is_some() {
+        name.to_string()
+    } else {
+        format!("{}.icns", name)
+    })
+}
+
+/// Loads an image file such as a PNG or JPEG, fitted into a `size` x `size` square.
+#[cfg(all(target_os = "macos", feature = "icons"))]
+pub(crate) fn load_image_icon(path: &Path, size: u16) -> Result<Icon> {
+    enter_span!(DEBUG, "load_image_icon", path = %path.display(), size);
+    if !path.exists() {
+        return Err(AppInfoError::FileIconError(
+            crate::error::FileIconError::PathDoesNotExist,
+        ));
+    }
+    let file_path = NSString::from_str(path.to_str().ok_or(AppInfoError::FileIconError(
+        crate::error::FileIconError::Failed,
+    ))?);
+
+    autoreleasepool(|_| {
+        let allocated: Allocated<NSImage> = unsafe { msg_send_id![class!(NSImage), alloc] };
+        let image = unsafe { NSImage::initWithContentsOfFile(allocated, &file_path) }.ok_or(
+            AppInfoError::FileIconError(crate::error::FileIconError::Failed),
+        )?;
+        render_image(&image, size, size, FitMode::Contain)
+    })
+}
+
+/// Looks up the icon image the Finder shows for a file.
+#[cfg(all(target_os = "macos", feature = "icons"))]
+fn icon_image_for_file(path: &Path) -> Result<Id<NSImage>> {
+    let workspace = unsafe { NSWorkspace::sharedWorkspace() };
+    icon_image_with_workspace(&workspace, path)
+}
+
+/// Looks up the icon image for a file through an existing workspace handle.
+#[cfg(all(target_os = "macos", feature = "icons"))]
+fn icon_image_with_workspace(workspace: &NSWorkspace, path: &Path) -> Result<Id<NSImage>> {
     let canonical_path = path
         .canonicalize()
         .map_err(|_| AppInfoError::FileIconError(crate::error::FileIconError::PathDoesNotExist))?;
@@ -138,16 +1523,70 @@ pub fn get_file_icon(path: &Path, size: u16) -> Result<Icon> {
             .ok_or_else(|| AppInfoError::FileIconError(crate::error::FileIconError::Failed))?,
     );
 
+    // Get the file icon
+    Ok(unsafe { workspace.iconForFile(&file_path) })
+}
+
+/// Returns the pixel size of the largest bitmap representation of an image, falling back
+/// to the image's point size when it only has resolution-independent representations.
+#[cfg(all(target_os = "macos", feature = "icons"))]
+fn largest_representation_size(image: &NSImage) -> (u16, u16) {
+    let (width, height) = representation_sizes(image)
+        .into_iter()
+        .max_by_key(|&(width, height)| width * height)
+        .unwrap_or_else(|| {
+            let size = unsafe { image.size() };
+            (size.width.round() as isize, size.height.round() as isize)
+        });
+
+    let clamp = |value: isize| value.clamp(1, u16::MAX as isize) as u16;
+    (clamp(width), clamp(height))
+}
+
+/// Lists the pixel sizes of an image's bitmap representations, leaving out the
+/// resolution-independent ones.
+#[cfg(all(target_os = "macos", feature = "icons"))]
+fn representation_sizes(image: &NSImage) -> Vec<(isize, isize)> {
+    let representations = unsafe { image.representations() };
+    (0..representations.count())
+        .map(|index| unsafe {
+            let rep = representations.objectAtIndex(index);
+            (rep.pixelsWide(), rep.pixelsHigh())
+        })
+        .filter(|&(width, height)| width > 0 && height > 0)
+        .collect()
+}
+
+/// Renders an `NSImage` into a `width` x `height` RGBA bitmap.
+#[cfg(all(target_os = "macos", feature = "icons"))]
+fn render_image(image: &NSImage, width: u16, height: u16, fit: FitMode) -> Result<Icon> {
     unsafe {
-        // Get the shared workspace and the file icon
-        let shared_workspace = NSWorkspace::sharedWorkspace();
-        let image: Id<NSImage> = shared_workspace.iconForFile(&file_path);
-
-        // Set the target size
-        let desired_size = CGSize {
-            width: size as CGFloat,
-            height: size as CGFloat,
-        };
+        // Work out where the image lands on the canvas
+        let source_size = image.size();
+        let (x, y, fit_width, fit_height) = crate::icon::fit_rect(
+            source_size.width.round().max(1.0) as u32,
+            source_size.height.round().max(1.0) as u32,
+            width as u32,
+            height as u32,
+            fit,
+        );
+        let canvas = CGRect::new(
+            CGPoint::ZERO,
+            CGSize {
+                width: width as CGFloat,
+                height: height as CGFloat,
+            },
+        );
+        let destination = CGRect::new(
+            CGPoint {
+                x: x as CGFloat,
+                y: y as CGFloat,
+            },
+            CGSize {
+                width: fit_width as CGFloat,
+                height: fit_height as CGFloat,
+            },
+        );
 
         // Create a bitmap representation
         let bitmap_representation: Id<NSBitmapImageRep> = {
@@ -156,14 +1595,14 @@ pub fn get_file_icon(path: &Path, size: u16) -> Result<Icon> {
             let rep: Id<NSBitmapImageRep> = msg_send_id![
                 allocated,
                 initWithBitmapDataPlanes: std::ptr::null_mut::<*mut u8>(),
-                pixelsWide: size as isize,
-                pixelsHigh: size as isize,
+                pixelsWide: width as isize,
+                pixelsHigh: height as isize,
                 bitsPerSample: 8 as isize,
                 samplesPerPixel: 4 as isize,
                 hasAlpha: true,
                 isPlanar: false,
                 colorSpaceName: &*NSString::from_str("NSDeviceRGBColorSpace"),
-                bytesPerRow: size as isize * 4,
+                bytesPerRow: width as isize * 4,
                 bitsPerPixel: 32 as isize
             ];
             rep
@@ -175,11 +1614,12 @@ pub fn get_file_icon(path: &Path, size: u16) -> Result<Icon> {
         context.saveGraphicsState();
         NSGraphicsContext::setCurrentContext(Some(&context));
 
-        // Draw the icon
-        image.setSize(desired_size);
-        image.drawAtPoint_fromRect_operation_fraction(
-            CGPoint::ZERO,
-            CGRect::new(CGPoint::ZERO, desired_size),
+        // Clear the canvas so letterboxed areas are transparent, then draw the icon.
+        // A zero source rect draws the whole image.
+        NSRectFillUsingOperation(canvas, NSCompositingOperation::Clear);
+        image.drawInRect_fromRect_operation_fraction(
+            destination,
+            CGRect::ZERO,
             NSCompositingOperation::Copy,
             1.0,
         );
@@ -195,10 +1635,295 @@ pub fn get_file_icon(path: &Path, size: u16) -> Result<Icon> {
         )
         .to_vec();
 
-        Ok(Icon {
-            width: size as u32,
-            height: size as u32,
+        // Bitmap representations store premultiplied alpha; the crate returns straight alpha
+        let mut icon = Icon {
+            width: width as u32,
+            height: height as u32,
             pixels,
-        })
+        };
+        icon.unpremultiply_alpha();
+        Ok(icon)
+    }
+}
+
+/// Icon support is not compiled in, so this always fails with
+/// [`FileIconError::FeatureDisabled`](crate::error::FileIconError::FeatureDisabled).
+#[cfg(all(target_os = "macos", not(feature = "icons")))]
+pub fn get_file_icon(path: &Path, size: u16) -> Result<Icon> {
+    let _ = (path, size);
+    Err(crate::icon::icons_disabled())
+}
+
+/// Icon support is not compiled in, so this always fails.
+#[cfg(all(target_os = "macos", not(feature = "icons")))]
+pub fn get_file_icon_sized(path: &Path, width: u16, height: u16, fit: FitMode) -> Result<Icon> {
+    let _ = (path, width, height, fit);
+    Err(crate::icon::icons_disabled())
+}
+
+/// Icon support is not compiled in, so every entry is an error.
+#[cfg(all(target_os = "macos", not(feature = "icons")))]
+pub fn get_file_icons(paths: &[&Path], size: u16) -> Vec<Result<Icon>> {
+    let _ = size;
+    paths
+        .iter()
+        .map(|_| Err(crate::icon::icons_disabled()))
+        .collect()
+}
+
+/// Icon support is not compiled in, so this always fails.
+#[cfg(all(target_os = "macos", not(feature = "icons")))]
+pub fn get_file_icon_native(path: &Path) -> Result<Icon> {
+    let _ = path;
+    Err(crate::icon::icons_disabled())
+}
+
+/// Icon support is not compiled in, so this always fails.
+#[cfg(all(target_os = "macos", not(feature = "icons")))]
+pub fn get_available_icon_sizes(path: &Path) -> Result<Vec<(u32, u32)>> {
+    let _ = path;
+    Err(crate::icon::icons_disabled())
+}
+
+/// Icon support is not compiled in, so this always fails.
+#[cfg(all(target_os = "macos", not(feature = "icons")))]
+pub fn get_icon_for_pid(pid: u32, size: u16) -> Result<Icon> {
+    let _ = (pid, size);
+    Err(crate::icon::icons_disabled())
+}
+
+/// Icon support is not compiled in, so there is no icon.
+#[cfg(all(target_os = "macos", not(feature = "icons")))]
+fn running_application_icon(application: &NSRunningApplication, size: u16) -> Option<Icon> {
+    let _ = (application, size);
+    None
+}
+
+/// Icon support is not compiled in, so this always fails.
+#[cfg(all(target_os = "macos", not(feature = "icons")))]
+pub fn get_icon_for_extension(extension: &str, size: u16) -> Result<Icon> {
+    let _ = (extension, size);
+    Err(crate::icon::icons_disabled())
+}
+
+#[cfg(all(target_os = "macos", not(feature = "icons")))]
+pub(crate) fn load_image_icon(path: &Path, size: u16) -> Result<Icon> {
+    let _ = (path, size);
+    Err(crate::icon::icons_disabled())
+}
+
+#[cfg(all(test, target_os = "macos"))]
+mod tests {
+    use super::*;
+
+    fn plist_dictionary(xml: &str) -> plist::Dictionary {
+        plist::from_bytes::<plist::Value>(xml.as_bytes())
+            .expect("Failed to parse fixture")
+            .into_dictionary()
+            .expect("Fixture is not a dictionary")
+    }
+
+    #[test]
+    fn test_parse_launch_agent() {
+        let agent = parse_launch_agent(&plist_dictionary(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>com.example.helper</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>/Applications/Example.app/Contents/MacOS/Helper</string>
+        <string>--background</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+</dict>
+</plist>"#,
+        ))
+        .expect("Failed to parse launch agent");
+        assert_eq!(agent.label, "com.example.helper");
+        assert_eq!(
+            agent.program,
+            PathBuf::from("/Applications/Example.app/Contents/MacOS/Helper")
+        );
+        assert_eq!(
+            agent.command_line(),
+            "/Applications/Example.app/Contents/MacOS/Helper --background"
+        );
+        assert!(!agent.disabled);
+
+        // Program wins over ProgramArguments, and Disabled is honoured
+        let agent = parse_launch_agent(&plist_dictionary(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>com.example.updater</string>
+    <key>Program</key>
+    <string>/usr/local/bin/updater</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>updater</string>
+        <string>--check</string>
+    </array>
+    <key>Disabled</key>
+    <true/>
+</dict>
+</plist>"#,
+        ))
+        .expect("Failed to parse launch agent");
+        assert_eq!(agent.program, PathBuf::from("/usr/local/bin/updater"));
+        assert!(agent.disabled);
+
+        // Without a label or anything to run there is no agent
+        assert!(parse_launch_agent(&plist_dictionary(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>com.example.empty</string>
+</dict>
+</plist>"#,
+        ))
+        .is_none());
+    }
+
+    #[test]
+    fn test_parse_document_types() {
+        let dict = plist_dictionary(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+    <key>CFBundleDocumentTypes</key>
+    <array>
+        <dict>
+            <key>CFBundleTypeRole</key>
+            <string>Editor</string>
+            <key>CFBundleTypeExtensions</key>
+            <array>
+                <string>TXT</string>
+                <string>md</string>
+                <string>*</string>
+            </array>
+        </dict>
+        <dict>
+            <key>CFBundleTypeRole</key>
+            <string>Viewer</string>
+            <key>LSItemContentTypes</key>
+            <array>
+                <string>public.png</string>
+                <string>com.adobe.pdf</string>
+            </array>
+        </dict>
+        <dict>
+            <key>LSItemContentTypes</key>
+            <array>
+                <string>public.png</string>
+            </array>
+        </dict>
+    </array>
+</dict>
+</plist>"#,
+        );
+
+        let associations = parse_document_types(&dict);
+        let extension = |extension: &str, role| FileAssociation {
+            extension: Some(extension.to_string()),
+            uti: None,
+            role,
+        };
+        let uti = |uti: &str, role| FileAssociation {
+            extension: None,
+            uti: Some(uti.to_string()),
+            role,
+        };
+        assert_eq!(
+            associations,
+            vec![
+                extension("txt", AssociationRole::Editor),
+                extension("md", AssociationRole::Editor),
+                uti("public.png", AssociationRole::Viewer),
+                uti("com.adobe.pdf", AssociationRole::Viewer),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_document_types_without_declarations() {
+        let dict = plist_dictionary(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+    <key>CFBundleIdentifier</key>
+    <string>com.example.NoDocuments</string>
+</dict>
+</plist>"#,
+        );
+        assert!(parse_document_types(&dict).is_empty());
+    }
+
+    #[test]
+    fn test_parse_url_types() {
+        let dict = plist_dictionary(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+    <key>CFBundleURLTypes</key>
+    <array>
+        <dict>
+            <key>CFBundleURLName</key>
+            <string>com.example.web</string>
+            <key>CFBundleURLSchemes</key>
+            <array>
+                <string>HTTP</string>
+                <string>https</string>
+            </array>
+        </dict>
+        <dict>
+            <key>CFBundleURLSchemes</key>
+            <array>
+                <string> example </string>
+                <string>http</string>
+                <string></string>
+            </array>
+        </dict>
+        <dict>
+            <key>CFBundleURLName</key>
+            <string>com.example.empty</string>
+        </dict>
+    </array>
+</dict>
+</plist>"#,
+        );
+        assert_eq!(parse_url_types(&dict), vec!["http", "https", "example"]);
+        assert!(parse_url_types(&plist::Dictionary::new()).is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "icons")]
+    fn test_icns_file_name() {
+        let info = |name: &str| {
+            plist_dictionary(&format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+    <key>CFBundleIconFile</key>
+    <string>{}</string>
+</dict>
+</plist>"#,
+                name
+            ))
+        };
+        assert_eq!(
+            icns_file_name(&info("AppIcon")).as_deref(),
+            Some("AppIcon.icns")
+        );
+        assert_eq!(
+            icns_file_name(&info("Shell.icns")).as_deref(),
+            Some("Shell.icns")
+        );
+        assert_eq!(icns_file_name(&info(" ")), None);
+        assert_eq!(icns_file_name(&plist::Dictionary::new()), None);
     }
 }
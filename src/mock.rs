@@ -0,0 +1,51 @@
+use crate::{AppInfo, AppSource, Result, ScanContext};
+
+/// An [`AppSource`] that yields a fixed list of applications, for testing code that
+/// consumes scan results.
+///
+/// It never touches the system, so it behaves the same on every platform, including
+/// ones this crate does not otherwise support. Combine it with
+/// [`ScanOptions::include_builtin`](crate::ScanOptions::include_builtin) set to `false`
+/// to make [`scan_apps`](crate::scan_apps) fully deterministic. Icons are only reported
+/// when the scan asks for them, and stored as it asks, like the built-in sources do.
+#[derive(Debug, Clone)]
+pub struct MockSource {
+    name: String,
+    apps: Vec<AppInfo>,
+}
+
+impl MockSource {
+    /// Creates a source named `"mock"` that reports `apps`.
+    pub fn new(apps: Vec<AppInfo>) -> Self {
+        MockSource {
+            name: "mock".to_string(),
+            apps,
+        }
+    }
+
+    /// Changes the name the source reports, to tell several mock sources apart.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+}
+
+impl AppSource for MockSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn scan(&self, ctx: &ScanContext) -> Result<Vec<AppInfo>> {
+        if ctx.is_cancelled() {
+            return Ok(Vec::new());
+        }
+
+        let mut apps = self.apps.clone();
+        for app in &mut apps {
+            let icon = app.icon.take().filter(|_| ctx.extracts_icons());
+            ctx.store_icon(app, icon);
+        }
+        ctx.report_progress(apps.len());
+        Ok(apps)
+    }
+}
@@ -0,0 +1,202 @@
+use crate::glob::fold_case;
+use crate::AppInfo;
+use std::collections::HashMap;
+
+/// Legal-entity designations dropped from the end of a publisher name, as case-folded
+/// words without their dots and commas. Longer entries come first so `GmbH & Co. KG` is
+/// removed as a whole.
+const LEGAL_SUFFIXES: &[&str] = &[
+    "gmbh & co kg",
+    "co ltd",
+    "co inc",
+    "pty ltd",
+    "corporation",
+    "corp",
+    "incorporated",
+    "inc",
+    "company",
+    "co",
+    "limited",
+    "ltd",
+    "llc",
+    "llp",
+    "lp",
+    "plc",
+    "gmbh",
+    "ag",
+    "kg",
+    "se",
+    "sa",
+    "sas",
+    "sarl",
+    "srl",
+    "spa",
+    "bv",
+    "nv",
+    "ab",
+    "oy",
+    "oyj",
+    "as",
+    "kk",
+];
+
+/// Designations written without a space before or after the name, as in Chinese and
+/// Japanese company names, dropped from the end and the start respectively.
+const ATTACHED_SUFFIXES: &[&str] = &["股份有限公司", "有限责任公司", "有限公司", "株式会社"];
+const ATTACHED_PREFIXES: &[&str] = &["株式会社"];
+
+/// Normalizes a publisher name so the spellings one vendor uses compare equal.
+///
+/// Surrounding whitespace is trimmed, runs of whitespace become single spaces, the name is
+/// case-folded, and legal-entity designations such as `Corporation`, `Corp.`, `Inc.` or
+/// `GmbH` are dropped from the end, so "Microsoft Corporation", "Microsoft Corp." and
+/// "MICROSOFT" all become `microsoft`. A name that is nothing but a designation is kept.
+/// Every other part of the name is left as it is.
+pub fn normalize_publisher(publisher: &str) -> String {
+    let folded = fold_case(publisher);
+    let mut words: Vec<&str> = folded.split_whitespace().collect();
+    let mut stripped = false;
+    // Designations can follow one another, as in "Company Limited"
+    while let Some(count) = designation_at_end(&words) {
+        words.truncate(words.len() - count);
+        stripped = true;
+    }
+
+    let mut name = words.join(" ");
+    if stripped {
+        // The separator left in front of a designation, as in "Dropbox, Inc."
+        name.truncate(name.trim_end_matches([',', '-']).len());
+    }
+    for suffix in ATTACHED_SUFFIXES {
+        if let Some(rest) = name.strip_suffix(suffix).filter(|rest| !rest.is_empty()) {
+            name = rest.to_string();
+        }
+    }
+    for prefix in ATTACHED_PREFIXES {
+        if let Some(rest) = name.strip_prefix(prefix).filter(|rest| !rest.is_empty()) {
+            name = rest.to_string();
+        }
+    }
+    name
+}
+
+/// The number of words a legal-entity designation takes up at the end of `words`, if one
+/// does and some of the name is left in front of it.
+fn designation_at_end(words: &[&str]) -> Option<usize> {
+    LEGAL_SUFFIXES.iter().find_map(|suffix| {
+        let suffix: Vec<&str> = suffix.split(' ').collect();
+        let start = words
+            .len()
+            .checked_sub(suffix.len())
+            .filter(|&start| start > 0)?;
+        words[start..]
+            .iter()
+            .zip(&suffix)
+            .all(|(word, expected)| bare_word(word) == *expected)
+            .then_some(suffix.len())
+    })
+}
+
+/// A word without the dots and commas abbreviations and separators add, so `Co.,`
+/// compares as `co`.
+fn bare_word(word: &str) -> String {
+    word.chars().filter(|c| !matches!(c, '.' | ',')).collect()
+}
+
+/// Groups applications by their publisher, normalized with [`normalize_publisher`].
+///
+/// Applications without a publisher, or with an empty one, are left out. Within a group
+/// the applications keep their order in `apps`.
+pub fn group_by_publisher(apps: &[AppInfo]) -> HashMap<String, Vec<&AppInfo>> {
+    let mut groups: HashMap<String, Vec<&AppInfo>> = HashMap::new();
+    for app in apps {
+        let Some(publisher) = app.publisher.as_deref() else {
+            continue;
+        };
+        let key = normalize_publisher(publisher);
+        if !key.is_empty() {
+            groups.entry(key).or_default().push(app);
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_publisher() {
+        let cases = [
+            ("Microsoft Corporation", "microsoft"),
+            ("Microsoft Corp.", "microsoft"),
+            ("  MICROSOFT  ", "microsoft"),
+            ("Google LLC", "google"),
+            ("Google Inc.", "google"),
+            ("Adobe Systems Incorporated", "adobe systems"),
+            ("Adobe Inc.", "adobe"),
+            ("Dropbox, Inc.", "dropbox"),
+            ("Oracle America, Inc.", "oracle america"),
+            ("TeamViewer Germany GmbH", "teamviewer germany"),
+            ("Bitdefender SRL", "bitdefender"),
+            ("Ubisoft Entertainment S.A.", "ubisoft entertainment"),
+            ("SAP SE", "sap"),
+            ("Brother Industries, Ltd.", "brother industries"),
+            (
+                "Tencent Technology (Shenzhen) Company Limited",
+                "tencent technology (shenzhen)",
+            ),
+            (
+                "Zoom Video Communications, Inc.",
+                "zoom video communications",
+            ),
+            ("Spotify AB", "spotify"),
+            ("Canonical Group Limited", "canonical group"),
+            ("Docker Inc", "docker"),
+            ("Viewsonic Co., Ltd.", "viewsonic"),
+            ("Endress+Hauser GmbH & Co. KG", "endress+hauser"),
+            ("Atlassian Pty Ltd", "atlassian"),
+            ("腾讯科技(深圳)有限公司", "腾讯科技(深圳)"),
+            (
+                "株式会社バンダイナムコエンターテインメント",
+                "バンダイナムコエンターテインメント",
+            ),
+            ("Schöne Straße\tSoftware   Co", "schöne strasse software"),
+            // Unrecognized publishers only lose spacing and case
+            ("  Valve  ", "valve"),
+            ("The Document Foundation", "the document foundation"),
+            ("Python Software Foundation", "python software foundation"),
+            ("AT&T", "at&t"),
+            // A name that is only a designation is kept
+            ("Inc.", "inc."),
+            ("", ""),
+        ];
+        for (publisher, expected) in cases {
+            assert_eq!(normalize_publisher(publisher), expected, "{:?}", publisher);
+        }
+    }
+
+    #[test]
+    fn test_group_by_publisher() {
+        let app = |name: &str, publisher: Option<&str>| AppInfo {
+            name: name.to_string(),
+            publisher: publisher.map(str::to_string),
+            ..Default::default()
+        };
+        let apps = vec![
+            app("Edge", Some("Microsoft Corporation")),
+            app("Teams", Some("Microsoft Corp.")),
+            app("Chrome", Some("Google LLC")),
+            app("Visual Studio Code", Some("Microsoft")),
+            app("Unknown", None),
+            app("Blank", Some("  ")),
+        ];
+
+        let groups = group_by_publisher(&apps);
+        assert_eq!(groups.len(), 2);
+        let names =
+            |key: &str| -> Vec<&str> { groups[key].iter().map(|app| app.name.as_str()).collect() };
+        assert_eq!(names("microsoft"), ["Edge", "Teams", "Visual Studio Code"]);
+        assert_eq!(names("google"), ["Chrome"]);
+    }
+}
@@ -0,0 +1,202 @@
+use crate::{AppInfo, Result};
+use regex::Regex;
+use std::ops::{BitOr, BitOrAssign};
+
+/// The [`AppInfo`] fields [`find_apps_matching`] searches, combined with `|`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MatchFields(u8);
+
+impl MatchFields {
+    /// [`AppInfo::name`]
+    pub const NAME: MatchFields = MatchFields(1);
+    /// [`AppInfo::identifier`]
+    pub const IDENTIFIER: MatchFields = MatchFields(1 << 1);
+    /// [`AppInfo::publisher`]
+    pub const PUBLISHER: MatchFields = MatchFields(1 << 2);
+    /// Every field
+    pub const ALL: MatchFields = MatchFields(0b111);
+
+    /// No fields; nothing matches.
+    pub const fn empty() -> Self {
+        MatchFields(0)
+    }
+
+    /// Whether every field in `other` is also in `self`.
+    pub const fn contains(self, other: MatchFields) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl Default for MatchFields {
+    fn default() -> Self {
+        MatchFields::NAME
+    }
+}
+
+impl BitOr for MatchFields {
+    type Output = MatchFields;
+
+    fn bitor(self, other: MatchFields) -> MatchFields {
+        MatchFields(self.0 | other.0)
+    }
+}
+
+impl BitOrAssign for MatchFields {
+    fn bitor_assign(&mut self, other: MatchFields) {
+        self.0 |= other.0;
+    }
+}
+
+/// Finds the installed applications where `re` matches any of the selected fields.
+///
+/// The regex is searched for anywhere in a field; anchor it with `^` and `$` to match whole
+/// values, and use `(?i)` to ignore case. Missing identifiers and publishers never match.
+/// Apps are matched before their icons are loaded, so only matches pay for icon extraction.
+/// Returns an empty vector when nothing matches.
+///
+/// # Arguments
+///
+/// * `re` - The regular expression to search for.
+/// * `fields` - Which fields to search, such as `MatchFields::NAME | MatchFields::PUBLISHER`.
+/// * `icon_size` - The desired icon size. If 0, no icon will be fetched.
+pub fn find_apps_matching(re: &Regex, fields: MatchFields, icon_size: u16) -> Result<Vec<AppInfo>> {
+    let mut apps = filter_matching(crate::get_installed_apps(0)?, re, fields);
+    if icon_size > 0 {
+        load_icons(&mut apps, icon_size);
+    }
+    Ok(apps)
+}
+
+fn filter_matching(apps: Vec<AppInfo>, re: &Regex, fields: MatchFields) -> Vec<AppInfo> {
+    apps.into_iter()
+        .filter(|app| app_matches(app, re, fields))
+        .collect()
+}
+
+fn app_matches(app: &AppInfo, re: &Regex, fields: MatchFields) -> bool {
+    (fields.contains(MatchFields::NAME) && re.is_match(&app.name))
+        || (fields.contains(MatchFields::IDENTIFIER)
+            && app.identifier.as_deref().is_some_and(|id| re.is_match(id)))
+        || (fields.contains(MatchFields::PUBLISHER)
+            && app
+                .publisher
+                .as_deref()
+                .is_some_and(|publisher| re.is_match(publisher)))
+}
+
+/// Fills in the icons of apps found by a scan without icons, the way the scan would have.
+fn load_icons(apps: &mut [AppInfo], icon_size: u16) {
+    for app in apps {
+        #[cfg(target_os = "macos")]
+        {
+            app.icon = crate::get_file_icon(&app.path, icon_size).ok();
+        }
+
+        // The icon comes from the Uninstall entry, so read that entry again
+        #[cfg(target_os = "windows")]
+        {
+            app.icon = app
+                .identifier
+                .as_deref()
+                .and_then(|identifier| crate::lookup_all_apps(identifier, icon_size).ok())
+                .and_then(|entries| {
+                    entries
+                        .into_iter()
+                        .find(|entry| entry.name == app.name && entry.path == app.path)
+                })
+                .and_then(|entry| entry.icon);
+        }
+
+        #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+        let _ = (app, icon_size);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn apps() -> Vec<AppInfo> {
+        let app = |name: &str, identifier: Option<&str>, publisher: Option<&str>| AppInfo {
+            name: name.to_string(),
+            path: PathBuf::from("/opt").join(name),
+            identifier: identifier.map(str::to_string),
+            publisher: publisher.map(str::to_string),
+            ..Default::default()
+        };
+        vec![
+            app(
+                "Java 8 Update 391",
+                Some("{26A24AE4-0391}"),
+                Some("Oracle Corporation"),
+            ),
+            app("JRE 11 Update 21", None, Some("Oracle Corporation")),
+            app("Java Development Kit", None, None),
+            app(
+                "Eclipse Temurin",
+                Some("org.adoptium.temurin"),
+                Some("Eclipse Adoptium"),
+            ),
+        ]
+    }
+
+    fn names(apps: Vec<AppInfo>) -> Vec<String> {
+        apps.into_iter().map(|app| app.name).collect()
+    }
+
+    #[test]
+    fn test_filter_matching() {
+        let stale_java = Regex::new(r"^(Java|JRE) \d+ Update \d+$").unwrap();
+        assert_eq!(
+            names(filter_matching(apps(), &stale_java, MatchFields::NAME)),
+            ["Java 8 Update 391", "JRE 11 Update 21"]
+        );
+
+        let oracle = Regex::new("(?i)^oracle").unwrap();
+        assert!(filter_matching(apps(), &oracle, MatchFields::NAME).is_empty());
+        assert_eq!(
+            filter_matching(apps(), &oracle, MatchFields::PUBLISHER).len(),
+            2
+        );
+
+        let adoptium = Regex::new("adoptium").unwrap();
+        assert_eq!(
+            names(filter_matching(
+                apps(),
+                &adoptium,
+                MatchFields::NAME | MatchFields::IDENTIFIER
+            )),
+            ["Eclipse Temurin"]
+        );
+        assert!(filter_matching(apps(), &adoptium, MatchFields::empty()).is_empty());
+    }
+
+    #[test]
+    fn test_match_fields() {
+        let mut fields = MatchFields::NAME;
+        fields |= MatchFields::PUBLISHER;
+        assert!(fields.contains(MatchFields::NAME));
+        assert!(fields.contains(MatchFields::PUBLISHER));
+        assert!(!fields.contains(MatchFields::IDENTIFIER));
+        assert!(MatchFields::ALL.contains(fields | MatchFields::IDENTIFIER));
+        assert_eq!(MatchFields::default(), MatchFields::NAME);
+    }
+
+    #[test]
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    fn test_find_apps_matching() {
+        let apps = crate::get_installed_apps(0).unwrap();
+        let Some(expected) = apps.first() else {
+            return;
+        };
+        let re = Regex::new(&format!("^{}$", regex::escape(&expected.name))).unwrap();
+        let found = find_apps_matching(&re, MatchFields::NAME, 32).expect("Failed to search");
+        assert!(found.iter().any(|app| app.path == expected.path));
+
+        let nothing = Regex::new("^ThisAppSurelyDoesNotExist12345$").unwrap();
+        assert!(find_apps_matching(&nothing, MatchFields::ALL, 0)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,1141 @@
+use crate::{error::AppInfoError, AppInfo, AppKind, DateOrder, Icon, InstallScope, Result};
+use std::collections::HashSet;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// A discovery backend that contributes applications to [`scan_apps`].
+///
+/// The built-in scanners implement this trait, and custom sources passed in
+/// [`ScanOptions::sources`] go through the same deduplication, sorting and reporting.
+///
+/// Implementations must not panic: failures are returned as errors, which the scan
+/// records in [`ScanReport::errors`] before moving on to the next source. Long scans
+/// should check [`ScanContext::is_cancelled`] regularly and return what they have found
+/// so far once it is set, and should honour [`ScanContext::icon_size`] and
+/// [`ScanContext::icon_storage`] the way the built-in sources do, by handing extracted
+/// icons to [`ScanContext::store_icon`].
+pub trait AppSource: Send + Sync {
+    /// A short, stable name identifying the source in progress updates and reports.
+    fn name(&self) -> &str;
+
+    /// Discovers applications.
+    fn scan(&self, ctx: &ScanContext) -> Result<Vec<AppInfo>>;
+}
+
+/// A cooperative cancellation flag for scans. Clones share the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Creates a token that has not been cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks every scan holding this token to stop as soon as possible.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether [`CancellationToken::cancel`] has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A progress update from a running scan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanProgress {
+    /// Name of the source reporting progress
+    pub source: String,
+    /// Applications the source has found so far
+    pub found: usize,
+    /// Whether the source has finished
+    pub finished: bool,
+}
+
+/// How a scan keeps the icons of the apps it finds, see [`ScanOptions::icon_storage`].
+///
+/// A 512 x 512 icon is 1 MiB of pixels, so a scan of a few hundred apps with
+/// [`IconStorage::Rgba`] holds hundreds of megabytes at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IconStorage {
+    /// Pixels in [`AppInfo::icon`], ready to draw. Uses the most memory.
+    #[default]
+    Rgba,
+    /// PNG in [`AppInfo::compressed_icon`], usually a tenth of the size or less. Each
+    /// icon is compressed as soon as it is extracted, which adds a few milliseconds per
+    /// app, and has to be decoded with [`CompressedIcon::decode`](crate::CompressedIcon)
+    /// before it is drawn.
+    Png,
+    /// No icon at all, only [`AppInfo::icon_source`] to read it from later with
+    /// [`AppInfo::load_icon`]. The scan is as fast as one without icons, and each icon
+    /// costs a read from disk when it is needed.
+    Deferred,
+}
+
+/// Whose per-user installs a scan reads on Windows, see [`ScanOptions::user_profiles`].
+///
+/// Per-user installs are listed in each user's own registry hive. The modes other than
+/// [`UserProfiles::Current`] read every user's hive instead of `HKEY_CURRENT_USER`, tag
+/// each entry with its [`AppInfo::owner`], and report each hive in
+/// [`SourceStats::locations`], so a profile that cannot be read is recorded there rather
+/// than failing the scan. They are meant for inventory agents running as an
+/// administrator or `SYSTEM`; other processes cannot open other users' hives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UserProfiles {
+    /// The current user's, under `HKEY_CURRENT_USER`
+    #[default]
+    Current,
+    /// Those of every user whose hive is loaded under `HKEY_USERS`: the users logged on,
+    /// and any whose hive another program has loaded. Service accounts such as `SYSTEM`
+    /// are left out.
+    Loaded,
+    /// Those of every user with a profile on the machine. The hives of users who are not
+    /// logged on are loaded from their `NTUSER.DAT` for the scan and unloaded again,
+    /// which needs the `SeBackupPrivilege` and `SeRestorePrivilege` that administrators
+    /// and `SYSTEM` hold; the scan enables them while it runs.
+    All,
+}
+
+/// Receives [`ScanProgress`] updates. It may be called from the scanning thread at any
+/// time, so it should return quickly.
+pub type ProgressSink = Arc<dyn Fn(&ScanProgress) + Send + Sync>;
+
+/// A stage of a source's work, timed with [`ScanContext::time`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanPhase {
+    /// Listing the entries to look at, such as registry keys or directory entries
+    Enumeration,
+    /// Reading the details of an entry. Time spent in [`ScanPhase::Icons`] meanwhile is
+    /// not counted here.
+    Metadata,
+    /// Extracting icons
+    Icons,
+}
+
+/// The counters one source updates through its [`ScanContext`].
+#[derive(Debug, Default)]
+struct StatsRecorder {
+    enumerated: AtomicUsize,
+    filtered: AtomicUsize,
+    failed: AtomicUsize,
+    skipped: Mutex<Vec<String>>,
+    locations: Mutex<Vec<VisitedLocation>>,
+    /// Nanoseconds spent in each [`ScanPhase`], in declaration order
+    phases: [AtomicU64; 3],
+}
+
+impl StatsRecorder {
+    fn finish(&self, source: String, duration: Duration) -> SourceStats {
+        let phase = |phase: ScanPhase| {
+            Duration::from_nanos(self.phases[phase as usize].load(Ordering::Relaxed))
+        };
+        let icon_extraction = phase(ScanPhase::Icons);
+        SourceStats {
+            source,
+            enumerated: self.enumerated.load(Ordering::Relaxed),
+            filtered: self.filtered.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+            skipped: std::mem::take(&mut *self.skipped.lock().unwrap_or_else(|e| e.into_inner())),
+            locations: std::mem::take(
+                &mut *self.locations.lock().unwrap_or_else(|e| e.into_inner()),
+            ),
+            duration,
+            enumeration: phase(ScanPhase::Enumeration),
+            metadata: phase(ScanPhase::Metadata).saturating_sub(icon_extraction),
+            icon_extraction,
+            ..Default::default()
+        }
+    }
+}
+
+/// What a scan hands to each [`AppSource`].
+#[derive(Clone)]
+pub struct ScanContext {
+    icon_size: u16,
+    icon_storage: IconStorage,
+    check_signatures: bool,
+    read_declarations: bool,
+    date_order: DateOrder,
+    extra_directories: Vec<PathBuf>,
+    user_profiles: UserProfiles,
+    cancellation: CancellationToken,
+    progress: Option<ProgressSink>,
+    source: String,
+    stats: Option<Arc<StatsRecorder>>,
+}
+
+impl ScanContext {
+    /// Creates a context with no cancellation and no progress reporting, for running a
+    /// source on its own.
+    pub fn new(icon_size: u16) -> Self {
+        ScanContext {
+            icon_size,
+            icon_storage: IconStorage::Rgba,
+            check_signatures: false,
+            read_declarations: false,
+            date_order: DateOrder::Auto,
+            extra_directories: Vec::new(),
+            user_profiles: UserProfiles::Current,
+            cancellation: CancellationToken::new(),
+            progress: None,
+            source: String::new(),
+            stats: None,
+        }
+    }
+
+    /// The desired icon size. If 0, no icon should be fetched.
+    pub fn icon_size(&self) -> u16 {
+        self.icon_size
+    }
+
+    /// Sets how icons are kept, see [`ScanOptions::icon_storage`].
+    pub fn with_icon_storage(mut self, icon_storage: IconStorage) -> Self {
+        self.icon_storage = icon_storage;
+        self
+    }
+
+    /// How icons should be kept.
+    pub fn icon_storage(&self) -> IconStorage {
+        self.icon_storage
+    }
+
+    /// Whether icons should be extracted now: an icon size is set and the icons are not
+    /// [`IconStorage::Deferred`].
+    pub fn extracts_icons(&self) -> bool {
+        self.icon_size > 0 && self.icon_storage != IconStorage::Deferred
+    }
+
+    /// Stores an extracted icon on `app` the way [`ScanContext::icon_storage`] asks, so
+    /// that with [`IconStorage::Png`] the pixels are freed straight away.
+    pub fn store_icon(&self, app: &mut AppInfo, icon: Option<Icon>) {
+        match self.icon_storage {
+            IconStorage::Rgba => app.icon = icon,
+            IconStorage::Png => {
+                app.icon = None;
+                app.compressed_icon = icon.and_then(|icon| icon.compress().ok());
+            }
+            IconStorage::Deferred => {
+                app.icon = None;
+                app.compressed_icon = None;
+            }
+        }
+    }
+
+    /// Also asks for code signatures to be checked, see [`ScanOptions::check_signatures`].
+    pub fn with_signatures(mut self, check_signatures: bool) -> Self {
+        self.check_signatures = check_signatures;
+        self
+    }
+
+    /// Whether [`AppInfo::signature`] should be filled in. Sources that cannot check
+    /// signatures ignore it.
+    pub fn check_signatures(&self) -> bool {
+        self.check_signatures
+    }
+
+    /// Also asks for declared URL schemes and file types, see
+    /// [`ScanOptions::read_declarations`].
+    pub fn with_declarations(mut self, read_declarations: bool) -> Self {
+        self.read_declarations = read_declarations;
+        self
+    }
+
+    /// Whether [`AppInfo::declared_url_schemes`] and [`AppInfo::declared_file_types`]
+    /// should be filled in. Sources without such declarations ignore it.
+    pub fn read_declarations(&self) -> bool {
+        self.read_declarations
+    }
+
+    /// Sets how ambiguous install dates are read, see [`ScanOptions::date_order`].
+    pub fn with_date_order(mut self, date_order: DateOrder) -> Self {
+        self.date_order = date_order;
+        self
+    }
+
+    /// How sources should read install dates whose day and month could be either way
+    /// round, for [`AppInfo::parsed_install_date`]. Sources that leave it unset have it
+    /// filled in from [`AppInfo::install_date`] by the scan.
+    pub fn date_order(&self) -> DateOrder {
+        self.date_order
+    }
+
+    /// Adds directories to search, see [`ScanOptions::extra_directories`].
+    pub fn with_extra_directories(mut self, extra_directories: Vec<PathBuf>) -> Self {
+        self.extra_directories = extra_directories;
+        self
+    }
+
+    /// Directories to search after the source's own, for sources that search
+    /// directories.
+    pub fn extra_directories(&self) -> &[PathBuf] {
+        &self.extra_directories
+    }
+
+    /// Sets whose per-user installs to read, see [`ScanOptions::user_profiles`].
+    pub fn with_user_profiles(mut self, user_profiles: UserProfiles) -> Self {
+        self.user_profiles = user_profiles;
+        self
+    }
+
+    /// Whose per-user installs to read, for sources that keep them per user.
+    pub fn user_profiles(&self) -> UserProfiles {
+        self.user_profiles
+    }
+
+    /// Whether the scan has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    /// Reports how many applications the current source has found so far.
+    pub fn report_progress(&self, found: usize) {
+        self.send_progress(found, false);
+    }
+
+    /// Counts entries the source has started looking at, for [`ScanReport::stats`]. Each
+    /// should end up as an application, or be counted with [`ScanContext::count_filtered`]
+    /// or [`ScanContext::count_failed`].
+    pub fn count_enumerated(&self, count: usize) {
+        if let Some(stats) = &self.stats {
+            stats.enumerated.fetch_add(count, Ordering::Relaxed);
+        }
+    }
+
+    /// Counts an entry the source left out on purpose, such as one marked hidden.
+    pub fn count_filtered(&self) {
+        if let Some(stats) = &self.stats {
+            stats.filtered.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Counts an entry left out because nothing usable could be read from it, such as a
+    /// registry entry without a name, and names it in [`SourceStats::skipped`].
+    pub fn skip_entry(&self, entry: &str) {
+        if let Some(stats) = &self.stats {
+            stats.filtered.fetch_add(1, Ordering::Relaxed);
+            stats
+                .skipped
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(entry.to_string());
+        }
+    }
+
+    /// Records a place the source looked in, or skipped, for [`SourceStats::locations`].
+    pub fn record_location(&self, location: ScanLocation, status: LocationStatus) {
+        if let Some(stats) = &self.stats {
+            stats
+                .locations
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(VisitedLocation { location, status });
+        }
+    }
+
+    /// Counts an entry that could not be read.
+    pub fn count_failed(&self) {
+        if let Some(stats) = &self.stats {
+            stats.failed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Runs `f` and adds the time it took to `phase`. Nothing is measured when the scan
+    /// does not collect statistics, as with [`ScanContext::new`].
+    pub fn time<T>(&self, phase: ScanPhase, f: impl FnOnce() -> T) -> T {
+        let Some(stats) = &self.stats else {
+            return f();
+        };
+        let started = Instant::now();
+        let result = f();
+        let nanos = u64::try_from(started.elapsed().as_nanos()).unwrap_or(u64::MAX);
+        stats.phases[phase as usize].fetch_add(nanos, Ordering::Relaxed);
+        result
+    }
+
+    fn send_progress(&self, found: usize, finished: bool) {
+        if let Some(progress) = &self.progress {
+            progress(&ScanProgress {
+                source: self.source.clone(),
+                found,
+                finished,
+            });
+        }
+    }
+}
+
+impl fmt::Debug for ScanContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScanContext")
+            .field("icon_size", &self.icon_size)
+            .field("icon_storage", &self.icon_storage)
+            .field("check_signatures", &self.check_signatures)
+            .field("read_declarations", &self.read_declarations)
+            .field("date_order", &self.date_order)
+            .field("extra_directories", &self.extra_directories)
+            .field("user_profiles", &self.user_profiles)
+            .field("cancellation", &self.cancellation)
+            .field("progress", &self.progress.is_some())
+            .field("source", &self.source)
+            .field("stats", &self.stats.is_some())
+            .finish()
+    }
+}
+
+/// Options for [`scan_apps`].
+pub struct ScanOptions {
+    /// The desired icon size. If 0, no icon will be fetched.
+    pub icon_size: u16,
+    /// How to keep the icons, to bound the memory a scan with large icons takes. Icons
+    /// are kept as pixels by default.
+    pub icon_storage: IconStorage,
+    /// Whether to check the code signature of each app's executable, filling in
+    /// [`AppInfo::signature`] and, where the installer recorded none,
+    /// [`AppInfo::publisher`] from the signer. Off by default, since each check takes
+    /// tens of milliseconds; only Windows supports it.
+    pub check_signatures: bool,
+    /// Whether to read the URL schemes and file types each app declares, filling in
+    /// [`AppInfo::declared_url_schemes`] and [`AppInfo::declared_file_types`]. Off by
+    /// default, since on Windows it means resolving every registered application's
+    /// capabilities.
+    pub read_declarations: bool,
+    /// Whether to run the platform's built-in sources, before the custom ones
+    pub include_builtin: bool,
+    /// Additional sources, run in order after the built-in ones
+    pub sources: Vec<Box<dyn AppSource>>,
+    /// Cancels the scan when triggered from another thread
+    pub cancellation: CancellationToken,
+    /// Receives progress updates, if set
+    pub progress: Option<ProgressSink>,
+    /// Keeps only apps of these kinds, such as `vec![AppKind::Application]` for GUI apps
+    /// only. Apps whose kind is unknown are left out too. Empty keeps every app.
+    pub kinds: Vec<AppKind>,
+    /// How to read install dates that could have the day and month either way round, see
+    /// [`AppInfo::parsed_install_date`]
+    pub date_order: DateOrder,
+    /// Directories to search for application bundles after the default ones, such as a
+    /// department folder mounted by MDM. Only the macOS source searches directories;
+    /// [`SourceStats::locations`] tells which were found.
+    pub extra_directories: Vec<PathBuf>,
+    /// Whose per-user installs to read on Windows. Only the current user's by default;
+    /// the other modes need administrator rights, see [`UserProfiles`].
+    pub user_profiles: UserProfiles,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        ScanOptions {
+            icon_size: 0,
+            icon_storage: IconStorage::Rgba,
+            check_signatures: false,
+            read_declarations: false,
+            include_builtin: true,
+            sources: Vec::new(),
+            cancellation: CancellationToken::new(),
+            progress: None,
+            kinds: Vec::new(),
+            date_order: DateOrder::Auto,
+            extra_directories: Vec::new(),
+            user_profiles: UserProfiles::Current,
+        }
+    }
+}
+
+impl fmt::Debug for ScanOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sources: Vec<&str> = self.sources.iter().map(|source| source.name()).collect();
+        f.debug_struct("ScanOptions")
+            .field("icon_size", &self.icon_size)
+            .field("icon_storage", &self.icon_storage)
+            .field("check_signatures", &self.check_signatures)
+            .field("read_declarations", &self.read_declarations)
+            .field("include_builtin", &self.include_builtin)
+            .field("sources", &sources)
+            .field("cancellation", &self.cancellation)
+            .field("progress", &self.progress.is_some())
+            .field("kinds", &self.kinds)
+            .field("date_order", &self.date_order)
+            .field("extra_directories", &self.extra_directories)
+            .field("user_profiles", &self.user_profiles)
+            .finish()
+    }
+}
+
+/// A place a built-in source reads applications from, see [`default_sources`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ScanLocation {
+    /// A directory searched for application bundles, on macOS
+    Directory {
+        path: PathBuf,
+        /// Whose applications the directory holds. `None` for
+        /// [`ScanOptions::extra_directories`].
+        scope: Option<InstallScope>,
+    },
+    /// A registry key whose subkeys are Uninstall entries, on Windows, written with the
+    /// full name of its root, as in `HKEY_CURRENT_USER\SOFTWARE\...`
+    RegistryKey { key: String, scope: InstallScope },
+}
+
+impl ScanLocation {
+    /// Whose applications the location holds, when known.
+    pub fn scope(&self) -> Option<InstallScope> {
+        match self {
+            ScanLocation::Directory { scope, .. } => *scope,
+            ScanLocation::RegistryKey { scope, .. } => Some(*scope),
+        }
+    }
+}
+
+impl fmt::Display for ScanLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanLocation::Directory { path, .. } => write!(f, "{}", path.display()),
+            ScanLocation::RegistryKey { key, .. } => f.write_str(key),
+        }
+    }
+}
+
+/// What became of a [`ScanLocation`] during a scan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LocationStatus {
+    /// The location was read
+    Scanned,
+    /// Skipped, since it does not exist
+    NotPresent,
+    /// Skipped, since this process may not read it
+    PermissionDenied,
+    /// Skipped after some other error, described by the message
+    Failed(String),
+}
+
+impl LocationStatus {
+    /// The status of a location that could not be opened, for sources recording their
+    /// own with [`ScanContext::record_location`].
+    pub fn from_io_error(error: &std::io::Error) -> Self {
+        match error.kind() {
+            std::io::ErrorKind::NotFound => LocationStatus::NotPresent,
+            std::io::ErrorKind::PermissionDenied => LocationStatus::PermissionDenied,
+            _ => LocationStatus::Failed(error.to_string()),
+        }
+    }
+}
+
+/// A location a source looked in, or skipped, as listed in [`SourceStats::locations`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VisitedLocation {
+    pub location: ScanLocation,
+    pub status: LocationStatus,
+}
+
+/// A source that failed during a scan.
+#[derive(Debug)]
+pub struct SourceError {
+    /// Name of the source
+    pub source: String,
+    /// What went wrong
+    pub error: AppInfoError,
+}
+
+/// Counts and timings for one source of a scan, see [`ScanStats`].
+///
+/// For a source that succeeded, `apps`, `filtered` and `failed` add up to `enumerated`.
+/// A source that failed returned no applications, so its counts only show how far it got.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceStats {
+    /// Name of the source
+    pub source: String,
+    /// Entries the source looked at, such as registry keys or bundles. For sources that
+    /// do not count them, the applications they returned.
+    pub enumerated: usize,
+    /// Entries the source left out on purpose, and apps [`ScanOptions::kinds`] left out
+    pub filtered: usize,
+    /// The filtered entries that were left out for lack of usable data, such as registry
+    /// keys, so they can be looked into
+    pub skipped: Vec<String>,
+    /// Entries that could not be read
+    pub failed: usize,
+    /// Applications the source returned
+    pub apps: usize,
+    /// Returned applications dropped because an earlier one had the same
+    /// [`AppInfo::stable_id`] and path
+    pub deduplicated: usize,
+    /// Returned applications that came with an icon
+    pub icons: usize,
+    /// The directories or registry keys the source looked in, in order, with those it
+    /// skipped. Sources that do not report them leave it empty.
+    pub locations: Vec<VisitedLocation>,
+    /// Time the whole source took
+    pub duration: Duration,
+    /// Time spent listing entries
+    pub enumeration: Duration,
+    /// Time spent reading entries, not counting their icons
+    pub metadata: Duration,
+    /// Time spent extracting icons
+    pub icon_extraction: Duration,
+}
+
+/// Where the time of a scan went, as reported in [`ScanReport::stats`].
+///
+/// Sources fill in their phases through [`ScanContext::count_enumerated`] and
+/// [`ScanContext::time`]; the built-in ones do.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanStats {
+    /// One entry per source that ran, in scan order
+    pub sources: Vec<SourceStats>,
+    /// Time the whole scan took
+    pub duration: Duration,
+}
+
+/// The outcome of [`scan_apps`].
+#[derive(Debug, Default)]
+pub struct ScanReport {
+    /// The applications found, deduplicated and sorted by name
+    pub apps: Vec<AppInfo>,
+    /// Sources that failed; the applications of the other sources are still reported
+    pub errors: Vec<SourceError>,
+    /// Whether the scan was cancelled before every source had finished
+    pub cancelled: bool,
+    /// Counts and timings per source
+    pub stats: ScanStats,
+}
+
+impl ScanReport {
+    /// Every location the sources looked in or skipped, in scan order.
+    pub fn locations(&self) -> impl Iterator<Item = &VisitedLocation> {
+        self.stats
+            .sources
+            .iter()
+            .flat_map(|source| source.locations.iter())
+    }
+}
+
+/// Lists the places the built-in sources read, in scan order: the application
+/// directories on macOS and the Uninstall registry keys on Windows. Empty on other
+/// platforms.
+///
+/// A scan reports what it found at each one in [`SourceStats::locations`].
+pub fn default_sources() -> Vec<ScanLocation> {
+    #[cfg(target_os = "macos")]
+    return crate::macos::default_scan_locations();
+
+    #[cfg(target_os = "windows")]
+    return crate::window::default_scan_locations();
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    Vec::new()
+}
+
+/// The platform's built-in sources, in scan order.
+fn builtin_sources() -> Vec<Box<dyn AppSource>> {
+    #[cfg(target_os = "macos")]
+    return vec![Box::new(crate::macos::ApplicationDirectoriesSource)];
+
+    #[cfg(target_os = "windows")]
+    return vec![Box::new(crate::window::UninstallRegistrySource)];
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    Vec::new()
+}
+
+/// Scans for applications with the built-in and any custom sources.
+///
+/// Sources run in order. An application found by several sources, or several times by
+/// one, is reported once, as first found. A failing source is recorded in
+/// [`ScanReport::errors`] without stopping the scan. Returns
+/// [`AppInfoError::UnsupportedPlatform`] when there is no source to run, which happens on
+/// unsupported platforms unless custom sources are given.
+pub fn scan_apps(options: &ScanOptions) -> Result<ScanReport> {
+    enter_span!(INFO, "scan_apps", icon_size = options.icon_size);
+
+    let builtin = if options.include_builtin {
+        builtin_sources()
+    } else {
+        Vec::new()
+    };
+    let sources: Vec<&dyn AppSource> = builtin
+        .iter()
+        .map(|source| source.as_ref())
+        .chain(options.sources.iter().map(|source| source.as_ref()))
+        .collect();
+
+    if sources.is_empty() {
+        return Err(AppInfoError::UnsupportedPlatform);
+    }
+
+    let started = Instant::now();
+    let mut report = ScanReport::default();
+    let mut seen = HashSet::new();
+
+    for source in sources {
+        if options.cancellation.is_cancelled() {
+            debug_event!("scan cancelled");
+            break;
+        }
+        enter_span!(DEBUG, "scan_source", source = source.name());
+
+        let recorder = Arc::new(StatsRecorder::default());
+        let ctx = ScanContext {
+            icon_size: options.icon_size,
+            icon_storage: options.icon_storage,
+            check_signatures: options.check_signatures,
+            read_declarations: options.read_declarations,
+            date_order: options.date_order,
+            extra_directories: options.extra_directories.clone(),
+            user_profiles: options.user_profiles,
+            cancellation: options.cancellation.clone(),
+            progress: options.progress.clone(),
+            source: source.name().to_string(),
+            stats: Some(Arc::clone(&recorder)),
+        };
+
+        let source_started = Instant::now();
+        let result = source.scan(&ctx);
+        let mut stats = recorder.finish(ctx.source.clone(), source_started.elapsed());
+        match result {
+            Ok(mut apps) => {
+                ctx.send_progress(apps.len(), true);
+                if stats.enumerated == 0 && stats.filtered == 0 && stats.failed == 0 {
+                    stats.enumerated = apps.len();
+                }
+                if !options.kinds.is_empty() {
+                    let returned = apps.len();
+                    apps.retain(|app| app.kind.is_some_and(|kind| options.kinds.contains(&kind)));
+                    stats.filtered += returned - apps.len();
+                }
+                for app in &mut apps {
+                    if app.parsed_install_date.is_none() && !app.install_date_ambiguous {
+                        crate::date::read_install_date(app, options.date_order);
+                    }
+                    // Custom sources may have left pixels behind
+                    if app.icon.is_some() && options.icon_storage != IconStorage::Rgba {
+                        let icon = app.icon.take();
+                        ctx.store_icon(app, icon);
+                    }
+                }
+                stats.apps = apps.len();
+                stats.icons = apps
+                    .iter()
+                    .filter(|app| app.icon.is_some() || app.compressed_icon.is_some())
+                    .count();
+                for app in apps {
+                    if seen.insert(installation_key(&app)) {
+                        report.apps.push(app);
+                    } else {
+                        stats.deduplicated += 1;
+                    }
+                }
+            }
+            Err(error) => {
+                debug_event!(error = %error, "source failed");
+                report.errors.push(SourceError {
+                    source: ctx.source,
+                    error,
+                });
+            }
+        }
+        report.stats.sources.push(stats);
+    }
+
+    report.cancelled = options.cancellation.is_cancelled();
+    report.stats.duration = started.elapsed();
+    report
+        .apps
+        .sort_by_cached_key(|app| app.name.to_lowercase());
+    Ok(report)
+}
+
+/// Identifies an installation across sources and scans: its stable ID, and its path in
+/// lower case without trailing separators. The path is part of the key because copies of
+/// one macOS bundle in several places share a stable ID.
+pub(crate) fn installation_key(app: &AppInfo) -> (String, String) {
+    let path = app.path.to_string_lossy().to_lowercase();
+    let path = path.trim_end_matches(['/', '\\']).to_string();
+    (app.stable_id(), path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// A source that returns fixed apps, or fails when it has none.
+    struct DummySource(Vec<AppInfo>);
+
+    impl AppSource for DummySource {
+        fn name(&self) -> &str {
+            "dummy"
+        }
+
+        fn scan(&self, ctx: &ScanContext) -> Result<Vec<AppInfo>> {
+            assert_eq!(ctx.icon_size(), 0);
+            if self.0.is_empty() {
+                return Err(AppInfoError::AppNotFound {
+                    name: "dummy".to_string(),
+                });
+            }
+            ctx.report_progress(self.0.len());
+            Ok(self.0.clone())
+        }
+    }
+
+    /// A source that looks at four entries, leaving one out and failing to read one.
+    struct CountingSource;
+
+    impl AppSource for CountingSource {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        fn scan(&self, ctx: &ScanContext) -> Result<Vec<AppInfo>> {
+            let names = ctx.time(ScanPhase::Enumeration, || {
+                ["Tool", "Hidden", "Broken", "Zeta Tool"]
+            });
+            ctx.count_enumerated(names.len());
+            ctx.skip_entry("Hidden");
+            ctx.count_failed();
+            Ok(ctx.time(ScanPhase::Metadata, || {
+                vec![app("Tool", "/opt/tool"), app("Zeta Tool", "/opt/zeta")]
+            }))
+        }
+    }
+
+    fn app(name: &str, path: &str) -> AppInfo {
+        AppInfo {
+            name: name.to_string(),
+            path: PathBuf::from(path),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_custom_source() {
+        let updates = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&updates);
+        let options = ScanOptions {
+            include_builtin: false,
+            sources: vec![
+                Box::new(DummySource(vec![
+                    app("Zeta Tool", "/opt/zeta"),
+                    app("alpha portal app", "/opt/alpha"),
+                    app("Zeta Tool", "/opt/zeta"),
+                ])),
+                Box::new(DummySource(vec![app("Zeta Tool", "/OPT/ZETA")])),
+                Box::new(DummySource(Vec::new())),
+            ],
+            progress: Some(Arc::new(move |progress: &ScanProgress| {
+                sink.lock().unwrap().push(progress.clone());
+            })),
+            ..Default::default()
+        };
+
+        let report = scan_apps(&options).expect("Failed to scan");
+        let names: Vec<&str> = report.apps.iter().map(|app| app.name.as_str()).collect();
+        assert_eq!(names, ["alpha portal app", "Zeta Tool"]);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].source, "dummy");
+        assert!(!report.cancelled);
+
+        let updates = updates.lock().unwrap();
+        assert!(updates.iter().all(|update| update.source == "dummy"));
+        assert_eq!(updates.iter().filter(|update| update.finished).count(), 2);
+    }
+
+    #[test]
+    fn test_scan_stats() {
+        let options = ScanOptions {
+            include_builtin: false,
+            sources: vec![
+                Box::new(DummySource(vec![app("Zeta Tool", "/opt/zeta")])),
+                Box::new(CountingSource),
+                Box::new(DummySource(Vec::new())),
+            ],
+            ..Default::default()
+        };
+        let report = scan_apps(&options).expect("Failed to scan");
+        let stats = &report.stats.sources;
+        assert_eq!(stats.len(), 3);
+
+        // Sources that count nothing report what they returned
+        assert_eq!((stats[0].enumerated, stats[0].apps), (1, 1));
+        assert_eq!(stats[0].enumeration, Duration::ZERO);
+
+        let counting = &stats[1];
+        assert_eq!(counting.source, "counting");
+        assert_eq!(
+            (counting.enumerated, counting.filtered, counting.failed),
+            (4, 1, 1)
+        );
+        assert_eq!(counting.skipped, ["Hidden"]);
+        assert_eq!((counting.apps, counting.deduplicated), (2, 1));
+        assert_eq!(
+            counting.apps + counting.filtered + counting.failed,
+            counting.enumerated
+        );
+        assert!(counting.enumeration + counting.metadata <= counting.duration);
+        assert_eq!(report.apps.len(), 2);
+
+        // The failed source is still listed
+        assert_eq!(stats[2].apps, 0);
+        assert!(stats.iter().map(|s| s.duration).sum::<Duration>() <= report.stats.duration);
+
+        // Contexts made for running a source alone collect nothing
+        let ctx = ScanContext::new(0);
+        ctx.count_enumerated(3);
+        assert_eq!(ctx.time(ScanPhase::Icons, || 7), 7);
+        assert!(ctx.stats.is_none());
+    }
+
+    /// A 512 x 512 disc shaded from the middle out on a transparent background, which is
+    /// about what an app icon looks like.
+    fn disc_icon(hue: u8) -> Icon {
+        let size = 512u32;
+        let pixels = (0..size * size)
+            .flat_map(|i| {
+                let (x, y) = ((i % size) as f32 - 256.0, (i / size) as f32 - 256.0);
+                let distance = (x * x + y * y).sqrt();
+                if distance > 240.0 {
+                    [0; 4]
+                } else {
+                    let shade = 255 - (distance / 240.0 * 128.0) as u8;
+                    [hue, shade, 255 - hue, 255]
+                }
+            })
+            .collect();
+        Icon {
+            width: size,
+            height: size,
+            pixels,
+        }
+    }
+
+    #[test]
+    fn test_icon_storage() {
+        let apps: Vec<AppInfo> = (0..8u8)
+            .map(|i| AppInfo {
+                icon: Some(disc_icon(i * 32)),
+                icon_source: Some(crate::IconSource::File(PathBuf::from(format!(
+                    "/opt/app{}",
+                    i
+                )))),
+                ..app(&format!("App {}", i), &format!("/opt/app{}", i))
+            })
+            .collect();
+        let raw: usize = apps
+            .iter()
+            .map(|app| app.icon.as_ref().unwrap().pixels.len())
+            .sum();
+        let scan = |icon_storage| {
+            scan_apps(&ScanOptions {
+                icon_size: 512,
+                icon_storage,
+                include_builtin: false,
+                sources: vec![Box::new(crate::MockSource::new(apps.clone()))],
+                ..Default::default()
+            })
+            .expect("Failed to scan")
+        };
+
+        let report = scan(IconStorage::Png);
+        assert_eq!(report.stats.sources[0].icons, apps.len());
+        let stored: usize = report
+            .apps
+            .iter()
+            .map(|app| app.compressed_icon.as_ref().unwrap().png.len())
+            .sum();
+        assert!(stored * 8 < raw, "{} bytes stored for {} raw", stored, raw);
+        for (app, original) in report.apps.iter().zip(&apps) {
+            assert!(app.icon.is_none());
+            let decoded = app.load_icon(512).expect("Failed to decode icon");
+            assert_eq!((decoded.width, decoded.height), (512, 512));
+            assert_eq!(decoded.pixels, original.icon.as_ref().unwrap().pixels);
+        }
+
+        let report = scan(IconStorage::Deferred);
+        assert_eq!(report.stats.sources[0].icons, 0);
+        for (app, original) in report.apps.iter().zip(&apps) {
+            assert!(app.icon.is_none() && app.compressed_icon.is_none());
+            assert_eq!(app.icon_source, original.icon_source);
+        }
+
+        let report = scan(IconStorage::Rgba);
+        assert!(report.apps.iter().all(|app| app.icon.is_some()));
+        assert!(report.apps.iter().all(|app| app.compressed_icon.is_none()));
+    }
+
+    /// A source that looks in one directory that exists and one that does not.
+    struct DirectorySource;
+
+    impl AppSource for DirectorySource {
+        fn name(&self) -> &str {
+            "directories"
+        }
+
+        fn scan(&self, ctx: &ScanContext) -> Result<Vec<AppInfo>> {
+            for path in ctx.extra_directories() {
+                let status = match std::fs::read_dir(path) {
+                    Ok(_) => LocationStatus::Scanned,
+                    Err(error) => LocationStatus::from_io_error(&error),
+                };
+                let location = ScanLocation::Directory {
+                    path: path.clone(),
+                    scope: None,
+                };
+                ctx.record_location(location, status);
+            }
+            Ok(Vec::new())
+        }
+    }
+
+    #[test]
+    fn test_scan_locations() {
+        let present = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let missing = present.join("no-such-directory");
+        let report = scan_apps(&ScanOptions {
+            include_builtin: false,
+            sources: vec![Box::new(DirectorySource)],
+            extra_directories: vec![present.clone(), missing.clone()],
+            ..Default::default()
+        })
+        .expect("Failed to scan");
+
+        let locations: Vec<(String, &LocationStatus)> = report
+            .locations()
+            .map(|visited| (visited.location.to_string(), &visited.status))
+            .collect();
+        assert_eq!(
+            locations,
+            [
+                (present.display().to_string(), &LocationStatus::Scanned),
+                (missing.display().to_string(), &LocationStatus::NotPresent),
+            ]
+        );
+        assert_eq!(report.stats.sources[0].locations.len(), 2);
+
+        let denied = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        assert_eq!(
+            LocationStatus::from_io_error(&denied),
+            LocationStatus::PermissionDenied
+        );
+        let other = std::io::Error::other("disk on fire");
+        assert_eq!(
+            LocationStatus::from_io_error(&other),
+            LocationStatus::Failed("disk on fire".to_string())
+        );
+
+        let registry = ScanLocation::RegistryKey {
+            key: "HKEY_CURRENT_USER\\SOFTWARE".to_string(),
+            scope: InstallScope::User,
+        };
+        assert_eq!(registry.scope(), Some(InstallScope::User));
+        assert_eq!(registry.to_string(), "HKEY_CURRENT_USER\\SOFTWARE");
+
+        // Contexts made for running a source alone record nothing
+        ScanContext::new(0).record_location(registry, LocationStatus::Scanned);
+    }
+
+    #[test]
+    fn test_kind_filter() {
+        let kind = |name: &str, kind| AppInfo {
+            kind,
+            ..app(name, &format!("/opt/{}", name))
+        };
+        let options = ScanOptions {
+            include_builtin: false,
+            sources: vec![Box::new(DummySource(vec![
+                kind("editor", Some(AppKind::Application)),
+                kind("grep", Some(AppKind::CommandLineTool)),
+                kind("unknown", None),
+                kind("viewer", Some(AppKind::Application)),
+            ]))],
+            kinds: vec![AppKind::Application],
+            ..Default::default()
+        };
+
+        let report = scan_apps(&options).expect("Failed to scan");
+        let names: Vec<&str> = report.apps.iter().map(|app| app.name.as_str()).collect();
+        assert_eq!(names, ["editor", "viewer"]);
+        let stats = &report.stats.sources[0];
+        assert_eq!((stats.enumerated, stats.apps, stats.filtered), (4, 2, 2));
+    }
+
+    #[test]
+    fn test_install_scope_dedup() {
+        let scoped = |scope| AppInfo {
+            install_scope: Some(scope),
+            ..app("Tool", "C:\\Tools\\tool.exe")
+        };
+        let options = ScanOptions {
+            include_builtin: false,
+            sources: vec![Box::new(DummySource(vec![
+                scoped(InstallScope::Machine),
+                scoped(InstallScope::User),
+                scoped(InstallScope::User),
+            ]))],
+            ..Default::default()
+        };
+
+        // Installs for the machine and for the user are kept apart
+        let report = scan_apps(&options).expect("Failed to scan");
+        let scopes: Vec<_> = report.apps.iter().map(|app| app.install_scope).collect();
+        assert_eq!(
+            scopes,
+            [Some(InstallScope::Machine), Some(InstallScope::User)]
+        );
+    }
+
+    #[test]
+    fn test_bundle_copies_kept() {
+        let copy = |path: &str| AppInfo {
+            identifier: Some("com.example.Foo".to_string()),
+            ..app("Foo", path)
+        };
+        let options = ScanOptions {
+            include_builtin: false,
+            sources: vec![
+                Box::new(DummySource(vec![
+                    copy("/Applications/Foo.app"),
+                    copy("/Users/me/Applications/Foo.app"),
+                    copy("/Applications/Foo 2.app"),
+                ])),
+                // Another source finding one of them again
+                Box::new(DummySource(vec![copy("/Applications/Foo.app/")])),
+            ],
+            ..Default::default()
+        };
+
+        let report = scan_apps(&options).expect("Failed to scan");
+        assert_eq!(report.apps.len(), 3);
+        assert_eq!(report.stats.sources[1].deduplicated, 1);
+        let groups = crate::find_duplicate_installs(&report.apps);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].apps.len(), 3);
+    }
+
+    #[test]
+    fn test_cancelled_scan() {
+        let options = ScanOptions {
+            include_builtin: false,
+            sources: vec![Box::new(DummySource(vec![app("Tool", "/opt/tool")]))],
+            ..Default::default()
+        };
+        options.cancellation.cancel();
+
+        let report = scan_apps(&options).expect("Failed to scan");
+        assert!(report.apps.is_empty());
+        assert!(report.cancelled);
+
+        let no_sources = ScanOptions {
+            include_builtin: false,
+            ..Default::default()
+        };
+        assert!(matches!(
+            scan_apps(&no_sources),
+            Err(AppInfoError::UnsupportedPlatform)
+        ));
+    }
+}
@@ -0,0 +1,257 @@
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// A path as read back: a plain string, or serde's platform-specific `OsString` form for
+/// paths that are not valid UTF-8.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PathRepr {
+    Text(String),
+    Native(OsString),
+}
+
+impl From<PathRepr> for PathBuf {
+    fn from(repr: PathRepr) -> Self {
+        match repr {
+            PathRepr::Text(text) => PathBuf::from(text),
+            PathRepr::Native(native) => PathBuf::from(native),
+        }
+    }
+}
+
+/// Writes a path as a string when it is valid UTF-8, and losslessly otherwise.
+pub(crate) mod path {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(path: &Path, serializer: S) -> Result<S::Ok, S::Error> {
+        match path.to_str() {
+            Some(text) => serializer.serialize_str(text),
+            None => path.as_os_str().serialize(serializer),
+        }
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<PathBuf, D::Error> {
+        PathRepr::deserialize(deserializer).map(PathBuf::from)
+    }
+}
+
+/// [`path`] for optional paths.
+pub(crate) mod option_path {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(
+        path: &Option<PathBuf>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match path {
+            Some(path) => serializer.serialize_some(&PathField(path)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<PathBuf>, D::Error> {
+        Option::<PathRepr>::deserialize(deserializer).map(|repr| repr.map(PathBuf::from))
+    }
+
+    struct PathField<'a>(&'a Path);
+
+    impl Serialize for PathField<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            super::path::serialize(self.0, serializer)
+        }
+    }
+}
+
+/// Writes bytes as a standard base64 string.
+pub(crate) mod base64_bytes {
+    use super::*;
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    pub(crate) fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<u8>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        STANDARD.decode(text).map_err(D::Error::custom)
+    }
+}
+
+/// Writes an I/O error as its message, since `std::io::Error` has no serde support.
+pub(crate) fn serialize_io_error<S: Serializer>(
+    error: &std::io::Error,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{AppInfoError, FileIconError};
+    use crate::{
+        AppCategory, AppInfo, AppKind, AssociationRole, FileAssociation, Icon, IconSource,
+        InstallScope, PackageRef, SignatureInfo, SignatureStatus, UserAccount,
+    };
+    use std::path::PathBuf;
+
+    fn round_trip(app: &AppInfo) -> AppInfo {
+        let json = serde_json::to_string(app).expect("Failed to serialize");
+        serde_json::from_str(&json).expect("Failed to deserialize")
+    }
+
+    #[test]
+    fn test_app_info_round_trip() {
+        let app = AppInfo {
+            name: "Café Übersetzer 翻訳".to_string(),
+            version: Some("2.1".to_string()),
+            path: PathBuf::from("/Applications/Café.app"),
+            executable_path: Some(PathBuf::from("/Applications/Café.app/Contents/MacOS/Café")),
+            icon: Some(Icon {
+                width: 2,
+                height: 1,
+                pixels: vec![255, 0, 0, 255, 0, 0, 255, 128],
+            }),
+            compressed_icon: None,
+            icon_source: Some(IconSource::Resource {
+                path: PathBuf::from("/Applications/Café.app/Contents/Resources/Café.icns"),
+                index: -3,
+            }),
+            identifier: Some("com.example.cafe".to_string()),
+            publisher: None,
+            install_date: Some("20240301".to_string()),
+            parsed_install_date: None,
+            install_date_ambiguous: false,
+            install_size: Some(1_048_576),
+            category: Some(AppCategory::DeveloperTools),
+            kind: Some(AppKind::Application),
+            package_manager: Some(PackageRef {
+                manager: "winget".to_string(),
+                id: "Example.Cafe".to_string(),
+                available_version: None,
+            }),
+            install_scope: Some(InstallScope::Machine),
+            registry_view: None,
+            owner: Some(UserAccount {
+                sid: "S-1-5-21-1-2-3-1001".to_string(),
+                name: None,
+            }),
+            can_uninstall: Some(false),
+            can_modify: None,
+            can_repair: Some(true),
+            declared_url_schemes: vec!["cafe".to_string()],
+            declared_file_types: vec![FileAssociation {
+                extension: Some("cafe".to_string()),
+                uti: None,
+                role: AssociationRole::Editor,
+            }],
+            signature: Some(SignatureInfo {
+                status: SignatureStatus::CatalogSigned,
+                signer: None,
+            }),
+            name_inferred: true,
+        };
+
+        let mut value = serde_json::to_value(&app).unwrap();
+        assert_eq!(value["path"], "/Applications/Café.app");
+        assert_eq!(value["category"], "Developer Tools");
+        assert_eq!(value["kind"], "Application");
+        assert_eq!(value["icon"]["pixels"], "/wAA/wAA/4A=");
+
+        // Output written before these fields existed reads back with their defaults
+        value.as_object_mut().unwrap().remove("name_inferred");
+        value.as_object_mut().unwrap().remove("kind");
+        value.as_object_mut().unwrap().remove("declared_file_types");
+        let older: AppInfo = serde_json::from_value(value).unwrap();
+        assert!(!older.name_inferred);
+        assert!(older.declared_file_types.is_empty());
+        assert_eq!(older.kind, None);
+
+        let decoded = round_trip(&app);
+        assert_eq!(decoded.name, app.name);
+        assert_eq!(decoded.path, app.path);
+        assert_eq!(decoded.executable_path, app.executable_path);
+        assert_eq!(decoded.install_size, app.install_size);
+        assert_eq!(decoded.category, app.category);
+        assert_eq!(decoded.kind, app.kind);
+        assert_eq!(decoded.package_manager, app.package_manager);
+        assert_eq!(decoded.install_scope, app.install_scope);
+        assert_eq!(decoded.signature, app.signature);
+        assert_eq!(decoded.icon_source, app.icon_source);
+        assert_eq!(decoded.declared_url_schemes, app.declared_url_schemes);
+        assert_eq!(decoded.declared_file_types, app.declared_file_types);
+        assert!(decoded.name_inferred);
+        assert_eq!(
+            (
+                decoded.can_uninstall,
+                decoded.can_modify,
+                decoded.can_repair
+            ),
+            (Some(false), None, Some(true))
+        );
+        let icon = decoded.icon.unwrap();
+        assert_eq!((icon.width, icon.height), (2, 1));
+        assert_eq!(icon.pixels, app.icon.unwrap().pixels);
+
+        let plain = AppInfo {
+            name: "Plain".to_string(),
+            path: PathBuf::from("C:\\Program Files\\Plain"),
+            ..Default::default()
+        };
+        let decoded = round_trip(&plain);
+        assert_eq!(decoded.name, "Plain");
+        assert_eq!(decoded.path, plain.path);
+        assert!(decoded.icon.is_none());
+        assert!(decoded.executable_path.is_none());
+
+        let bad_pixels = r#"{"width":1,"height":1,"pixels":"not base64!"}"#;
+        assert!(serde_json::from_str::<Icon>(bad_pixels).is_err());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_non_utf8_path_round_trip() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let app = AppInfo {
+            name: "Latin-1".to_string(),
+            path: PathBuf::from(OsStr::from_bytes(b"/opt/caf\xe9")),
+            executable_path: Some(PathBuf::from(OsStr::from_bytes(b"/opt/caf\xe9/bin"))),
+            ..Default::default()
+        };
+        let decoded = round_trip(&app);
+        assert_eq!(decoded.path, app.path);
+        assert_eq!(decoded.executable_path, app.executable_path);
+    }
+
+    #[test]
+    fn test_error_serialize() {
+        let not_found = AppInfoError::AppNotFound {
+            name: "Missing".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_string(&not_found).unwrap(),
+            r#"{"AppNotFound":{"name":"Missing"}}"#
+        );
+
+        let io = AppInfoError::from(std::io::Error::other("disk gone"));
+        assert_eq!(
+            serde_json::to_string(&io).unwrap(),
+            r#"{"DirectoryReadError":"disk gone"}"#
+        );
+
+        let icon = AppInfoError::from(FileIconError::NullIconSize);
+        assert_eq!(
+            serde_json::to_string(&icon).unwrap(),
+            r#"{"FileIconError":"NullIconSize"}"#
+        );
+    }
+}
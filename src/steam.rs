@@ -0,0 +1,589 @@
+use crate::{AppCategory, AppInfo, AppKind, AppSource, IconSource, Result, ScanContext};
+use std::path::{Path, PathBuf};
+
+#[cfg(target_os = "macos")]
+use crate::macos::load_image_icon;
+#[cfg(target_os = "windows")]
+use crate::window::load_image_icon;
+
+/// A source for games installed through Steam, which only registers itself with the
+/// system and not the titles it installs.
+///
+/// Every library listed in `steamapps/libraryfolders.vdf` is read, and each
+/// `appmanifest_*.acf` becomes an app with an identifier like `steam:440`. Icons come
+/// from Steam's library cache when it has one for the game. Machines without Steam
+/// simply yield no apps.
+///
+/// The source is not part of the built-in ones; add it to [`crate::ScanOptions::sources`].
+#[derive(Debug, Clone, Default)]
+pub struct SteamSource {
+    root: Option<PathBuf>,
+}
+
+impl SteamSource {
+    /// Creates a source for the Steam installation of the current user, if any.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a source for the Steam installation at `root`, the directory containing
+    /// `steamapps`.
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        SteamSource {
+            root: Some(root.into()),
+        }
+    }
+
+    fn root(&self) -> Option<PathBuf> {
+        self.root.clone().or_else(steam_install_path)
+    }
+}
+
+impl AppSource for SteamSource {
+    fn name(&self) -> &str {
+        "steam"
+    }
+
+    fn scan(&self, ctx: &ScanContext) -> Result<Vec<AppInfo>> {
+        let Some(root) = self.root().filter(|root| root.is_dir()) else {
+            return Ok(Vec::new());
+        };
+
+        let mut apps = Vec::new();
+        for library in library_folders(&root) {
+            let Ok(entries) = std::fs::read_dir(library.join("steamapps")) else {
+                continue;
+            };
+
+            for entry in entries.flatten() {
+                if ctx.is_cancelled() {
+                    return Ok(apps);
+                }
+
+                let path = entry.path();
+                let is_manifest = path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("appmanifest_") && name.ends_with(".acf"));
+                if !is_manifest {
+                    continue;
+                }
+
+                enter_span!(DEBUG, "steam_manifest", path = %path.display());
+                let Some(manifest) = std::fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|text| parse_vdf(&text))
+                else {
+                    debug_event!("skipping unreadable manifest");
+                    continue;
+                };
+
+                let Some(mut app) = manifest_app(&manifest, &library) else {
+                    debug_event!("skipping incomplete manifest");
+                    continue;
+                };
+                let cached_icon = app
+                    .identifier
+                    .as_deref()
+                    .and_then(|id| id.strip_prefix("steam:"))
+                    .and_then(|appid| library_cache_icon(&root, appid));
+                if ctx.extracts_icons() {
+                    let icon = cached_icon
+                        .as_deref()
+                        .and_then(|icon| load_image_icon(icon, ctx.icon_size()).ok());
+                    ctx.store_icon(&mut app, icon);
+                }
+                app.icon_source = cached_icon.map(IconSource::Image);
+                apps.push(app);
+                ctx.report_progress(apps.len());
+            }
+        }
+
+        Ok(apps)
+    }
+}
+
+/// Finds the Steam installation of the current user.
+#[cfg(target_os = "macos")]
+fn steam_install_path() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .map(|home| PathBuf::from(home).join("Library/Application Support/Steam"))
+}
+
+#[cfg(target_os = "windows")]
+fn steam_install_path() -> Option<PathBuf> {
+    crate::window::steam_install_path()
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn steam_install_path() -> Option<PathBuf> {
+    None
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn load_image_icon(path: &Path, size: u16) -> Result<crate::Icon> {
+    let _ = (path, size);
+    Err(crate::error::AppInfoError::UnsupportedPlatform)
+}
+
+/// The library folders of a Steam installation. The installation itself always comes
+/// first, since older versions of `libraryfolders.vdf` do not list it.
+fn library_folders(root: &Path) -> Vec<PathBuf> {
+    let mut folders = vec![root.to_path_buf()];
+    let listed = std::fs::read_to_string(root.join("steamapps").join("libraryfolders.vdf"))
+        .ok()
+        .and_then(|text| parse_vdf(&text))
+        .map(|vdf| library_paths(&vdf))
+        .unwrap_or_default();
+
+    for folder in listed {
+        let known = folders.iter().any(|known| same_folder(known, &folder));
+        if !known {
+            folders.push(folder);
+        }
+    }
+    folders
+}
+
+fn same_folder(a: &Path, b: &Path) -> bool {
+    let normalize = |path: &Path| {
+        path.to_string_lossy()
+            .replace('\\', "/")
+            .trim_end_matches('/')
+            .to_lowercase()
+    };
+    normalize(a) == normalize(b)
+}
+
+/// The library paths listed in a parsed `libraryfolders.vdf`.
+///
+/// Current files map each numeric key to a table with a `path` entry; older ones map it
+/// directly to the path. Other keys, such as `contentstatsid`, are ignored.
+fn library_paths(vdf: &VdfValue) -> Vec<PathBuf> {
+    let Some(VdfValue::Table(entries)) = vdf.get("libraryfolders") else {
+        return Vec::new();
+    };
+
+    entries
+        .iter()
+        .filter(|(key, _)| !key.is_empty() && key.bytes().all(|b| b.is_ascii_digit()))
+        .filter_map(|(_, value)| match value {
+            VdfValue::String(path) => Some(path.as_str()),
+            VdfValue::Table(_) => value.get("path").and_then(VdfValue::as_str),
+        })
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// Builds the app described by a parsed `appmanifest_*.acf` in `library`.
+fn manifest_app(manifest: &VdfValue, library: &Path) -> Option<AppInfo> {
+    let state = manifest.get("AppState")?;
+    let appid = state.get("appid").and_then(VdfValue::as_str)?;
+    let name = state.get("name").and_then(VdfValue::as_str)?;
+    let install_dir = state.get("installdir").and_then(VdfValue::as_str)?;
+    if appid.is_empty() || name.is_empty() || install_dir.is_empty() {
+        return None;
+    }
+
+    Some(AppInfo {
+        name: name.to_string(),
+        version: state
+            .get("buildid")
+            .and_then(VdfValue::as_str)
+            .filter(|build| !build.is_empty() && *build != "0")
+            .map(str::to_string),
+        path: library.join("steamapps").join("common").join(install_dir),
+        executable_path: None,
+        icon: None,
+        compressed_icon: None,
+        icon_source: None,
+        identifier: Some(format!("steam:{}", appid)),
+        publisher: None,
+        install_date: None,
+        parsed_install_date: None,
+        install_date_ambiguous: false,
+        install_size: state
+            .get("SizeOnDisk")
+            .and_then(VdfValue::as_str)
+            .and_then(|size| size.parse().ok())
+            .filter(|size| *size > 0),
+        category: Some(AppCategory::Games),
+        kind: Some(AppKind::Application),
+        package_manager: None,
+        install_scope: None,
+        registry_view: None,
+        owner: None,
+        can_uninstall: None,
+        can_modify: None,
+        can_repair: None,
+        declared_url_schemes: Vec::new(),
+        declared_file_types: Vec::new(),
+        signature: None,
+        name_inferred: false,
+    })
+}
+
+/// Finds the icon Steam cached for a game. Older clients store it as
+/// `<appid>_icon.jpg`; newer ones put it in a per-game folder, named by its hash.
+fn library_cache_icon(root: &Path, appid: &str) -> Option<PathBuf> {
+    let cache = root.join("appcache").join("librarycache");
+    let legacy = cache.join(format!("{}_icon.jpg", appid));
+    if legacy.is_file() {
+        return Some(legacy);
+    }
+
+    std::fs::read_dir(cache.join(appid))
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| {
+            let is_jpg = path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("jpg"));
+            let hashed = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .is_some_and(|stem| {
+                    stem.len() == 40 && stem.bytes().all(|b| b.is_ascii_hexdigit())
+                });
+            is_jpg && hashed && path.is_file()
+        })
+}
+
+/// A value in Valve's KeyValues text format, as used by `.vdf` and `.acf` files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum VdfValue {
+    String(String),
+    /// Entries in file order; keys may repeat
+    Table(Vec<(String, VdfValue)>),
+}
+
+impl VdfValue {
+    /// Looks up the first entry of a table by key, ignoring case as Steam does.
+    fn get(&self, key: &str) -> Option<&VdfValue> {
+        match self {
+            VdfValue::Table(entries) => entries
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(key))
+                .map(|(_, value)| value),
+            VdfValue::String(_) => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            VdfValue::String(value) => Some(value),
+            VdfValue::Table(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum VdfToken {
+    Text(String),
+    Open,
+    Close,
+}
+
+/// Splits KeyValues text into tokens. Strings may be quoted, with backslash escapes, or
+/// bare; `//` starts a comment and `[$WIN32]`-style conditionals are dropped.
+fn tokenize_vdf(text: &str) -> Vec<VdfToken> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '{' => {
+                chars.next();
+                tokens.push(VdfToken::Open);
+            }
+            '}' => {
+                chars.next();
+                tokens.push(VdfToken::Close);
+            }
+            '/' => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    for c in chars.by_ref() {
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                } else {
+                    tokens.push(VdfToken::Text(bare_word(&mut chars, "/".to_string())));
+                }
+            }
+            '[' => {
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                }
+            }
+            '"' => {
+                chars.next();
+                let mut value = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some('n') => value.push('\n'),
+                            Some('t') => value.push('\t'),
+                            Some(other) => value.push(other),
+                            None => break,
+                        },
+                        c => value.push(c),
+                    }
+                }
+                tokens.push(VdfToken::Text(value));
+            }
+            _ => tokens.push(VdfToken::Text(bare_word(&mut chars, String::new()))),
+        }
+    }
+
+    tokens
+}
+
+fn bare_word(chars: &mut std::iter::Peekable<std::str::Chars<'_>>, mut word: String) -> String {
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() || matches!(c, '{' | '}' | '"') {
+            break;
+        }
+        word.push(c);
+        chars.next();
+    }
+    word
+}
+
+/// Parses KeyValues text into a table of its top-level entries. Returns `None` for
+/// malformed input.
+fn parse_vdf(text: &str) -> Option<VdfValue> {
+    let mut tokens = tokenize_vdf(text).into_iter();
+    let entries = parse_vdf_entries(&mut tokens, false)?;
+    Some(VdfValue::Table(entries))
+}
+
+fn parse_vdf_entries(
+    tokens: &mut impl Iterator<Item = VdfToken>,
+    nested: bool,
+) -> Option<Vec<(String, VdfValue)>> {
+    let mut entries = Vec::new();
+    loop {
+        let key = match tokens.next() {
+            Some(VdfToken::Text(key)) => key,
+            Some(VdfToken::Close) if nested => return Some(entries),
+            None if !nested => return Some(entries),
+            _ => return None,
+        };
+        let value = match tokens.next()? {
+            VdfToken::Text(value) => VdfValue::String(value),
+            VdfToken::Open => VdfValue::Table(parse_vdf_entries(tokens, true)?),
+            VdfToken::Close => return None,
+        };
+        entries.push((key, value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIBRARY_FOLDERS: &str = r#"
+"libraryfolders"
+{
+	"0"
+	{
+		"path"		"C:\\Program Files (x86)\\Steam"
+		"label"		""
+		"contentid"		"4153212089462759101"
+		"totalsize"		"0"
+		"apps"
+		{
+			"228980"		"188108001"
+			"440"		"25902283225"
+		}
+	}
+	"1"
+	{
+		"path"		"D:\\SteamLibrary"
+		"label"		"Games"
+		"apps"
+		{
+		}
+	}
+}
+"#;
+
+    const LEGACY_LIBRARY_FOLDERS: &str = r#"
+"LibraryFolders"
+{
+	"TimeNextStatsReport"		"1561832478"
+	"ContentStatsID"		"-158337411110787451"
+	"1"		"D:\\SteamLibrary"
+	"2"		"E:\\Games\\Steam"
+}
+"#;
+
+    const APP_MANIFEST: &str = r#"
+// Written by the Steam client
+"AppState"
+{
+	"appid"		"440"
+	"Universe"		"1"
+	"name"		"Team Fortress 2"
+	"StateFlags"		"4"
+	"installdir"		"Team Fortress 2"
+	"LastUpdated"		"1700000000"
+	"SizeOnDisk"		"25902283225"
+	"buildid"		"12708690"
+	"InstalledDepots"
+	{
+		"232251"
+		{
+			"manifest"		"4051929664011096342"
+			"size"		"19720511004"
+		}
+	}
+	"UserConfig"
+	{
+		"language"		"english"
+	}
+}
+"#;
+
+    #[test]
+    fn test_parse_vdf() {
+        let vdf = parse_vdf(APP_MANIFEST).expect("Failed to parse manifest");
+        let state = vdf.get("appstate").unwrap();
+        assert_eq!(
+            state.get("name").and_then(VdfValue::as_str),
+            Some("Team Fortress 2")
+        );
+        assert_eq!(
+            state
+                .get("InstalledDepots")
+                .and_then(|depots| depots.get("232251"))
+                .and_then(|depot| depot.get("size"))
+                .and_then(VdfValue::as_str),
+            Some("19720511004")
+        );
+
+        let escaped = parse_vdf("key \"a \\\"quoted\\\" word\" [$WIN32] bare value").unwrap();
+        assert_eq!(
+            escaped.get("key").and_then(VdfValue::as_str),
+            Some("a \"quoted\" word")
+        );
+        assert_eq!(
+            escaped.get("bare").and_then(VdfValue::as_str),
+            Some("value")
+        );
+
+        assert_eq!(parse_vdf("\"unclosed\" {"), None);
+        assert_eq!(parse_vdf("\"dangling\""), None);
+        assert_eq!(parse_vdf("}"), None);
+    }
+
+    #[test]
+    fn test_library_paths() {
+        let current = parse_vdf(LIBRARY_FOLDERS).unwrap();
+        assert_eq!(
+            library_paths(&current),
+            [
+                PathBuf::from("C:\\Program Files (x86)\\Steam"),
+                PathBuf::from("D:\\SteamLibrary")
+            ]
+        );
+
+        let legacy = parse_vdf(LEGACY_LIBRARY_FOLDERS).unwrap();
+        assert_eq!(
+            library_paths(&legacy),
+            [
+                PathBuf::from("D:\\SteamLibrary"),
+                PathBuf::from("E:\\Games\\Steam")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_manifest_app() {
+        let library = Path::new("/games");
+        let app = manifest_app(&parse_vdf(APP_MANIFEST).unwrap(), library).unwrap();
+        assert_eq!(app.name, "Team Fortress 2");
+        assert_eq!(app.identifier.as_deref(), Some("steam:440"));
+        assert_eq!(app.version.as_deref(), Some("12708690"));
+        assert_eq!(app.install_size, Some(25_902_283_225));
+        assert_eq!(
+            app.path,
+            library
+                .join("steamapps")
+                .join("common")
+                .join("Team Fortress 2")
+        );
+
+        let incomplete = parse_vdf("\"AppState\" { \"appid\" \"1\" \"name\" \"No dir\" }").unwrap();
+        assert!(manifest_app(&incomplete, library).is_none());
+    }
+
+    #[test]
+    fn test_steam_source() {
+        let root = std::env::temp_dir().join(format!("app-info-steam-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let library = root.join("library");
+        std::fs::create_dir_all(root.join("steamapps")).unwrap();
+        std::fs::create_dir_all(library.join("steamapps")).unwrap();
+
+        let folders = format!(
+            "\"libraryfolders\" {{ \"0\" {{ \"path\" \"{}\" }} \"1\" {{ \"path\" \"{}\" }} }}",
+            root.display().to_string().replace('\\', "\\\\"),
+            library.display().to_string().replace('\\', "\\\\")
+        );
+        std::fs::write(root.join("steamapps").join("libraryfolders.vdf"), folders).unwrap();
+        std::fs::write(
+            root.join("steamapps").join("appmanifest_440.acf"),
+            APP_MANIFEST,
+        )
+        .unwrap();
+        std::fs::write(
+            library.join("steamapps").join("appmanifest_70.acf"),
+            "\"AppState\" { \"appid\" \"70\" \"name\" \"Half-Life\" \"installdir\" \"Half-Life\" }",
+        )
+        .unwrap();
+        std::fs::write(library.join("steamapps").join("notes.acf"), "ignored").unwrap();
+
+        let cache = root.join("appcache").join("librarycache");
+        std::fs::create_dir_all(cache.join("70")).unwrap();
+        let hashed = cache
+            .join("70")
+            .join("0123456789abcdef0123456789abcdef01234567.jpg");
+        std::fs::write(&hashed, b"").unwrap();
+        std::fs::write(cache.join("440_icon.jpg"), b"").unwrap();
+        assert_eq!(library_cache_icon(&root, "70"), Some(hashed));
+        assert_eq!(
+            library_cache_icon(&root, "440"),
+            Some(cache.join("440_icon.jpg"))
+        );
+        assert_eq!(library_cache_icon(&root, "10"), None);
+
+        let mut apps = SteamSource::with_root(&root)
+            .scan(&ScanContext::new(0))
+            .expect("Failed to scan");
+        apps.sort_by(|a, b| a.name.cmp(&b.name));
+        let ids: Vec<_> = apps.iter().map(|app| app.identifier.as_deref()).collect();
+        assert_eq!(ids, [Some("steam:70"), Some("steam:440")]);
+        assert_eq!(
+            apps[0].path,
+            library.join("steamapps").join("common").join("Half-Life")
+        );
+
+        let missing = SteamSource::with_root(root.join("missing"))
+            .scan(&ScanContext::new(0))
+            .unwrap();
+        assert!(missing.is_empty());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,109 @@
+// Instrumentation macros that compile to nothing without the `tracing` feature, so call
+// sites need no cfg attributes. Field values must never include icon pixel data.
+
+/// Enters a span for the rest of the enclosing block, e.g.
+/// `enter_span!(DEBUG, "parse_app_bundle", path = %path.display())`.
+#[cfg(feature = "tracing")]
+macro_rules! enter_span {
+    ($level:ident, $($arg:tt)+) => {
+        let _span = tracing::span!(tracing::Level::$level, $($arg)+).entered();
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! enter_span {
+    ($level:ident, $($arg:tt)+) => {};
+}
+
+/// Emits a debug event, with the same syntax as `tracing::debug!`.
+#[cfg(feature = "tracing")]
+macro_rules! debug_event {
+    ($($arg:tt)+) => {
+        tracing::debug!($($arg)+)
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! debug_event {
+    ($($arg:tt)+) => {};
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use crate::{scan_apps, ScanOptions, SteamSource};
+    use std::sync::{Arc, Mutex};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    /// A subscriber that records the name of every span created.
+    #[derive(Clone, Default)]
+    struct SpanNames(Arc<Mutex<Vec<&'static str>>>);
+
+    impl SpanNames {
+        fn contains(&self, name: &str) -> bool {
+            self.0.lock().unwrap().contains(&name)
+        }
+    }
+
+    impl Subscriber for SpanNames {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut names = self.0.lock().unwrap();
+            names.push(span.metadata().name());
+            Id::from_u64(names.len() as u64)
+        }
+
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, _event: &Event<'_>) {}
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+
+    #[test]
+    fn test_scan_spans() {
+        let root = std::env::temp_dir().join(format!("app-info-trace-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("steamapps")).unwrap();
+        std::fs::write(
+            root.join("steamapps").join("appmanifest_70.acf"),
+            "\"AppState\" { \"appid\" \"70\" \"name\" \"Half-Life\" \"installdir\" \"Half-Life\" }",
+        )
+        .unwrap();
+
+        let spans = SpanNames::default();
+        let options = ScanOptions {
+            include_builtin: false,
+            sources: vec![Box::new(SteamSource::with_root(&root))],
+            ..Default::default()
+        };
+        let report = tracing::subscriber::with_default(spans.clone(), || scan_apps(&options))
+            .expect("Failed to scan");
+        let _ = std::fs::remove_dir_all(&root);
+
+        assert_eq!(report.apps.len(), 1);
+        assert!(spans.contains("scan_apps"));
+        assert!(spans.contains("scan_source"));
+        assert!(spans.contains("steam_manifest"));
+    }
+
+    #[test]
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    fn test_installed_apps_spans() {
+        let spans = SpanNames::default();
+        let apps =
+            tracing::subscriber::with_default(spans.clone(), || crate::get_installed_apps(0))
+                .expect("Failed to get installed apps");
+
+        assert!(!apps.is_empty());
+        assert!(spans.contains("get_installed_apps"));
+        assert!(spans.contains("parse_app_bundle") || spans.contains("parse_registry_app"));
+    }
+}
@@ -0,0 +1,157 @@
+use std::path::PathBuf;
+
+/// Options for [`AppInfo::uninstall`](crate::AppInfo::uninstall).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UninstallOptions {
+    /// Remove the application without showing the uninstaller: the entry's
+    /// `QuietUninstallString`, or `msiexec /qn` for Windows Installer products. Entries
+    /// that register no quiet command fail rather than show a window nobody may be there
+    /// to answer.
+    pub quiet: bool,
+    /// Run nothing, and return what would have run as [`UninstallOutcome::DryRun`].
+    pub dry_run: bool,
+}
+
+/// What came of [`AppInfo::uninstall`](crate::AppInfo::uninstall).
+///
+/// On Windows this is read from the uninstaller's exit code, following the codes Windows
+/// Installer uses, which most other installers follow too. Some uninstallers hand over to
+/// a copy of themselves and exit at once; for those it only tells that the hand-over
+/// worked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UninstallOutcome {
+    /// The uninstaller finished successfully
+    Uninstalled,
+    /// The uninstaller finished, and the removal completes when the machine restarts:
+    /// exit code 3010, or 1641 when the uninstaller has already started the restart
+    RestartRequired { restart_started: bool },
+    /// The user declined the elevation prompt or cancelled the uninstaller (exit code 1602)
+    Cancelled,
+    /// Windows Installer does not know the product, so it is already gone (exit code 1605)
+    NotInstalled,
+    /// Another installation is in progress, and the uninstall can be tried again once it
+    /// finishes (exit code 1618)
+    InstallerBusy,
+    /// The uninstaller failed with this exit code
+    Failed { exit_code: u32 },
+    /// The bundle was moved to the Trash, on macOS, and is now at `path`. This is not a
+    /// full uninstall: files the application keeps elsewhere, such as its preferences and
+    /// caches, stay, and the bundle can be put back.
+    MovedToTrash { path: PathBuf },
+    /// Nothing ran, as [`UninstallOptions::dry_run`] asked; this is what would have
+    DryRun(UninstallPlan),
+}
+
+/// What [`AppInfo::uninstall`](crate::AppInfo::uninstall) does for an application, as
+/// returned for a dry run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UninstallPlan {
+    /// Run the registered uninstaller, on Windows
+    Run(UninstallCommand),
+    /// Move the bundle at this path to the Trash, on macOS
+    MoveToTrash(PathBuf),
+}
+
+/// An uninstaller command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UninstallCommand {
+    /// The program to run, with environment variables expanded
+    pub program: PathBuf,
+    /// The arguments, as one string passed on exactly as written
+    pub arguments: String,
+    /// Whether the command runs elevated through the `runas` verb, which shows a UAC
+    /// prompt. Machine-wide installs ask for it when the process is not elevated already.
+    pub elevated: bool,
+}
+
+impl UninstallCommand {
+    /// The whole command line, with the program quoted.
+    pub fn command_line(&self) -> String {
+        let program = format!("\"{}\"", self.program.display());
+        if self.arguments.is_empty() {
+            program
+        } else {
+            format!("{} {}", program, self.arguments)
+        }
+    }
+}
+
+/// Reads an uninstaller's exit code.
+#[cfg(any(target_os = "windows", test))]
+pub(crate) fn outcome_for_exit_code(exit_code: u32) -> UninstallOutcome {
+    match exit_code {
+        0 => UninstallOutcome::Uninstalled,
+        // ERROR_SUCCESS_REBOOT_INITIATED and ERROR_SUCCESS_REBOOT_REQUIRED
+        1641 => UninstallOutcome::RestartRequired {
+            restart_started: true,
+        },
+        3010 => UninstallOutcome::RestartRequired {
+            restart_started: false,
+        },
+        // ERROR_INSTALL_USEREXIT
+        1602 => UninstallOutcome::Cancelled,
+        // ERROR_UNKNOWN_PRODUCT
+        1605 => UninstallOutcome::NotInstalled,
+        // ERROR_INSTALL_ALREADY_RUNNING
+        1618 => UninstallOutcome::InstallerBusy,
+        exit_code => UninstallOutcome::Failed { exit_code },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outcome_for_exit_code() {
+        assert_eq!(outcome_for_exit_code(0), UninstallOutcome::Uninstalled);
+        assert_eq!(
+            outcome_for_exit_code(3010),
+            UninstallOutcome::RestartRequired {
+                restart_started: false
+            }
+        );
+        assert_eq!(
+            outcome_for_exit_code(1641),
+            UninstallOutcome::RestartRequired {
+                restart_started: true
+            }
+        );
+        assert_eq!(outcome_for_exit_code(1602), UninstallOutcome::Cancelled);
+        assert_eq!(outcome_for_exit_code(1605), UninstallOutcome::NotInstalled);
+        assert_eq!(outcome_for_exit_code(1618), UninstallOutcome::InstallerBusy);
+        assert_eq!(
+            outcome_for_exit_code(1603),
+            UninstallOutcome::Failed { exit_code: 1603 }
+        );
+        assert_eq!(
+            outcome_for_exit_code(u32::MAX),
+            UninstallOutcome::Failed {
+                exit_code: u32::MAX
+            }
+        );
+    }
+
+    #[test]
+    fn test_command_line() {
+        let command = UninstallCommand {
+            program: PathBuf::from(r"C:\Program Files\Tool\uninstall.exe"),
+            arguments: "/S /D=x".to_string(),
+            elevated: false,
+        };
+        assert_eq!(
+            command.command_line(),
+            r#""C:\Program Files\Tool\uninstall.exe" /S /D=x"#
+        );
+        let command = UninstallCommand {
+            arguments: String::new(),
+            ..command
+        };
+        assert_eq!(
+            command.command_line(),
+            r#""C:\Program Files\Tool\uninstall.exe""#
+        );
+    }
+}
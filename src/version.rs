@@ -0,0 +1,125 @@
+use std::cmp::Ordering;
+
+/// Compares two version strings the way installers number releases.
+///
+/// Versions are split into parts at `.`, `-`, `_`, `+` and spaces, and at each change
+/// between digits and letters, so `1.2.10` is newer than `1.2.9` and `2.0b1` splits into
+/// `2`, `0`, `b`, `1`. Numeric parts compare as numbers and other parts ignoring case, with
+/// numbers ranking above words. Missing parts count as zero, so `1.2` equals `1.2.0`,
+/// except that a trailing word marks a pre-release: `1.0-beta` is older than `1.0`. A
+/// leading `v` is ignored.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (a, b) = (parts(a), parts(b));
+    for index in 0..a.len().max(b.len()) {
+        let ordering = match (a.get(index), b.get(index)) {
+            (Some(left), Some(right)) => left.cmp(right),
+            (Some(left), None) => left.cmp_missing(),
+            (None, Some(right)) => right.cmp_missing().reverse(),
+            (None, None) => Ordering::Equal,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Part {
+    Number(u64),
+    Word(String),
+}
+
+impl Part {
+    /// How the part compares against a part the other version does not have.
+    fn cmp_missing(&self) -> Ordering {
+        match self {
+            Part::Number(number) => number.cmp(&0),
+            Part::Word(_) => Ordering::Less,
+        }
+    }
+}
+
+impl Ord for Part {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Part::Number(left), Part::Number(right)) => left.cmp(right),
+            (Part::Number(_), Part::Word(_)) => Ordering::Greater,
+            (Part::Word(_), Part::Number(_)) => Ordering::Less,
+            (Part::Word(left), Part::Word(right)) => left.cmp(right),
+        }
+    }
+}
+
+impl PartialOrd for Part {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn parts(version: &str) -> Vec<Part> {
+    let version = version.trim();
+    let version = version
+        .strip_prefix(['v', 'V'])
+        .filter(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+        .unwrap_or(version);
+
+    let mut parts = Vec::new();
+    for piece in version.split(['.', '-', '_', '+', ' ']) {
+        let mut chars = piece.chars().peekable();
+        while let Some(&first) = chars.peek() {
+            let numeric = first.is_ascii_digit();
+            let mut run = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_ascii_digit() != numeric {
+                    break;
+                }
+                run.push(c);
+                chars.next();
+            }
+            parts.push(match run.parse() {
+                Ok(number) if numeric => Part::Number(number),
+                _ => Part::Word(run.to_lowercase()),
+            });
+        }
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_versions() {
+        let cases = [
+            ("1.2.10", "1.2.9", Ordering::Greater),
+            ("1.2", "1.2.0", Ordering::Equal),
+            ("1.2.0.1", "1.2", Ordering::Greater),
+            ("14.38.33135.0", "14.30.30704.0", Ordering::Greater),
+            ("23.01", "22.00", Ordering::Greater),
+            ("1.0-beta", "1.0", Ordering::Less),
+            ("1.0-beta", "1.0-alpha", Ordering::Greater),
+            ("2.0b1", "2.0b2", Ordering::Less),
+            ("2.0rc1", "2.0.1", Ordering::Less),
+            ("v3.4", "3.4", Ordering::Equal),
+            ("1.10 Build 5", "1.10 Build 12", Ordering::Less),
+            ("RELEASE", "release", Ordering::Equal),
+            ("", "", Ordering::Equal),
+            ("", "1.0", Ordering::Less),
+        ];
+        for (a, b, expected) in cases {
+            assert_eq!(compare_versions(a, b), expected, "{} vs {}", a, b);
+            assert_eq!(compare_versions(b, a), expected.reverse(), "{} vs {}", b, a);
+        }
+    }
+
+    #[test]
+    fn test_compare_versions_overflow() {
+        // Parts too long for a number still compare, as words
+        assert_eq!(
+            compare_versions("1.99999999999999999999999", "1.99999999999999999999999"),
+            Ordering::Equal
+        );
+    }
+}
@@ -0,0 +1,401 @@
+#[cfg(any(target_os = "macos", target_os = "windows", test))]
+use crate::scan::installation_key;
+use crate::{error::AppInfoError, AppInfo, Result};
+#[cfg(any(target_os = "macos", target_os = "windows", test))]
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+use std::time::Duration;
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+use std::time::SystemTime;
+
+#[cfg(target_os = "macos")]
+use crate::macos as backend;
+#[cfg(target_os = "windows")]
+use crate::window as backend;
+
+/// How often the notification thread checks whether the watcher has been dropped.
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// A change to the set of installed applications.
+// Events are rare, so boxing `Updated` is not worth the less convenient matching
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum AppEvent {
+    /// An application appeared.
+    Installed(AppInfo),
+    /// An application disappeared. Carries the information last seen for it.
+    Removed(AppInfo),
+    /// An application's metadata, such as its version, changed.
+    Updated {
+        /// The information seen before the change
+        before: AppInfo,
+        /// The information seen after the change
+        after: AppInfo,
+    },
+}
+
+/// Options for [`AppWatcher`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatcherOptions {
+    /// Icon size for the apps in `Installed` and `Updated` events. If 0, no icon will be
+    /// fetched.
+    pub icon_size: u16,
+    /// How long the system must stay quiet after a change before it is rescanned, so an
+    /// installer touching many keys or files produces one set of events.
+    pub debounce: Duration,
+}
+
+impl Default for WatcherOptions {
+    fn default() -> Self {
+        WatcherOptions {
+            icon_size: 0,
+            debounce: Duration::from_secs(2),
+        }
+    }
+}
+
+/// Watches for applications being installed, removed or updated.
+///
+/// On Windows the Uninstall registry keys are watched; on macOS the application
+/// directories are. Only the part of the system that changed is rescanned, and events
+/// are reported relative to the state seen when the watcher was created.
+///
+/// Dropping the watcher stops it.
+pub struct AppWatcher {
+    events: mpsc::Receiver<AppEvent>,
+    stop: Arc<AtomicBool>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl AppWatcher {
+    /// Starts watching, delivering events through [`AppWatcher::events`].
+    pub fn new(options: WatcherOptions) -> Result<AppWatcher> {
+        Self::start(options, None)
+    }
+
+    /// Starts watching, calling `callback` with each event. The callback runs on a
+    /// background thread and never on the one receiving system notifications.
+    pub fn with_callback<F>(options: WatcherOptions, callback: F) -> Result<AppWatcher>
+    where
+        F: FnMut(AppEvent) + Send + 'static,
+    {
+        Self::start(options, Some(Box::new(callback)))
+    }
+
+    /// The channel events are delivered on. It stays empty when a callback was given.
+    pub fn events(&self) -> &mpsc::Receiver<AppEvent> {
+        &self.events
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    fn start(
+        options: WatcherOptions,
+        callback: Option<Box<dyn FnMut(AppEvent) + Send>>,
+    ) -> Result<AppWatcher> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let (event_sender, events) = mpsc::channel();
+        let (change_sender, changes) = mpsc::channel::<Vec<usize>>();
+        let (ready_sender, ready) = mpsc::channel::<Result<()>>();
+
+        // Snapshot every scope up front so events are relative to this moment
+        let snapshots: Vec<Snapshot> = (0..backend::watched_scope_count())
+            .map(|scope| {
+                let scanned_at = SystemTime::now();
+                Snapshot {
+                    apps: backend::scan_scope(scope, &[], SystemTime::UNIX_EPOCH),
+                    scanned_at,
+                }
+            })
+            .collect();
+
+        // The notification thread only waits and forwards which scopes changed
+        let notifier_stop = Arc::clone(&stop);
+        let notifier = std::thread::Builder::new()
+            .name("app-watcher-notify".to_string())
+            .spawn(move || {
+                let mut monitor = match backend::ChangeMonitor::new() {
+                    Ok(monitor) => {
+                        let _ = ready_sender.send(Ok(()));
+                        monitor
+                    }
+                    Err(err) => {
+                        let _ = ready_sender.send(Err(err));
+                        return;
+                    }
+                };
+
+                while !notifier_stop.load(Ordering::Relaxed) {
+                    let changed = monitor.wait(POLL_INTERVAL);
+                    if !changed.is_empty() && change_sender.send(changed).is_err() {
+                        break;
+                    }
+                }
+            })
+            .map_err(|err| AppInfoError::WatcherError(err.to_string()))?;
+
+        match ready.recv() {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => {
+                let _ = notifier.join();
+                return Err(err);
+            }
+            Err(_) => {
+                let _ = notifier.join();
+                return Err(AppInfoError::WatcherError(
+                    "The notification thread exited during startup".to_string(),
+                ));
+            }
+        }
+
+        // Rescanning and icon extraction happen on a separate worker thread
+        let mut callback = callback;
+        let emit = move |event: AppEvent| match callback.as_mut() {
+            Some(callback) => {
+                callback(event);
+                true
+            }
+            None => event_sender.send(event).is_ok(),
+        };
+        let worker = std::thread::Builder::new()
+            .name("app-watcher".to_string())
+            .spawn(move || run_worker(changes, snapshots, &options, emit));
+
+        let worker = match worker {
+            Ok(worker) => worker,
+            Err(err) => {
+                stop.store(true, Ordering::Relaxed);
+                let _ = notifier.join();
+                return Err(AppInfoError::WatcherError(err.to_string()));
+            }
+        };
+
+        Ok(AppWatcher {
+            events,
+            stop,
+            threads: vec![notifier, worker],
+        })
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    fn start(
+        options: WatcherOptions,
+        callback: Option<Box<dyn FnMut(AppEvent) + Send>>,
+    ) -> Result<AppWatcher> {
+        let _ = (options, callback);
+        Err(AppInfoError::UnsupportedPlatform)
+    }
+}
+
+impl Drop for AppWatcher {
+    fn drop(&mut self) {
+        // The notifier notices the flag within one poll interval; its exit closes the
+        // change channel, which in turn ends the worker
+        self.stop.store(true, Ordering::Relaxed);
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// The applications last seen in one watched scope.
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+struct Snapshot {
+    apps: Vec<AppInfo>,
+    scanned_at: SystemTime,
+}
+
+/// Debounces change notifications, rescans the affected scopes and emits the
+/// differences. Returns once the notifier is gone or `emit` reports nobody is listening.
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn run_worker(
+    changes: mpsc::Receiver<Vec<usize>>,
+    mut snapshots: Vec<Snapshot>,
+    options: &WatcherOptions,
+    mut emit: impl FnMut(AppEvent) -> bool,
+) {
+    while let Ok(mut scopes) = changes.recv() {
+        // Keep collecting until the system has been quiet for the debounce period
+        loop {
+            match changes.recv_timeout(options.debounce) {
+                Ok(more) => scopes.extend(more),
+                Err(mpsc::RecvTimeoutError::Timeout) => break,
+                Err(mpsc::RecvTimeoutError::Disconnected) => return,
+            }
+        }
+        scopes.sort_unstable();
+        scopes.dedup();
+
+        for scope in scopes {
+            let Some(snapshot) = snapshots.get_mut(scope) else {
+                continue;
+            };
+
+            let scanned_at = SystemTime::now();
+            let apps = backend::scan_scope(scope, &snapshot.apps, snapshot.scanned_at);
+
+            for event in diff_apps(&snapshot.apps, &apps) {
+                if !emit(with_icon(event, options.icon_size)) {
+                    return;
+                }
+            }
+
+            *snapshot = Snapshot { apps, scanned_at };
+        }
+    }
+}
+
+/// Fetches the icon of the newly seen app in an event.
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn with_icon(event: AppEvent, icon_size: u16) -> AppEvent {
+    if icon_size == 0 {
+        return event;
+    }
+
+    let fetch = |mut app: AppInfo| {
+        app.icon = app.load_icon(icon_size).ok();
+        app
+    };
+
+    match event {
+        AppEvent::Installed(app) => AppEvent::Installed(fetch(app)),
+        AppEvent::Updated { before, after } => AppEvent::Updated {
+            before,
+            after: fetch(after),
+        },
+        removed => removed,
+    }
+}
+
+/// Whether anything worth reporting differs between two scans of the same app.
+#[cfg(any(target_os = "macos", target_os = "windows", test))]
+fn metadata_changed(before: &AppInfo, after: &AppInfo) -> bool {
+    before.name != after.name
+        || before.path != after.path
+        || before.version != after.version
+        || before.publisher != after.publisher
+        || before.identifier != after.identifier
+        || before.executable_path != after.executable_path
+        || before.install_date != after.install_date
+        || before.install_size != after.install_size
+}
+
+/// Computes the events that turn `before` into `after`.
+#[cfg(any(target_os = "macos", target_os = "windows", test))]
+fn diff_apps(before: &[AppInfo], after: &[AppInfo]) -> Vec<AppEvent> {
+    let previous: HashMap<_, _> = before
+        .iter()
+        .map(|app| (installation_key(app), app))
+        .collect();
+    let current: HashMap<_, _> = after
+        .iter()
+        .map(|app| (installation_key(app), app))
+        .collect();
+    let mut events = Vec::new();
+
+    for app in after {
+        match previous.get(&installation_key(app)) {
+            None => events.push(AppEvent::Installed(app.clone())),
+            Some(old) if metadata_changed(old, app) => events.push(AppEvent::Updated {
+                before: (*old).clone(),
+                after: app.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+
+    for app in before {
+        if !current.contains_key(&installation_key(app)) {
+            events.push(AppEvent::Removed(app.clone()));
+        }
+    }
+
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+
+    fn app(name: &str, path: &str, version: &str) -> AppInfo {
+        AppInfo {
+            name: name.to_string(),
+            path: PathBuf::from(path),
+            version: Some(version.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff_apps() {
+        let before = vec![
+            app("Kept", "/Applications/Kept.app", "1.0"),
+            app("Upgraded", "/Applications/Upgraded.app", "1.0"),
+            app("Gone", "/Applications/Gone.app", "1.0"),
+        ];
+        let after = vec![
+            app("Kept", "/Applications/Kept.app", "1.0"),
+            app("Upgraded", "/Applications/Upgraded.app", "2.0"),
+            app("New", "/Applications/New.app", "1.0"),
+        ];
+
+        let events = diff_apps(&before, &after);
+        assert_eq!(events.len(), 3);
+        assert!(matches!(
+            &events[0],
+            AppEvent::Updated { before, after }
+                if before.version.as_deref() == Some("1.0")
+                    && after.version.as_deref() == Some("2.0")
+        ));
+        assert!(matches!(&events[1], AppEvent::Installed(app) if app.name == "New"));
+        assert!(matches!(&events[2], AppEvent::Removed(app) if app.name == "Gone"));
+
+        assert!(diff_apps(&after, &after).is_empty());
+    }
+
+    #[test]
+    fn test_diff_apps_shared_path() {
+        // Two registry entries pointing at the same installer path stay distinct
+        let before = vec![
+            app("Runtime x86", "", "14.0"),
+            app("Runtime x64", "", "14.0"),
+        ];
+        let after = vec![app("Runtime x64", "", "14.0")];
+
+        let events = diff_apps(&before, &after);
+        assert_eq!(events.len(), 1);
+        assert!(matches!(&events[0], AppEvent::Removed(app) if app.name == "Runtime x86"));
+    }
+
+    #[test]
+    fn test_diff_apps_bundle_copies() {
+        // Copies of one bundle share a stable ID but are separate installations
+        let copy = |path: &str| AppInfo {
+            identifier: Some("com.example.Foo".to_string()),
+            ..app("Foo", path, "1.0")
+        };
+        let apps = vec![
+            copy("/Applications/Foo.app"),
+            copy("/Applications/Foo 2.app"),
+        ];
+        assert!(diff_apps(&apps, &apps).is_empty());
+
+        let events = diff_apps(&apps, &apps[..1]);
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            &events[0],
+            AppEvent::Removed(app) if app.path == Path::new("/Applications/Foo 2.app")
+        ));
+    }
+
+    #[test]
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    fn test_watcher_start_and_drop() {
+        let watcher = AppWatcher::new(WatcherOptions::default()).unwrap();
+        assert!(watcher.events().try_recv().is_err());
+        drop(watcher);
+    }
+}
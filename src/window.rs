@@ -1,30 +1,131 @@
+#[cfg(any(target_os = "windows", test))]
+use crate::kind::PeSubsystem;
 #[cfg(target_os = "windows")]
-use crate::{error::AppInfoError, AppInfo, Icon, Result};
+use crate::kind::{windows_kind, WindowsSignals};
+#[cfg(target_os = "windows")]
+use crate::uninstall::outcome_for_exit_code;
+#[cfg(any(target_os = "windows", test))]
+use crate::{
+    error::AppInfoError, AppInfo, InstallScope, RegistryView, Result, SignatureStatus,
+    UninstallCommand,
+};
+#[cfg(target_os = "windows")]
+use crate::{
+    normalize_url_scheme, AppSource, AppUsage, AssociationRole, FileAssociation, FitMode, Icon,
+    IconSource, LaunchOptions, LaunchedApp, LocationStatus, RunningApp, RunningAppFilter,
+    ScanContext, ScanLocation, ScanPhase, SignatureInfo, StartupApp, StartupMechanism,
+    UninstallOptions, UninstallOutcome, UninstallPlan, UserAccount, UserProfiles,
+};
 #[cfg(target_os = "windows")]
 use scopeguard::defer;
 #[cfg(target_os = "windows")]
+use std::ffi::OsString;
+#[cfg(target_os = "windows")]
 use std::path::{Path, PathBuf};
+#[cfg(all(target_os = "windows", feature = "icons"))]
+use windows::Win32::{
+    Foundation::{FreeLibrary, GENERIC_READ, HMODULE, SIZE},
+    Graphics::{
+        Gdi::{
+            CreateCompatibleDC, DeleteDC, DeleteObject, GetDIBits, GetObjectW, BITMAP, BITMAPINFO,
+            BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS, HBITMAP, HDC,
+        },
+        Imaging::{
+            CLSID_WICImagingFactory, GUID_WICPixelFormat32bppBGRA, GUID_WICPixelFormat32bppPBGRA,
+            GUID_WICPixelFormat32bppPRGBA, GUID_WICPixelFormat32bppRGBA, IWICBitmap,
+            IWICImagingFactory, WICBitmapCacheOnLoad, WICBitmapUsePremultipliedAlpha,
+            WICConvertBitmapSource, WICDecodeMetadataCacheOnDemand, WICRect,
+        },
+    },
+    Storage::FileSystem::{FILE_ATTRIBUTE_NORMAL, FILE_FLAGS_AND_ATTRIBUTES},
+    System::{
+        Com::CLSCTX_ALL,
+        LibraryLoader::{
+            EnumResourceNamesW, FindResourceW, LoadLibraryExW, LoadResource, LockResource,
+            SizeofResource, LOAD_LIBRARY_AS_DATAFILE, LOAD_LIBRARY_AS_IMAGE_RESOURCE,
+        },
+    },
+    UI::{
+        Controls::{IImageList, ILD_TRANSPARENT},
+        Shell::{
+            IShellItemImageFactory, SHCreateItemFromParsingName, SHGetFileInfoW, SHGetImageList,
+            SHFILEINFOW, SHGFI_SYSICONINDEX, SHGFI_USEFILEATTRIBUTES, SHIL_EXTRALARGE, SHIL_JUMBO,
+            SHIL_LARGE, SHIL_SMALL, SIIGBF, SIIGBF_ICONONLY, SIIGBF_SCALEUP,
+        },
+        WindowsAndMessaging::{
+            CreateIconFromResourceEx, DestroyIcon, GetIconInfo, HICON, ICONINFO, LR_DEFAULTCOLOR,
+            RT_ICON,
+        },
+    },
+};
 #[cfg(target_os = "windows")]
 use windows::{
-    core::{HSTRING, PWSTR},
+    core::{w, Interface, HSTRING, PCWSTR, PWSTR},
     Win32::{
-        Foundation::SIZE,
-        Graphics::{
-            Gdi::DeleteObject,
-            Imaging::{
-                CLSID_WICImagingFactory, GUID_WICPixelFormat32bppBGRA,
-                GUID_WICPixelFormat32bppRGBA, IWICImagingFactory, WICBitmapUseAlpha, WICRect,
+        Foundation::{
+            CloseHandle, BOOL, ERROR_CANCELLED, ERROR_MORE_DATA, HANDLE, HWND, LPARAM, LUID, PSID,
+            TRUE, WAIT_FAILED, WAIT_OBJECT_0,
+        },
+        Security::{
+            AdjustTokenPrivileges,
+            Cryptography::{
+                szOID_COMMON_NAME,
+                Catalog::{
+                    CryptCATAdminAcquireContext2, CryptCATAdminCalcHashFromFileHandle2,
+                    CryptCATAdminEnumCatalogFromHash, CryptCATAdminReleaseCatalogContext,
+                    CryptCATAdminReleaseContext,
+                },
+                CertCloseStore, CertFindCertificateInStore, CertFreeCertificateContext,
+                CertGetNameStringW, CryptMsgClose, CryptMsgGetParam, CryptQueryObject,
+                CERT_FIND_SUBJECT_CERT, CERT_INFO, CERT_NAME_ATTR_TYPE,
+                CERT_QUERY_CONTENT_FLAG_PKCS7_SIGNED_EMBED, CERT_QUERY_FORMAT_FLAG_BINARY,
+                CERT_QUERY_OBJECT_FILE, CMSG_SIGNER_INFO, CMSG_SIGNER_INFO_PARAM, HCERTSTORE,
+                PKCS_7_ASN_ENCODING, X509_ASN_ENCODING,
+            },
+            GetTokenInformation, LookupAccountSidW, LookupPrivilegeValueW, TokenElevation,
+            WinTrust::{
+                WinVerifyTrust, WINTRUST_ACTION_GENERIC_VERIFY_V2, WINTRUST_DATA, WINTRUST_DATA_0,
+                WINTRUST_FILE_INFO, WTD_CACHE_ONLY_URL_RETRIEVAL, WTD_CHOICE_FILE, WTD_REVOKE_NONE,
+                WTD_STATEACTION_CLOSE, WTD_STATEACTION_VERIFY, WTD_UI_NONE,
             },
+            LUID_AND_ATTRIBUTES, SE_PRIVILEGE_ENABLED, SID_NAME_USE, TOKEN_ADJUST_PRIVILEGES,
+            TOKEN_ELEVATION, TOKEN_PRIVILEGES, TOKEN_QUERY,
         },
+        Storage::FileSystem::{GetFileVersionInfoSizeW, GetFileVersionInfoW, VerQueryValueW},
         System::{
-            Com::{CoCreateInstance, CoInitialize, CoUninitialize, CLSCTX_ALL},
+            Com::{
+                CoCreateInstance, CoInitialize, CoTaskMemFree, CoUninitialize, IPersistFile,
+                CLSCTX_INPROC_SERVER, STGM_READ,
+            },
+            ProcessStatus::EnumProcesses,
             Registry::{
-                RegCloseKey, RegEnumKeyExW, RegOpenKeyExW, RegQueryValueExW, HKEY_LOCAL_MACHINE,
-                KEY_READ,
+                RegCloseKey, RegEnumKeyExW, RegEnumValueW, RegLoadKeyW, RegNotifyChangeKeyValue,
+                RegOpenKeyExW, RegQueryValueExW, RegUnLoadKeyW, HKEY, HKEY_CLASSES_ROOT,
+                HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, HKEY_USERS, KEY_READ,
+                REG_NOTIFY_CHANGE_LAST_SET, REG_NOTIFY_CHANGE_NAME,
+            },
+            SystemInformation::{
+                GetNativeSystemInfo, PROCESSOR_ARCHITECTURE_AMD64, PROCESSOR_ARCHITECTURE_ARM,
+                PROCESSOR_ARCHITECTURE_ARM64, PROCESSOR_ARCHITECTURE_IA64,
+                PROCESSOR_ARCHITECTURE_INTEL, SYSTEM_INFO,
+            },
+            Threading::{
+                CreateEventW, GetCurrentProcess, GetExitCodeProcess, GetProcessId, OpenProcess,
+                OpenProcessToken, QueryFullProcessImageNameW, WaitForMultipleObjects,
+                WaitForSingleObject, INFINITE, PROCESS_NAME_WIN32,
+                PROCESS_QUERY_LIMITED_INFORMATION,
             },
         },
-        UI::Shell::{
-            IShellItemImageFactory, SHCreateItemFromParsingName, SIIGBF_ICONONLY, SIIGBF_SCALEUP,
+        UI::{
+            Shell::{
+                IShellLinkW, SHGetKnownFolderPath, ShellExecuteExW, ShellLink, KF_FLAG_DEFAULT,
+                SEE_MASK_FLAG_NO_UI, SEE_MASK_NOASYNC, SEE_MASK_NOCLOSEPROCESS, SHELLEXECUTEINFOW,
+            },
+            WindowsAndMessaging::{
+                EnumWindows, GetClassNameW, GetForegroundWindow, GetShellWindow, GetWindow,
+                GetWindowLongW, GetWindowThreadProcessId, IsWindowVisible, GWL_EXSTYLE, GW_OWNER,
+                SW_HIDE, SW_SHOWNOACTIVATE, SW_SHOWNORMAL, WS_EX_TOOLWINDOW,
+            },
         },
     },
 };
@@ -32,359 +133,6214 @@ use windows::{
 /// Gets all installed applications on Windows by scanning the registry.
 #[cfg(target_os = "windows")]
 pub fn get_installed_apps(icon_size: u16) -> Result<Vec<AppInfo>> {
-    let mut apps = Vec::new();
+    UninstallRegistrySource.scan(&ScanContext::new(icon_size))
+}
+
+/// The built-in source that reads installed programs from the Uninstall registry keys.
+#[cfg(target_os = "windows")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UninstallRegistrySource;
+
+#[cfg(target_os = "windows")]
+impl AppSource for UninstallRegistrySource {
+    fn name(&self) -> &str {
+        "windows-uninstall-registry"
+    }
+
+    fn scan(&self, ctx: &ScanContext) -> Result<Vec<AppInfo>> {
+        let mut apps = Vec::new();
+
+        // Search for installed programs in every Uninstall hive
+        let all_users = ctx.user_profiles() != UserProfiles::Current;
+        for (root, uninstall_key) in uninstall_hives() {
+            if ctx.is_cancelled() {
+                break;
+            }
+            // The current user's hive is read with the others under HKEY_USERS
+            if all_users && root == HKEY_CURRENT_USER {
+                continue;
+            }
+            apps.extend(scan_registry_key(root, uninstall_key, ctx, apps.len())?);
+        }
+        if all_users && !ctx.is_cancelled() {
+            let found_before = apps.len();
+            apps.extend(scan_user_hives(ctx, found_before));
+        }
+
+        Ok(apps)
+    }
+}
 
-    // Search for installed programs in the registry
+/// The registry keys that list installed programs, in scan order.
+#[cfg(target_os = "windows")]
+fn uninstall_hives() -> Vec<(HKEY, &'static str)> {
     // HKEY_LOCAL_MACHINE\SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall
-    let uninstall_key = "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall";
-    apps.extend(scan_registry_key(uninstall_key, icon_size)?);
+    let mut hives = vec![(
+        HKEY_LOCAL_MACHINE,
+        "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall",
+    )];
 
     // For 64-bit systems, also search for 32-bit programs
     #[cfg(target_pointer_width = "64")]
-    {
-        let uninstall_key_wow64 =
-            "SOFTWARE\\WOW6432Node\\Microsoft\\Windows\\CurrentVersion\\Uninstall";
-        apps.extend(scan_registry_key(uninstall_key_wow64, icon_size)?);
-    }
+    hives.push((
+        HKEY_LOCAL_MACHINE,
+        "SOFTWARE\\WOW6432Node\\Microsoft\\Windows\\CurrentVersion\\Uninstall",
+    ));
 
-    Ok(apps)
+    // Per-user installs
+    hives.push((
+        HKEY_CURRENT_USER,
+        "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall",
+    ));
+    hives
+}
+
+/// The Uninstall keys the built-in source reads, as listed by
+/// [`default_sources`](crate::default_sources).
+#[cfg(target_os = "windows")]
+pub(crate) fn default_scan_locations() -> Vec<ScanLocation> {
+    uninstall_hives()
+        .into_iter()
+        .map(|(root, key_path)| registry_location(root, key_path))
+        .collect()
+}
+
+/// Where Windows lists the profile of every user who has signed in to the machine.
+#[cfg(target_os = "windows")]
+const PROFILE_LIST_KEY: &str = "SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion\\ProfileList";
+
+/// A profile listed under [`PROFILE_LIST_KEY`].
+#[cfg(target_os = "windows")]
+struct UserProfile {
+    sid: String,
+    /// The profile's `NTUSER.DAT`
+    hive_file: Option<String>,
+    /// The SID in binary form, for looking up the account
+    binary_sid: Option<Vec<u8>>,
 }
 
-/// Scans a registry key for application information.
+/// Reads the per-user installs of every user [`ScanContext::user_profiles`] asks for,
+/// tagging each with its owner. Hives that cannot be read or loaded are recorded as
+/// locations and skipped.
 #[cfg(target_os = "windows")]
-fn scan_registry_key(key_path: &str, icon_size: u16) -> Result<Vec<AppInfo>> {
-    use windows::Win32::System::Registry::HKEY;
+fn scan_user_hives(ctx: &ScanContext, found_before: usize) -> Vec<AppInfo> {
+    let load_unloaded = ctx.user_profiles() == UserProfiles::All;
+    let profiles = read_profile_list();
+    let profile_sids: Vec<String> = profiles.iter().map(|profile| profile.sid.clone()).collect();
+    let loaded = registry_subkey_names(HKEY_USERS);
+
+    // Administrators hold the privileges RegLoadKeyW needs, but they start disabled
+    let _privileges = load_unloaded
+        .then(|| EnabledPrivileges::enable(&[w!("SeBackupPrivilege"), w!("SeRestorePrivilege")]));
 
     let mut apps = Vec::new();
-    let mut hkey: HKEY = HKEY::default();
+    for (sid, is_loaded) in user_hive_sids(&loaded, &profile_sids) {
+        if ctx.is_cancelled() {
+            break;
+        }
+        let profile = profiles
+            .iter()
+            .find(|profile| profile.sid.eq_ignore_ascii_case(&sid));
+        let found = found_before + apps.len();
+        let mut user_apps = if is_loaded {
+            scan_user_hive(&sid, ctx, found)
+        } else if load_unloaded {
+            let hive_file = profile.and_then(|profile| profile.hive_file.as_deref());
+            scan_unloaded_user_hive(&sid, hive_file, ctx, found)
+        } else {
+            // The user is not signed in
+            let location = registry_location(HKEY_USERS, &user_uninstall_key(&sid));
+            ctx.record_location(location, LocationStatus::NotPresent);
+            continue;
+        };
 
-    // Open the registry key
-    let key_name = HSTRING::from(key_path);
-    let result = unsafe { RegOpenKeyExW(HKEY_LOCAL_MACHINE, &key_name, 0, KEY_READ, &mut hkey) };
+        let owner = UserAccount {
+            name: profile
+                .and_then(|profile| profile.binary_sid.as_deref())
+                .and_then(account_name),
+            sid,
+        };
+        for app in &mut user_apps {
+            app.owner = Some(owner.clone());
+        }
+        apps.extend(user_apps);
+    }
+    apps
+}
+
+/// Reads the Uninstall key of the user hive loaded as `hive` under `HKEY_USERS`.
+#[cfg(target_os = "windows")]
+fn scan_user_hive(hive: &str, ctx: &ScanContext, found_before: usize) -> Vec<AppInfo> {
+    let key_path = user_uninstall_key(hive);
+    scan_registry_key(HKEY_USERS, &key_path, ctx, found_before).unwrap_or_else(|error| {
+        let location = registry_location(HKEY_USERS, &key_path);
+        ctx.record_location(location, LocationStatus::Failed(error.to_string()));
+        Vec::new()
+    })
+}
+
+/// Loads the hive of a user who is not signed in from `hive_file`, reads it and unloads
+/// it again, whether or not the read succeeds.
+#[cfg(target_os = "windows")]
+fn scan_unloaded_user_hive(
+    sid: &str,
+    hive_file: Option<&str>,
+    ctx: &ScanContext,
+    found_before: usize,
+) -> Vec<AppInfo> {
+    let mount = hive_mount_name(sid);
+    let location = registry_location(HKEY_USERS, &user_uninstall_key(&mount));
+    let Some(hive_file) = hive_file else {
+        ctx.record_location(location, LocationStatus::NotPresent);
+        return Vec::new();
+    };
 
+    let mount_name = HSTRING::from(mount.as_str());
+    let result = unsafe { RegLoadKeyW(HKEY_USERS, &mount_name, &HSTRING::from(hive_file)) };
     if result.is_err() {
-        return Ok(apps);
+        let error = std::io::Error::from_raw_os_error(result.0 as i32);
+        debug_event!(sid, hive_file, error = %error, "cannot load user hive");
+        ctx.record_location(location, LocationStatus::from_io_error(&error));
+        return Vec::new();
     }
+    // Every key in the hive is closed by the time this runs, which unloading requires
+    defer!(unsafe {
+        let _ = RegUnLoadKeyW(HKEY_USERS, &mount_name);
+    });
+    scan_user_hive(&mount, ctx, found_before)
+}
 
+/// Lists the user profiles on the machine, without service accounts.
+#[cfg(target_os = "windows")]
+fn read_profile_list() -> Vec<UserProfile> {
+    let mut hkey = HKEY::default();
+    let key_name = HSTRING::from(PROFILE_LIST_KEY);
+    if unsafe { RegOpenKeyExW(HKEY_LOCAL_MACHINE, &key_name, 0, KEY_READ, &mut hkey) }.is_err() {
+        return Vec::new();
+    }
     defer!(unsafe {
         let _ = RegCloseKey(hkey);
     });
 
-    // Enumerate subkeys
-    let mut index = 0u32;
-    loop {
-        let mut subkey_name = [0u16; 256];
-        let mut subkey_name_len = subkey_name.len() as u32;
+    registry_subkey_names(hkey)
+        .into_iter()
+        .filter(|sid| is_user_sid(sid))
+        .map(|sid| {
+            let mut profile = HKEY::default();
+            let key_name = HSTRING::from(sid.as_str());
+            if unsafe { RegOpenKeyExW(hkey, &key_name, 0, KEY_READ, &mut profile) }.is_err() {
+                return UserProfile {
+                    sid,
+                    hive_file: None,
+                    binary_sid: None,
+                };
+            }
+            defer!(unsafe {
+                let _ = RegCloseKey(profile);
+            });
+            UserProfile {
+                hive_file: read_registry_string(profile, "ProfileImagePath")
+                    .ok()
+                    .and_then(|path| profile_hive_file(&path, |name| std::env::var(name).ok())),
+                binary_sid: read_registry_binary(profile, "Sid"),
+                sid,
+            }
+        })
+        .collect()
+}
 
-        let result = unsafe {
-            RegEnumKeyExW(
-                hkey,
-                index,
-                PWSTR(subkey_name.as_mut_ptr()),
-                &mut subkey_name_len,
-                Some(std::ptr::null()),
-                PWSTR::null(),
-                Some(std::ptr::null_mut()),
-                Some(std::ptr::null_mut()),
+/// Looks up the account a binary SID names, as `DOMAIN\user`.
+#[cfg(target_os = "windows")]
+fn account_name(sid: &[u8]) -> Option<String> {
+    let mut sid = sid.to_vec();
+    let mut name = [0u16; 256];
+    let mut name_len = name.len() as u32;
+    let mut domain = [0u16; 256];
+    let mut domain_len = domain.len() as u32;
+    let mut sid_use = SID_NAME_USE::default();
+    unsafe {
+        LookupAccountSidW(
+            PCWSTR::null(),
+            PSID(sid.as_mut_ptr().cast()),
+            PWSTR(name.as_mut_ptr()),
+            &mut name_len,
+            PWSTR(domain.as_mut_ptr()),
+            &mut domain_len,
+            &mut sid_use,
+        )
+    }
+    .ok()?;
+    let name = String::from_utf16_lossy(&name[..name_len as usize]);
+    let domain = String::from_utf16_lossy(&domain[..domain_len as usize]);
+    Some(if domain.is_empty() {
+        name
+    } else {
+        format!("{}\\{}", domain, name)
+    })
+}
+
+/// Privileges enabled on the process token, set back as they were when dropped.
+#[cfg(target_os = "windows")]
+struct EnabledPrivileges {
+    token: HANDLE,
+    previous: Vec<TOKEN_PRIVILEGES>,
+}
+
+#[cfg(target_os = "windows")]
+impl EnabledPrivileges {
+    /// Enables the named privileges the process holds. Those it lacks stay off, and what
+    /// needs them fails with `ERROR_PRIVILEGE_NOT_HELD`.
+    fn enable(names: &[PCWSTR]) -> Option<Self> {
+        let mut token = HANDLE::default();
+        unsafe {
+            OpenProcessToken(
+                GetCurrentProcess(),
+                TOKEN_ADJUST_PRIVILEGES | TOKEN_QUERY,
+                &mut token,
             )
-        };
+        }
+        .ok()?;
 
-        if result.is_err() {
-            break;
+        let mut previous = Vec::new();
+        for &name in names {
+            let mut luid = LUID::default();
+            if unsafe { LookupPrivilegeValueW(PCWSTR::null(), name, &mut luid) }.is_err() {
+                continue;
+            }
+            let privileges = TOKEN_PRIVILEGES {
+                PrivilegeCount: 1,
+                Privileges: [LUID_AND_ATTRIBUTES {
+                    Luid: luid,
+                    Attributes: SE_PRIVILEGE_ENABLED,
+                }],
+            };
+            let mut before = TOKEN_PRIVILEGES::default();
+            let mut before_len = 0u32;
+            let adjusted = unsafe {
+                AdjustTokenPrivileges(
+                    token,
+                    false,
+                    Some(&privileges as *const _),
+                    std::mem::size_of::<TOKEN_PRIVILEGES>() as u32,
+                    Some(&mut before as *mut _),
+                    Some(&mut before_len as *mut _),
+                )
+            };
+            if adjusted.is_ok() {
+                previous.push(before);
+            }
+        }
+        Some(EnabledPrivileges { token, previous })
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for EnabledPrivileges {
+    fn drop(&mut self) {
+        for before in self.previous.iter().rev() {
+            unsafe {
+                let _ = AdjustTokenPrivileges(
+                    self.token,
+                    false,
+                    Some(before as *const _),
+                    0,
+                    None,
+                    None,
+                );
+            }
+        }
+        unsafe {
+            let _ = CloseHandle(self.token);
         }
+    }
+}
 
-        // Construct the subkey path
-        let subkey_path = format!(
-            "{}\\{}",
-            key_path,
-            String::from_utf16_lossy(&subkey_name[..subkey_name_len as usize])
-        );
+/// Whether a subkey of `HKEY_USERS` or of the profile list is a person's account: a
+/// local or domain account (`S-1-5-21-...`) or a Microsoft Entra one (`S-1-12-1-...`).
+/// Service accounts such as `SYSTEM` (`S-1-5-18`), `.DEFAULT` and the `_Classes` hive
+/// each signed-in user has alongside their own are not.
+#[cfg(any(target_os = "windows", test))]
+fn is_user_sid(name: &str) -> bool {
+    let upper = name.to_ascii_uppercase();
+    (upper.starts_with("S-1-5-21-") || upper.starts_with("S-1-12-1-"))
+        && upper[2..]
+            .split('-')
+            .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+}
 
-        // Parse application info
-        if let Ok(app_info) = parse_registry_app(&subkey_path, icon_size) {
-            apps.push(app_info);
+/// The users whose hives to read, each once, with whether their hive is loaded: the user
+/// subkeys of `HKEY_USERS` first, then the other profiles.
+#[cfg(any(target_os = "windows", test))]
+fn user_hive_sids(loaded: &[String], profiles: &[String]) -> Vec<(String, bool)> {
+    let mut sids: Vec<(String, bool)> = Vec::new();
+    for (sid, is_loaded) in loaded
+        .iter()
+        .map(|sid| (sid, true))
+        .chain(profiles.iter().map(|sid| (sid, false)))
+    {
+        if is_user_sid(sid) && !sids.iter().any(|(seen, _)| seen.eq_ignore_ascii_case(sid)) {
+            sids.push((sid.clone(), is_loaded));
         }
+    }
+    sids
+}
 
-        index += 1;
+/// The `NTUSER.DAT` of a profile, from the `ProfileImagePath` the profile list gives,
+/// which usually starts with `%SystemDrive%`.
+#[cfg(any(target_os = "windows", test))]
+fn profile_hive_file(
+    profile_image_path: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Option<String> {
+    let directory = expand_environment_strings(unquote_path(profile_image_path.trim()), lookup);
+    let directory = directory.trim_end_matches(['\\', '/']);
+    (!directory.is_empty()).then(|| format!("{}\\NTUSER.DAT", directory))
+}
+
+/// The subkey of `HKEY_USERS` a user's hive is loaded as for the scan. It is not the SID
+/// itself, so it cannot be mistaken for a hive Windows loaded.
+#[cfg(any(target_os = "windows", test))]
+fn hive_mount_name(sid: &str) -> String {
+    format!("app-info_{}", sid)
+}
+
+/// The Uninstall key of the user hive loaded as `hive` under `HKEY_USERS`.
+#[cfg(any(target_os = "windows", test))]
+fn user_uninstall_key(hive: &str) -> String {
+    format!(
+        "{}\\Software\\Microsoft\\Windows\\CurrentVersion\\Uninstall",
+        hive
+    )
+}
+
+/// Describes a registry key under `root` for scan reports.
+#[cfg(target_os = "windows")]
+fn registry_location(root: HKEY, key_path: &str) -> ScanLocation {
+    let root_name = if root == HKEY_CURRENT_USER {
+        "HKEY_CURRENT_USER"
+    } else if root == HKEY_USERS {
+        "HKEY_USERS"
+    } else if root == HKEY_LOCAL_MACHINE {
+        "HKEY_LOCAL_MACHINE"
+    } else {
+        "HKEY_CLASSES_ROOT"
+    };
+    ScanLocation::RegistryKey {
+        key: format!("{}\\{}", root_name, key_path),
+        scope: hive_scope(root),
     }
+}
 
-    Ok(apps)
+/// Whether entries under `root` are installed for the machine or for a user.
+#[cfg(target_os = "windows")]
+fn hive_scope(root: HKEY) -> InstallScope {
+    if root == HKEY_CURRENT_USER || root == HKEY_USERS {
+        InstallScope::User
+    } else {
+        InstallScope::Machine
+    }
 }
 
-/// Parses application information from a specific registry key.
+/// The registry view a key under `root` is read through. Keys in user hives are shared by
+/// both views. A 32-bit build on 64-bit Windows sees the WOW64 view of
+/// `HKEY_LOCAL_MACHINE` wherever it looks.
 #[cfg(target_os = "windows")]
-fn parse_registry_app(key_path: &str, icon_size: u16) -> Result<AppInfo> {
-    use windows::Win32::System::Registry::HKEY;
+fn registry_view(root: HKEY, key_path: &str) -> Option<RegistryView> {
+    if root == HKEY_CURRENT_USER || root == HKEY_USERS {
+        return None;
+    }
+    let wow64 = key_path
+        .split('\\')
+        .any(|part| part.eq_ignore_ascii_case("WOW6432Node"))
+        || (cfg!(target_pointer_width = "32") && os_is_64bit() == Some(true));
+    Some(if wow64 {
+        RegistryView::Wow64
+    } else {
+        RegistryView::Native
+    })
+}
+
+/// Whether Windows itself is 64-bit, whatever this process is built for.
+#[cfg(target_os = "windows")]
+fn os_is_64bit() -> Option<bool> {
+    let mut info = SYSTEM_INFO::default();
+    unsafe { GetNativeSystemInfo(&mut info) };
+    match unsafe { info.Anonymous.Anonymous.wProcessorArchitecture } {
+        PROCESSOR_ARCHITECTURE_AMD64
+        | PROCESSOR_ARCHITECTURE_ARM64
+        | PROCESSOR_ARCHITECTURE_IA64 => Some(true),
+        PROCESSOR_ARCHITECTURE_INTEL | PROCESSOR_ARCHITECTURE_ARM => Some(false),
+        _ => None,
+    }
+}
+
+/// Whether an app is 32-bit on 64-bit Windows, see [`AppInfo::is_32bit_on_64bit_os`].
+#[cfg(target_os = "windows")]
+pub(crate) fn is_32bit_on_64bit_os(app: &AppInfo) -> Option<bool> {
+    let image_64bit = app
+        .executable_path
+        .as_deref()
+        .and_then(read_pe_header)
+        .and_then(|header| pe_is_64bit(&header));
+    is_32bit_on_64bit(app.registry_view, image_64bit, os_is_64bit())
+}
+
+/// Combines what the registry view and the executable say about an app's bitness. When
+/// both are known they must agree.
+#[cfg(any(target_os = "windows", test))]
+fn is_32bit_on_64bit(
+    view: Option<RegistryView>,
+    image_64bit: Option<bool>,
+    os_64bit: Option<bool>,
+) -> Option<bool> {
+    if !os_64bit? {
+        return Some(false);
+    }
+    let view_64bit = view.map(|view| view == RegistryView::Native);
+    let app_64bit = match (view_64bit, image_64bit) {
+        (Some(view), Some(image)) if view != image => return None,
+        (view, image) => view.or(image)?,
+    };
+    Some(!app_64bit)
+}
 
+/// Scans a registry key for application information. `found_before` counts the apps
+/// earlier keys contributed, for progress reports.
+#[cfg(target_os = "windows")]
+fn scan_registry_key(
+    root: HKEY,
+    key_path: &str,
+    ctx: &ScanContext,
+    found_before: usize,
+) -> Result<Vec<AppInfo>> {
+    let mut apps = Vec::new();
     let mut hkey: HKEY = HKEY::default();
-    let key_name = HSTRING::from(key_path);
 
-    let result = unsafe { RegOpenKeyExW(HKEY_LOCAL_MACHINE, &key_name, 0, KEY_READ, &mut hkey) };
+    // Open the registry key
+    let key_name = HSTRING::from(key_path);
+    let result = unsafe { RegOpenKeyExW(root, &key_name, 0, KEY_READ, &mut hkey) };
 
+    let location = registry_location(root, key_path);
     if result.is_err() {
-        return Err(AppInfoError::RegistryError(
-            "Failed to open registry key".to_string(),
-        ));
+        let error = std::io::Error::from_raw_os_error(result.0 as i32);
+        debug_event!(key_path, error = %error, "skipping registry key");
+        ctx.record_location(location, LocationStatus::from_io_error(&error));
+        return Ok(apps);
     }
+    ctx.record_location(location, LocationStatus::Scanned);
 
     defer!(unsafe {
         let _ = RegCloseKey(hkey);
     });
 
-    // Read application information
-    let display_name = read_registry_string(hkey, "DisplayName")?;
-    let version = read_registry_string(hkey, "DisplayVersion").ok();
-    let publisher = read_registry_string(hkey, "Publisher").ok();
-    let install_location = read_registry_string(hkey, "InstallLocation").ok();
-    let install_date = read_registry_string(hkey, "InstallDate").ok();
-    let display_icon_path = read_registry_string(hkey, "DisplayIcon").ok();
+    let declarations = ctx.read_declarations().then(registered_declarations);
 
-    // Determine the path for the application and its icon
-    let (app_path, icon_path) = if let Some(icon_str) = display_icon_path {
-        // DisplayIcon can be "path,index" or just "path"
-        let path_part = icon_str.split(',').next().unwrap_or("").trim();
-        let path = PathBuf::from(path_part);
-        if path.exists() {
-            (path.clone(), Some(path))
-        } else {
-            // DisplayIcon path doesn't exist, fallback to InstallLocation
-            install_location
-                .as_ref()
-                .and_then(|loc| find_main_executable(&PathBuf::from(loc)))
-                .map_or((PathBuf::new(), None), |p| (p.clone(), Some(p)))
+    // Enumerate subkeys
+    let subkey_names = ctx.time(ScanPhase::Enumeration, || registry_subkey_names(hkey));
+    for subkey_name in subkey_names {
+        if ctx.is_cancelled() {
+            break;
         }
-    } else {
-        // No DisplayIcon, search in InstallLocation
-        install_location
-            .as_ref()
-            .and_then(|loc| find_main_executable(&PathBuf::from(loc)))
-            .map_or((PathBuf::new(), None), |p| (p.clone(), Some(p)))
-    };
+        ctx.count_enumerated(1);
 
-    // Get the icon
-    let icon = if icon_size > 0 {
-        icon_path.and_then(|path| get_file_icon(&path, icon_size).ok())
-    } else {
-        None
-    };
+        // Construct the subkey path
+        let subkey_path = format!("{}\\{}", key_path, subkey_name);
 
-    Ok(AppInfo {
-        name: display_name,
-        version,
-        path: app_path,
-        icon,
-        identifier: None, // Windows typically uses a ProductCode, simplified here
-        publisher,
-        install_date,
-    })
+        // Parse application info
+        match ctx.time(ScanPhase::Metadata, || {
+            read_registry_app(root, &subkey_path, ctx)
+        }) {
+            Ok(Some(mut app_info)) => {
+                if ctx.check_signatures() {
+                    apply_signature(&mut app_info);
+                }
+                if let Some(declarations) = &declarations {
+                    declarations.apply(&mut app_info);
+                }
+                apps.push(app_info);
+                ctx.report_progress(found_before + apps.len());
+            }
+            Ok(None) => {
+                debug_event!(key_path = %subkey_path, "no name for registry entry");
+                ctx.skip_entry(&subkey_path);
+            }
+            Err(_err) => {
+                ctx.count_failed();
+                debug_event!(key_path = %subkey_path, error = %_err, "skipping registry entry");
+            }
+        }
+    }
+
+    Ok(apps)
 }
 
-/// Reads a string value from the registry.
+/// Checks the signature of an app's executable, and takes a valid signer as the publisher
+/// when the Uninstall entry names none.
 #[cfg(target_os = "windows")]
-fn read_registry_string(
-    hkey: windows::Win32::System::Registry::HKEY,
-    value_name: &str,
-) -> Result<String> {
-    use windows::Win32::System::Registry::REG_VALUE_TYPE;
-
-    let value_name = HSTRING::from(value_name);
-    let mut data_type: REG_VALUE_TYPE = REG_VALUE_TYPE(0);
-    let mut data_size = 0u32;
-
-    // First, get the size of the data
-    let result = unsafe {
-        RegQueryValueExW(
-            hkey,
-            &value_name,
-            None,
-            Some(&mut data_type),
-            None,
-            Some(&mut data_size),
-        )
+fn apply_signature(app: &mut AppInfo) {
+    let Some(signature) = app
+        .executable_path
+        .as_deref()
+        .and_then(|executable| get_signature_info(executable).ok())
+    else {
+        return;
     };
 
-    if result.is_err() || data_size == 0 {
-        return Err(AppInfoError::RegistryError(
-            "Failed to read registry value".to_string(),
-        ));
+    let has_publisher = app
+        .publisher
+        .as_deref()
+        .is_some_and(|publisher| !publisher.trim().is_empty());
+    if !has_publisher && signature.status == SignatureStatus::Valid {
+        app.publisher = signature.signer.clone();
+    }
+    app.signature = Some(signature);
+}
+
+/// Checks the Authenticode signature of a file on Windows.
+#[cfg(target_os = "windows")]
+pub fn get_signature_info(path: &Path) -> Result<SignatureInfo> {
+    enter_span!(DEBUG, "get_signature_info", path = %path.display());
+    if !long_path(path).is_file() {
+        return Err(AppInfoError::ExecutableNotFound {
+            path: path.display().to_string(),
+        });
     }
 
-    // Allocate a buffer and read the data
-    let mut buffer = vec![0u8; data_size as usize];
+    let path_string = HSTRING::from(long_path(path).to_string_lossy().as_ref());
+    let mut file_info = WINTRUST_FILE_INFO {
+        cbStruct: std::mem::size_of::<WINTRUST_FILE_INFO>() as u32,
+        pcwszFilePath: PCWSTR(path_string.as_ptr()),
+        hFile: HANDLE::default(),
+        pgKnownSubject: std::ptr::null_mut(),
+    };
+    let mut trust_data = WINTRUST_DATA {
+        cbStruct: std::mem::size_of::<WINTRUST_DATA>() as u32,
+        dwUIChoice: WTD_UI_NONE,
+        fdwRevocationChecks: WTD_REVOKE_NONE,
+        dwUnionChoice: WTD_CHOICE_FILE,
+        Anonymous: WINTRUST_DATA_0 {
+            pFile: &mut file_info,
+        },
+        dwStateAction: WTD_STATEACTION_VERIFY,
+        // Only use what is cached locally rather than fetching from the network
+        dwProvFlags: WTD_CACHE_ONLY_URL_RETRIEVAL,
+        ..Default::default()
+    };
+    let mut action = WINTRUST_ACTION_GENERIC_VERIFY_V2;
     let result = unsafe {
-        RegQueryValueExW(
-            hkey,
-            &value_name,
-            None,
-            Some(&mut data_type),
-            Some(buffer.as_mut_ptr()),
-            Some(&mut data_size),
+        WinVerifyTrust(
+            HWND::default(),
+            &mut action,
+            &mut trust_data as *mut WINTRUST_DATA as *mut _,
         )
     };
-
-    if result.is_err() {
-        return Err(AppInfoError::RegistryError(
-            "Failed to read registry value".to_string(),
-        ));
+    // Release the state the verification kept
+    trust_data.dwStateAction = WTD_STATEACTION_CLOSE;
+    unsafe {
+        WinVerifyTrust(
+            HWND::default(),
+            &mut action,
+            &mut trust_data as *mut WINTRUST_DATA as *mut _,
+        );
     }
+    debug_event!(result, "verified trust");
 
-    // Convert to string
-    let wide_chars =
-        unsafe { std::slice::from_raw_parts(buffer.as_ptr() as *const u16, buffer.len() / 2) };
-
-    // Remove trailing null characters
-    let end = wide_chars
-        .iter()
-        .position(|&x| x == 0)
-        .unwrap_or(wide_chars.len());
-    Ok(String::from_utf16_lossy(&wide_chars[..end]))
+    let signature = match trust_status(result) {
+        Some(status) => SignatureInfo {
+            status,
+            signer: embedded_signer(&path_string),
+        },
+        None => SignatureInfo {
+            status: if is_catalog_signed(path) {
+                SignatureStatus::CatalogSigned
+            } else {
+                SignatureStatus::Unsigned
+            },
+            signer: None,
+        },
+    };
+    Ok(signature)
 }
 
-/// Finds the main executable file in an installation directory.
-#[cfg(target_os = "windows")]
-fn find_main_executable(install_dir: &Path) -> Option<PathBuf> {
-    use std::fs;
+/// Maps the result of `WinVerifyTrust` to the status of an embedded signature, or `None`
+/// when the file has no embedded signature to check.
+#[cfg(any(target_os = "windows", test))]
+fn trust_status(result: i32) -> Option<SignatureStatus> {
+    /// `TRUST_E_NOSIGNATURE`, `TRUST_E_SUBJECT_FORM_UNKNOWN` and `TRUST_E_PROVIDER_UNKNOWN`,
+    /// for files without a signature or of a type that cannot carry one
+    const NOT_SIGNED: [u32; 3] = [0x800B_0100, 0x800B_0003, 0x800B_0001];
 
-    if !install_dir.exists() {
-        return None;
+    match result {
+        0 => Some(SignatureStatus::Valid),
+        result if NOT_SIGNED.contains(&(result as u32)) => None,
+        _ => Some(SignatureStatus::Invalid),
     }
+}
 
-    // Look for .exe files, but avoid uninstaller files
-    if let Ok(entries) = fs::read_dir(install_dir) {
-        let mut exe_files: Vec<PathBuf> = Vec::new();
-
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path.extension().and_then(|s| s.to_str()) == Some("exe") {
-                exe_files.push(path);
-            }
+/// Reads the subject common name of the certificate that signed a file.
+#[cfg(target_os = "windows")]
+fn embedded_signer(path: &HSTRING) -> Option<String> {
+    let mut store = HCERTSTORE::default();
+    let mut message: *mut std::ffi::c_void = std::ptr::null_mut();
+    unsafe {
+        CryptQueryObject(
+            CERT_QUERY_OBJECT_FILE,
+            path.as_ptr() as *const _,
+            CERT_QUERY_CONTENT_FLAG_PKCS7_SIGNED_EMBED,
+            CERT_QUERY_FORMAT_FLAG_BINARY,
+            0,
+            None,
+            None,
+            None,
+            Some(&mut store),
+            Some(&mut message),
+            None,
+        )
+    }
+    .ok()?;
+    defer!(unsafe {
+        let _ = CryptMsgClose(Some(message));
+        let _ = CertCloseStore(store, 0);
+    });
+
+    // The signer info is a variable-length structure, so keep the buffer aligned for it
+    let mut size = 0u32;
+    unsafe { CryptMsgGetParam(message, CMSG_SIGNER_INFO_PARAM, 0, None, &mut size) }.ok()?;
+    let mut buffer = vec![0u64; (size as usize).div_ceil(8)];
+    unsafe {
+        CryptMsgGetParam(
+            message,
+            CMSG_SIGNER_INFO_PARAM,
+            0,
+            Some(buffer.as_mut_ptr().cast()),
+            &mut size,
+        )
+    }
+    .ok()?;
+    let signer_info = unsafe { &*(buffer.as_ptr() as *const CMSG_SIGNER_INFO) };
+
+    let subject = CERT_INFO {
+        Issuer: signer_info.Issuer,
+        SerialNumber: signer_info.SerialNumber,
+        ..Default::default()
+    };
+    let certificate = unsafe {
+        CertFindCertificateInStore(
+            store,
+            X509_ASN_ENCODING | PKCS_7_ASN_ENCODING,
+            0,
+            CERT_FIND_SUBJECT_CERT,
+            Some(&subject as *const CERT_INFO as *const _),
+            None,
+        )
+    };
+    if certificate.is_null() {
+        return None;
+    }
+    defer!(unsafe {
+        let _ = CertFreeCertificateContext(Some(certificate));
+    });
+
+    let common_name = Some(szOID_COMMON_NAME.as_ptr() as *const _);
+    let len = unsafe { CertGetNameStringW(certificate, CERT_NAME_ATTR_TYPE, 0, common_name, None) };
+    let mut name = vec![0u16; len as usize];
+    unsafe {
+        CertGetNameStringW(
+            certificate,
+            CERT_NAME_ATTR_TYPE,
+            0,
+            common_name,
+            Some(&mut name),
+        )
+    };
+    let name = String::from_utf16_lossy(&name[..wide_len(&name)]);
+    (!name.is_empty()).then_some(name)
+}
+
+/// Whether a file without an embedded signature is listed in a system catalog.
+#[cfg(target_os = "windows")]
+fn is_catalog_signed(path: &Path) -> bool {
+    use std::os::windows::io::AsRawHandle;
+
+    let Ok(file) = std::fs::File::open(long_path(path)) else {
+        return false;
+    };
+    let handle = HANDLE(file.as_raw_handle() as isize);
+
+    let mut admin = 0isize;
+    let acquired = unsafe {
+        CryptCATAdminAcquireContext2(&mut admin, None, &HSTRING::from("SHA256"), None, 0)
+    };
+    if acquired.is_err() {
+        return false;
+    }
+    defer!(unsafe {
+        let _ = CryptCATAdminReleaseContext(admin, 0);
+    });
+
+    // The first call only reports the size of the hash
+    let mut size = 0u32;
+    let _ = unsafe { CryptCATAdminCalcHashFromFileHandle2(admin, handle, &mut size, None, 0) };
+    if size == 0 {
+        return false;
+    }
+    let mut hash = vec![0u8; size as usize];
+    let hashed = unsafe {
+        CryptCATAdminCalcHashFromFileHandle2(admin, handle, &mut size, Some(hash.as_mut_ptr()), 0)
+    };
+    if hashed.is_err() {
+        return false;
+    }
+
+    let catalog = unsafe { CryptCATAdminEnumCatalogFromHash(admin, &hash, 0, None) };
+    if catalog == 0 {
+        return false;
+    }
+    unsafe {
+        let _ = CryptCATAdminReleaseCatalogContext(admin, catalog, 0);
+    }
+    true
+}
+
+/// Number of independently watched scopes (one per Uninstall hive) for the app watcher.
+#[cfg(target_os = "windows")]
+pub(crate) fn watched_scope_count() -> usize {
+    uninstall_hives().len()
+}
+
+/// Rescans a single Uninstall hive for the app watcher. Registry entries are cheap to
+/// re-read, so the previous snapshot is not reused.
+#[cfg(target_os = "windows")]
+pub(crate) fn scan_scope(
+    scope: usize,
+    _previous: &[AppInfo],
+    _since: std::time::SystemTime,
+) -> Vec<AppInfo> {
+    match uninstall_hives().get(scope) {
+        Some(&(root, key_path)) => {
+            scan_registry_key(root, key_path, &ScanContext::new(0), 0).unwrap_or_default()
         }
+        None => Vec::new(),
+    }
+}
 
-        // Filter out likely uninstaller executables
-        let filtered_exes: Vec<PathBuf> = exe_files
-            .into_iter()
-            .filter(|path| {
-                if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
-                    let name_lower = name.to_lowercase();
-                    // Skip common uninstaller patterns
-                    !name_lower.contains("unins")
-                        && !name_lower.contains("uninst")
-                        && !name_lower.contains("uninstall")
-                        && !name_lower.contains("remove")
-                        && !name_lower.starts_with("un")
-                } else {
-                    true
-                }
-            })
-            .collect();
+/// Waits for changes under the Uninstall hives with `RegNotifyChangeKeyValue`.
+///
+/// Notifications are tied to the thread that registered them, so the monitor must be
+/// created and waited on from the same thread.
+#[cfg(target_os = "windows")]
+pub(crate) struct ChangeMonitor {
+    /// One entry per hive, in `uninstall_hives` order; `None` when the hive is missing
+    watches: Vec<Option<(HKEY, HANDLE)>>,
+}
 
-        // Return the first non-uninstaller executable, or first one if none found
-        filtered_exes.into_iter().next().or_else(|| {
-            // If all were filtered out, try to get any exe file as fallback
-            if let Ok(entries) = fs::read_dir(install_dir) {
-                for entry in entries.flatten() {
-                    let path = entry.path();
-                    if path.extension().and_then(|s| s.to_str()) == Some("exe") {
-                        return Some(path);
+#[cfg(target_os = "windows")]
+impl ChangeMonitor {
+    pub(crate) fn new() -> Result<Self> {
+        let mut watches = Vec::new();
+
+        for (root, key_path) in uninstall_hives() {
+            let watch = open_registry_key(root, key_path).and_then(|hkey| {
+                match unsafe { CreateEventW(None, false, false, None) } {
+                    Ok(event) => Some((hkey, event)),
+                    Err(_) => {
+                        unsafe {
+                            let _ = RegCloseKey(hkey);
+                        }
+                        None
                     }
                 }
+            });
+            watches.push(watch);
+        }
+
+        let monitor = ChangeMonitor { watches };
+        let mut armed = false;
+        for scope in 0..monitor.watches.len() {
+            armed |= monitor.arm(scope);
+        }
+
+        if !armed {
+            return Err(AppInfoError::WatcherError(
+                "No Uninstall registry key could be watched".to_string(),
+            ));
+        }
+
+        Ok(monitor)
+    }
+
+    /// Requests a single notification for the next change under a hive.
+    fn arm(&self, scope: usize) -> bool {
+        let Some(Some((hkey, event))) = self.watches.get(scope) else {
+            return false;
+        };
+        let result = unsafe {
+            RegNotifyChangeKeyValue(
+                *hkey,
+                TRUE,
+                REG_NOTIFY_CHANGE_NAME | REG_NOTIFY_CHANGE_LAST_SET,
+                *event,
+                TRUE,
+            )
+        };
+        result.is_ok()
+    }
+
+    /// Blocks for up to `timeout` and returns the scopes that changed.
+    pub(crate) fn wait(&mut self, timeout: std::time::Duration) -> Vec<usize> {
+        let (scopes, events): (Vec<usize>, Vec<HANDLE>) = self
+            .watches
+            .iter()
+            .enumerate()
+            .filter_map(|(scope, watch)| watch.map(|(_, event)| (scope, event)))
+            .unzip();
+
+        let milliseconds = u32::try_from(timeout.as_millis()).unwrap_or(u32::MAX);
+        let result = unsafe { WaitForMultipleObjects(&events, false, milliseconds) };
+
+        if result == WAIT_FAILED {
+            // Avoid spinning if the handles have become unusable
+            std::thread::sleep(timeout);
+            return Vec::new();
+        }
+
+        let signaled = result.0.wrapping_sub(WAIT_OBJECT_0.0) as usize;
+        match scopes.get(signaled) {
+            Some(&scope) => {
+                // Notifications fire once, so re-arm before reporting the change
+                self.arm(scope);
+                vec![scope]
             }
-            None
-        })
-    } else {
-        None
+            None => Vec::new(),
+        }
     }
 }
 
-/// Gets the icon for a given file path on Windows.
 #[cfg(target_os = "windows")]
-pub fn get_file_icon(path: &Path, size: u16) -> Result<Icon> {
-    // Helper struct to ensure CoUninitialize is called.
-    struct InitializationToken;
-
-    impl Drop for InitializationToken {
-        fn drop(&mut self) {
+impl Drop for ChangeMonitor {
+    fn drop(&mut self) {
+        for (hkey, event) in self.watches.iter().flatten() {
             unsafe {
-                CoUninitialize();
+                let _ = RegCloseKey(*hkey);
+                let _ = CloseHandle(*event);
             }
         }
     }
+}
 
-    // Initialize COM
-    let _token = if unsafe { CoInitialize(None) }.is_ok() {
-        Some(InitializationToken)
+/// Lists the names of all subkeys of a registry key.
+#[cfg(target_os = "windows")]
+fn registry_subkey_names(hkey: HKEY) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut index = 0u32;
+    loop {
+        let mut subkey_name = [0u16; 256];
+        let mut subkey_name_len = subkey_name.len() as u32;
+
+        let result = unsafe {
+            RegEnumKeyExW(
+                hkey,
+                index,
+                PWSTR(subkey_name.as_mut_ptr()),
+                &mut subkey_name_len,
+                Some(std::ptr::null()),
+                PWSTR::null(),
+                Some(std::ptr::null_mut()),
+                Some(std::ptr::null_mut()),
+            )
+        };
+
+        if result.is_err() {
+            break;
+        }
+
+        names.push(String::from_utf16_lossy(
+            &subkey_name[..subkey_name_len as usize],
+        ));
+        index += 1;
+    }
+    names
+}
+
+/// Parses application information from a specific registry key.
+#[cfg(target_os = "windows")]
+fn parse_registry_app(root: HKEY, key_path: &str, icon_size: u16) -> Result<AppInfo> {
+    read_registry_app(root, key_path, &ScanContext::new(icon_size))?
+        .ok_or_else(|| AppInfoError::RegistryError("Failed to read registry value".to_string()))
+}
+
+/// Reads an Uninstall entry, timing its icon through `ctx`. Entries without a
+/// `DisplayName` are named by [`infer_display_name`]; returns `None` when that finds
+/// nothing either.
+#[cfg(target_os = "windows")]
+fn read_registry_app(root: HKEY, key_path: &str, ctx: &ScanContext) -> Result<Option<AppInfo>> {
+    enter_span!(DEBUG, "parse_registry_app", key_path);
+    let mut hkey: HKEY = HKEY::default();
+    let key_name = HSTRING::from(key_path);
+
+    let result = unsafe { RegOpenKeyExW(root, &key_name, 0, KEY_READ, &mut hkey) };
+
+    if result.is_err() {
+        return Err(AppInfoError::RegistryError(
+            "Failed to open registry key".to_string(),
+        ));
+    }
+
+    defer!(unsafe {
+        let _ = RegCloseKey(hkey);
+    });
+
+    // Read application information
+    let display_name = read_registry_string(hkey, "DisplayName")
+        .ok()
+        .filter(|name| !name.trim().is_empty());
+    let version = read_registry_string(hkey, "DisplayVersion").ok();
+    let publisher = read_registry_string(hkey, "Publisher").ok();
+    let install_location = read_registry_string(hkey, "InstallLocation")
+        .ok()
+        .filter(|location| !unquote_path(location).trim().is_empty());
+    let install_date = read_registry_string(hkey, "InstallDate").ok();
+    let display_icon_path = read_registry_string(hkey, "DisplayIcon").ok();
+    let is_windows_installer = read_registry_flag(hkey, "WindowsInstaller").unwrap_or(false);
+    let system_component = read_registry_flag(hkey, "SystemComponent").unwrap_or(false);
+    // Add/Remove Programs disables its buttons for entries that set these
+    let can_uninstall = read_registry_flag(hkey, "NoRemove").map(|no_remove| !no_remove);
+    let can_modify = read_registry_flag(hkey, "NoModify").map(|no_modify| !no_modify);
+    let can_repair = read_registry_flag(hkey, "NoRepair").map(|no_repair| !no_repair);
+    // EstimatedSize is a DWORD in KiB
+    let install_size = read_registry_binary(hkey, "EstimatedSize")
+        .and_then(|data| {
+            data.get(..4)
+                .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+        })
+        .map(|kib| u64::from(kib) * 1024);
+
+    // Determine the path for the application and its icon
+    let display_icon = display_icon_path
+        .as_deref()
+        .and_then(parse_display_icon)
+        .map(|(path, index)| (system_file(path), index))
+        .filter(|(path, _)| long_path(path).exists());
+    let (app_path, icon_location) = match display_icon {
+        Some((path, index)) => (path.clone(), Some((path, index))),
+        None => {
+            // DisplayIcon is missing, names no file or a missing one: search InstallLocation
+            debug_event!(display_icon = ?display_icon_path, "no DisplayIcon file, searching InstallLocation");
+            install_location
+                .as_ref()
+                .and_then(|loc| find_main_executable(&PathBuf::from(unquote_path(loc))))
+                .map_or((PathBuf::new(), None), |p| (p.clone(), Some((p, 0))))
+        }
+    };
+
+    // The key name, which MSI installs set to their ProductCode
+    let identifier = key_path.rsplit('\\').next().map(str::to_string);
+
+    // Where the icon comes from, falling back to the copy Windows Installer caches for
+    // Add/Remove Programs when the entry points nowhere useful
+    let cached_icon = || {
+        let product_code = identifier.as_deref().filter(|_| is_windows_installer)?;
+        debug_event!("trying the Windows Installer icon cache");
+        installer_cache_icon(&windows_installer_dir()?, product_code)
+    };
+    let icon_source = match icon_location {
+        Some((path, index)) => Some(IconSource::Resource { path, index }),
+        None => cached_icon().map(IconSource::File),
+    };
+    let icon = if ctx.extracts_icons() {
+        let icon_size = ctx.icon_size();
+        ctx.time(ScanPhase::Icons, || {
+            let icon = icon_source.as_ref()?.load(icon_size).ok();
+            match icon_source {
+                Some(IconSource::Resource { .. }) if icon.is_none() => {
+                    get_file_icon(&cached_icon()?, icon_size).ok()
+                }
+                _ => icon,
+            }
+        })
     } else {
         None
     };
 
-    // Create a Shell item
-    let path_string = HSTRING::from(path.to_string_lossy().as_ref());
-    let image_factory: IShellItemImageFactory =
-        unsafe { SHCreateItemFromParsingName(&path_string, None) }
-            .map_err(|_| AppInfoError::FileIconError(crate::error::FileIconError::Failed))?;
+    let is_executable = app_path
+        .extension()
+        .and_then(|s| s.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("exe"));
+    let executable_path = is_executable.then(|| app_path.clone());
 
-    // Set the icon size
-    let bitmap_size = SIZE {
-        cx: size as i32,
-        cy: size as i32,
+    // Without an InstallLocation, the program DisplayIcon names still tells where the
+    // app is installed
+    let inferred_location = match (&install_location, &executable_path) {
+        (None, Some(executable)) => {
+            infer_install_dir(display_name.as_deref(), &executable.to_string_lossy())
+                .map(str::to_string)
+        }
+        _ => None,
     };
+    let install_location = install_location.or(inferred_location.clone());
+    let app_path = inferred_location.map_or(app_path, PathBuf::from);
 
-    // Get the icon bitmap
-    let bitmap = unsafe { image_factory.GetImage(bitmap_size, SIIGBF_ICONONLY | SIIGBF_SCALEUP) }
-        .map_err(|_| AppInfoError::FileIconError(crate::error::FileIconError::Failed))?;
+    let name_inferred = display_name.is_none();
+    let name = match display_name {
+        Some(name) => name,
+        None => {
+            let msi_name = identifier
+                .as_deref()
+                .and_then(|product_code| msi_product_name(root, product_code));
+            let version_info = executable_path.as_deref().and_then(read_file_version_info);
+            let version_info = version_info.unwrap_or_default();
+            let inferred = infer_display_name(
+                msi_name.as_deref(),
+                version_info.product_name.as_deref(),
+                version_info.file_description.as_deref(),
+                install_location.as_deref(),
+            );
+            match inferred {
+                Some(name) => name,
+                None => return Ok(None),
+            }
+        }
+    };
 
-    // Ensure the bitmap is deleted when the function ends
-    defer!(unsafe {
-        let _ = DeleteObject(bitmap);
+    let kind = windows_kind(&WindowsSignals {
+        name: &name,
+        path: &app_path.to_string_lossy(),
+        install_location: install_location.as_deref(),
+        system_component,
+        subsystem: executable_path.as_deref().and_then(read_pe_subsystem),
     });
 
-    // Create a WIC factory
-    let imaging_factory: IWICImagingFactory =
-        unsafe { CoCreateInstance(&CLSID_WICImagingFactory, None, CLSCTX_ALL) }
-            .map_err(|_| AppInfoError::FileIconError(crate::error::FileIconError::Failed))?;
-
-    // Create a WIC bitmap from the HBITMAP
-    let wic_bitmap =
-        unsafe { imaging_factory.CreateBitmapFromHBITMAP(bitmap, None, WICBitmapUseAlpha) }
-            .map_err(|_| AppInfoError::FileIconError(crate::error::FileIconError::Failed))?;
+    let mut app = AppInfo {
+        name,
+        version,
+        path: app_path,
+        executable_path,
+        icon: None,
+        compressed_icon: None,
+        icon_source,
+        identifier,
+        publisher,
+        install_date,
+        parsed_install_date: None,
+        install_date_ambiguous: false,
+        install_size,
+        category: None,
+        kind,
+        package_manager: None,
+        install_scope: Some(hive_scope(root)),
+        registry_view: registry_view(root, key_path),
+        owner: None,
+        can_uninstall,
+        can_modify,
+        can_repair,
+        declared_url_schemes: Vec::new(),
+        declared_file_types: Vec::new(),
+        signature: None,
+        name_inferred,
+    };
+    ctx.store_icon(&mut app, icon);
+    crate::date::read_install_date(&mut app, ctx.date_order());
+    Ok(Some(app))
+}
 
-    // Define the source rectangle
-    let source_rectangle = WICRect {
-        X: 0,
-        Y: 0,
-        Width: size as i32,
-        Height: size as i32,
+/// Reads a `DisplayIcon` value into the file holding the icon and the icon's index in it,
+/// which counts icons from zero when positive and is a resource ID when negative.
+///
+/// The value is normally a path, optionally quoted and followed by `,index`. URLs and
+/// `ms-resource:` or `@{...}` references name no file and give `None`. A `rundll32` or
+/// `control` command line gives the `.cpl` or `.dll` it runs, or the program itself when
+/// it runs neither.
+#[cfg(any(target_os = "windows", test))]
+fn parse_display_icon(value: &str) -> Option<(std::path::PathBuf, i32)> {
+    let value = value.trim();
+    if value.is_empty() || is_resource_reference(value) {
+        return None;
+    }
+    let expand = |path: &str| {
+        let path = expand_environment_strings(unquote_path(path), |name| std::env::var(name).ok());
+        (!path.is_empty()).then(|| std::path::PathBuf::from(path))
     };
 
-    // Get and process pixel data
-    let pixel_format = unsafe { wic_bitmap.GetPixelFormat() }
-        .map_err(|_| AppInfoError::FileIconError(crate::error::FileIconError::Failed))?;
+    let (program, arguments) = split_command_line(value);
+    if is_command_host(program) {
+        return Some((expand(command_target(arguments).unwrap_or(program))?, 0));
+    }
 
-    #[allow(non_upper_case_globals)]
-    let pixels = match pixel_format {
-        GUID_WICPixelFormat32bppBGRA | GUID_WICPixelFormat32bppRGBA => {
-            let mut pixels = vec![0u8; size as usize * size as usize * 4];
-            unsafe { wic_bitmap.CopyPixels(&source_rectangle, size as u32 * 4, &mut pixels) }
-                .map_err(|_| AppInfoError::FileIconError(crate::error::FileIconError::Failed))?;
-
-            // If the format is BGRA, convert it to RGBA
-            if pixel_format == GUID_WICPixelFormat32bppBGRA {
-                for chunk in pixels.chunks_exact_mut(4) {
-                    chunk.swap(0, 2);
-                }
+    // Only a number after the last comma is an index; other commas belong to the path
+    let (path, index) = value
+        .rsplit_once(',')
+        .and_then(|(path, index)| Some((path, index.trim().parse().ok()?)))
+        .unwrap_or((value, 0));
+    Some((expand(path)?, index))
+}
+
+/// Whether a value is a URL or an indirect resource string rather than a path. A single
+/// letter before the colon is a drive, not a scheme.
+#[cfg(any(target_os = "windows", test))]
+fn is_resource_reference(value: &str) -> bool {
+    let value = value.trim_start_matches('"');
+    if value.starts_with('@') {
+        return true;
+    }
+    value.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.len() > 1
+            && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+/// Whether a program is `rundll32` or `control`, which run the file named in their
+/// arguments. Programs with those names elsewhere than the system directory do not count.
+#[cfg(any(target_os = "windows", test))]
+fn is_command_host(program: &str) -> bool {
+    // Split by hand, since `Path` only treats backslashes as separators on Windows
+    let mut components = unquote_path(program).rsplit(['\\', '/']);
+    let file_name = components.next().unwrap_or("").to_ascii_lowercase();
+    let stem = file_name.strip_suffix(".exe").unwrap_or(&file_name);
+    let in_system_dir = components.next().is_none_or(|dir| {
+        let dir = dir.to_ascii_lowercase();
+        dir == "system32" || dir == "syswow64"
+    });
+    (stem == "rundll32" || stem == "control") && in_system_dir
+}
+
+/// Finds the file a `rundll32` or `control` command runs in its arguments, such as
+/// `mmsys.cpl` in `shell32.dll,Control_RunDLL mmsys.cpl`: the last `.cpl`, or else the
+/// first `.dll`.
+#[cfg(any(target_os = "windows", test))]
+fn command_target(arguments: &str) -> Option<&str> {
+    let mut pieces = Vec::new();
+    let mut rest = arguments;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        if rest.is_empty() {
+            break;
+        }
+        let (piece, after) = match rest.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"').unwrap_or(quoted.len());
+                (&quoted[..end], quoted.get(end + 1..).unwrap_or(""))
+            }
+            None => {
+                let end = rest
+                    .find(|c: char| c.is_whitespace() || c == ',')
+                    .unwrap_or(rest.len());
+                rest.split_at(end)
             }
-            pixels
+        };
+        pieces.push(piece);
+        rest = after;
+    }
+
+    let has_extension = |piece: &&str, extension: &str| {
+        piece
+            .rsplit_once('.')
+            .is_some_and(|(_, ext)| ext.eq_ignore_ascii_case(extension))
+    };
+    let control_panel_item = pieces
+        .iter()
+        .rev()
+        .find(|piece| has_extension(piece, "cpl"));
+    control_panel_item
+        .or_else(|| pieces.iter().find(|piece| has_extension(piece, "dll")))
+        .copied()
+}
+
+/// Resolves a bare file name such as `shell32.dll` in the system directory, where
+/// `rundll32` and `control` look for it. Other paths are returned as they are.
+#[cfg(target_os = "windows")]
+fn system_file(path: PathBuf) -> PathBuf {
+    if path.parent() != Some(Path::new("")) {
+        return path;
+    }
+    match std::env::var_os("SystemRoot") {
+        Some(windir) => PathBuf::from(windir).join("System32").join(path),
+        None => path,
+    }
+}
+
+/// Picks a name for an Uninstall entry without a `DisplayName`, from the first of these
+/// that is not empty: the product name Windows Installer registered for the entry, the
+/// `ProductName` and then the `FileDescription` of its executable, and the last component
+/// of its `InstallLocation`.
+#[cfg(any(target_os = "windows", test))]
+fn infer_display_name(
+    msi_product_name: Option<&str>,
+    product_name: Option<&str>,
+    file_description: Option<&str>,
+    install_location: Option<&str>,
+) -> Option<String> {
+    let directory_name = install_location.and_then(|location| {
+        unquote_path(location)
+            .trim_end_matches(['\\', '/'])
+            .rsplit(['\\', '/'])
+            .next()
+            // A drive root such as `C:\` names nothing
+            .filter(|name| !name.ends_with(':'))
+    });
+    [
+        msi_product_name,
+        product_name,
+        file_description,
+        directory_name,
+    ]
+    .into_iter()
+    .flatten()
+    .map(str::trim)
+    .find(|name| !name.is_empty())
+    .map(str::to_string)
+}
+
+/// Guesses the install directory of an Uninstall entry without an `InstallLocation` from
+/// the executable its `DisplayIcon` names. Walking up from the executable's directory,
+/// this skips layout folders such as `bin`, `x64`, version numbers and the `app-<version>`
+/// folders of Squirrel-installed Electron apps, and returns the first directory whose
+/// name resembles `display_name`, or else the first directory that is not a layout
+/// folder. It never goes up to `Program Files`, `AppData\Local` and the like, so an
+/// executable placed straight into one of those gives `None`.
+#[cfg(any(target_os = "windows", test))]
+fn infer_install_dir<'a>(display_name: Option<&str>, executable: &'a str) -> Option<&'a str> {
+    let folded = |text: &str| -> String {
+        text.chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect()
+    };
+    let display_name = display_name.map(folded).unwrap_or_default();
+    let resembles = |directory: &str| {
+        let directory = folded(directory);
+        directory.len() >= 3
+            && display_name.len() >= 3
+            && (display_name.contains(&directory) || directory.contains(&display_name))
+    };
+
+    let separators: Vec<usize> = executable
+        .match_indices(['\\', '/'])
+        .map(|(index, _)| index)
+        .collect();
+    let mut first_directory = None;
+    for pair in separators.windows(2).rev() {
+        let directory = &executable[pair[0] + 1..pair[1]];
+        let lower = directory.to_lowercase();
+        if matches!(
+            lower.as_str(),
+            "program files"
+                | "program files (x86)"
+                | "programs"
+                | "programdata"
+                | "appdata"
+                | "local"
+                | "roaming"
+                | "users"
+                | "windows"
+                | "system32"
+                | "syswow64"
+        ) {
+            break;
         }
-        _ => {
-            return Err(AppInfoError::FileIconError(
-                crate::error::FileIconError::Failed,
-            ))
+        if is_layout_folder(&lower) {
+            continue;
+        }
+        let path = &executable[..pair[1]];
+        if resembles(directory) {
+            return Some(path);
         }
+        first_directory.get_or_insert(path);
+    }
+    first_directory
+}
+
+/// Whether a folder name, in lower case, is one installers put executables under rather
+/// than one naming the product.
+#[cfg(any(target_os = "windows", test))]
+fn is_layout_folder(name: &str) -> bool {
+    let is_version = |name: &str| {
+        name.starts_with(|c: char| c.is_ascii_digit())
+            && name.chars().all(|c| c.is_ascii_digit() || c == '.')
     };
+    matches!(
+        name,
+        "bin"
+            | "bin32"
+            | "bin64"
+            | "x86"
+            | "x64"
+            | "amd64"
+            | "arm64"
+            | "win32"
+            | "win64"
+            | "app"
+            | "application"
+            | "current"
+            | "program"
+    ) || is_version(name.strip_prefix('v').unwrap_or(name))
+        || name.strip_prefix("app-").is_some_and(is_version)
+}
 
-    Ok(Icon {
-        width: size as u32,
-        height: size as u32,
-        pixels,
+/// Reads the `ProductName` Windows Installer keeps for a product under its packed
+/// ProductCode, in the machine's or the user's registration.
+#[cfg(target_os = "windows")]
+fn msi_product_name(root: HKEY, product_code: &str) -> Option<String> {
+    let packed = packed_product_code(product_code)?;
+    let products_key = if root == HKEY_CURRENT_USER {
+        "Software\\Microsoft\\Installer\\Products"
+    } else {
+        "SOFTWARE\\Classes\\Installer\\Products"
+    };
+
+    let mut hkey = HKEY::default();
+    let key_name = HSTRING::from(format!("{}\\{}", products_key, packed));
+    unsafe { RegOpenKeyExW(root, &key_name, 0, KEY_READ, &mut hkey) }
+        .ok()
+        .ok()?;
+    defer!(unsafe {
+        let _ = RegCloseKey(hkey);
+    });
+    read_registry_string(hkey, "ProductName")
+        .ok()
+        .filter(|name| !name.trim().is_empty())
+}
+
+/// The form Windows Installer stores a ProductCode in under `Installer\Products`: the
+/// first three groups of the GUID reversed, and the bytes of the last two with their hex
+/// digits swapped. Returns `None` for anything but a braced GUID.
+#[cfg(any(target_os = "windows", test))]
+fn packed_product_code(product_code: &str) -> Option<String> {
+    let groups: Vec<&str> = product_code
+        .strip_prefix('{')?
+        .strip_suffix('}')?
+        .split('-')
+        .collect();
+    let lengths: Vec<usize> = groups.iter().map(|group| group.len()).collect();
+    let is_guid = lengths == [8, 4, 4, 4, 12]
+        && groups
+            .iter()
+            .all(|group| group.chars().all(|c| c.is_ascii_hexdigit()));
+    if !is_guid {
+        return None;
+    }
+
+    let mut packed = String::with_capacity(32);
+    for group in &groups[..3] {
+        packed.extend(group.chars().rev());
+    }
+    for group in &groups[3..] {
+        for pair in group.as_bytes().chunks(2) {
+            packed.push(pair[1] as char);
+            packed.push(pair[0] as char);
+        }
+    }
+    Some(packed.to_ascii_uppercase())
+}
+
+/// Looks up an application by the name of its Uninstall registry key on Windows.
+#[cfg(target_os = "windows")]
+pub fn lookup_app(identifier: &str, icon_size: u16) -> Result<AppInfo> {
+    lookup_all_apps(identifier, icon_size)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| AppInfoError::AppNotFound {
+            name: identifier.to_string(),
+        })
+}
+
+/// Looks up an Uninstall key by name in every hive on Windows, opening only that key.
+#[cfg(target_os = "windows")]
+pub fn lookup_all_apps(identifier: &str, icon_size: u16) -> Result<Vec<AppInfo>> {
+    // A key name cannot contain a separator; refuse paths into other keys
+    if identifier.is_empty() || identifier.contains('\\') {
+        return Ok(Vec::new());
+    }
+
+    Ok(uninstall_hives()
+        .into_iter()
+        .filter_map(|(root, uninstall_key)| {
+            let key_path = format!("{}\\{}", uninstall_key, identifier);
+            parse_registry_app(root, &key_path, icon_size).ok()
+        })
+        .collect())
+}
+
+/// Describes a single application on Windows from an executable or an installation
+/// directory.
+#[cfg(target_os = "windows")]
+pub fn get_app_info(path: &Path, icon_size: u16) -> Result<AppInfo> {
+    let not_an_application = || AppInfoError::BundleParseError {
+        path: path.display().to_string(),
+    };
+
+    let is_executable = path
+        .extension()
+        .and_then(|s| s.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("exe"));
+    let (executable, install_dir) = if path.is_dir() {
+        let executable = find_main_executable(path).ok_or_else(not_an_application)?;
+        (executable, path.to_path_buf())
+    } else if path.is_file() && is_executable {
+        let install_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        (path.to_path_buf(), install_dir)
+    } else {
+        return Err(not_an_application());
+    };
+
+    match registry_app_for_path(&executable, &install_dir, icon_size) {
+        Some(mut app) => {
+            app.path = executable.clone();
+            app.executable_path = Some(executable);
+            Ok(app)
+        }
+        None => Ok(app_for_executable(&executable, &[], icon_size)),
+    }
+}
+
+/// Finds the Uninstall entry whose `DisplayIcon` or `InstallLocation` points at an
+/// installation. Only those two values are read until an entry matches.
+#[cfg(target_os = "windows")]
+fn registry_app_for_path(executable: &Path, install_dir: &Path, icon_size: u16) -> Option<AppInfo> {
+    let install_dir = install_dir.to_string_lossy();
+    let install_dir = Path::new(install_dir.trim_end_matches(['\\', '/']));
+
+    for (root, uninstall_key) in uninstall_hives() {
+        let Some(hkey) = open_registry_key(root, uninstall_key) else {
+            continue;
+        };
+        let subkey_names = registry_subkey_names(hkey);
+        unsafe {
+            let _ = RegCloseKey(hkey);
+        }
+
+        for subkey_name in subkey_names {
+            let subkey_path = format!("{}\\{}", uninstall_key, subkey_name);
+            let Some(subkey) = open_registry_key(root, &subkey_path) else {
+                continue;
+            };
+            let display_icon = read_registry_string(subkey, "DisplayIcon").ok();
+            let install_location = read_registry_string(subkey, "InstallLocation").ok();
+            unsafe {
+                let _ = RegCloseKey(subkey);
+            }
+
+            let icon_matches = display_icon.as_deref().is_some_and(|icon| {
+                // DisplayIcon can be "path,index" or just "path"
+                let icon_path = icon
+                    .split(',')
+                    .next()
+                    .unwrap_or("")
+                    .trim()
+                    .trim_matches('"');
+                paths_equal(Path::new(icon_path), executable)
+            });
+            let location_matches = install_location.as_deref().is_some_and(|location| {
+                let location = location
+                    .trim()
+                    .trim_matches('"')
+                    .trim_end_matches(['\\', '/']);
+                !location.is_empty() && paths_equal(Path::new(location), install_dir)
+            });
+
+            if icon_matches || location_matches {
+                if let Ok(app) = parse_registry_app(root, &subkey_path, icon_size) {
+                    return Some(app);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Reads a string value from the registry.
+#[cfg(target_os = "windows")]
+fn read_registry_string(
+    hkey: windows::Win32::System::Registry::HKEY,
+    value_name: &str,
+) -> Result<String> {
+    read_registry_typed(hkey, value_name)
+        .and_then(|(data_type, data)| decode_registry_string(data_type, &data))
+        .ok_or_else(|| AppInfoError::RegistryError("Failed to read registry value".to_string()))
+}
+
+/// Decodes the data of a `REG_SZ`, `REG_EXPAND_SZ` or `REG_MULTI_SZ` value, of which only
+/// the first string is kept. Registry data is whatever the writer stored: the terminating
+/// NUL may be missing, an odd trailing byte cannot be part of a UTF-16 unit and is
+/// dropped, and unpaired surrogates become U+FFFD. Returns `None` for other types and for
+/// values with no data at all.
+#[cfg(any(target_os = "windows", test))]
+fn decode_registry_string(data_type: u32, data: &[u8]) -> Option<String> {
+    /// `REG_SZ`, `REG_EXPAND_SZ` and `REG_MULTI_SZ`
+    const STRING_TYPES: [u32; 3] = [1, 2, 7];
+
+    if !STRING_TYPES.contains(&data_type) || data.is_empty() {
+        return None;
+    }
+    let wide: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    let end = wide
+        .iter()
+        .position(|&unit| unit == 0)
+        .unwrap_or(wide.len());
+    Some(String::from_utf16_lossy(&wide[..end]))
+}
+
+/// Finds the main executable file in an installation directory, after following the links
+/// [`resolve_install_location`] follows.
+#[cfg(target_os = "windows")]
+fn find_main_executable(install_dir: &Path) -> Option<PathBuf> {
+    use std::fs;
+
+    let install_dir = &resolve_install_location(install_dir);
+    if is_exe_path(install_dir) && install_dir.is_file() {
+        return Some(install_dir.clone());
+    }
+
+    // List the directory through its long form, but report paths as the registry has them
+    let listing_dir = long_path(install_dir);
+    if !listing_dir.exists() {
+        return None;
+    }
+
+    // Look for .exe files, but avoid uninstaller files
+    if let Ok(entries) = fs::read_dir(&listing_dir) {
+        let mut exe_files: Vec<PathBuf> = Vec::new();
+
+        for entry in entries.flatten() {
+            let path = install_dir.join(entry.file_name());
+            if path.extension().and_then(|s| s.to_str()) == Some("exe") {
+                exe_files.push(path);
+            }
+        }
+
+        // Filter out likely uninstaller executables
+        let filtered_exes: Vec<PathBuf> = exe_files
+            .into_iter()
+            .filter(|path| {
+                if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
+                    let name_lower = name.to_lowercase();
+                    // Skip common uninstaller patterns
+                    !name_lower.contains("unins")
+                        && !name_lower.contains("uninst")
+                        && !name_lower.contains("uninstall")
+                        && !name_lower.contains("remove")
+                        && !name_lower.starts_with("un")
+                } else {
+                    true
+                }
+            })
+            .collect();
+
+        // Return the first non-uninstaller executable, or first one if none found
+        filtered_exes.into_iter().next().or_else(|| {
+            // If all were filtered out, try to get any exe file as fallback
+            if let Ok(entries) = fs::read_dir(&listing_dir) {
+                for entry in entries.flatten() {
+                    let path = install_dir.join(entry.file_name());
+                    if path.extension().and_then(|s| s.to_str()) == Some("exe") {
+                        return Some(path);
+                    }
+                }
+            }
+            None
+        })
+    } else {
+        None
+    }
+}
+
+/// How many links [`resolve_install_location`] follows before giving up.
+#[cfg(target_os = "windows")]
+const MAX_INSTALL_LINKS: usize = 8;
+
+/// Follows the links some installers leave in place of an installation directory: a
+/// junction or symbolic link into the real directory, as Scoop's `current` is, or a
+/// directory holding nothing but a `.lnk` shortcut. Returns the directory it ends at, or
+/// the program a shortcut points to. Broken links end the walk where they are, and cycles
+/// stop once a directory comes round again.
+#[cfg(target_os = "windows")]
+fn resolve_install_location(install_dir: &Path) -> PathBuf {
+    let mut location = install_dir.to_path_buf();
+    let mut visited: Vec<PathBuf> = Vec::new();
+    for _ in 0..MAX_INSTALL_LINKS {
+        let is_link = std::fs::symlink_metadata(long_path(&location))
+            .is_ok_and(|metadata| metadata.file_type().is_symlink());
+        if is_link {
+            // Fails for broken and cyclic links rather than looping
+            match std::fs::canonicalize(long_path(&location)) {
+                Ok(target) => location = strip_verbatim_prefix(&target),
+                Err(_) => break,
+            }
+        }
+
+        let key = location.to_string_lossy().to_lowercase();
+        if visited
+            .iter()
+            .any(|seen| seen.to_string_lossy().to_lowercase() == key)
+        {
+            debug_event!(path = %location.display(), "install location links form a cycle");
+            break;
+        }
+        visited.push(location.clone());
+
+        let Some((target, _)) = lone_shortcut(&location).and_then(|link| resolve_shortcut(&link))
+        else {
+            break;
+        };
+        let target_path = long_path(&target);
+        if target_path.is_file() && is_exe_path(&target) {
+            return target;
+        }
+        if !target_path.is_dir() {
+            break;
+        }
+        location = target;
+    }
+    location
+}
+
+/// Whether a path names an `.exe` file, going by its extension.
+#[cfg(target_os = "windows")]
+fn is_exe_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|s| s.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("exe"))
+}
+
+/// The only `.lnk` file in a directory without executables, if there is exactly one.
+#[cfg(target_os = "windows")]
+fn lone_shortcut(dir: &Path) -> Option<PathBuf> {
+    let mut shortcuts = Vec::new();
+    for entry in std::fs::read_dir(long_path(dir)).ok()?.flatten() {
+        let path = dir.join(entry.file_name());
+        if is_exe_path(&path) {
+            return None;
+        }
+        let is_shortcut = path
+            .extension()
+            .and_then(|s| s.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("lnk"));
+        if is_shortcut {
+            shortcuts.push(path);
+        }
+    }
+    match <[PathBuf; 1]>::try_from(shortcuts) {
+        Ok([shortcut]) => Some(shortcut),
+        Err(_) => None,
+    }
+}
+
+/// Turns a `\\?\` path, as `fs::canonicalize` returns, back into the plain form the
+/// registry uses. [`long_path`] adds the prefix again where a path needs it.
+#[cfg(any(target_os = "windows", test))]
+fn strip_verbatim_prefix(path: &std::path::Path) -> std::path::PathBuf {
+    let text = path.to_string_lossy();
+    if let Some(unc) = text.strip_prefix(r"\\?\UNC\") {
+        return std::path::PathBuf::from(format!(r"\\{}", unc));
+    }
+    match text.strip_prefix(r"\\?\") {
+        // Only drive paths have a plain form; volume GUID paths keep the prefix
+        Some(rest) if rest.as_bytes().get(1) == Some(&b':') => std::path::PathBuf::from(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Directories this long need the `\\?\` prefix for Win32 file APIs, which leave room for
+/// an 8.3 file name below `MAX_PATH`.
+#[cfg(any(target_os = "windows", test))]
+const LONG_PATH_LIMIT: usize = 248;
+
+/// Strips the whitespace and quotes installers leave around paths in registry values.
+#[cfg(any(target_os = "windows", test))]
+pub(crate) fn unquote_path(raw: &str) -> &str {
+    raw.trim().trim_matches('"').trim()
+}
+
+/// Returns a form of `path` that Win32 file APIs accept at any length. Short, relative and
+/// already prefixed paths are returned unchanged; long ones become `\\?\C:\...` or
+/// `\\?\UNC\server\share\...`, with `.` and `..` resolved because the prefix turns off
+/// that normalization.
+#[cfg(any(target_os = "windows", test))]
+fn long_path(path: &std::path::Path) -> std::path::PathBuf {
+    let text = path.to_string_lossy();
+    if text.encode_utf16().count() < LONG_PATH_LIMIT
+        || text.starts_with(r"\\?\")
+        || text.starts_with(r"\\.\")
+    {
+        return path.to_path_buf();
+    }
+
+    let text = text.replace('/', "\\");
+    let bytes = text.as_bytes();
+    let (prefix, root, rest) = if let Some(unc) = text.strip_prefix(r"\\") {
+        // The server and share form the root, which `..` cannot leave
+        let mut parts = unc.splitn(3, '\\');
+        match (parts.next(), parts.next()) {
+            (Some(server), Some(share)) if !server.is_empty() && !share.is_empty() => (
+                r"\\?\UNC\",
+                format!("{}\\{}", server, share),
+                parts.next().unwrap_or(""),
+            ),
+            _ => return path.to_path_buf(),
+        }
+    } else if bytes.len() > 2 && bytes[0].is_ascii_alphabetic() && &bytes[1..3] == b":\\" {
+        (r"\\?\", text[..2].to_string(), &text[3..])
+    } else {
+        return path.to_path_buf();
+    };
+
+    let mut components: Vec<&str> = Vec::new();
+    for component in rest.split('\\') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop();
+            }
+            component => components.push(component),
+        }
+    }
+    let mut long = format!("{}{}", prefix, root);
+    for component in components {
+        long.push('\\');
+        long.push_str(component);
+    }
+    std::path::PathBuf::from(long)
+}
+
+/// Returns the name `SHCreateItemFromParsingName` expects for `path`. Shell items do not
+/// take the `\\?\` prefix, so it is removed again, and `\\server\share` paths are kept in
+/// that form.
+#[cfg(any(all(target_os = "windows", feature = "icons"), test))]
+fn shell_parsing_name(path: &std::path::Path) -> String {
+    let text = unquote_path(&path.to_string_lossy()).replace('/', "\\");
+    if let Some(unc) = text.strip_prefix(r"\\?\UNC\") {
+        return format!(r"\\{}", unc);
+    }
+    // Leave volume GUID paths such as `\\?\Volume{...}\` alone
+    match text.strip_prefix(r"\\?\") {
+        Some(local) if local.as_bytes().get(1) == Some(&b':') => local.to_string(),
+        _ => text,
+    }
+}
+
+/// `%WINDIR%\\Installer`, where Windows Installer caches packages and product icons.
+#[cfg(target_os = "windows")]
+fn windows_installer_dir() -> Option<PathBuf> {
+    std::env::var_os("SystemRoot")
+        .or_else(|| std::env::var_os("windir"))
+        .map(|windir| PathBuf::from(windir).join("Installer"))
+}
+
+/// Interprets a yes/no registry value. Installers normally write a `REG_DWORD`, but some
+/// write the number as a string, so `"1"` counts as set too. Other types are not a flag.
+#[cfg(any(target_os = "windows", test))]
+fn parse_registry_flag(data_type: u32, data: &[u8]) -> Option<bool> {
+    /// `REG_SZ`, `REG_EXPAND_SZ`, `REG_DWORD` and `REG_QWORD`
+    const STRING: u32 = 1;
+    const EXPAND_STRING: u32 = 2;
+    const DWORD: u32 = 4;
+    const QWORD: u32 = 11;
+
+    match data_type {
+        DWORD => Some(u32::from_le_bytes(data.get(..4)?.try_into().ok()?) != 0),
+        QWORD => Some(u64::from_le_bytes(data.get(..8)?.try_into().ok()?) != 0),
+        STRING | EXPAND_STRING => {
+            let wide: Vec<u16> = data
+                .chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .take_while(|&unit| unit != 0)
+                .collect();
+            String::from_utf16(&wide)
+                .ok()?
+                .trim()
+                .parse::<u64>()
+                .ok()
+                .map(|value| value != 0)
+        }
+        _ => None,
+    }
+}
+
+/// Finds the Add/Remove Programs icon Windows Installer cached for a product, in
+/// `<installer_dir>\\<ProductCode>`. Files named after the `ARPPRODUCTICON` property come
+/// first, then `.ico` files, then executables, which hold their icon as a resource.
+#[cfg(any(target_os = "windows", test))]
+fn installer_cache_icon(
+    installer_dir: &std::path::Path,
+    product_code: &str,
+) -> Option<std::path::PathBuf> {
+    // Product codes are GUIDs; anything else could point outside the cache
+    let is_guid = product_code.len() == 38
+        && product_code.starts_with('{')
+        && product_code.ends_with('}')
+        && product_code[1..37]
+            .chars()
+            .all(|c| c.is_ascii_hexdigit() || c == '-');
+    if !is_guid {
+        return None;
+    }
+
+    let rank = |path: &std::path::Path| {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "ico" | "exe" if name.starts_with("arpproducticon") => Some(0),
+            "ico" => Some(1),
+            "exe" => Some(2),
+            _ => None,
+        }
+    };
+
+    let mut candidates: Vec<(u8, std::path::PathBuf)> =
+        std::fs::read_dir(installer_dir.join(product_code))
+            .ok()?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file())
+            .filter_map(|path| Some((rank(&path)?, path)))
+            .collect();
+    candidates.sort();
+    candidates.into_iter().next().map(|(_, path)| path)
+}
+
+/// Per-user URL scheme choices, under `HKEY_CURRENT_USER`.
+#[cfg(target_os = "windows")]
+const URL_ASSOCIATIONS_KEY: &str =
+    "Software\\Microsoft\\Windows\\Shell\\Associations\\UrlAssociations";
+
+/// Gets every application registered to open URLs with the given scheme on Windows,
+/// default handler first. `scheme` is expected to be normalized (lowercase, no `://`).
+#[cfg(target_os = "windows")]
+pub fn get_apps_for_url_scheme(scheme: &str, icon_size: u16) -> Result<Vec<AppInfo>> {
+    let mut executables: Vec<PathBuf> = Vec::new();
+    for executable in url_scheme_commands(scheme)
+        .iter()
+        .filter_map(|command| command_line_executable(command))
+    {
+        if executable.exists() && !executables.iter().any(|e| paths_equal(e, &executable)) {
+            executables.push(executable);
+        }
+    }
+
+    if executables.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let installed = get_installed_apps(0).unwrap_or_default();
+    Ok(executables
+        .iter()
+        .map(|executable| app_for_executable(executable, &installed, icon_size))
+        .collect())
+}
+
+/// Gets the application Windows opens a URL scheme with by default.
+/// `scheme` is expected to be normalized (lowercase, no `://`).
+#[cfg(target_os = "windows")]
+pub fn get_default_app_for_url_scheme(scheme: &str, icon_size: u16) -> Result<AppInfo> {
+    let executable = url_scheme_commands(scheme)
+        .iter()
+        .filter_map(|command| command_line_executable(command))
+        .find(|executable| executable.exists())
+        .ok_or_else(|| AppInfoError::UrlSchemeNotHandled {
+            scheme: scheme.to_string(),
+        })?;
+
+    let installed = get_installed_apps(0).unwrap_or_default();
+    Ok(app_for_executable(&executable, &installed, icon_size))
+}
+
+/// Collects the open commands registered for a URL scheme, in priority order: the user's
+/// choice, the scheme's own `HKCR\<scheme>` handler, then every registered application
+/// whose capabilities list the scheme.
+#[cfg(target_os = "windows")]
+fn url_scheme_commands(scheme: &str) -> Vec<String> {
+    let mut commands = Vec::new();
+
+    // The per-user choice made in Settings or the "Open with" prompt
+    let user_choice = format!("{}\\{}\\UserChoice", URL_ASSOCIATIONS_KEY, scheme);
+    if let Some(command) = read_key_string(HKEY_CURRENT_USER, &user_choice, "ProgId")
+        .and_then(|prog_id| prog_id_open_command(&prog_id))
+    {
+        commands.push(command);
+    }
+
+    // A classic protocol handler, which is only valid with a "URL Protocol" value
+    if let Some(hkey) = open_registry_key(HKEY_CLASSES_ROOT, scheme) {
+        defer!(unsafe {
+            let _ = RegCloseKey(hkey);
+        });
+        if has_registry_value(hkey, "URL Protocol") {
+            commands.extend(prog_id_open_command(scheme));
+        }
+    }
+
+    // Applications that declare the scheme in their registered capabilities
+    for (root, capabilities) in registered_application_capabilities() {
+        let url_associations = format!("{}\\URLAssociations", capabilities);
+        if let Some(command) = read_key_string(root, &url_associations, scheme)
+            .and_then(|prog_id| prog_id_open_command(&prog_id))
+        {
+            commands.push(command);
+        }
+    }
+
+    commands
+}
+
+/// Per-user file type choices and "Open with" history, under `HKEY_CURRENT_USER`.
+#[cfg(target_os = "windows")]
+const FILE_EXTS_KEY: &str = "Software\\Microsoft\\Windows\\CurrentVersion\\Explorer\\FileExts";
+
+/// Gets every application Windows offers to open a file with, default handler first.
+#[cfg(target_os = "windows")]
+pub fn get_apps_for_file(path: &Path, icon_size: u16) -> Result<Vec<AppInfo>> {
+    let Some(extension) = path.extension().and_then(|s| s.to_str()) else {
+        return Ok(Vec::new());
+    };
+    let extension = format!(".{}", extension.to_lowercase());
+
+    let mut executables: Vec<PathBuf> = Vec::new();
+    for executable in file_type_commands(&extension)
+        .iter()
+        .filter_map(|command| command_line_executable(command))
+    {
+        if executable.exists() && !executables.iter().any(|e| paths_equal(e, &executable)) {
+            executables.push(executable);
+        }
+    }
+
+    if executables.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let installed = get_installed_apps(0).unwrap_or_default();
+    Ok(executables
+        .iter()
+        .map(|executable| app_for_executable(executable, &installed, icon_size))
+        .collect())
+}
+
+/// Collects the open commands registered for a file extension such as `.txt`, in priority
+/// order: the user's choice, the extension's default ProgId, the ProgIds and applications
+/// listed for "Open with", most recently used first, then every registered application
+/// whose capabilities list the extension.
+#[cfg(target_os = "windows")]
+fn file_type_commands(extension: &str) -> Vec<String> {
+    let mut prog_ids: Vec<String> = Vec::new();
+    let mut applications: Vec<String> = Vec::new();
+    let user_key = format!("{}\\{}", FILE_EXTS_KEY, extension);
+
+    // The per-user choice made in Settings or the "Open with" prompt
+    prog_ids.extend(read_key_string(
+        HKEY_CURRENT_USER,
+        &format!("{}\\UserChoice", user_key),
+        "ProgId",
+    ));
+    prog_ids.extend(read_key_string(HKEY_CLASSES_ROOT, extension, ""));
+
+    // ProgIds and executables offered under "Open with", for the machine and the user
+    for (root, key_path) in [
+        (HKEY_CLASSES_ROOT, extension.to_string()),
+        (HKEY_CURRENT_USER, user_key),
+    ] {
+        if let Some(hkey) = open_registry_key(root, &format!("{}\\OpenWithProgids", key_path)) {
+            prog_ids.extend(registry_value_names(hkey));
+            unsafe {
+                let _ = RegCloseKey(hkey);
+            }
+        }
+        if let Some(hkey) = open_registry_key(root, &format!("{}\\OpenWithList", key_path)) {
+            // The user's list names executables in values `a`, `b`, ..., ordered by
+            // `MRUList`; the machine's names them as subkeys
+            let entries: Vec<(String, String)> = registry_value_names(hkey)
+                .into_iter()
+                .filter_map(|name| {
+                    let value = read_registry_string(hkey, &name).ok()?;
+                    Some((name, value))
+                })
+                .collect();
+            let mru_list = read_registry_string(hkey, "MRUList").ok();
+            applications.extend(open_with_list_order(mru_list.as_deref(), &entries));
+            applications.extend(registry_subkey_names(hkey));
+            unsafe {
+                let _ = RegCloseKey(hkey);
+            }
+        }
+    }
+
+    // Applications that declare the extension in their registered capabilities
+    for (root, capabilities) in registered_application_capabilities() {
+        let file_associations = format!("{}\\FileAssociations", capabilities);
+        prog_ids.extend(read_key_string(root, &file_associations, extension));
+    }
+
+    let mut commands: Vec<String> = prog_ids
+        .iter()
+        .filter(|prog_id| !prog_id.is_empty())
+        .filter_map(|prog_id| prog_id_open_command(prog_id))
+        .collect();
+    commands.extend(
+        applications.iter().filter_map(|application| {
+            prog_id_open_command(&format!("Applications\\{}", application))
+        }),
+    );
+    commands
+}
+
+/// Orders the executables of a per-user `OpenWithList`: the values named by the letters
+/// of `MRUList` first, in that order, then any others by value name. `MRUList` itself,
+/// and executables listed twice, are skipped.
+#[cfg(any(target_os = "windows", test))]
+fn open_with_list_order(mru_list: Option<&str>, entries: &[(String, String)]) -> Vec<String> {
+    let lookup = |name: &str| {
+        entries
+            .iter()
+            .find(|(entry_name, _)| entry_name.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.clone())
+    };
+
+    let mut ordered: Vec<String> = Vec::new();
+    for letter in mru_list.unwrap_or("").chars() {
+        if let Some(value) = lookup(&letter.to_string()) {
+            if !value.is_empty() && !ordered.contains(&value) {
+                ordered.push(value);
+            }
+        }
+    }
+
+    let mut rest: Vec<&(String, String)> = entries
+        .iter()
+        .filter(|(name, _)| !name.eq_ignore_ascii_case("MRUList"))
+        .collect();
+    rest.sort_by_key(|(name, _)| name.to_lowercase());
+    for (_, value) in rest {
+        if !value.is_empty() && !ordered.contains(value) {
+            ordered.push(value.clone());
+        }
+    }
+    ordered
+}
+
+/// Reads the `shell\open\command` of a ProgId.
+#[cfg(target_os = "windows")]
+fn prog_id_open_command(prog_id: &str) -> Option<String> {
+    let command_key = format!("{}\\shell\\open\\command", prog_id);
+    read_key_string(HKEY_CLASSES_ROOT, &command_key, "")
+}
+
+/// Lists the `Capabilities` keys of every application in `RegisteredApplications`, for
+/// the current user and the machine, with the root each path is relative to.
+#[cfg(target_os = "windows")]
+fn registered_application_capabilities() -> Vec<(HKEY, String)> {
+    let mut capabilities = Vec::new();
+    for root in [HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE] {
+        let Some(hkey) = open_registry_key(root, "Software\\RegisteredApplications") else {
+            continue;
+        };
+        defer!(unsafe {
+            let _ = RegCloseKey(hkey);
+        });
+
+        for value_name in registry_value_names(hkey) {
+            if let Ok(path) = read_registry_string(hkey, &value_name) {
+                capabilities.push((root, path));
+            }
+        }
+    }
+    capabilities
+}
+
+/// The URL schemes and file types registered applications declare in their
+/// `Capabilities`, each with the executable its ProgId opens.
+#[cfg(target_os = "windows")]
+struct Declarations {
+    url_schemes: Vec<(PathBuf, String)>,
+    file_types: Vec<(PathBuf, FileAssociation)>,
+}
+
+#[cfg(target_os = "windows")]
+impl Declarations {
+    /// Fills in the schemes and file types declared for the app's executable.
+    fn apply(&self, app: &mut AppInfo) {
+        let Some(executable) = app.executable_path.as_deref() else {
+            return;
+        };
+        for (_, scheme) in self
+            .url_schemes
+            .iter()
+            .filter(|(path, _)| paths_equal(path, executable))
+        {
+            if !app.declared_url_schemes.contains(scheme) {
+                app.declared_url_schemes.push(scheme.clone());
+            }
+        }
+        for (_, association) in self
+            .file_types
+            .iter()
+            .filter(|(path, _)| paths_equal(path, executable))
+        {
+            if !app.declared_file_types.contains(association) {
+                app.declared_file_types.push(association.clone());
+            }
+        }
+    }
+}
+
+/// Reads the `URLAssociations` and `FileAssociations` of every registered application.
+/// Entries whose ProgId has no open command are left out, since they cannot be tied to
+/// an executable.
+#[cfg(target_os = "windows")]
+fn registered_declarations() -> Declarations {
+    use std::collections::HashMap;
+
+    // Many entries share a ProgId, so resolve each one only once
+    let mut executables: HashMap<String, Option<PathBuf>> = HashMap::new();
+    let mut executable_for = |prog_id: &str| -> Option<PathBuf> {
+        executables
+            .entry(prog_id.to_lowercase())
+            .or_insert_with(|| {
+                prog_id_open_command(prog_id).and_then(|command| command_line_executable(&command))
+            })
+            .clone()
+    };
+
+    let mut declarations = Declarations {
+        url_schemes: Vec::new(),
+        file_types: Vec::new(),
+    };
+    for (root, capabilities) in registered_application_capabilities() {
+        for (name, is_scheme) in [("URLAssociations", true), ("FileAssociations", false)] {
+            let Some(hkey) = open_registry_key(root, &format!("{}\\{}", capabilities, name)) else {
+                continue;
+            };
+            defer!(unsafe {
+                let _ = RegCloseKey(hkey);
+            });
+
+            for value_name in registry_value_names(hkey) {
+                let Ok(prog_id) = read_registry_string(hkey, &value_name) else {
+                    continue;
+                };
+                let Some(executable) = executable_for(&prog_id) else {
+                    continue;
+                };
+                if is_scheme {
+                    let scheme = normalize_url_scheme(&value_name);
+                    if !scheme.is_empty() {
+                        declarations.url_schemes.push((executable, scheme));
+                    }
+                } else {
+                    let role = prog_id_role(&prog_id, &executable).unwrap_or_default();
+                    let association = FileAssociation {
+                        extension: Some(value_name.trim_start_matches('.').to_lowercase()),
+                        uti: None,
+                        role,
+                    };
+                    declarations.file_types.push((executable, association));
+                }
+            }
+        }
+    }
+    declarations
+}
+
+/// Lists the file extensions whose handlers open with the given executable on Windows.
+///
+/// Both the extensions' own ProgIds (`HKCR\.<ext>` and its `OpenWithProgids`) and the
+/// `Capabilities\FileAssociations` of registered applications are considered; a ProgId
+/// counts when its `shell\open\command` runs `app_path`.
+#[cfg(target_os = "windows")]
+pub fn file_associations(app_path: &Path) -> Result<Vec<FileAssociation>> {
+    use std::collections::HashMap;
+
+    let is_executable = app_path
+        .extension()
+        .and_then(|s| s.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("exe"));
+    if !is_executable {
+        return Ok(Vec::new());
+    }
+
+    // Many extensions share a ProgId, so resolve each one only once
+    let mut prog_id_roles: HashMap<String, Option<AssociationRole>> = HashMap::new();
+    let mut role_for = |prog_id: &str| -> Option<AssociationRole> {
+        *prog_id_roles
+            .entry(prog_id.to_lowercase())
+            .or_insert_with(|| prog_id_role(prog_id, app_path))
+    };
+
+    let mut associations: Vec<FileAssociation> = Vec::new();
+    let mut add = |extension: &str, role: AssociationRole| {
+        let association = FileAssociation {
+            extension: Some(extension.trim_start_matches('.').to_lowercase()),
+            uti: None,
+            role,
+        };
+        if !associations.contains(&association) {
+            associations.push(association);
+        }
+    };
+
+    // Types the application declares in its registered capabilities
+    for (root, capabilities) in registered_application_capabilities() {
+        let file_associations = format!("{}\\FileAssociations", capabilities);
+        let Some(hkey) = open_registry_key(root, &file_associations) else {
+            continue;
+        };
+        defer!(unsafe {
+            let _ = RegCloseKey(hkey);
+        });
+
+        for extension in registry_value_names(hkey) {
+            if let Some(role) = read_registry_string(hkey, &extension)
+                .ok()
+                .and_then(|prog_id| role_for(&prog_id))
+            {
+                add(&extension, role);
+            }
+        }
+    }
+
+    // Extensions whose default or "Open with" ProgIds launch the executable
+    for extension in registry_subkey_names(HKEY_CLASSES_ROOT)
+        .into_iter()
+        .filter(|name| name.starts_with('.'))
+    {
+        let Some(hkey) = open_registry_key(HKEY_CLASSES_ROOT, &extension) else {
+            continue;
+        };
+        defer!(unsafe {
+            let _ = RegCloseKey(hkey);
+        });
+
+        let mut prog_ids: Vec<String> = read_registry_string(hkey, "").into_iter().collect();
+        if let Some(open_with) = open_registry_key(hkey, "OpenWithProgids") {
+            defer!(unsafe {
+                let _ = RegCloseKey(open_with);
+            });
+            prog_ids.extend(registry_value_names(open_with));
+        }
+
+        if let Some(role) = prog_ids.iter().find_map(|prog_id| role_for(prog_id)) {
+            add(&extension, role);
+        }
+    }
+
+    Ok(associations)
+}
+
+/// Works out the role a ProgId gives an executable: `Editor` when its edit verb also runs
+/// the executable, `Viewer` when only its open verb does, and nothing when it runs neither.
+#[cfg(target_os = "windows")]
+fn prog_id_role(prog_id: &str, executable: &Path) -> Option<AssociationRole> {
+    let runs_executable = |command: Option<String>| {
+        command
+            .and_then(|command| command_line_executable(&command))
+            .is_some_and(|path| paths_equal(&path, executable))
+    };
+
+    if !runs_executable(prog_id_open_command(prog_id)) {
+        return None;
+    }
+
+    let edit_command = format!("{}\\shell\\edit\\command", prog_id);
+    if runs_executable(read_key_string(HKEY_CLASSES_ROOT, &edit_command, "")) {
+        Some(AssociationRole::Editor)
+    } else {
+        Some(AssociationRole::Viewer)
+    }
+}
+
+/// Gets the running applications on Windows, one entry per process.
+///
+/// Regular applications are the processes owning a visible, unowned top-level window
+/// that is not a tool window, which is roughly what the taskbar shows.
+#[cfg(target_os = "windows")]
+pub fn get_running_apps(icon_size: u16, filter: RunningAppFilter) -> Result<Vec<RunningApp>> {
+    use std::collections::HashMap;
+
+    let windowed = windowed_process_ids();
+    let pids = match filter {
+        RunningAppFilter::Regular => windowed.clone(),
+        RunningAppFilter::All => all_process_ids(),
+    };
+    let foreground = foreground_process_id();
+    let installed = get_installed_apps(0).unwrap_or_default();
+
+    // Several processes often share one executable, so describe each executable once
+    let mut apps_by_executable: HashMap<String, AppInfo> = HashMap::new();
+    let mut running = Vec::new();
+    for pid in pids {
+        // Protected and system processes cannot be opened; skip them
+        let Some(executable) = process_image_path(pid) else {
+            continue;
+        };
+        let key = executable.to_string_lossy().to_lowercase();
+        let app = apps_by_executable
+            .entry(key)
+            .or_insert_with(|| app_for_executable(&executable, &installed, icon_size))
+            .clone();
+
+        running.push(RunningApp {
+            app,
+            pid,
+            is_active: foreground == Some(pid),
+            is_hidden: !windowed.contains(&pid),
+        });
+    }
+    Ok(running)
+}
+
+/// Checks whether an application is running on Windows by comparing every process's
+/// executable with the application's.
+///
+/// Paths are compared case-insensitively and, when they differ textually, after
+/// canonicalization, which resolves 8.3 short names, `\\?\` prefixes and links.
+#[cfg(target_os = "windows")]
+pub fn is_running(app: &AppInfo) -> Result<bool> {
+    let is_executable = |path: &Path| {
+        path.extension()
+            .and_then(|s| s.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("exe"))
+    };
+    let Some(executable) = app
+        .executable_path
+        .as_deref()
+        .or_else(|| Some(app.path.as_path()).filter(|path| is_executable(path)))
+    else {
+        return Ok(false);
+    };
+    let canonical = canonical_key(executable);
+
+    Ok(all_process_ids().into_iter().any(|pid| {
+        process_image_path(pid).is_some_and(|path| {
+            paths_equal(&path, executable)
+                || (canonical.is_some() && canonical_key(&path) == canonical)
+        })
+    }))
+}
+
+/// Launches an application's executable on Windows.
+///
+/// `ShellExecuteExW` is tried first so elevation manifests and the `runas` verb work; if
+/// it fails for any reason other than the user declining elevation, the executable is
+/// spawned directly instead.
+#[cfg(target_os = "windows")]
+pub fn launch(app: &AppInfo, args: &[OsString], options: &LaunchOptions) -> Result<LaunchedApp> {
+    let is_executable = |path: &Path| {
+        path.extension()
+            .and_then(|s| s.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("exe"))
+    };
+    let executable = app
+        .executable_path
+        .as_deref()
+        .or_else(|| Some(app.path.as_path()).filter(|path| is_executable(path)))
+        .filter(|path| path.is_file())
+        .ok_or_else(|| AppInfoError::ExecutableNotFound {
+            path: app.path.display().to_string(),
+        })?;
+
+    let working_directory = options
+        .working_directory
+        .clone()
+        .or_else(|| executable.parent().map(Path::to_path_buf));
+
+    match shell_execute(executable, args, working_directory.as_deref(), options) {
+        Err(AppInfoError::LaunchCancelled) => Err(AppInfoError::LaunchCancelled),
+        // Without elevation a direct spawn is equivalent, so retry that way
+        Err(_) if !options.elevated => {
+            let mut command = std::process::Command::new(executable);
+            command.args(args);
+            if let Some(working_directory) = &working_directory {
+                command.current_dir(working_directory);
+            }
+            let child = command
+                .spawn()
+                .map_err(|e| AppInfoError::LaunchFailed(e.to_string()))?;
+            Ok(LaunchedApp {
+                pid: Some(child.id()),
+            })
+        }
+        result => result,
+    }
+}
+
+/// Starts an executable through the shell, returning its process ID when reported.
+#[cfg(target_os = "windows")]
+fn shell_execute(
+    executable: &Path,
+    args: &[OsString],
+    working_directory: Option<&Path>,
+    options: &LaunchOptions,
+) -> Result<LaunchedApp> {
+    let file = HSTRING::from(executable);
+    let parameters = HSTRING::from(
+        args.iter()
+            .map(|arg| quote_windows_argument(&arg.to_string_lossy()))
+            .collect::<Vec<_>>()
+            .join(" "),
+    );
+    let directory = working_directory.map(HSTRING::from);
+
+    let show = if options.hidden {
+        SW_HIDE
+    } else if options.activate {
+        SW_SHOWNORMAL
+    } else {
+        SW_SHOWNOACTIVATE
+    };
+
+    let mut info = SHELLEXECUTEINFOW {
+        cbSize: std::mem::size_of::<SHELLEXECUTEINFOW>() as u32,
+        fMask: SEE_MASK_NOCLOSEPROCESS | SEE_MASK_NOASYNC | SEE_MASK_FLAG_NO_UI,
+        lpVerb: if options.elevated {
+            w!("runas")
+        } else {
+            PCWSTR::null()
+        },
+        lpFile: PCWSTR(file.as_ptr()),
+        lpParameters: PCWSTR(parameters.as_ptr()),
+        lpDirectory: directory
+            .as_ref()
+            .map_or(PCWSTR::null(), |directory| PCWSTR(directory.as_ptr())),
+        nShow: show.0,
+        ..Default::default()
+    };
+
+    unsafe { ShellExecuteExW(&mut info) }.map_err(|e| {
+        if e.code() == ERROR_CANCELLED.to_hresult() {
+            AppInfoError::LaunchCancelled
+        } else {
+            AppInfoError::LaunchFailed(e.message().to_string())
+        }
+    })?;
+
+    // Launches handed to an existing instance (DDE) have no process handle
+    if info.hProcess.is_invalid() {
+        return Ok(LaunchedApp { pid: None });
+    }
+    defer!(unsafe {
+        let _ = CloseHandle(info.hProcess);
+    });
+    let pid = unsafe { GetProcessId(info.hProcess) };
+    Ok(LaunchedApp {
+        pid: (pid != 0).then_some(pid),
+    })
+}
+
+/// Uninstalls an app with the command its Uninstall entry registers, see
+/// [`AppInfo::uninstall`].
+#[cfg(target_os = "windows")]
+pub fn uninstall(app: &AppInfo, options: &UninstallOptions) -> Result<UninstallOutcome> {
+    if app.can_uninstall == Some(false) {
+        return Err(AppInfoError::UninstallFailed(
+            "The entry does not allow removal".to_string(),
+        ));
+    }
+    let (root, key_path) = uninstall_entry_key(app).ok_or_else(|| {
+        AppInfoError::UninstallFailed("No Uninstall entry lists the application".to_string())
+    })?;
+    let entry = read_uninstall_entry(root, &key_path)?;
+
+    // Machine-wide uninstallers write to Program Files and HKLM, so they need the rights
+    let elevated = app.install_scope == Some(InstallScope::Machine) && !process_is_elevated();
+    let command = uninstall_command(
+        &entry,
+        app.identifier.as_deref(),
+        options.quiet,
+        elevated,
+        |name| std::env::var(name).ok(),
+    )?;
+    if options.dry_run {
+        return Ok(UninstallOutcome::DryRun(UninstallPlan::Run(command)));
+    }
+    run_uninstaller(&command, options.quiet)
+}
+
+/// The root key an Uninstall entry is under.
+#[cfg(any(target_os = "windows", test))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UninstallRoot {
+    LocalMachine,
+    CurrentUser,
+    Users,
+}
+
+/// Finds the Uninstall entry a scan read an app from: the key named by its identifier, in
+/// the hive its install scope and owner give and the view its registry view gives.
+#[cfg(any(target_os = "windows", test))]
+fn uninstall_entry_key(app: &AppInfo) -> Option<(UninstallRoot, String)> {
+    const UNINSTALL: &str = "Microsoft\\Windows\\CurrentVersion\\Uninstall";
+
+    let identifier = app
+        .identifier
+        .as_deref()
+        .filter(|identifier| !identifier.is_empty() && !identifier.contains('\\'))?;
+    match (app.install_scope?, &app.owner) {
+        (InstallScope::Machine, _) => {
+            let software = match app.registry_view {
+                Some(RegistryView::Wow64) => "SOFTWARE\\WOW6432Node",
+                _ => "SOFTWARE",
+            };
+            Some((
+                UninstallRoot::LocalMachine,
+                format!("{}\\{}\\{}", software, UNINSTALL, identifier),
+            ))
+        }
+        (InstallScope::User, Some(owner)) => Some((
+            UninstallRoot::Users,
+            format!("{}\\{}", user_uninstall_key(&owner.sid), identifier),
+        )),
+        (InstallScope::User, None) => Some((
+            UninstallRoot::CurrentUser,
+            format!("SOFTWARE\\{}\\{}", UNINSTALL, identifier),
+        )),
+    }
+}
+
+/// What an Uninstall entry says about removing it.
+#[cfg(any(target_os = "windows", test))]
+#[derive(Debug, Clone, Default)]
+struct UninstallEntry {
+    uninstall_string: Option<String>,
+    quiet_uninstall_string: Option<String>,
+    windows_installer: bool,
+}
+
+/// Reads the values of an Uninstall entry that say how to remove it.
+#[cfg(target_os = "windows")]
+fn read_uninstall_entry(root: UninstallRoot, key_path: &str) -> Result<UninstallEntry> {
+    let root = match root {
+        UninstallRoot::LocalMachine => HKEY_LOCAL_MACHINE,
+        UninstallRoot::CurrentUser => HKEY_CURRENT_USER,
+        UninstallRoot::Users => HKEY_USERS,
+    };
+    let mut hkey = HKEY::default();
+    let key_name = HSTRING::from(key_path);
+    if unsafe { RegOpenKeyExW(root, &key_name, 0, KEY_READ, &mut hkey) }.is_err() {
+        return Err(AppInfoError::UninstallFailed(
+            "The application's Uninstall entry is gone".to_string(),
+        ));
+    }
+    defer!(unsafe {
+        let _ = RegCloseKey(hkey);
+    });
+
+    Ok(UninstallEntry {
+        uninstall_string: read_registry_string(hkey, "UninstallString").ok(),
+        quiet_uninstall_string: read_registry_string(hkey, "QuietUninstallString").ok(),
+        windows_installer: read_registry_flag(hkey, "WindowsInstaller").unwrap_or(false),
+    })
+}
+
+/// Works out the command that removes an entry, expanding `%NAME%` references with
+/// `lookup`.
+///
+/// Windows Installer products, which the entry marks with its `WindowsInstaller` flag or an
+/// `msiexec` uninstall string, are removed with `msiexec /x` and the product code in
+/// `identifier`: their uninstall string often runs `msiexec /I`, which opens the
+/// maintenance dialog rather than removing anything. A quiet removal adds
+/// `/qn /norestart`, so a needed restart is reported with exit code 3010 instead of
+/// happening unasked. Other entries run their `QuietUninstallString` when `quiet` and
+/// their `UninstallString` otherwise.
+#[cfg(any(target_os = "windows", test))]
+fn uninstall_command(
+    entry: &UninstallEntry,
+    identifier: Option<&str>,
+    quiet: bool,
+    elevated: bool,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<UninstallCommand> {
+    let non_empty = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+    let uninstall_string = non_empty(&entry.uninstall_string);
+
+    let runs_msiexec = uninstall_string.as_deref().is_some_and(|command| {
+        let (program, _) = split_command_line(command);
+        let file_name = program
+            .trim()
+            .rsplit(['\\', '/'])
+            .next()
+            .unwrap_or_default();
+        file_name.eq_ignore_ascii_case("msiexec") || file_name.eq_ignore_ascii_case("msiexec.exe")
+    });
+    let product_code = identifier
+        .map(str::trim)
+        .filter(|identifier| crate::identity::is_product_code(identifier));
+    if let Some(product_code) = product_code.filter(|_| entry.windows_installer || runs_msiexec) {
+        let program = match lookup("SystemRoot") {
+            Some(windows_dir) => format!("{}\\System32\\msiexec.exe", windows_dir),
+            None => "msiexec.exe".to_string(),
+        };
+        let mut arguments = format!("/x {}", product_code);
+        if quiet {
+            arguments.push_str(" /qn /norestart");
+        }
+        return Ok(UninstallCommand {
+            program: std::path::PathBuf::from(program),
+            arguments,
+            elevated,
+        });
+    }
+
+    let command = if quiet {
+        non_empty(&entry.quiet_uninstall_string).ok_or_else(|| {
+            AppInfoError::UninstallFailed(
+                "The entry registers no quiet uninstall command".to_string(),
+            )
+        })?
+    } else {
+        uninstall_string.ok_or_else(|| {
+            AppInfoError::UninstallFailed("The entry registers no uninstall command".to_string())
+        })?
+    };
+    let (program, arguments) = split_command_line(&command);
+    let program = expand_environment_strings(program.trim(), &lookup);
+    if program.is_empty() {
+        return Err(AppInfoError::UninstallFailed(format!(
+            "No program in the uninstall command {:?}",
+            command
+        )));
+    }
+    Ok(UninstallCommand {
+        program: std::path::PathBuf::from(program),
+        arguments: expand_environment_strings(arguments.trim(), &lookup),
+        elevated,
+    })
+}
+
+/// Whether this process runs elevated, with the administrator rights UAC grants.
+#[cfg(target_os = "windows")]
+fn process_is_elevated() -> bool {
+    let mut token = HANDLE::default();
+    if unsafe { OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) }.is_err() {
+        return false;
+    }
+    defer!(unsafe {
+        let _ = CloseHandle(token);
+    });
+
+    let mut elevation = TOKEN_ELEVATION::default();
+    let mut length = 0u32;
+    let read = unsafe {
+        GetTokenInformation(
+            token,
+            TokenElevation,
+            Some(&mut elevation as *mut TOKEN_ELEVATION as *mut std::ffi::c_void),
+            std::mem::size_of::<TOKEN_ELEVATION>() as u32,
+            &mut length,
+        )
+    };
+    read.is_ok() && elevation.TokenIsElevated != 0
+}
+
+/// Runs an uninstaller through the shell, which shows the UAC prompt for an elevated one,
+/// and waits for it to exit.
+#[cfg(target_os = "windows")]
+fn run_uninstaller(command: &UninstallCommand, quiet: bool) -> Result<UninstallOutcome> {
+    let file = HSTRING::from(command.program.as_path());
+    let parameters = HSTRING::from(command.arguments.as_str());
+    let show = if quiet { SW_HIDE } else { SW_SHOWNORMAL };
+
+    let mut info = SHELLEXECUTEINFOW {
+        cbSize: std::mem::size_of::<SHELLEXECUTEINFOW>() as u32,
+        fMask: SEE_MASK_NOCLOSEPROCESS | SEE_MASK_NOASYNC | SEE_MASK_FLAG_NO_UI,
+        lpVerb: if command.elevated {
+            w!("runas")
+        } else {
+            PCWSTR::null()
+        },
+        lpFile: PCWSTR(file.as_ptr()),
+        lpParameters: PCWSTR(parameters.as_ptr()),
+        nShow: show.0,
+        ..Default::default()
+    };
+
+    match unsafe { ShellExecuteExW(&mut info) } {
+        Ok(()) => {}
+        Err(e) if e.code() == ERROR_CANCELLED.to_hresult() => {
+            return Ok(UninstallOutcome::Cancelled);
+        }
+        Err(e) => return Err(AppInfoError::UninstallFailed(e.message().to_string())),
+    }
+    if info.hProcess.is_invalid() {
+        return Err(AppInfoError::UninstallFailed(
+            "The uninstaller's process cannot be waited for".to_string(),
+        ));
+    }
+    defer!(unsafe {
+        let _ = CloseHandle(info.hProcess);
+    });
+
+    let mut exit_code = 0u32;
+    unsafe {
+        WaitForSingleObject(info.hProcess, INFINITE);
+        GetExitCodeProcess(info.hProcess, &mut exit_code)
+    }
+    .map_err(|e| AppInfoError::UninstallFailed(e.message().to_string()))?;
+    Ok(outcome_for_exit_code(exit_code))
+}
+
+/// Canonicalizes a path into a lowercase key for comparisons.
+#[cfg(target_os = "windows")]
+fn canonical_key(path: &Path) -> Option<String> {
+    path.canonicalize()
+        .ok()
+        .map(|path| path.to_string_lossy().to_lowercase())
+}
+
+/// Lists the IDs of all processes on the system.
+#[cfg(target_os = "windows")]
+fn all_process_ids() -> Vec<u32> {
+    let mut pids = vec![0u32; 1024];
+    loop {
+        let capacity = (pids.len() * std::mem::size_of::<u32>()) as u32;
+        let mut needed = 0u32;
+        if unsafe { EnumProcesses(pids.as_mut_ptr(), capacity, &mut needed) }.is_err() {
+            return Vec::new();
+        }
+
+        // A full buffer may mean there are more processes than fit
+        if needed < capacity {
+            pids.truncate(needed as usize / std::mem::size_of::<u32>());
+            return pids;
+        }
+        pids.resize(pids.len() * 2, 0);
+    }
+}
+
+/// Lists the IDs of processes that own a taskbar-style window, in window Z order.
+#[cfg(target_os = "windows")]
+fn windowed_process_ids() -> Vec<u32> {
+    unsafe extern "system" fn collect(hwnd: HWND, pids: LPARAM) -> BOOL {
+        let pids = &mut *(pids.0 as *mut Vec<u32>);
+        let is_tool_window = GetWindowLongW(hwnd, GWL_EXSTYLE) as u32 & WS_EX_TOOLWINDOW.0 != 0;
+        if IsWindowVisible(hwnd).as_bool() && GetWindow(hwnd, GW_OWNER).0 == 0 && !is_tool_window {
+            let mut pid = 0u32;
+            GetWindowThreadProcessId(hwnd, Some(&mut pid));
+            if pid != 0 && !pids.contains(&pid) {
+                pids.push(pid);
+            }
+        }
+        TRUE
+    }
+
+    let mut pids: Vec<u32> = Vec::new();
+    unsafe {
+        let _ = EnumWindows(Some(collect), LPARAM(&mut pids as *mut Vec<u32> as isize));
+    }
+    pids
+}
+
+/// Gets the application that owns the foreground window on Windows.
+///
+/// This runs on hot paths, so the executable is described from its version resource
+/// alone instead of being matched against the installed scan.
+#[cfg(target_os = "windows")]
+pub fn get_frontmost_app(icon_size: u16) -> Result<AppInfo> {
+    let hwnd = unsafe { GetForegroundWindow() };
+    if hwnd.0 == 0 || is_desktop_window(hwnd) {
+        return Err(AppInfoError::NoForegroundApp);
+    }
+
+    let executable = window_process_id(hwnd)
+        .and_then(process_image_path)
+        .ok_or(AppInfoError::NoForegroundApp)?;
+    Ok(app_for_executable(&executable, &[], icon_size))
+}
+
+/// Gets the application a process belongs to on Windows.
+#[cfg(target_os = "windows")]
+pub fn get_app_for_pid(pid: u32, icon_size: u16) -> Result<AppInfo> {
+    let executable = process_image_path(pid).ok_or(AppInfoError::ProcessNotFound { pid })?;
+    let installed = get_installed_apps(0).unwrap_or_default();
+    Ok(app_for_executable(&executable, &installed, icon_size))
+}
+
+/// Gets the ID of the process that owns the foreground window.
+#[cfg(target_os = "windows")]
+fn foreground_process_id() -> Option<u32> {
+    let hwnd = unsafe { GetForegroundWindow() };
+    if hwnd.0 == 0 {
+        return None;
+    }
+    window_process_id(hwnd)
+}
+
+/// Gets the ID of the process that created a window.
+#[cfg(target_os = "windows")]
+fn window_process_id(hwnd: HWND) -> Option<u32> {
+    let mut pid = 0u32;
+    unsafe { GetWindowThreadProcessId(hwnd, Some(&mut pid)) };
+    (pid != 0).then_some(pid)
+}
+
+/// Checks whether a window is the desktop, which belongs to Explorer but is not an app.
+#[cfg(target_os = "windows")]
+fn is_desktop_window(hwnd: HWND) -> bool {
+    if hwnd == unsafe { GetShellWindow() } {
+        return true;
+    }
+
+    let mut class_name = [0u16; 64];
+    let length = unsafe { GetClassNameW(hwnd, &mut class_name) }.max(0) as usize;
+    matches!(
+        String::from_utf16_lossy(&class_name[..length]).as_str(),
+        "Progman" | "WorkerW"
+    )
+}
+
+/// Gets the full path of a process's executable, or `None` when the process has exited or
+/// cannot be opened with limited query rights.
+#[cfg(target_os = "windows")]
+fn process_image_path(pid: u32) -> Option<PathBuf> {
+    let process = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) }.ok()?;
+    defer!(unsafe {
+        let _ = CloseHandle(process);
+    });
+
+    // Long paths can exceed MAX_PATH, so use the largest size the API accepts
+    let mut buffer = vec![0u16; 32768];
+    let mut size = buffer.len() as u32;
+    unsafe {
+        QueryFullProcessImageNameW(
+            process,
+            PROCESS_NAME_WIN32,
+            PWSTR(buffer.as_mut_ptr()),
+            &mut size,
+        )
+    }
+    .ok()?;
+    Some(PathBuf::from(String::from_utf16_lossy(
+        &buffer[..size as usize],
+    )))
+}
+
+/// Gets the icon of a running process's executable on Windows.
+#[cfg(target_os = "windows")]
+pub fn get_icon_for_pid(pid: u32, size: u16) -> Result<Icon> {
+    let executable = process_image_path(pid).ok_or(AppInfoError::ProcessNotFound { pid })?;
+    get_file_icon(&executable, size)
+}
+
+/// Gets the applications configured to start at login on Windows: the `Run` and `RunOnce`
+/// keys of both hives and the per-user and all-users Startup folders.
+#[cfg(target_os = "windows")]
+pub fn get_startup_apps(icon_size: u16) -> Result<Vec<StartupApp>> {
+    let installed = get_installed_apps(0).unwrap_or_default();
+    let mut startup_apps = Vec::new();
+
+    for location in run_key_locations() {
+        let Some(hkey) = open_registry_key(location.root, location.key_path) else {
+            continue;
+        };
+        defer!(unsafe {
+            let _ = RegCloseKey(hkey);
+        });
+
+        for name in registry_value_names(hkey) {
+            let Ok(command) = read_registry_string(hkey, &name) else {
+                continue;
+            };
+            let Some(executable) = command_line_executable(&command) else {
+                continue;
+            };
+
+            let enabled = location
+                .approved_key
+                .is_none_or(|approved_key| startup_approved(location.root, approved_key, &name));
+            startup_apps.push(StartupApp {
+                app: app_for_executable(&executable, &installed, icon_size),
+                mechanism: location.mechanism,
+                name,
+                command: Some(command),
+                all_users: location.root == HKEY_LOCAL_MACHINE,
+                enabled,
+            });
+        }
+    }
+
+    for (root, variable, all_users) in [
+        (HKEY_CURRENT_USER, "APPDATA", false),
+        (HKEY_LOCAL_MACHINE, "ProgramData", true),
+    ] {
+        let Some(base) = std::env::var_os(variable) else {
+            continue;
+        };
+        let folder = PathBuf::from(base).join("Microsoft\\Windows\\Start Menu\\Programs\\Startup");
+        let Ok(entries) = std::fs::read_dir(&folder) else {
+            continue;
+        };
+
+        let mut paths: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file())
+            .filter(|path| {
+                !path
+                    .file_name()
+                    .is_some_and(|name| name.eq_ignore_ascii_case("desktop.ini"))
+            })
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let file_name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let is_shortcut = path
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("lnk"));
+            let (target, command) = match is_shortcut.then(|| resolve_shortcut(&path)).flatten() {
+                Some((target, arguments)) => {
+                    let mut command = quote_windows_argument(&target.to_string_lossy());
+                    if !arguments.is_empty() {
+                        command.push(' ');
+                        command.push_str(&arguments);
+                    }
+                    (target, Some(command))
+                }
+                None => (path.clone(), None),
+            };
+
+            startup_apps.push(StartupApp {
+                app: app_for_executable(&target, &installed, icon_size),
+                mechanism: StartupMechanism::StartupFolder,
+                name: path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| file_name.clone()),
+                command,
+                all_users,
+                enabled: startup_approved(root, STARTUP_APPROVED_FOLDER_KEY, &file_name),
+            });
+        }
+    }
+
+    Ok(startup_apps)
+}
+
+/// Where Task Manager records whether Startup folder entries are enabled.
+#[cfg(target_os = "windows")]
+const STARTUP_APPROVED_FOLDER_KEY: &str =
+    "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Explorer\\StartupApproved\\StartupFolder";
+
+/// A registry key whose values are commands run at login.
+#[cfg(target_os = "windows")]
+struct RunKeyLocation {
+    root: HKEY,
+    key_path: &'static str,
+    mechanism: StartupMechanism,
+    /// The `StartupApproved` key holding the enabled state of the entries, if any
+    approved_key: Option<&'static str>,
+}
+
+/// The `Run` and `RunOnce` keys, in scan order.
+#[cfg(target_os = "windows")]
+fn run_key_locations() -> Vec<RunKeyLocation> {
+    const RUN: &str = "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Run";
+    const RUN_ONCE: &str = "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\RunOnce";
+    const APPROVED_RUN: &str =
+        "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Explorer\\StartupApproved\\Run";
+
+    let mut locations = Vec::new();
+    for root in [HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE] {
+        locations.push(RunKeyLocation {
+            root,
+            key_path: RUN,
+            mechanism: StartupMechanism::Run,
+            approved_key: Some(APPROVED_RUN),
+        });
+        locations.push(RunKeyLocation {
+            root,
+            key_path: RUN_ONCE,
+            mechanism: StartupMechanism::RunOnce,
+            approved_key: None,
+        });
+    }
+
+    // 32-bit programs register under the WOW6432Node view on 64-bit systems
+    #[cfg(target_pointer_width = "64")]
+    locations.push(RunKeyLocation {
+        root: HKEY_LOCAL_MACHINE,
+        key_path: "SOFTWARE\\WOW6432Node\\Microsoft\\Windows\\CurrentVersion\\Run",
+        mechanism: StartupMechanism::Run,
+        approved_key: Some(
+            "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Explorer\\StartupApproved\\Run32",
+        ),
+    });
+
+    locations
+}
+
+/// Checks the `StartupApproved` state of a startup entry. Entries Task Manager has never
+/// touched have no state and are enabled.
+#[cfg(target_os = "windows")]
+fn startup_approved(root: HKEY, approved_key: &str, name: &str) -> bool {
+    let Some(hkey) = open_registry_key(root, approved_key) else {
+        return true;
+    };
+    defer!(unsafe {
+        let _ = RegCloseKey(hkey);
+    });
+    read_registry_binary(hkey, name).is_none_or(|data| startup_approved_enabled(&data))
+}
+
+/// Resolves a `.lnk` shortcut to its target and arguments.
+#[cfg(target_os = "windows")]
+fn resolve_shortcut(path: &Path) -> Option<(PathBuf, String)> {
+    let _com = initialize_com();
+
+    let link: IShellLinkW =
+        unsafe { CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER) }.ok()?;
+    let persist_file: IPersistFile = link.cast().ok()?;
+    unsafe { persist_file.Load(&HSTRING::from(path), STGM_READ) }.ok()?;
+
+    let mut target = vec![0u16; 32768];
+    unsafe { link.GetPath(&mut target, std::ptr::null_mut(), 0) }.ok()?;
+    let target = String::from_utf16_lossy(&target[..wide_len(&target)]);
+    if target.is_empty() {
+        return None;
+    }
+
+    let mut arguments = vec![0u16; 32768];
+    let arguments = match unsafe { link.GetArguments(&mut arguments) } {
+        Ok(()) => String::from_utf16_lossy(&arguments[..wide_len(&arguments)]),
+        Err(_) => String::new(),
+    };
+
+    Some((PathBuf::from(target), arguments))
+}
+
+/// Length of a NUL-terminated UTF-16 buffer.
+#[cfg(target_os = "windows")]
+fn wide_len(buffer: &[u16]) -> usize {
+    buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len())
+}
+
+/// Where Explorer records how often and when programs were started.
+#[cfg(target_os = "windows")]
+const USER_ASSIST_KEY: &str = "Software\\Microsoft\\Windows\\CurrentVersion\\Explorer\\UserAssist";
+
+/// Gets the current user's UserAssist usage data on Windows.
+///
+/// UserAssist keeps one `Count` key per kind of item (executables, shortcuts) whose value
+/// names are ROT13-encoded paths. Shortcuts are resolved to their targets, and entries
+/// for the same target are merged.
+#[cfg(target_os = "windows")]
+pub fn get_app_usage() -> Result<Vec<AppUsage>> {
+    let mut usage: Vec<AppUsage> = Vec::new();
+
+    let Some(user_assist) = open_registry_key(HKEY_CURRENT_USER, USER_ASSIST_KEY) else {
+        return Ok(usage);
+    };
+    let categories = registry_subkey_names(user_assist);
+    unsafe {
+        let _ = RegCloseKey(user_assist);
+    }
+
+    for category in categories {
+        let count_key = format!("{}\\{}\\Count", USER_ASSIST_KEY, category);
+        let Some(hkey) = open_registry_key(HKEY_CURRENT_USER, &count_key) else {
+            continue;
+        };
+        defer!(unsafe {
+            let _ = RegCloseKey(hkey);
+        });
+
+        for value_name in registry_value_names(hkey) {
+            let name = rot13(&value_name);
+            // Session bookkeeping rather than programs
+            if name.starts_with("UEME_") {
+                continue;
+            }
+            let Some(entry) = read_registry_binary(hkey, &value_name)
+                .and_then(|data| parse_user_assist_entry(&data))
+            else {
+                continue;
+            };
+
+            let target = user_assist_target(&name);
+            match usage
+                .iter_mut()
+                .find(|existing| existing.identifier_or_path.eq_ignore_ascii_case(&target))
+            {
+                Some(existing) => {
+                    existing.launch_count = existing.launch_count.max(Some(entry.run_count));
+                    existing.last_used = existing.last_used.max(entry.last_run);
+                }
+                None => usage.push(AppUsage {
+                    identifier_or_path: target,
+                    last_used: entry.last_run,
+                    launch_count: Some(entry.run_count),
+                }),
+            }
+        }
+    }
+
+    Ok(usage)
+}
+
+/// Gets the UserAssist usage data of each app's executable on Windows.
+#[cfg(target_os = "windows")]
+pub fn get_usage_for_apps(apps: &[AppInfo]) -> Result<Vec<Option<AppUsage>>> {
+    let usage = get_app_usage()?;
+    Ok(apps
+        .iter()
+        .map(|app| {
+            let executable = app.executable_path.as_deref().unwrap_or(&app.path);
+            usage
+                .iter()
+                .find(|entry| paths_equal(Path::new(&entry.identifier_or_path), executable))
+                .cloned()
+        })
+        .collect())
+}
+
+/// Turns a decoded UserAssist name into a path, expanding a leading known folder GUID and
+/// following shortcuts. Names that are not paths, such as AppUserModelIDs, are kept.
+#[cfg(target_os = "windows")]
+fn user_assist_target(name: &str) -> String {
+    let path = match split_known_folder(name) {
+        Some((folder_id, rest)) => match known_folder_path(folder_id) {
+            Some(folder) => folder.join(rest.trim_start_matches('\\')),
+            None => return name.to_string(),
+        },
+        None => PathBuf::from(name),
+    };
+
+    let is_shortcut = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("lnk"));
+    if is_shortcut {
+        if let Some((target, _)) = resolve_shortcut(&path) {
+            return target.to_string_lossy().into_owned();
+        }
+    }
+    path.to_string_lossy().into_owned()
+}
+
+/// Looks up the path of a known folder such as Program Files.
+#[cfg(target_os = "windows")]
+fn known_folder_path(folder_id: u128) -> Option<PathBuf> {
+    let folder_id = windows::core::GUID::from_u128(folder_id);
+    let path =
+        unsafe { SHGetKnownFolderPath(&folder_id, KF_FLAG_DEFAULT, HANDLE::default()) }.ok()?;
+    defer!(unsafe {
+        CoTaskMemFree(Some(path.0 as *const _));
+    });
+    unsafe { path.to_string() }.ok().map(PathBuf::from)
+}
+
+/// Describes an executable, preferring the installed application it belongs to and
+/// otherwise falling back to its version resource.
+#[cfg(target_os = "windows")]
+fn app_for_executable(executable: &Path, installed: &[AppInfo], icon_size: u16) -> AppInfo {
+    let mut app = installed
+        .iter()
+        .find(|app| {
+            paths_equal(&app.path, executable)
+                || app
+                    .executable_path
+                    .as_deref()
+                    .is_some_and(|path| paths_equal(path, executable))
+        })
+        .cloned()
+        .unwrap_or_else(|| {
+            let version_info = read_file_version_info(executable).unwrap_or_default();
+            let name = version_info
+                .file_description
+                .or(version_info.product_name)
+                .unwrap_or_else(|| {
+                    executable
+                        .file_stem()
+                        .and_then(|s| s.to_str())
+                        .unwrap_or("Unknown")
+                        .to_string()
+                });
+            let kind = windows_kind(&WindowsSignals {
+                name: &name,
+                path: &executable.to_string_lossy(),
+                subsystem: read_pe_subsystem(executable),
+                ..Default::default()
+            });
+            AppInfo {
+                name,
+                version: version_info.product_version,
+                path: executable.to_path_buf(),
+                executable_path: Some(executable.to_path_buf()),
+                icon: None,
+                compressed_icon: None,
+                icon_source: Some(IconSource::File(executable.to_path_buf())),
+                identifier: None,
+                publisher: version_info.company_name,
+                install_date: None,
+                parsed_install_date: None,
+                install_date_ambiguous: false,
+                install_size: None,
+                category: None,
+                kind,
+                package_manager: None,
+                install_scope: None,
+                registry_view: None,
+                owner: None,
+                can_uninstall: None,
+                can_modify: None,
+                can_repair: None,
+                declared_url_schemes: Vec::new(),
+                declared_file_types: Vec::new(),
+                signature: None,
+                name_inferred: false,
+            }
+        });
+
+    if icon_size > 0 {
+        app.icon = get_file_icon(executable, icon_size).ok();
+    }
+    app
+}
+
+/// Compares two paths the way the Windows file system does, ignoring case and separators.
+#[cfg(target_os = "windows")]
+fn paths_equal(a: &Path, b: &Path) -> bool {
+    let normalize = |path: &Path| path.to_string_lossy().replace('/', "\\").to_lowercase();
+    normalize(a) == normalize(b)
+}
+
+/// String fields from an executable's `VERSIONINFO` resource.
+#[cfg(target_os = "windows")]
+#[derive(Debug, Default)]
+struct FileVersionInfo {
+    product_name: Option<String>,
+    file_description: Option<String>,
+    company_name: Option<String>,
+    product_version: Option<String>,
+}
+
+/// Reads the version resource of an executable, using its first listed translation.
+#[cfg(target_os = "windows")]
+fn read_file_version_info(path: &Path) -> Option<FileVersionInfo> {
+    let path_string = HSTRING::from(path.to_string_lossy().as_ref());
+    let size = unsafe { GetFileVersionInfoSizeW(&path_string, None) };
+    if size == 0 {
+        return None;
+    }
+
+    let mut data = vec![0u8; size as usize];
+    unsafe { GetFileVersionInfoW(&path_string, 0, size, data.as_mut_ptr() as *mut _) }.ok()?;
+
+    // Looks up a value, returning a pointer into `data` and its length
+    let query = |sub_block: &str| -> Option<(*const u8, usize)> {
+        let sub_block = HSTRING::from(sub_block);
+        let mut value = std::ptr::null_mut();
+        let mut length = 0u32;
+        let found = unsafe {
+            VerQueryValueW(
+                data.as_ptr() as *const _,
+                &sub_block,
+                &mut value,
+                &mut length,
+            )
+        };
+        (found.as_bool() && !value.is_null() && length > 0)
+            .then_some((value as *const u8, length as usize))
+    };
+
+    // Fall back to US English in Unicode when no translation table is present
+    let (language, code_page) = query("\\VarFileInfo\\Translation")
+        .filter(|&(_, length)| length >= 4)
+        .map(|(value, _)| unsafe {
+            let translation = std::slice::from_raw_parts(value, 4);
+            (
+                u16::from_le_bytes([translation[0], translation[1]]),
+                u16::from_le_bytes([translation[2], translation[3]]),
+            )
+        })
+        .unwrap_or((0x0409, 0x04b0));
+
+    // String values report their length in characters, including the terminator
+    let field = |name: &str| -> Option<String> {
+        let (value, length) = query(&format!(
+            "\\StringFileInfo\\{:04x}{:04x}\\{}",
+            language, code_page, name
+        ))?;
+        let wide_chars = unsafe { std::slice::from_raw_parts(value as *const u16, length) };
+        let end = wide_chars
+            .iter()
+            .position(|&x| x == 0)
+            .unwrap_or(wide_chars.len());
+        let text = String::from_utf16_lossy(&wide_chars[..end])
+            .trim()
+            .to_string();
+        (!text.is_empty()).then_some(text)
+    };
+
+    Some(FileVersionInfo {
+        product_name: field("ProductName"),
+        file_description: field("FileDescription"),
+        company_name: field("CompanyName"),
+        product_version: field("ProductVersion"),
+    })
+}
+
+/// Reads the Control Panel applets registered for the machine and the current user, as
+/// (scope, value name, value data). Keys that are missing or locked down are skipped.
+#[cfg(target_os = "windows")]
+pub(crate) fn cpl_registrations() -> Vec<(InstallScope, String, String)> {
+    let keys = [
+        (
+            HKEY_LOCAL_MACHINE,
+            "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Control Panel\\Cpls",
+        ),
+        (
+            HKEY_LOCAL_MACHINE,
+            "SOFTWARE\\WOW6432Node\\Microsoft\\Windows\\CurrentVersion\\Control Panel\\Cpls",
+        ),
+        (
+            HKEY_CURRENT_USER,
+            "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Control Panel\\Cpls",
+        ),
+    ];
+
+    let mut registrations = Vec::new();
+    for (root, key_path) in keys {
+        let Some(hkey) = open_registry_key(root, key_path) else {
+            debug_event!(key_path, "cannot open Cpls key");
+            continue;
+        };
+        for name in registry_value_names(hkey) {
+            if let Ok(data) = read_registry_string(hkey, &name) {
+                registrations.push((hive_scope(root), name, data));
+            }
+        }
+        unsafe {
+            let _ = RegCloseKey(hkey);
+        }
+    }
+    registrations
+}
+
+/// Reads whether each optional feature is selected, from the records Component Based
+/// Servicing keeps for "Turn Windows features on or off".
+#[cfg(target_os = "windows")]
+pub(crate) fn optional_feature_selections() -> Vec<(String, Option<bool>)> {
+    const OPTIONAL_FEATURES: &str = "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Component Based Servicing\\Notifications\\OptionalFeatures";
+
+    let Some(hkey) = open_registry_key(HKEY_LOCAL_MACHINE, OPTIONAL_FEATURES) else {
+        debug_event!("cannot open OptionalFeatures key");
+        return Vec::new();
+    };
+    let names = registry_subkey_names(hkey);
+    unsafe {
+        let _ = RegCloseKey(hkey);
+    }
+
+    names
+        .into_iter()
+        .map(|name| {
+            let key_path = format!("{}\\{}", OPTIONAL_FEATURES, name);
+            let selected = open_registry_key(HKEY_LOCAL_MACHINE, &key_path).and_then(|hkey| {
+                let selected = read_registry_flag(hkey, "Selection");
+                unsafe {
+                    let _ = RegCloseKey(hkey);
+                }
+                selected
+            });
+            (name, selected)
+        })
+        .collect()
+}
+
+/// Fills in the name, publisher and version of an app from the version resource of the
+/// file at its path, and its shell icon as `ctx` asks.
+#[cfg(target_os = "windows")]
+pub(crate) fn apply_file_details(app: &mut AppInfo, ctx: &ScanContext) {
+    if let Some(version_info) = read_file_version_info(&app.path) {
+        if let Some(name) = version_info.file_description.or(version_info.product_name) {
+            app.name = name;
+        }
+        app.publisher = version_info.company_name;
+        app.version = version_info.product_version;
+    }
+    app.icon_source = Some(IconSource::File(app.path.clone()));
+    if ctx.extracts_icons() {
+        let icon = get_file_icon(&app.path, ctx.icon_size()).ok();
+        ctx.store_icon(app, icon);
+    }
+}
+
+/// Finds the Steam installation from the paths its installer records.
+#[cfg(target_os = "windows")]
+pub(crate) fn steam_install_path() -> Option<PathBuf> {
+    read_key_string(HKEY_CURRENT_USER, "SOFTWARE\\Valve\\Steam", "SteamPath")
+        .or_else(|| {
+            read_key_string(
+                HKEY_LOCAL_MACHINE,
+                "SOFTWARE\\WOW6432Node\\Valve\\Steam",
+                "InstallPath",
+            )
+        })
+        .or_else(|| read_key_string(HKEY_LOCAL_MACHINE, "SOFTWARE\\Valve\\Steam", "InstallPath"))
+        // SteamPath is written with forward slashes
+        .map(|path| PathBuf::from(path.replace('/', "\\")))
+}
+
+/// Opens a registry key for reading. The caller must close it with `RegCloseKey`.
+#[cfg(target_os = "windows")]
+fn open_registry_key(root: HKEY, key_path: &str) -> Option<HKEY> {
+    let mut hkey: HKEY = HKEY::default();
+    let key_name = HSTRING::from(key_path);
+    let result = unsafe { RegOpenKeyExW(root, &key_name, 0, KEY_READ, &mut hkey) };
+    result.is_ok().then_some(hkey)
+}
+
+/// Reads a single string value, opening and closing the key around the read. An empty
+/// `value_name` reads the key's default value.
+#[cfg(target_os = "windows")]
+fn read_key_string(root: HKEY, key_path: &str, value_name: &str) -> Option<String> {
+    let hkey = open_registry_key(root, key_path)?;
+    defer!(unsafe {
+        let _ = RegCloseKey(hkey);
+    });
+    read_registry_string(hkey, value_name).ok()
+}
+
+/// The day and month order of the current user's short date format, from
+/// `HKEY_CURRENT_USER\Control Panel\International`. Read once per process.
+#[cfg(target_os = "windows")]
+pub(crate) fn user_date_order() -> Option<crate::DateOrder> {
+    static ORDER: std::sync::OnceLock<Option<crate::DateOrder>> = std::sync::OnceLock::new();
+    *ORDER.get_or_init(|| {
+        read_key_string(
+            HKEY_CURRENT_USER,
+            r"Control Panel\International",
+            "sShortDate",
+        )
+        .and_then(|pattern| short_date_order(&pattern))
+    })
+}
+
+/// Whether a short date pattern such as `dd.MM.yyyy` puts the day or the month first.
+/// Year-first patterns put the month first. Quoted text is left out.
+#[cfg(any(target_os = "windows", test))]
+fn short_date_order(pattern: &str) -> Option<crate::DateOrder> {
+    let mut quoted = false;
+    for c in pattern.chars() {
+        match c {
+            '\'' => quoted = !quoted,
+            'd' if !quoted => return Some(crate::DateOrder::DayFirst),
+            'M' if !quoted => return Some(crate::DateOrder::MonthFirst),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Checks whether a value exists on a key, whatever its type or contents.
+#[cfg(target_os = "windows")]
+fn has_registry_value(hkey: HKEY, value_name: &str) -> bool {
+    let value_name = HSTRING::from(value_name);
+    unsafe { RegQueryValueExW(hkey, &value_name, None, None, None, None) }.is_ok()
+}
+
+/// Reads a binary value from the registry.
+#[cfg(target_os = "windows")]
+fn read_registry_binary(hkey: HKEY, value_name: &str) -> Option<Vec<u8>> {
+    read_registry_typed(hkey, value_name).map(|(_, data)| data)
+}
+
+/// Reads a registry value's raw bytes along with its `REG_*` type.
+#[cfg(target_os = "windows")]
+fn read_registry_typed(hkey: HKEY, value_name: &str) -> Option<(u32, Vec<u8>)> {
+    use windows::Win32::System::Registry::REG_VALUE_TYPE;
+
+    let value_name = HSTRING::from(value_name);
+    let mut data_type = REG_VALUE_TYPE(0);
+    let mut data_size = 0u32;
+    unsafe {
+        RegQueryValueExW(
+            hkey,
+            &value_name,
+            None,
+            Some(&mut data_type),
+            None,
+            Some(&mut data_size),
+        )
+    }
+    .ok()
+    .ok()?;
+
+    // The value can grow between the two calls, in which case the second one reports the
+    // new size; retry a few times rather than spinning on a value that keeps changing
+    for _ in 0..4 {
+        let mut buffer = vec![0u8; data_size as usize];
+        let result = unsafe {
+            RegQueryValueExW(
+                hkey,
+                &value_name,
+                None,
+                Some(&mut data_type),
+                Some(buffer.as_mut_ptr()),
+                Some(&mut data_size),
+            )
+        };
+        if result == ERROR_MORE_DATA {
+            continue;
+        }
+        result.ok().ok()?;
+        buffer.truncate(data_size as usize);
+        return Some((data_type.0, buffer));
+    }
+    None
+}
+
+/// Reads a yes/no registry value such as `NoRemove`; `None` when it is absent.
+#[cfg(target_os = "windows")]
+fn read_registry_flag(hkey: HKEY, value_name: &str) -> Option<bool> {
+    let (data_type, data) = read_registry_typed(hkey, value_name)?;
+    parse_registry_flag(data_type, &data)
+}
+
+/// Lists the names of all values on a registry key.
+#[cfg(target_os = "windows")]
+fn registry_value_names(hkey: HKEY) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut index = 0u32;
+    loop {
+        // Value names are limited to 16383 characters
+        let mut value_name = vec![0u16; 16384];
+        let mut value_name_len = value_name.len() as u32;
+
+        let result = unsafe {
+            RegEnumValueW(
+                hkey,
+                index,
+                PWSTR(value_name.as_mut_ptr()),
+                &mut value_name_len,
+                None,
+                None,
+                None,
+                None,
+            )
+        };
+
+        if result.is_err() {
+            break;
+        }
+
+        names.push(String::from_utf16_lossy(
+            &value_name[..value_name_len as usize],
+        ));
+        index += 1;
+    }
+    names
+}
+
+/// Decodes ROT13, which UserAssist applies to the letters of its value names.
+#[cfg(any(target_os = "windows", test))]
+fn rot13(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c {
+            'a'..='z' => (((c as u8 - b'a') + 13) % 26 + b'a') as char,
+            'A'..='Z' => (((c as u8 - b'A') + 13) % 26 + b'A') as char,
+            _ => c,
+        })
+        .collect()
+}
+
+/// Splits a leading `{GUID}` known folder reference off a UserAssist path.
+#[cfg(any(target_os = "windows", test))]
+fn split_known_folder(name: &str) -> Option<(u128, &str)> {
+    let rest = name.strip_prefix('{')?;
+    let end = rest.find('}')?;
+    let guid = &rest[..end];
+    if guid.len() != 36 || guid.matches('-').count() != 4 {
+        return None;
+    }
+    let folder_id = u128::from_str_radix(&guid.replace('-', ""), 16).ok()?;
+    Some((folder_id, &rest[end + 1..]))
+}
+
+/// A decoded UserAssist value.
+#[cfg(any(target_os = "windows", test))]
+#[derive(Debug, PartialEq, Eq)]
+struct UserAssistEntry {
+    run_count: u32,
+    last_run: Option<std::time::SystemTime>,
+}
+
+/// Decodes a UserAssist value. Windows 7 and later write 72 bytes with the run count at
+/// offset 4 and the last run `FILETIME` at offset 60; XP and Vista wrote 16 bytes with
+/// the `FILETIME` at offset 8 and a run count that starts at 5.
+#[cfg(any(target_os = "windows", test))]
+fn parse_user_assist_entry(data: &[u8]) -> Option<UserAssistEntry> {
+    let u32_at = |offset: usize| {
+        data.get(offset..offset + 4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+    };
+    let u64_at = |offset: usize| {
+        data.get(offset..offset + 8)
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+    };
+
+    if data.len() >= 68 {
+        Some(UserAssistEntry {
+            run_count: u32_at(4)?,
+            last_run: u64_at(60).and_then(filetime_to_system_time),
+        })
+    } else if data.len() == 16 {
+        Some(UserAssistEntry {
+            run_count: u32_at(4)?.saturating_sub(5),
+            last_run: u64_at(8).and_then(filetime_to_system_time),
+        })
+    } else {
+        None
+    }
+}
+
+/// Converts a `FILETIME` (100 ns intervals since 1601) to a `SystemTime`. Zero, which
+/// means "never", and times before 1970 give `None`.
+#[cfg(any(target_os = "windows", test))]
+fn filetime_to_system_time(filetime: u64) -> Option<std::time::SystemTime> {
+    const UNIX_EPOCH_AS_FILETIME: u64 = 116_444_736_000_000_000;
+    let since_unix_epoch = filetime.checked_sub(UNIX_EPOCH_AS_FILETIME)?;
+    std::time::UNIX_EPOCH.checked_add(std::time::Duration::from_nanos(
+        since_unix_epoch.checked_mul(100)?,
+    ))
+}
+
+/// Decodes a `StartupApproved` value. Task Manager writes 12 bytes whose first byte is
+/// even while the entry is enabled and odd once it has been disabled.
+#[cfg(any(target_os = "windows", test))]
+fn startup_approved_enabled(data: &[u8]) -> bool {
+    data.first().is_none_or(|flags| flags & 1 == 0)
+}
+
+/// Quotes one argument for a Windows command line so `CommandLineToArgvW` reads it back
+/// unchanged: backslashes are only doubled where they precede a quote.
+#[cfg(any(target_os = "windows", test))]
+fn quote_windows_argument(argument: &str) -> String {
+    let needs_quotes = argument.is_empty()
+        || argument
+            .chars()
+            .any(|c| matches!(c, ' ' | '\t' | '\n' | '\x0b' | '"'));
+    if !needs_quotes {
+        return argument.to_string();
+    }
+
+    let mut quoted = String::with_capacity(argument.len() + 2);
+    quoted.push('"');
+    let mut backslashes = 0;
+    for c in argument.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                quoted.extend(std::iter::repeat_n('\\', backslashes * 2 + 1));
+                quoted.push('"');
+                backslashes = 0;
+            }
+            _ => {
+                quoted.extend(std::iter::repeat_n('\\', backslashes));
+                quoted.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    // Trailing backslashes would otherwise escape the closing quote
+    quoted.extend(std::iter::repeat_n('\\', backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+/// Extracts the executable from a shell command line such as
+/// `"C:\Program Files\App\app.exe" --url "%1"`, expanding `%VAR%` references.
+#[cfg(any(target_os = "windows", test))]
+fn command_line_executable(command: &str) -> Option<std::path::PathBuf> {
+    let (executable, _) = split_command_line(command);
+    let executable = expand_environment_strings(executable.trim(), |name| std::env::var(name).ok());
+    (!executable.is_empty()).then(|| std::path::PathBuf::from(executable))
+}
+
+/// Splits a shell command line into its program, as written and without quotes, and the
+/// arguments after it.
+#[cfg(any(target_os = "windows", test))]
+fn split_command_line(command: &str) -> (&str, &str) {
+    let command = command.trim_start();
+    if let Some(rest) = command.strip_prefix('"') {
+        let end = rest.find('"').unwrap_or(rest.len());
+        return (&rest[..end], rest.get(end + 1..).unwrap_or(""));
+    }
+
+    // Unquoted paths may still contain spaces, so cut after the first ".exe" if any. A
+    // quote starts an argument, so one after it belongs to the arguments
+    let unquoted = &command[..command.find('"').unwrap_or(command.len())];
+    let end = match unquoted.to_ascii_lowercase().find(".exe") {
+        Some(end) => end + 4,
+        None => command.find(char::is_whitespace).unwrap_or(command.len()),
+    };
+    command.split_at(end)
+}
+
+/// Expands `%NAME%` references using `lookup`, leaving unknown references untouched.
+#[cfg(any(target_os = "windows", test))]
+pub(crate) fn expand_environment_strings(
+    value: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> String {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('%') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('%') else {
+            rest = &rest[start..];
+            break;
+        };
+
+        let name = &after[..end];
+        match lookup(name).filter(|_| !name.is_empty()) {
+            Some(value) => expanded.push_str(&value),
+            None => {
+                expanded.push('%');
+                expanded.push_str(name);
+                expanded.push('%');
+            }
+        }
+        rest = &after[end + 1..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// Gets the icon for a given file path on Windows at an arbitrary size.
+///
+/// The shell only renders square icons, so a square image is extracted at the size the
+/// fit mode needs and then resampled into the requested rectangle.
+#[cfg(all(target_os = "windows", feature = "icons"))]
+pub fn get_file_icon_sized(path: &Path, width: u16, height: u16, fit: FitMode) -> Result<Icon> {
+    let side = match fit {
+        FitMode::Contain => width.min(height),
+        FitMode::Stretch | FitMode::Cover => width.max(height),
+    };
+
+    let icon = get_file_icon(path, side)?;
+    if width == height {
+        return Ok(icon);
+    }
+    Ok(icon.resized(width as u32, height as u32, fit))
+}
+
+/// Gets the icon for a given file path on Windows.
+#[cfg(all(target_os = "windows", feature = "icons"))]
+pub fn get_file_icon(path: &Path, size: u16) -> Result<Icon> {
+    enter_span!(DEBUG, "get_file_icon", path = %path.display(), size);
+    let _token = initialize_com();
+    let imaging_factory = create_imaging_factory()?;
+    shell_icon(
+        &imaging_factory,
+        path,
+        size,
+        SIIGBF_ICONONLY | SIIGBF_SCALEUP,
+    )
+}
+
+/// Gets the icons for several files on Windows, sharing one COM apartment and one WIC
+/// factory across the batch.
+#[cfg(all(target_os = "windows", feature = "icons"))]
+pub fn get_file_icons(paths: &[&Path], size: u16) -> Vec<Result<Icon>> {
+    enter_span!(DEBUG, "get_file_icons", count = paths.len(), size);
+    let _token = initialize_com();
+    let imaging_factory = match create_imaging_factory() {
+        Ok(factory) => factory,
+        Err(_) => {
+            return paths
+                .iter()
+                .map(|_| {
+                    Err(AppInfoError::FileIconError(
+                        crate::error::FileIconError::Failed,
+                    ))
+                })
+                .collect()
+        }
+    };
+
+    paths
+        .iter()
+        .map(|path| {
+            shell_icon(
+                &imaging_factory,
+                path,
+                size,
+                SIIGBF_ICONONLY | SIIGBF_SCALEUP,
+            )
+        })
+        .collect()
+}
+
+/// Gets the largest icon genuinely available for a file on Windows, without upscaling.
+///
+/// `.ico` files and executables are searched for their largest embedded frame; for other
+/// files the shell's jumbo icon is used at whatever size it is natively rendered.
+#[cfg(all(target_os = "windows", feature = "icons"))]
+pub fn get_file_icon_native(path: &Path) -> Result<Icon> {
+    enter_span!(DEBUG, "get_file_icon_native", path = %path.display());
+    let _token = initialize_com();
+    let imaging_factory = create_imaging_factory()?;
+
+    // Prefer the largest frame embedded in the file itself
+    if let Some(hicon) = load_largest_icon(path) {
+        defer!(unsafe {
+            let _ = DestroyIcon(hicon);
+        });
+        let bitmap = unsafe { imaging_factory.CreateBitmapFromHICON(hicon) }
+            .map_err(|_| AppInfoError::FileIconError(crate::error::FileIconError::Failed))?;
+        return wic_bitmap_to_icon(&bitmap);
+    }
+
+    // Otherwise take the shell's jumbo icon without scaling it up
+    shell_icon(&imaging_factory, path, JUMBO_ICON_SIZE, SIIGBF_ICONONLY)
+}
+
+/// Lists the sizes of the frames in an `.ico` file or in the first icon group of an
+/// executable or DLL, in directory order. Other files get the sizes of the shell's image
+/// list tiers their icon is drawn from, and files without an icon get none.
+#[cfg(all(target_os = "windows", feature = "icons"))]
+pub fn get_available_icon_sizes(path: &Path) -> Result<Vec<(u32, u32)>> {
+    enter_span!(DEBUG, "get_available_icon_sizes", path = %path.display());
+    if is_ico_file(path) {
+        let bytes = std::fs::read(long_path(path)).unwrap_or_default();
+        return Ok(ico_file_sizes(&bytes).unwrap_or_default());
+    }
+    if let Some(sizes) = icon_group_sizes(path) {
+        return Ok(sizes);
+    }
+    Ok(shell_tier_sizes(path))
+}
+
+/// Gets the generic icon the shell shows for files with the given extension.
+///
+/// The extension is passed through `SHGetFileInfoW` with `SHGFI_USEFILEATTRIBUTES`, so no
+/// file needs to exist; unknown extensions get the generic document icon. `extension` is
+/// expected to be normalized (no leading dot).
+#[cfg(all(target_os = "windows", feature = "icons"))]
+pub fn get_icon_for_extension(extension: &str, size: u16) -> Result<Icon> {
+    enter_span!(DEBUG, "get_icon_for_extension", extension, size);
+    let _token = initialize_com();
+    let imaging_factory = create_imaging_factory()?;
+
+    // Look up the system image list index for a hypothetical file of this type
+    let file_name = HSTRING::from(format!("file.{}", extension));
+    let mut file_info = SHFILEINFOW::default();
+    let result = unsafe {
+        SHGetFileInfoW(
+            &file_name,
+            FILE_ATTRIBUTE_NORMAL,
+            Some(&mut file_info),
+            std::mem::size_of::<SHFILEINFOW>() as u32,
+            SHGFI_USEFILEATTRIBUTES | SHGFI_SYSICONINDEX,
+        )
+    };
+    if result == 0 {
+        return Err(AppInfoError::FileIconError(
+            crate::error::FileIconError::Failed,
+        ));
+    }
+
+    // Take the icon from the smallest image list tier that is at least as large as requested
+    let tier = match size {
+        0..=16 => SHIL_SMALL,
+        17..=32 => SHIL_LARGE,
+        33..=48 => SHIL_EXTRALARGE,
+        _ => SHIL_JUMBO,
+    };
+    let image_list: IImageList = unsafe { SHGetImageList(tier as i32) }
+        .map_err(|_| AppInfoError::FileIconError(crate::error::FileIconError::Failed))?;
+    let hicon = unsafe { image_list.GetIcon(file_info.iIcon, ILD_TRANSPARENT.0) }
+        .map_err(|_| AppInfoError::FileIconError(crate::error::FileIconError::Failed))?;
+    defer!(unsafe {
+        let _ = DestroyIcon(hicon);
+    });
+
+    let bitmap = unsafe { imaging_factory.CreateBitmapFromHICON(hicon) }
+        .map_err(|_| AppInfoError::FileIconError(crate::error::FileIconError::Failed))?;
+    let icon = wic_bitmap_to_icon(&bitmap)?;
+    if icon.width == size as u32 && icon.height == size as u32 {
+        return Ok(icon);
+    }
+    Ok(icon.resized(size as u32, size as u32, FitMode::Stretch))
+}
+
+/// Icon support is not compiled in, so this always fails with
+/// [`FileIconError::FeatureDisabled`](crate::error::FileIconError::FeatureDisabled).
+#[cfg(all(target_os = "windows", not(feature = "icons")))]
+pub fn get_file_icon(path: &Path, size: u16) -> Result<Icon> {
+    let _ = (path, size);
+    Err(crate::icon::icons_disabled())
+}
+
+/// Icon support is not compiled in, so this always fails.
+#[cfg(all(target_os = "windows", not(feature = "icons")))]
+pub fn get_file_icon_sized(path: &Path, width: u16, height: u16, fit: FitMode) -> Result<Icon> {
+    let _ = (path, width, height, fit);
+    Err(crate::icon::icons_disabled())
+}
+
+/// Icon support is not compiled in, so every entry is an error.
+#[cfg(all(target_os = "windows", not(feature = "icons")))]
+pub fn get_file_icons(paths: &[&Path], size: u16) -> Vec<Result<Icon>> {
+    let _ = size;
+    paths
+        .iter()
+        .map(|_| Err(crate::icon::icons_disabled()))
+        .collect()
+}
+
+/// Icon support is not compiled in, so this always fails.
+#[cfg(all(target_os = "windows", not(feature = "icons")))]
+pub fn get_file_icon_native(path: &Path) -> Result<Icon> {
+    let _ = path;
+    Err(crate::icon::icons_disabled())
+}
+
+/// Icon support is not compiled in, so this always fails.
+#[cfg(all(target_os = "windows", not(feature = "icons")))]
+pub fn get_available_icon_sizes(path: &Path) -> Result<Vec<(u32, u32)>> {
+    let _ = path;
+    Err(crate::icon::icons_disabled())
+}
+
+/// Icon support is not compiled in, so this always fails.
+#[cfg(all(target_os = "windows", not(feature = "icons")))]
+pub fn get_icon_for_extension(extension: &str, size: u16) -> Result<Icon> {
+    let _ = (extension, size);
+    Err(crate::icon::icons_disabled())
+}
+
+#[cfg(all(target_os = "windows", not(feature = "icons")))]
+pub(crate) fn load_image_icon(path: &Path, size: u16) -> Result<Icon> {
+    let _ = (path, size);
+    Err(crate::icon::icons_disabled())
+}
+
+/// Size of the largest icon tier the shell renders.
+#[cfg(all(target_os = "windows", feature = "icons"))]
+const JUMBO_ICON_SIZE: u16 = 256;
+
+/// `MAKEINTRESOURCE(RT_GROUP_ICON)`, which the windows crate does not define.
+#[cfg(all(target_os = "windows", feature = "icons"))]
+const RT_GROUP_ICON: PCWSTR = PCWSTR(14 as _);
+
+/// Helper struct to ensure CoUninitialize is called.
+#[cfg(target_os = "windows")]
+pub(crate) struct InitializationToken;
+
+#[cfg(target_os = "windows")]
+impl Drop for InitializationToken {
+    fn drop(&mut self) {
+        unsafe {
+            CoUninitialize();
+        }
+    }
+}
+
+/// Initializes COM on the current thread; COM stays initialized until the token is dropped.
+#[cfg(target_os = "windows")]
+pub(crate) fn initialize_com() -> Option<InitializationToken> {
+    if unsafe { CoInitialize(None) }.is_ok() {
+        Some(InitializationToken)
+    } else {
+        None
+    }
+}
+
+/// Loads an image file such as a PNG or JPEG through WIC, fitted into a `size` x `size`
+/// square.
+#[cfg(all(target_os = "windows", feature = "icons"))]
+pub(crate) fn load_image_icon(path: &Path, size: u16) -> Result<Icon> {
+    enter_span!(DEBUG, "load_image_icon", path = %path.display(), size);
+    let failed = |_| AppInfoError::FileIconError(crate::error::FileIconError::Failed);
+    if !path.exists() {
+        return Err(AppInfoError::FileIconError(
+            crate::error::FileIconError::PathDoesNotExist,
+        ));
+    }
+
+    let _token = initialize_com();
+    let imaging_factory = create_imaging_factory()?;
+    let bitmap = unsafe {
+        let decoder = imaging_factory
+            .CreateDecoderFromFilename(
+                &HSTRING::from(path),
+                None,
+                GENERIC_READ,
+                WICDecodeMetadataCacheOnDemand,
+            )
+            .map_err(failed)?;
+        let frame = decoder.GetFrame(0).map_err(failed)?;
+        let converted =
+            WICConvertBitmapSource(&GUID_WICPixelFormat32bppBGRA, &frame).map_err(failed)?;
+        imaging_factory
+            .CreateBitmapFromSource(&converted, WICBitmapCacheOnLoad)
+            .map_err(failed)?
+    };
+
+    let icon = wic_bitmap_to_icon(&bitmap)?;
+    Ok(icon.resized(size as u32, size as u32, FitMode::Contain))
+}
+
+/// Creates a WIC factory.
+#[cfg(all(target_os = "windows", feature = "icons"))]
+fn create_imaging_factory() -> Result<IWICImagingFactory> {
+    unsafe { CoCreateInstance(&CLSID_WICImagingFactory, None, CLSCTX_ALL) }
+        .map_err(|_| AppInfoError::FileIconError(crate::error::FileIconError::Failed))
+}
+
+/// Renders a file's icon through the shell. Without `SIIGBF_SCALEUP` in `flags`, `size`
+/// is an upper bound and the icon keeps its native size.
+#[cfg(all(target_os = "windows", feature = "icons"))]
+fn shell_icon(
+    imaging_factory: &IWICImagingFactory,
+    path: &Path,
+    size: u16,
+    flags: SIIGBF,
+) -> Result<Icon> {
+    // Create a Shell item
+    let path_string = HSTRING::from(shell_parsing_name(path));
+    let image_factory: IShellItemImageFactory =
+        unsafe { SHCreateItemFromParsingName(&path_string, None) }
+            .map_err(|_| AppInfoError::FileIconError(crate::error::FileIconError::Failed))?;
+
+    // Set the icon size
+    let bitmap_size = SIZE {
+        cx: size as i32,
+        cy: size as i32,
+    };
+
+    // Get the icon bitmap
+    let bitmap = unsafe { image_factory.GetImage(bitmap_size, flags) }
+        .map_err(|_| AppInfoError::FileIconError(crate::error::FileIconError::Failed))?;
+
+    // Ensure the bitmap is deleted when the function ends
+    defer!(unsafe {
+        let _ = DeleteObject(bitmap);
+    });
+
+    // Create a WIC bitmap from the HBITMAP. The shell renders icons with premultiplied
+    // alpha, so declare it as such rather than letting WIC treat it as straight alpha.
+    let wic_bitmap = unsafe {
+        imaging_factory.CreateBitmapFromHBITMAP(bitmap, None, WICBitmapUsePremultipliedAlpha)
+    }
+    .map_err(|_| AppInfoError::FileIconError(crate::error::FileIconError::Failed))?;
+
+    wic_bitmap_to_icon(&wic_bitmap)
+}
+
+/// Copies a WIC bitmap into a straight-alpha RGBA icon of the bitmap's own size.
+#[cfg(all(target_os = "windows", feature = "icons"))]
+fn wic_bitmap_to_icon(wic_bitmap: &IWICBitmap) -> Result<Icon> {
+    let (mut width, mut height) = (0u32, 0u32);
+    unsafe { wic_bitmap.GetSize(&mut width, &mut height) }
+        .map_err(|_| AppInfoError::FileIconError(crate::error::FileIconError::Failed))?;
+
+    // Define the source rectangle
+    let source_rectangle = WICRect {
+        X: 0,
+        Y: 0,
+        Width: width as i32,
+        Height: height as i32,
+    };
+
+    // Get and process pixel data
+    let pixel_format = unsafe { wic_bitmap.GetPixelFormat() }
+        .map_err(|_| AppInfoError::FileIconError(crate::error::FileIconError::Failed))?;
+
+    #[allow(non_upper_case_globals)]
+    let (is_bgra, is_premultiplied) = match pixel_format {
+        GUID_WICPixelFormat32bppBGRA => (true, false),
+        GUID_WICPixelFormat32bppPBGRA => (true, true),
+        GUID_WICPixelFormat32bppRGBA => (false, false),
+        GUID_WICPixelFormat32bppPRGBA => (false, true),
+        _ => {
+            return Err(AppInfoError::FileIconError(
+                crate::error::FileIconError::Failed,
+            ))
+        }
+    };
+
+    let mut pixels = vec![0u8; width as usize * height as usize * 4];
+    unsafe { wic_bitmap.CopyPixels(&source_rectangle, width * 4, &mut pixels) }
+        .map_err(|_| AppInfoError::FileIconError(crate::error::FileIconError::Failed))?;
+
+    // If the format is BGRA, convert it to RGBA
+    if is_bgra {
+        crate::icon::swap_red_blue(&mut pixels);
+    }
+
+    let mut icon = Icon {
+        width,
+        height,
+        pixels,
+    };
+    if is_premultiplied {
+        icon.unpremultiply_alpha();
+    }
+    Ok(icon)
+}
+
+/// Renders the icon at `index` in a file, numbered as [`parse_display_icon`] describes.
+/// Index 0 is the icon the shell shows for the file.
+#[cfg(all(target_os = "windows", feature = "icons"))]
+pub(crate) fn icon_at_index(path: &Path, index: i32, size: u16) -> Result<Icon> {
+    if index == 0 || is_ico_file(path) {
+        return get_file_icon(path, size);
+    }
+    extract_icon_from_resource(path, index, size)
+}
+
+/// Icon support is not compiled in, so this always fails.
+#[cfg(all(target_os = "windows", not(feature = "icons")))]
+pub(crate) fn icon_at_index(path: &Path, index: i32, size: u16) -> Result<Icon> {
+    let _ = (path, index, size);
+    Err(crate::icon::icons_disabled())
+}
+
+/// Extracts an icon from the resources of an executable or DLL, as file type registrations
+/// such as `shell32.dll,-16770` name them: a non-negative `index` counts the module's icon
+/// groups from zero, and a negative one is a resource ID. The frame closest to `size` is
+/// scaled to `size` x `size`.
+///
+/// Returns [`FileIconError::IconIndexOutOfRange`](crate::error::FileIconError) when the
+/// module has no icon at `index`.
+#[cfg(all(target_os = "windows", feature = "icons"))]
+pub fn extract_icon_from_resource(path: &Path, index: i32, size: u16) -> Result<Icon> {
+    use crate::error::FileIconError;
+
+    enter_span!(DEBUG, "extract_icon_from_resource", path = %path.display(), index, size);
+    let failed = || AppInfoError::FileIconError(FileIconError::Failed);
+    let out_of_range = || AppInfoError::FileIconError(FileIconError::IconIndexOutOfRange { index });
+    if size == 0 {
+        return Err(AppInfoError::FileIconError(FileIconError::NullIconSize));
+    }
+    if !long_path(path).exists() {
+        return Err(AppInfoError::FileIconError(FileIconError::PathDoesNotExist));
+    }
+
+    let module = load_resource_module(path).ok_or_else(failed)?;
+    defer!(unsafe {
+        let _ = FreeLibrary(module);
+    });
+    let group = match usize::try_from(index) {
+        Ok(position) => resource_names(module, RT_GROUP_ICON)
+            .into_iter()
+            .nth(position),
+        Err(_) => u16::try_from(index.unsigned_abs())
+            .ok()
+            .map(ResourceName::Id),
+    };
+    let group_data = group
+        .and_then(|group| resource_bytes(module, &group, RT_GROUP_ICON))
+        .ok_or_else(out_of_range)?;
+    let entries = parse_icon_directory(&group_data, true).ok_or_else(failed)?;
+    let entry = *closest_icon_entry(&entries, size as u32).ok_or_else(failed)?;
+    let data = resource_bytes(
+        module,
+        &ResourceName::Id(entry.offset_or_id as u16),
+        RT_ICON,
+    )
+    .ok_or_else(failed)?;
+
+    let hicon = icon_from_resource_data(&data, &entry).ok_or_else(failed)?;
+    defer!(unsafe {
+        let _ = DestroyIcon(hicon);
+    });
+    let icon = hicon_to_icon(hicon)?;
+    if icon.width == size as u32 && icon.height == size as u32 {
+        return Ok(icon);
+    }
+    Ok(icon.resized(size as u32, size as u32, FitMode::Stretch))
+}
+
+/// Icon support is not compiled in, so this always fails.
+#[cfg(all(target_os = "windows", not(feature = "icons")))]
+pub fn extract_icon_from_resource(path: &Path, index: i32, size: u16) -> Result<Icon> {
+    let _ = (path, index, size);
+    Err(crate::icon::icons_disabled())
+}
+
+/// Copies an icon into a straight-alpha RGBA icon of its own size, from its color and mask
+/// bitmaps. Icons without an alpha channel take their transparency from the mask, and
+/// monochrome icons, which only have a mask, come out black and white.
+#[cfg(all(target_os = "windows", feature = "icons"))]
+fn hicon_to_icon(hicon: HICON) -> Result<Icon> {
+    let failed = || AppInfoError::FileIconError(crate::error::FileIconError::Failed);
+    let mut info = ICONINFO::default();
+    unsafe { GetIconInfo(hicon, &mut info) }.map_err(|_| failed())?;
+    // GetIconInfo hands out copies of both bitmaps, which are ours to delete
+    defer!(unsafe {
+        let _ = DeleteObject(info.hbmMask);
+        if !info.hbmColor.is_invalid() {
+            let _ = DeleteObject(info.hbmColor);
+        }
+    });
+
+    let dc = unsafe { CreateCompatibleDC(None) };
+    if dc.is_invalid() {
+        return Err(failed());
+    }
+    defer!(unsafe {
+        let _ = DeleteDC(dc);
+    });
+
+    let (width, mask_height, mask) = bitmap_bgra(dc, info.hbmMask).ok_or_else(failed)?;
+    if info.hbmColor.is_invalid() {
+        // The AND mask sits above the XOR mask, so the bitmap is twice the icon's height
+        let height = mask_height / 2;
+        let (and, xor) = mask.split_at(width as usize * height as usize * 4);
+        let pixels = and
+            .chunks_exact(4)
+            .zip(xor.chunks_exact(4))
+            .flat_map(|(and, xor)| {
+                // Pixels that invert the screen have no RGBA form, so they are drawn black
+                match (and[0] != 0, xor[0] != 0) {
+                    (false, false) | (true, true) => [0, 0, 0, 255],
+                    (false, true) => [255, 255, 255, 255],
+                    (true, false) => [0, 0, 0, 0],
+                }
+            })
+            .collect();
+        return Ok(Icon {
+            width,
+            height,
+            pixels,
+        });
+    }
+
+    let (width, height, mut pixels) = bitmap_bgra(dc, info.hbmColor).ok_or_else(failed)?;
+    crate::icon::swap_red_blue(&mut pixels);
+    if pixels.chunks_exact(4).all(|pixel| pixel[3] == 0) {
+        // No alpha channel, so the mask says which pixels show
+        for (pixel, mask) in pixels.chunks_exact_mut(4).zip(mask.chunks_exact(4)) {
+            pixel[3] = if mask[0] == 0 { 255 } else { 0 };
+        }
+    }
+    Ok(Icon {
+        width,
+        height,
+        pixels,
+    })
+}
+
+/// Reads a bitmap as top-down 32-bit BGRA rows, with its width and height.
+#[cfg(all(target_os = "windows", feature = "icons"))]
+fn bitmap_bgra(dc: HDC, bitmap: HBITMAP) -> Option<(u32, u32, Vec<u8>)> {
+    let mut header = BITMAP::default();
+    let written = unsafe {
+        GetObjectW(
+            bitmap,
+            std::mem::size_of::<BITMAP>() as i32,
+            Some(&mut header as *mut BITMAP as *mut _),
+        )
+    };
+    if written == 0 || header.bmWidth <= 0 || header.bmHeight <= 0 {
+        return None;
+    }
+
+    let (width, height) = (header.bmWidth as u32, header.bmHeight as u32);
+    let mut info = BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width as i32,
+            // A negative height asks for rows from the top down
+            biHeight: -(height as i32),
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB.0,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut pixels = vec![0u8; width as usize * height as usize * 4];
+    let lines = unsafe {
+        GetDIBits(
+            dc,
+            bitmap,
+            0,
+            height,
+            Some(pixels.as_mut_ptr() as *mut _),
+            &mut info,
+            DIB_RGB_COLORS,
+        )
+    };
+    (lines == height as i32).then_some((width, height, pixels))
+}
+
+/// Loads the largest icon frame stored in an `.ico` file or in the first icon group of an
+/// executable or DLL.
+#[cfg(all(target_os = "windows", feature = "icons"))]
+fn load_largest_icon(path: &Path) -> Option<HICON> {
+    let (data, entry) = if is_ico_file(path) {
+        let bytes = std::fs::read(path).ok()?;
+        let entries = parse_icon_directory(&bytes, false)?;
+        let entry = *largest_icon_entry(&entries)?;
+        let start = entry.offset_or_id as usize;
+        let data = bytes.get(start..start.checked_add(entry.size as usize)?)?;
+        (data.to_vec(), entry)
+    } else {
+        let module = load_resource_module(path)?;
+        defer!(unsafe {
+            let _ = FreeLibrary(module);
+        });
+
+        let group = resource_names(module, RT_GROUP_ICON).into_iter().next()?;
+        let group_data = resource_bytes(module, &group, RT_GROUP_ICON)?;
+        let entries = parse_icon_directory(&group_data, true)?;
+        let entry = *largest_icon_entry(&entries)?;
+        let data = resource_bytes(
+            module,
+            &ResourceName::Id(entry.offset_or_id as u16),
+            RT_ICON,
+        )?;
+        (data, entry)
+    };
+    icon_from_resource_data(&data, &entry)
+}
+
+/// Creates an icon from the data of one frame, as stored in an `.ico` file or an `RT_ICON`
+/// resource.
+#[cfg(all(target_os = "windows", feature = "icons"))]
+fn icon_from_resource_data(data: &[u8], entry: &IconDirEntry) -> Option<HICON> {
+    // Frames larger than 255px record 0 in the directory, so trust the PNG header instead
+    let (width, height) = png_dimensions(data).unwrap_or((entry.width, entry.height));
+    unsafe {
+        CreateIconFromResourceEx(
+            data,
+            TRUE,
+            0x0003_0000,
+            width as i32,
+            height as i32,
+            LR_DEFAULTCOLOR,
+        )
+    }
+    .ok()
+}
+
+/// Whether a path names an `.ico` file rather than a module with icon resources.
+#[cfg(all(target_os = "windows", feature = "icons"))]
+fn is_ico_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|s| s.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("ico"))
+}
+
+/// Lists the frame sizes of the first icon group in an executable or DLL, which is empty
+/// if the module has no icons. Returns `None` for files that are not modules.
+#[cfg(all(target_os = "windows", feature = "icons"))]
+fn icon_group_sizes(path: &Path) -> Option<Vec<(u32, u32)>> {
+    let module = load_resource_module(path)?;
+    defer!(unsafe {
+        let _ = FreeLibrary(module);
+    });
+
+    let Some(group) = resource_names(module, RT_GROUP_ICON).into_iter().next() else {
+        return Some(Vec::new());
+    };
+    let entries = resource_bytes(module, &group, RT_GROUP_ICON)
+        .and_then(|data| parse_icon_directory(&data, true))
+        .unwrap_or_default();
+    let sizes = entries
+        .iter()
+        .map(|entry| {
+            // Only frames recorded as 256px can be larger, and those are PNGs
+            let data = (entry.width == 256 || entry.height == 256)
+                .then(|| {
+                    resource_bytes(
+                        module,
+                        &ResourceName::Id(entry.offset_or_id as u16),
+                        RT_ICON,
+                    )
+                })
+                .flatten();
+            data.as_deref()
+                .and_then(png_dimensions)
+                .unwrap_or((entry.width, entry.height))
+        })
+        .collect();
+    Some(sizes)
+}
+
+/// Lists the sizes of the shell image list tiers, if the shell has an icon for the file.
+#[cfg(all(target_os = "windows", feature = "icons"))]
+fn shell_tier_sizes(path: &Path) -> Vec<(u32, u32)> {
+    let _token = initialize_com();
+    let path_string = HSTRING::from(shell_parsing_name(path));
+    let mut file_info = SHFILEINFOW::default();
+    let result = unsafe {
+        SHGetFileInfoW(
+            &path_string,
+            FILE_FLAGS_AND_ATTRIBUTES(0),
+            Some(&mut file_info),
+            std::mem::size_of::<SHFILEINFOW>() as u32,
+            SHGFI_SYSICONINDEX,
+        )
+    };
+    if result == 0 {
+        return Vec::new();
+    }
+
+    [SHIL_SMALL, SHIL_LARGE, SHIL_EXTRALARGE, SHIL_JUMBO]
+        .into_iter()
+        .filter_map(|tier| {
+            let image_list: IImageList = unsafe { SHGetImageList(tier as i32) }.ok()?;
+            let (mut width, mut height) = (0, 0);
+            unsafe { image_list.GetIconSize(&mut width, &mut height) }.ok()?;
+            (width > 0 && height > 0).then_some((width as u32, height as u32))
+        })
+        .collect()
+}
+
+/// Lists the frame sizes of an `.ico` file, reading the real size of PNG frames from their
+/// header. Returns `None` if the data is not an icon file.
+#[cfg(any(all(target_os = "windows", feature = "icons"), test))]
+fn ico_file_sizes(bytes: &[u8]) -> Option<Vec<(u32, u32)>> {
+    let entries = parse_icon_directory(bytes, false)?;
+    Some(
+        entries
+            .iter()
+            .map(|entry| {
+                bytes
+                    .get(entry.offset_or_id as usize..)
+                    .and_then(png_dimensions)
+                    .unwrap_or((entry.width, entry.height))
+            })
+            .collect(),
+    )
+}
+
+/// Loads an executable or DLL for resource access only, without running any of its code.
+#[cfg(all(target_os = "windows", feature = "icons"))]
+fn load_resource_module(path: &Path) -> Option<HMODULE> {
+    let path_string = HSTRING::from(long_path(path).to_string_lossy().as_ref());
+    unsafe {
+        LoadLibraryExW(
+            &path_string,
+            None,
+            LOAD_LIBRARY_AS_DATAFILE | LOAD_LIBRARY_AS_IMAGE_RESOURCE,
+        )
+    }
+    .ok()
+}
+
+/// The name of a module resource, which is either an integer ID or a string.
+#[cfg(all(target_os = "windows", feature = "icons"))]
+#[derive(Debug, Clone)]
+enum ResourceName {
+    Id(u16),
+    Name(Vec<u16>),
+}
+
+#[cfg(all(target_os = "windows", feature = "icons"))]
+impl ResourceName {
+    /// Copies a resource name handed out by the loader, which is only valid temporarily.
+    unsafe fn from_pcwstr(name: PCWSTR) -> Self {
+        if (name.0 as usize) >> 16 == 0 {
+            ResourceName::Id(name.0 as usize as u16)
+        } else {
+            let mut wide = name.as_wide().to_vec();
+            wide.push(0);
+            ResourceName::Name(wide)
+        }
+    }
+
+    fn as_pcwstr(&self) -> PCWSTR {
+        match self {
+            ResourceName::Id(id) => PCWSTR(*id as usize as *const u16),
+            ResourceName::Name(wide) => PCWSTR(wide.as_ptr()),
+        }
+    }
+}
+
+/// Lists the names of all resources of the given type in a module, in resource order.
+#[cfg(all(target_os = "windows", feature = "icons"))]
+fn resource_names(module: HMODULE, resource_type: PCWSTR) -> Vec<ResourceName> {
+    unsafe extern "system" fn collect(
+        _module: HMODULE,
+        _resource_type: PCWSTR,
+        name: PCWSTR,
+        names: isize,
+    ) -> BOOL {
+        let names = &mut *(names as *mut Vec<ResourceName>);
+        names.push(ResourceName::from_pcwstr(name));
+        TRUE
+    }
+
+    let mut names: Vec<ResourceName> = Vec::new();
+    unsafe {
+        let _ = EnumResourceNamesW(
+            module,
+            resource_type,
+            Some(collect),
+            &mut names as *mut Vec<ResourceName> as isize,
+        );
+    }
+    names
+}
+
+/// Copies the raw bytes of a module resource.
+#[cfg(all(target_os = "windows", feature = "icons"))]
+fn resource_bytes(module: HMODULE, name: &ResourceName, resource_type: PCWSTR) -> Option<Vec<u8>> {
+    unsafe {
+        let resource = FindResourceW(module, name.as_pcwstr(), resource_type);
+        if resource.is_invalid() {
+            return None;
+        }
+
+        let size = SizeofResource(module, resource) as usize;
+        let handle = LoadResource(module, resource).ok()?;
+        let data = LockResource(handle) as *const u8;
+        if data.is_null() || size == 0 {
+            return None;
+        }
+        Some(std::slice::from_raw_parts(data, size).to_vec())
+    }
+}
+
+/// One image listed in an icon directory (an `.ico` file header or an `RT_GROUP_ICON`
+/// resource).
+#[cfg(any(all(target_os = "windows", feature = "icons"), test))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct IconDirEntry {
+    width: u32,
+    height: u32,
+    bit_count: u16,
+    /// Size of the image data in bytes
+    size: u32,
+    /// File offset of the image data for `.ico` files, `RT_ICON` resource ID for groups
+    offset_or_id: u32,
+}
+
+/// Parses an icon directory. `.ico` files use 16-byte entries ending in a file offset,
+/// while `RT_GROUP_ICON` resources (`grouped`) use 14-byte entries ending in a resource ID.
+#[cfg(any(all(target_os = "windows", feature = "icons"), test))]
+fn parse_icon_directory(data: &[u8], grouped: bool) -> Option<Vec<IconDirEntry>> {
+    let read_u16 = |offset: usize| -> Option<u16> {
+        Some(u16::from_le_bytes(
+            data.get(offset..offset + 2)?.try_into().ok()?,
+        ))
+    };
+    let read_u32 = |offset: usize| -> Option<u32> {
+        Some(u32::from_le_bytes(
+            data.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    };
+
+    // ICONDIR: reserved (0), type (1 = icon), image count
+    if read_u16(0)? != 0 || read_u16(2)? != 1 {
+        return None;
+    }
+    let count = read_u16(4)? as usize;
+    let entry_size = if grouped { 14 } else { 16 };
+
+    (0..count)
+        .map(|index| {
+            let offset = 6 + index * entry_size;
+            // A stored dimension of 0 means 256 pixels
+            let dimension = |value: u8| if value == 0 { 256 } else { value as u32 };
+            Some(IconDirEntry {
+                width: dimension(*data.get(offset)?),
+                height: dimension(*data.get(offset + 1)?),
+                bit_count: read_u16(offset + 6)?,
+                size: read_u32(offset + 8)?,
+                offset_or_id: if grouped {
+                    read_u16(offset + 12)? as u32
+                } else {
+                    read_u32(offset + 12)?
+                },
+            })
+        })
+        .collect()
+}
+
+/// Picks the entry with the most pixels, preferring higher color depth on ties.
+#[cfg(any(all(target_os = "windows", feature = "icons"), test))]
+fn largest_icon_entry(entries: &[IconDirEntry]) -> Option<&IconDirEntry> {
+    entries
+        .iter()
+        .max_by_key(|entry| (entry.width as u64 * entry.height as u64, entry.bit_count))
+}
+
+/// Picks the entry to render at `size` pixels: the smallest at least that large, since
+/// scaling down loses less than scaling up, or else the largest. Among frames of one size
+/// the higher color depth wins.
+#[cfg(any(all(target_os = "windows", feature = "icons"), test))]
+fn closest_icon_entry(entries: &[IconDirEntry], size: u32) -> Option<&IconDirEntry> {
+    entries
+        .iter()
+        .filter(|entry| entry.width.min(entry.height) >= size)
+        .min_by_key(|entry| {
+            (
+                entry.width as u64 * entry.height as u64,
+                std::cmp::Reverse(entry.bit_count),
+            )
+        })
+        .or_else(|| largest_icon_entry(entries))
+}
+
+/// Reads the start of an executable, where the PE headers are.
+#[cfg(target_os = "windows")]
+fn read_pe_header(path: &Path) -> Option<Vec<u8>> {
+    use std::io::Read;
+
+    // The headers sit in the first page of any image the loader accepts
+    let mut header = Vec::with_capacity(4096);
+    std::fs::File::open(long_path(path))
+        .ok()?
+        .take(4096)
+        .read_to_end(&mut header)
+        .ok()?;
+    Some(header)
+}
+
+/// Reads the subsystem from the PE header of an executable.
+#[cfg(target_os = "windows")]
+fn read_pe_subsystem(path: &Path) -> Option<PeSubsystem> {
+    pe_subsystem(&read_pe_header(path)?)
+}
+
+/// The offset of the NT headers in the start of a PE file, after checking both the DOS
+/// and PE signatures.
+#[cfg(any(target_os = "windows", test))]
+fn pe_nt_offset(header: &[u8]) -> Option<usize> {
+    if !header.starts_with(b"MZ") {
+        return None;
+    }
+    let nt_offset = u32::from_le_bytes(header.get(0x3c..0x40)?.try_into().ok()?) as usize;
+    if header.get(nt_offset..nt_offset.checked_add(4)?)? != b"PE\0\0" {
+        return None;
+    }
+    Some(nt_offset)
+}
+
+/// Whether the start of a PE file is a 64-bit (PE32+) image, from the magic number that
+/// opens its optional header.
+#[cfg(any(target_os = "windows", test))]
+fn pe_is_64bit(header: &[u8]) -> Option<bool> {
+    let offset = pe_nt_offset(header)? + 24;
+    match u16::from_le_bytes(header.get(offset..offset + 2)?.try_into().ok()?) {
+        0x10b => Some(false),
+        0x20b => Some(true),
+        _ => None,
+    }
+}
+
+/// Parses the subsystem from the start of a PE file. The DOS header points to the NT
+/// headers, whose optional header holds the subsystem at the same offset in 32- and 64-bit
+/// images.
+#[cfg(any(target_os = "windows", test))]
+fn pe_subsystem(header: &[u8]) -> Option<PeSubsystem> {
+    let nt_offset = pe_nt_offset(header)?;
+    // The signature and the 20-byte file header come before the optional header
+    let offset = nt_offset + 24 + 68;
+    let subsystem = u16::from_le_bytes(header.get(offset..offset + 2)?.try_into().ok()?);
+    Some(match subsystem {
+        // IMAGE_SUBSYSTEM_WINDOWS_GUI and IMAGE_SUBSYSTEM_WINDOWS_CE_GUI
+        2 | 9 => PeSubsystem::Gui,
+        3 => PeSubsystem::Console,
+        1 => PeSubsystem::Native,
+        _ => PeSubsystem::Other,
     })
 }
+
+/// Reads the dimensions from a PNG header, as used by large icon frames.
+#[cfg(any(all(target_os = "windows", feature = "icons"), test))]
+fn png_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+    if !data.starts_with(PNG_SIGNATURE) || data.get(12..16)? != b"IHDR" {
+        return None;
+    }
+    let width = u32::from_be_bytes(data.get(16..20)?.try_into().ok()?);
+    let height = u32::from_be_bytes(data.get(20..24)?.try_into().ok()?);
+    Some((width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Builds an icon directory with the given (width byte, height byte, bit count) images.
+    fn icon_directory(images: &[(u8, u8, u16)], grouped: bool) -> Vec<u8> {
+        let mut data = vec![0, 0, 1, 0];
+        data.extend_from_slice(&(images.len() as u16).to_le_bytes());
+        for (index, &(width, height, bit_count)) in images.iter().enumerate() {
+            data.extend_from_slice(&[width, height, 0, 0]);
+            data.extend_from_slice(&1u16.to_le_bytes());
+            data.extend_from_slice(&bit_count.to_le_bytes());
+            data.extend_from_slice(&1000u32.to_le_bytes());
+            if grouped {
+                data.extend_from_slice(&(index as u16 + 1).to_le_bytes());
+            } else {
+                data.extend_from_slice(&(6 + 16 * images.len() as u32).to_le_bytes());
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn test_parse_icon_directory() {
+        let file = icon_directory(&[(16, 16, 32), (0, 0, 32), (48, 48, 8)], false);
+        let entries = parse_icon_directory(&file, false).expect("Failed to parse directory");
+        assert_eq!(entries.len(), 3);
+        assert_eq!((entries[1].width, entries[1].height), (256, 256));
+        assert_eq!(entries[0].offset_or_id, 6 + 16 * 3);
+        assert_eq!(largest_icon_entry(&entries), Some(&entries[1]));
+
+        let group = icon_directory(&[(32, 32, 8), (32, 32, 32), (16, 16, 32)], true);
+        let entries = parse_icon_directory(&group, true).expect("Failed to parse group");
+        assert_eq!(
+            entries.iter().map(|e| e.offset_or_id).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        // Equal sizes are broken by color depth
+        assert_eq!(largest_icon_entry(&entries).unwrap().offset_or_id, 2);
+    }
+
+    #[test]
+    fn test_closest_icon_entry() {
+        let group = icon_directory(
+            &[
+                (16, 16, 32),
+                (32, 32, 8),
+                (32, 32, 32),
+                (48, 48, 32),
+                (0, 0, 32),
+            ],
+            true,
+        );
+        let entries = parse_icon_directory(&group, true).unwrap();
+        let closest = |size| closest_icon_entry(&entries, size).unwrap().offset_or_id;
+        assert_eq!(closest(16), 1);
+        // Color depth breaks ties, and larger frames are scaled down
+        assert_eq!(closest(32), 3);
+        assert_eq!(closest(20), 3);
+        assert_eq!(closest(64), 5);
+        assert_eq!(closest(512), 5);
+        assert_eq!(closest_icon_entry(&[], 32), None);
+    }
+
+    #[test]
+    #[cfg(all(target_os = "windows", feature = "icons"))]
+    fn test_extract_icon_from_resource() {
+        use crate::error::FileIconError;
+
+        let shell32 = PathBuf::from(std::env::var_os("SystemRoot").unwrap())
+            .join("System32")
+            .join("shell32.dll");
+        // The first icon, and the folder icon by position and by resource ID
+        for index in [0, 3, -4] {
+            for size in [16, 32, 48, 100] {
+                let icon = extract_icon_from_resource(&shell32, index, size)
+                    .unwrap_or_else(|err| panic!("index {}: {}", index, err));
+                assert_eq!((icon.width, icon.height), (size as u32, size as u32));
+                assert_eq!(icon.pixels.len(), size as usize * size as usize * 4);
+                assert!(icon.pixels.chunks_exact(4).any(|pixel| pixel[3] > 0));
+                assert!(icon.pixels.chunks_exact(4).any(|pixel| pixel[3] < 255));
+            }
+        }
+
+        for index in [100_000, -65_000, i32::MIN] {
+            assert!(matches!(
+                extract_icon_from_resource(&shell32, index, 32),
+                Err(AppInfoError::FileIconError(FileIconError::IconIndexOutOfRange { index: found }))
+                    if found == index
+            ));
+        }
+        assert!(matches!(
+            extract_icon_from_resource(&shell32, 0, 0),
+            Err(AppInfoError::FileIconError(FileIconError::NullIconSize))
+        ));
+        assert!(matches!(
+            extract_icon_from_resource(Path::new(r"C:\Nowhere\none.dll"), 0, 32),
+            Err(AppInfoError::FileIconError(FileIconError::PathDoesNotExist))
+        ));
+    }
+
+    #[test]
+    fn test_ico_file_sizes() {
+        let fixture = include_bytes!("../tests/fixtures/app.ico");
+        // The 256 and 512 pixel frames are PNGs recorded as 0 in the directory
+        assert_eq!(
+            ico_file_sizes(fixture),
+            Some(vec![
+                (16, 16),
+                (32, 32),
+                (32, 32),
+                (48, 48),
+                (256, 256),
+                (512, 512)
+            ])
+        );
+        assert_eq!(ico_file_sizes(b"not an icon"), None);
+    }
+
+    #[test]
+    fn test_parse_icon_directory_rejects_invalid_data() {
+        assert_eq!(parse_icon_directory(&[], false), None);
+        // Cursor directories are not icons
+        assert_eq!(parse_icon_directory(&[0, 0, 2, 0, 0, 0], false), None);
+        // Truncated entry list
+        let mut truncated = icon_directory(&[(16, 16, 32)], false);
+        truncated.truncate(12);
+        assert_eq!(parse_icon_directory(&truncated, false), None);
+    }
+
+    #[test]
+    fn test_rot13() {
+        assert_eq!(
+            rot13(r"{6Q809377-6NS0-444O-8957-N3773S02200R}\Abgrcnq++\abgrcnq++.rkr"),
+            r"{6D809377-6AF0-444B-8957-A3773F02200E}\Notepad++\notepad++.exe"
+        );
+        assert_eq!(rot13("HRZR_PGYFRFFVBA"), "UEME_CTLSESSION");
+        assert_eq!(rot13(&rot13("Mixed Case 123")), "Mixed Case 123");
+    }
+
+    #[test]
+    fn test_split_known_folder() {
+        assert_eq!(
+            split_known_folder(r"{6D809377-6AF0-444B-8957-A3773F02200E}\Vendor\app.exe"),
+            Some((0x6d809377_6af0_444b_8957_a3773f02200e, r"\Vendor\app.exe"))
+        );
+        assert_eq!(split_known_folder(r"C:\Tools\tool.exe"), None);
+        assert_eq!(split_known_folder("Microsoft.Windows.Explorer"), None);
+        assert_eq!(split_known_folder("{not-a-guid}\\x"), None);
+    }
+
+    #[test]
+    fn test_parse_user_assist_entry() {
+        // A Windows 10 value: 14 runs, last run 2024-03-01T12:00:00Z
+        let mut data = vec![0u8; 72];
+        data[4..8].copy_from_slice(&14u32.to_le_bytes());
+        data[8..12].copy_from_slice(&3u32.to_le_bytes());
+        data[12..16].copy_from_slice(&95_000u32.to_le_bytes());
+        data[16..60].fill(0xff);
+        data[60..68].copy_from_slice(&0x01da_6bcf_fc87_a000u64.to_le_bytes());
+        let entry = parse_user_assist_entry(&data).expect("Failed to parse entry");
+        assert_eq!(entry.run_count, 14);
+        assert_eq!(
+            entry.last_run,
+            Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_709_294_400))
+        );
+
+        // Pinned but never started
+        let mut data = vec![0u8; 72];
+        data[16..60].fill(0xff);
+        let entry = parse_user_assist_entry(&data).expect("Failed to parse entry");
+        assert_eq!(entry.run_count, 0);
+        assert_eq!(entry.last_run, None);
+
+        // An XP-era value, whose count starts at 5
+        let mut data = vec![0u8; 16];
+        data[4..8].copy_from_slice(&8u32.to_le_bytes());
+        data[8..16].copy_from_slice(&0x01da_6bcf_fc87_a000u64.to_le_bytes());
+        let entry = parse_user_assist_entry(&data).expect("Failed to parse entry");
+        assert_eq!(entry.run_count, 3);
+        assert!(entry.last_run.is_some());
+
+        assert_eq!(parse_user_assist_entry(&[1, 2, 3]), None);
+    }
+
+    #[test]
+    fn test_startup_approved_enabled() {
+        assert!(startup_approved_enabled(&[
+            0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0
+        ]));
+        assert!(startup_approved_enabled(&[
+            0x06, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0
+        ]));
+        assert!(!startup_approved_enabled(&[
+            0x03, 0, 0, 0, 0xd0, 0x3c, 0x1b, 0x6e, 0x8f, 0x3a, 0xd8, 0x01
+        ]));
+        assert!(!startup_approved_enabled(&[0x07]));
+        assert!(startup_approved_enabled(&[]));
+    }
+
+    #[test]
+    fn test_run_key_commands() {
+        // Values as installers typically write them under the Run keys
+        assert_eq!(
+            command_line_executable(
+                r#""C:\Program Files\Vendor\Tray\tray.exe" /background --minimized"#
+            ),
+            Some(PathBuf::from(r"C:\Program Files\Vendor\Tray\tray.exe"))
+        );
+        assert_eq!(
+            command_line_executable(r"C:\Windows\System32\SecurityHealthSystray.exe"),
+            Some(PathBuf::from(
+                r"C:\Windows\System32\SecurityHealthSystray.exe"
+            ))
+        );
+        assert_eq!(
+            command_line_executable(r"C:\Program Files (x86)\Helper\helper.exe -autorun"),
+            Some(PathBuf::from(r"C:\Program Files (x86)\Helper\helper.exe"))
+        );
+    }
+
+    #[test]
+    fn test_command_line_executable() {
+        assert_eq!(
+            command_line_executable(r#""C:\Program Files\App\app.exe" --url "%1""#),
+            Some(PathBuf::from(r"C:\Program Files\App\app.exe"))
+        );
+        assert_eq!(
+            command_line_executable(r"C:\Program Files\App\App.EXE %1"),
+            Some(PathBuf::from(r"C:\Program Files\App\App.EXE"))
+        );
+        assert_eq!(
+            command_line_executable("rundll32 url.dll,FileProtocolHandler %1"),
+            Some(PathBuf::from("rundll32"))
+        );
+        assert_eq!(command_line_executable("  "), None);
+        assert_eq!(command_line_executable(r#""""#), None);
+    }
+
+    #[test]
+    fn test_quote_windows_argument() {
+        assert_eq!(quote_windows_argument("plain"), "plain");
+        assert_eq!(quote_windows_argument(r"C:\dir\file"), r"C:\dir\file");
+        assert_eq!(quote_windows_argument(""), r#""""#);
+        assert_eq!(quote_windows_argument("two words"), r#""two words""#);
+        assert_eq!(quote_windows_argument(r#"say "hi""#), r#""say \"hi\"""#);
+        assert_eq!(quote_windows_argument(r"C:\my dir\"), r#""C:\my dir\\""#);
+        assert_eq!(quote_windows_argument(r#"a\"b"#), r#""a\\\"b""#);
+    }
+
+    #[test]
+    fn test_infer_display_name() {
+        // Windows Installer's registration comes first
+        assert_eq!(
+            infer_display_name(
+                Some("Contoso Suite"),
+                Some("Contoso"),
+                None,
+                Some(r"C:\Program Files\Contoso")
+            )
+            .as_deref(),
+            Some("Contoso Suite")
+        );
+        assert_eq!(
+            infer_display_name(None, Some(" "), Some("Contoso Tool"), None).as_deref(),
+            Some("Contoso Tool")
+        );
+        assert_eq!(
+            infer_display_name(
+                None,
+                None,
+                None,
+                Some(r#""C:\Program Files\Fabrikam App\""#)
+            )
+            .as_deref(),
+            Some("Fabrikam App")
+        );
+        assert_eq!(infer_display_name(None, None, None, Some(r"C:\")), None);
+        assert_eq!(infer_display_name(None, None, None, Some("  ")), None);
+        assert_eq!(infer_display_name(None, None, None, None), None);
+    }
+
+    #[test]
+    fn test_uninstall_entry_key() {
+        let app = |identifier: &str, scope: Option<InstallScope>| AppInfo {
+            identifier: Some(identifier.to_string()),
+            install_scope: scope,
+            ..Default::default()
+        };
+        assert_eq!(
+            uninstall_entry_key(&app("7-Zip", Some(InstallScope::Machine))),
+            Some((
+                UninstallRoot::LocalMachine,
+                r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall\7-Zip".to_string()
+            ))
+        );
+        let wow64 = AppInfo {
+            registry_view: Some(RegistryView::Wow64),
+            ..app("Git_is1", Some(InstallScope::Machine))
+        };
+        assert_eq!(
+            uninstall_entry_key(&wow64),
+            Some((
+                UninstallRoot::LocalMachine,
+                r"SOFTWARE\WOW6432Node\Microsoft\Windows\CurrentVersion\Uninstall\Git_is1"
+                    .to_string()
+            ))
+        );
+        assert_eq!(
+            uninstall_entry_key(&app("Slack", Some(InstallScope::User))),
+            Some((
+                UninstallRoot::CurrentUser,
+                r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall\Slack".to_string()
+            ))
+        );
+        let other_user = AppInfo {
+            owner: Some(crate::UserAccount {
+                sid: "S-1-5-21-1-2-3-1001".to_string(),
+                name: None,
+            }),
+            ..app("Slack", Some(InstallScope::User))
+        };
+        assert_eq!(
+            uninstall_entry_key(&other_user),
+            Some((
+                UninstallRoot::Users,
+                r"S-1-5-21-1-2-3-1001\Software\Microsoft\Windows\CurrentVersion\Uninstall\Slack"
+                    .to_string()
+            ))
+        );
+
+        // Apps no Uninstall entry lists
+        assert_eq!(uninstall_entry_key(&app("steam:440", None)), None);
+        assert_eq!(
+            uninstall_entry_key(&app("", Some(InstallScope::User))),
+            None
+        );
+        assert_eq!(
+            uninstall_entry_key(&app(r"..\Run", Some(InstallScope::User))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_uninstall_command() {
+        let env = |name: &str| match name {
+            "SystemRoot" => Some(r"C:\Windows".to_string()),
+            "ProgramFiles" => Some(r"C:\Program Files".to_string()),
+            _ => None,
+        };
+        let entry =
+            |uninstall: Option<&str>, quiet: Option<&str>, windows_installer| UninstallEntry {
+                uninstall_string: uninstall.map(str::to_string),
+                quiet_uninstall_string: quiet.map(str::to_string),
+                windows_installer,
+            };
+        let command = |entry: &UninstallEntry, identifier: &str, quiet: bool| {
+            uninstall_command(entry, Some(identifier), quiet, false, env)
+                .map(|command| command.command_line())
+        };
+        let product_code = "{23170F69-40C1-2702-2301-000001000000}";
+
+        // Windows Installer products are removed by product code, not the entry's string
+        let msi = entry(
+            Some("MsiExec.exe /I{23170F69-40C1-2702-2301-000001000000}"),
+            None,
+            true,
+        );
+        assert_eq!(
+            command(&msi, product_code, false).unwrap(),
+            r#""C:\Windows\System32\msiexec.exe" /x {23170F69-40C1-2702-2301-000001000000}"#
+        );
+        assert_eq!(
+            command(&msi, product_code, true).unwrap(),
+            r#""C:\Windows\System32\msiexec.exe" /x {23170F69-40C1-2702-2301-000001000000} /qn /norestart"#
+        );
+        // An msiexec string marks the product even without the flag
+        let unflagged = entry(Some(r"C:\Windows\system32\msiexec.exe /I{X}"), None, false);
+        assert_eq!(
+            command(&unflagged, product_code, false).unwrap(),
+            r#""C:\Windows\System32\msiexec.exe" /x {23170F69-40C1-2702-2301-000001000000}"#
+        );
+        // Without a product code the entry's own string is all there is
+        assert_eq!(
+            command(&msi, "Contoso", false).unwrap(),
+            r#""MsiExec.exe" /I{23170F69-40C1-2702-2301-000001000000}"#
+        );
+
+        let exe = entry(
+            Some(r#""C:\Program Files\Git\unins000.exe""#),
+            Some(r#""C:\Program Files\Git\unins000.exe" /VERYSILENT /NORESTART"#),
+            false,
+        );
+        assert_eq!(
+            command(&exe, "Git_is1", false).unwrap(),
+            r#""C:\Program Files\Git\unins000.exe""#
+        );
+        assert_eq!(
+            command(&exe, "Git_is1", true).unwrap(),
+            r#""C:\Program Files\Git\unins000.exe" /VERYSILENT /NORESTART"#
+        );
+        // A product code alone does not make an entry a Windows Installer one
+        assert_eq!(
+            command(&exe, product_code, false).unwrap(),
+            r#""C:\Program Files\Git\unins000.exe""#
+        );
+
+        let unquoted = entry(
+            Some(r"%ProgramFiles%\Mozilla Firefox\uninstall\helper.exe /S"),
+            None,
+            false,
+        );
+        let firefox = uninstall_command(&unquoted, Some("Firefox"), false, true, env).unwrap();
+        assert_eq!(
+            firefox.program,
+            PathBuf::from(r"C:\Program Files\Mozilla Firefox\uninstall\helper.exe")
+        );
+        assert_eq!(firefox.arguments, "/S");
+        assert!(firefox.elevated);
+
+        // Nothing to run
+        assert!(matches!(
+            command(&unquoted, "Firefox", true),
+            Err(AppInfoError::UninstallFailed(_))
+        ));
+        assert!(matches!(
+            command(&entry(Some("  "), None, false), "Tool", false),
+            Err(AppInfoError::UninstallFailed(_))
+        ));
+        assert!(matches!(
+            command(&UninstallEntry::default(), product_code, false),
+            Err(AppInfoError::UninstallFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_user_hive_sids() {
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        let loaded: Vec<String> = names(&[
+            ".DEFAULT",
+            "S-1-5-18",
+            "S-1-5-19",
+            "S-1-5-20",
+            "S-1-5-21-1004336348-1177238915-682003330-1001",
+            "S-1-5-21-1004336348-1177238915-682003330-1001_Classes",
+            "S-1-12-1-3405226718-1319637123-2843526316-2571519512",
+        ]);
+        let profiles: Vec<String> = names(&[
+            "S-1-5-18",
+            "S-1-5-19",
+            "S-1-5-21-1004336348-1177238915-682003330-1001",
+            "s-1-5-21-1004336348-1177238915-682003330-1002",
+            "S-1-5-21-1004336348-1177238915-682003330-1003.bak",
+            "S-1-5-80-956008885-3418522649-1831038044-1853292631-2271478464",
+        ]);
+        assert_eq!(
+            user_hive_sids(&loaded, &profiles),
+            [
+                ("S-1-5-21-1004336348-1177238915-682003330-1001", true),
+                ("S-1-12-1-3405226718-1319637123-2843526316-2571519512", true),
+                ("s-1-5-21-1004336348-1177238915-682003330-1002", false),
+            ]
+            .map(|(sid, loaded)| (sid.to_string(), loaded))
+        );
+        assert!(!is_user_sid("S-1-5-21-"));
+        assert!(!is_user_sid("S-1-5-21--1001"));
+        assert!(!is_user_sid("app-info_S-1-5-21-1-2-3-1001"));
+    }
+
+    #[test]
+    fn test_user_hive_paths() {
+        let env = |name: &str| (name == "SystemDrive").then(|| "C:".to_string());
+        assert_eq!(
+            profile_hive_file(r"%SystemDrive%\Users\alice", env).as_deref(),
+            Some(r"C:\Users\alice\NTUSER.DAT")
+        );
+        assert_eq!(
+            profile_hive_file(r#""D:\Profiles\bob\""#, env).as_deref(),
+            Some(r"D:\Profiles\bob\NTUSER.DAT")
+        );
+        assert_eq!(profile_hive_file("  ", env), None);
+
+        let sid = "S-1-5-21-1004336348-1177238915-682003330-1002";
+        assert_eq!(
+            user_uninstall_key(sid),
+            r"S-1-5-21-1004336348-1177238915-682003330-1002\Software\Microsoft\Windows\CurrentVersion\Uninstall"
+        );
+        assert_eq!(
+            user_uninstall_key(&hive_mount_name(sid)),
+            r"app-info_S-1-5-21-1004336348-1177238915-682003330-1002\Software\Microsoft\Windows\CurrentVersion\Uninstall"
+        );
+    }
+
+    #[test]
+    fn test_infer_install_dir() {
+        let cases = [
+            // Squirrel-installed Electron apps keep each version in an app-<version> folder
+            (
+                Some("Slack"),
+                r"C:\Users\me\AppData\Local\slack\app-4.36.140\slack.exe",
+                Some(r"C:\Users\me\AppData\Local\slack"),
+            ),
+            (
+                Some("Discord"),
+                r"C:\Users\me\AppData\Local\Discord\app-1.0.9013\Discord.exe",
+                Some(r"C:\Users\me\AppData\Local\Discord"),
+            ),
+            // electron-builder's per-user installs
+            (
+                Some("Obsidian"),
+                r"C:\Users\me\AppData\Local\Programs\Obsidian\Obsidian.exe",
+                Some(r"C:\Users\me\AppData\Local\Programs\Obsidian"),
+            ),
+            (
+                Some("Zoom Workplace"),
+                r"C:\Users\me\AppData\Roaming\Zoom\bin\Zoom.exe",
+                Some(r"C:\Users\me\AppData\Roaming\Zoom"),
+            ),
+            (
+                Some("Microsoft Edge"),
+                r"C:\Program Files (x86)\Microsoft\Edge\Application\msedge.exe",
+                Some(r"C:\Program Files (x86)\Microsoft\Edge"),
+            ),
+            (
+                Some("Mozilla Firefox (x64 en-US)"),
+                r"C:\Program Files\Mozilla Firefox\firefox.exe",
+                Some(r"C:\Program Files\Mozilla Firefox"),
+            ),
+            // The product folder under the vendor's
+            (
+                Some("Fabrikam Studio 2024"),
+                r"C:\Program Files\Fabrikam\Studio 2024\bin\studio.exe",
+                Some(r"C:\Program Files\Fabrikam\Studio 2024"),
+            ),
+            // Nothing resembles the name: the first folder that is not a layout folder
+            (
+                Some("R for Windows 4.3.1"),
+                r"C:\Program Files\R\R-4.3.1\bin\x64\Rgui.exe",
+                Some(r"C:\Program Files\R\R-4.3.1"),
+            ),
+            (
+                None,
+                r"C:\Program Files\Contoso\bin\contoso.exe",
+                Some(r"C:\Program Files\Contoso"),
+            ),
+            (
+                Some("Contoso"),
+                "C:/Program Files/Contoso/v2.1/contoso.exe",
+                Some("C:/Program Files/Contoso"),
+            ),
+            // Single-executable tools
+            (
+                Some("PuTTY release 0.80 (64-bit)"),
+                r"C:\Program Files\PuTTY\putty.exe",
+                Some(r"C:\Program Files\PuTTY"),
+            ),
+            (Some("Tool"), r"C:\Program Files\tool.exe", None),
+            (
+                Some("Tool"),
+                r"C:\Users\me\AppData\Local\Programs\tool.exe",
+                None,
+            ),
+            (Some("Tool"), r"C:\tool.exe", None),
+            (Some("Tool"), r"C:\bin\tool.exe", None),
+        ];
+        for (display_name, executable, expected) in cases {
+            assert_eq!(
+                infer_install_dir(display_name, executable),
+                expected,
+                "{}",
+                executable
+            );
+        }
+    }
+
+    #[test]
+    fn test_packed_product_code() {
+        assert_eq!(
+            packed_product_code("{90160000-008C-0000-0000-0000000FF1CE}").as_deref(),
+            Some("00006109C80000000000000000F01FEC")
+        );
+        assert_eq!(
+            packed_product_code("{ea457b21-f73e-494c-acab-524fde069978}").as_deref(),
+            Some("12B754AEE37FC494CABA25F4ED609987")
+        );
+        assert_eq!(packed_product_code("Git_is1"), None);
+        assert_eq!(
+            packed_product_code("{90160000-008C-0000-0000-0000000FF1C}"),
+            None
+        );
+        assert_eq!(
+            packed_product_code("{9016000G-008C-0000-0000-0000000FF1CE}"),
+            None
+        );
+    }
+
+    /// Uninstall-shaped entries under a scratch key of the current user, deleted on drop.
+    #[cfg(target_os = "windows")]
+    struct UninstallFixture(String);
+
+    #[cfg(target_os = "windows")]
+    impl UninstallFixture {
+        fn new(name: &str, entries: &[(&str, &[(&str, &str)])]) -> Self {
+            use windows::Win32::System::Registry::{
+                RegCreateKeyExW, RegSetValueExW, KEY_ALL_ACCESS, REG_OPTION_NON_VOLATILE, REG_SZ,
+            };
+
+            let fixture = UninstallFixture(format!(
+                "Software\\app-info-tests-{}-{}",
+                name,
+                std::process::id()
+            ));
+            for (entry, values) in entries {
+                let mut hkey = HKEY::default();
+                let key_name = HSTRING::from(format!("{}\\{}", fixture.0, entry));
+                unsafe {
+                    RegCreateKeyExW(
+                        HKEY_CURRENT_USER,
+                        &key_name,
+                        0,
+                        PCWSTR::null(),
+                        REG_OPTION_NON_VOLATILE,
+                        KEY_ALL_ACCESS,
+                        None,
+                        &mut hkey,
+                        None,
+                    )
+                }
+                .ok()
+                .unwrap();
+                defer!(unsafe {
+                    let _ = RegCloseKey(hkey);
+                });
+                for (value_name, value) in *values {
+                    let data: Vec<u8> = value
+                        .encode_utf16()
+                        .chain([0])
+                        .flat_map(|unit| unit.to_le_bytes())
+                        .collect();
+                    unsafe {
+                        RegSetValueExW(hkey, &HSTRING::from(*value_name), 0, REG_SZ, Some(&data))
+                    }
+                    .ok()
+                    .unwrap();
+                }
+            }
+            fixture
+        }
+
+        /// Scans the entries, ordered by key name.
+        fn scan(&self) -> Vec<AppInfo> {
+            let mut apps =
+                scan_registry_key(HKEY_CURRENT_USER, &self.0, &ScanContext::new(0), 0).unwrap();
+            apps.sort_by(|a, b| a.identifier.cmp(&b.identifier));
+            apps
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    impl Drop for UninstallFixture {
+        fn drop(&mut self) {
+            use windows::Win32::System::Registry::RegDeleteTreeW;
+            unsafe {
+                let _ = RegDeleteTreeW(HKEY_CURRENT_USER, &HSTRING::from(self.0.as_str()));
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "windows")]
+    fn test_registry_entry_without_display_name() {
+        let executable = PathBuf::from(std::env::var_os("SystemRoot").unwrap())
+            .join("System32")
+            .join("notepad.exe");
+        let executable_text = executable.to_string_lossy().into_owned();
+        let fixture = UninstallFixture::new(
+            "no-name",
+            &[
+                ("NoName", &[("DisplayIcon", executable_text.as_str())]),
+                (
+                    "Folder",
+                    &[("InstallLocation", r"C:\Nowhere\Contoso Tool\")],
+                ),
+                ("Empty", &[("Comments", "nothing to go by")]),
+            ],
+        );
+
+        let apps = fixture.scan();
+        assert_eq!(apps.len(), 2);
+        assert!(apps.iter().all(|app| app.name_inferred));
+
+        assert_eq!(apps[0].identifier.as_deref(), Some("Folder"));
+        assert_eq!(apps[0].name, "Contoso Tool");
+
+        let version_info = read_file_version_info(&executable).unwrap();
+        let expected = version_info
+            .product_name
+            .or(version_info.file_description)
+            .unwrap();
+        assert_eq!(apps[1].identifier.as_deref(), Some("NoName"));
+        assert_eq!(apps[1].name, expected);
+        assert_eq!(
+            apps[1].executable_path.as_deref(),
+            Some(executable.as_path())
+        );
+    }
+
+    #[test]
+    fn test_short_date_order() {
+        let cases = [
+            ("M/d/yyyy", Some(crate::DateOrder::MonthFirst)),
+            ("dd.MM.yyyy", Some(crate::DateOrder::DayFirst)),
+            ("dd/MM/yy", Some(crate::DateOrder::DayFirst)),
+            ("yyyy-MM-dd", Some(crate::DateOrder::MonthFirst)),
+            ("yyyy/M/d", Some(crate::DateOrder::MonthFirst)),
+            ("'den 'd. MMMM", Some(crate::DateOrder::DayFirst)),
+            ("yyyy", None),
+            ("", None),
+        ];
+        for (pattern, expected) in cases {
+            assert_eq!(short_date_order(pattern), expected, "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_parse_display_icon() {
+        let file = |path: &str, index: i32| Some((PathBuf::from(path), index));
+        let cases = [
+            (
+                r"C:\Program Files\Mozilla Firefox\firefox.exe,0",
+                file(r"C:\Program Files\Mozilla Firefox\firefox.exe", 0),
+            ),
+            (
+                r#""C:\Program Files\Git\mingw64\share\git\git-for-windows.ico""#,
+                file(r"C:\Program Files\Git\mingw64\share\git\git-for-windows.ico", 0),
+            ),
+            (
+                r#""C:\Program Files\Microsoft VS Code\Code.exe",1"#,
+                file(r"C:\Program Files\Microsoft VS Code\Code.exe", 1),
+            ),
+            (
+                r"C:\Windows\System32\imageres.dll,-5302",
+                file(r"C:\Windows\System32\imageres.dll", -5302),
+            ),
+            // A comma that is part of the path
+            (
+                r"C:\Program Files (x86)\Contoso, Inc\Tool\tool.exe",
+                file(r"C:\Program Files (x86)\Contoso, Inc\Tool\tool.exe", 0),
+            ),
+            // Command lines give the file they run
+            (
+                "rundll32.exe shell32.dll,Control_RunDLL mmsys.cpl",
+                file("mmsys.cpl", 0),
+            ),
+            (
+                r"C:\Windows\System32\control.exe desk.cpl,,@Themes",
+                file("desk.cpl", 0),
+            ),
+            (
+                r#"RunDll32 C:\PROGRA~1\COMMON~1\INSTAL~1\PROFES~1\RunTime\11\50\Intel32\Ctor.dll,LaunchSetup "C:\Program Files\InstallShield Installation Information\{4F9B2E1C-7A5D-4C3B-9E2F-1A6D3B5C8E70}\setup.exe" -l0x9"#,
+                file(
+                    r"C:\PROGRA~1\COMMON~1\INSTAL~1\PROFES~1\RunTime\11\50\Intel32\Ctor.dll",
+                    0,
+                ),
+            ),
+            (
+                r#""C:\Windows\SysWOW64\rundll32.exe" "C:\Program Files (x86)\Realtek\Audio\RtlUpd.dll",Uninstall"#,
+                file(r"C:\Program Files (x86)\Realtek\Audio\RtlUpd.dll", 0),
+            ),
+            (
+                r"C:\Windows\System32\control.exe /name Microsoft.Mouse",
+                file(r"C:\Windows\System32\control.exe", 0),
+            ),
+            // A program that only shares the name
+            (
+                r"C:\Program Files\Contoso\control.exe,0",
+                file(r"C:\Program Files\Contoso\control.exe", 0),
+            ),
+            // References that name no file
+            ("https://www.contoso.com/favicon.ico", None),
+            ("http://contoso.com/app.ico,0", None),
+            ("ms-resource:AppIcon", None),
+            (
+                "@{Microsoft.WindowsCalculator_11.2307.4.0_x64__8wekyb3d8bbwe?ms-resource://Microsoft.WindowsCalculator/Files/Assets/CalculatorAppList.png}",
+                None,
+            ),
+            ("  ", None),
+            (r#""""#, None),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(parse_display_icon(value), expected, "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_pe_subsystem() {
+        let image = |subsystem: u16| {
+            let mut header = vec![0u8; 0x200];
+            header[..2].copy_from_slice(b"MZ");
+            header[0x3c..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+            header[0x80..0x84].copy_from_slice(b"PE\0\0");
+            header[0x80 + 24 + 68..0x80 + 24 + 70].copy_from_slice(&subsystem.to_le_bytes());
+            header
+        };
+        assert_eq!(pe_subsystem(&image(2)), Some(PeSubsystem::Gui));
+        assert_eq!(pe_subsystem(&image(3)), Some(PeSubsystem::Console));
+        assert_eq!(pe_subsystem(&image(1)), Some(PeSubsystem::Native));
+        assert_eq!(pe_subsystem(&image(10)), Some(PeSubsystem::Other));
+
+        // Cut short, or not a PE image
+        assert_eq!(pe_subsystem(&image(2)[..0x90]), None);
+        let mut elf = image(2);
+        elf[..4].copy_from_slice(b"\x7fELF");
+        assert_eq!(pe_subsystem(&elf), None);
+        let mut far = image(2);
+        far[0x3c..0x40].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(pe_subsystem(&far), None);
+
+        #[cfg(target_os = "windows")]
+        {
+            let test_binary = std::env::current_exe().unwrap();
+            assert_eq!(read_pe_subsystem(&test_binary), Some(PeSubsystem::Console));
+            let notepad = PathBuf::from(std::env::var_os("SystemRoot").unwrap())
+                .join("System32")
+                .join("notepad.exe");
+            assert_eq!(read_pe_subsystem(&notepad), Some(PeSubsystem::Gui));
+        }
+    }
+
+    #[test]
+    fn test_pe_is_64bit() {
+        let image = |magic: u16| {
+            let mut header = vec![0u8; 0x200];
+            header[..2].copy_from_slice(b"MZ");
+            header[0x3c..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+            header[0x80..0x84].copy_from_slice(b"PE\0\0");
+            header[0x80 + 24..0x80 + 26].copy_from_slice(&magic.to_le_bytes());
+            header
+        };
+        assert_eq!(pe_is_64bit(&image(0x10b)), Some(false));
+        assert_eq!(pe_is_64bit(&image(0x20b)), Some(true));
+        assert_eq!(pe_is_64bit(&image(0x107)), None);
+        assert_eq!(pe_is_64bit(&image(0x20b)[..0x98]), None);
+        assert_eq!(pe_is_64bit(b"\x7fELF"), None);
+
+        #[cfg(target_os = "windows")]
+        {
+            let test_binary = std::env::current_exe().unwrap();
+            let header = read_pe_header(&test_binary).unwrap();
+            assert_eq!(
+                pe_is_64bit(&header),
+                Some(cfg!(target_pointer_width = "64"))
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_32bit_on_64bit() {
+        use RegistryView::{Native, Wow64};
+        // (registry view, image is 64-bit, OS is 64-bit, expected)
+        let cases = [
+            (Some(Wow64), Some(false), Some(true), Some(true)),
+            (Some(Wow64), None, Some(true), Some(true)),
+            (None, Some(false), Some(true), Some(true)),
+            (Some(Native), Some(true), Some(true), Some(false)),
+            (Some(Native), None, Some(true), Some(false)),
+            (None, Some(true), Some(true), Some(false)),
+            // The signals disagree
+            (Some(Native), Some(false), Some(true), None),
+            (Some(Wow64), Some(true), Some(true), None),
+            (None, None, Some(true), None),
+            // Nothing is 32-bit on 64-bit on a 32-bit OS
+            (Some(Native), Some(false), Some(false), Some(false)),
+            (Some(Wow64), Some(false), None, None),
+        ];
+        for (view, image_64bit, os_64bit, expected) in cases {
+            assert_eq!(
+                is_32bit_on_64bit(view, image_64bit, os_64bit),
+                expected,
+                "{:?} {:?} {:?}",
+                view,
+                image_64bit,
+                os_64bit
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "windows")]
+    fn test_registry_view() {
+        let mut wow64_apps = Vec::new();
+        for (root, uninstall_key) in uninstall_hives() {
+            let apps = scan_registry_key(root, uninstall_key, &ScanContext::new(0), 0).unwrap();
+            for app in apps {
+                let expected = if root == HKEY_CURRENT_USER {
+                    None
+                } else if uninstall_key.contains("WOW6432Node") {
+                    Some(RegistryView::Wow64)
+                } else {
+                    Some(RegistryView::Native)
+                };
+                assert_eq!(app.registry_view, expected, "{}", app.name);
+                if expected == Some(RegistryView::Wow64) {
+                    wow64_apps.push(app);
+                }
+            }
+        }
+
+        // 64-bit runners have 32-bit programs, such as parts of Visual Studio or Edge
+        if os_is_64bit() == Some(true) && cfg!(target_pointer_width = "64") {
+            assert!(
+                wow64_apps
+                    .iter()
+                    .any(|app| app.is_32bit_on_64bit_os() == Some(true)),
+                "no 32-bit app among {} WOW6432Node entries",
+                wow64_apps.len()
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "windows")]
+    fn test_display_icon_forms_keep_entries() {
+        let fixture = UninstallFixture::new(
+            "display-icon",
+            &[
+                (
+                    "Url",
+                    &[
+                        ("DisplayName", "Web Tool"),
+                        ("DisplayIcon", "https://www.contoso.com/favicon.ico"),
+                    ],
+                ),
+                (
+                    "Resource",
+                    &[
+                        ("DisplayName", "Packaged Tool"),
+                        ("DisplayIcon", "ms-resource:AppIcon"),
+                    ],
+                ),
+                (
+                    "Sound",
+                    &[
+                        ("DisplayName", "Sound"),
+                        (
+                            "DisplayIcon",
+                            "rundll32.exe shell32.dll,Control_RunDLL mmsys.cpl",
+                        ),
+                    ],
+                ),
+            ],
+        );
+
+        let apps = fixture.scan();
+        let names: Vec<&str> = apps.iter().map(|app| app.name.as_str()).collect();
+        assert_eq!(names, ["Packaged Tool", "Sound", "Web Tool"]);
+        assert_eq!(apps[0].path, PathBuf::new());
+        assert!(apps[1]
+            .path
+            .to_string_lossy()
+            .to_ascii_lowercase()
+            .ends_with(r"\system32\mmsys.cpl"));
+    }
+
+    #[test]
+    #[cfg(target_os = "windows")]
+    fn test_install_scope() {
+        for (root, uninstall_key) in uninstall_hives() {
+            let expected = if root == HKEY_LOCAL_MACHINE {
+                InstallScope::Machine
+            } else {
+                InstallScope::User
+            };
+            let apps = scan_registry_key(root, uninstall_key, &ScanContext::new(0), 0).unwrap();
+            for app in apps {
+                assert_eq!(app.install_scope, Some(expected), "{}", app.name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_registry_string() {
+        let wide = |text: &str| -> Vec<u8> {
+            text.encode_utf16()
+                .flat_map(|unit| unit.to_le_bytes())
+                .collect()
+        };
+        let terminated = |text: &str| {
+            let mut data = wide(text);
+            data.extend_from_slice(&[0, 0]);
+            data
+        };
+
+        assert_eq!(
+            decode_registry_string(1, &terminated("Mozilla Firefox")).as_deref(),
+            Some("Mozilla Firefox")
+        );
+        assert_eq!(
+            decode_registry_string(2, &terminated("%ProgramFiles%\\App")).as_deref(),
+            Some("%ProgramFiles%\\App")
+        );
+
+        // Missing terminator
+        assert_eq!(
+            decode_registry_string(1, &wide("Café")).as_deref(),
+            Some("Café")
+        );
+
+        // Odd length: the stray byte is dropped, the text before it kept
+        let mut odd = wide("7-Zip");
+        odd.push(b'x');
+        assert_eq!(decode_registry_string(1, &odd).as_deref(), Some("7-Zip"));
+        assert_eq!(decode_registry_string(1, &[0x41]).as_deref(), Some(""));
+
+        // Garbage after the first NUL, and the later strings of a REG_MULTI_SZ
+        let mut padded = terminated("Tool");
+        padded.extend_from_slice(&wide("junk"));
+        assert_eq!(decode_registry_string(1, &padded).as_deref(), Some("Tool"));
+        let mut multi = terminated("first");
+        multi.extend_from_slice(&terminated("second"));
+        multi.extend_from_slice(&[0, 0]);
+        assert_eq!(decode_registry_string(7, &multi).as_deref(), Some("first"));
+
+        // Unpaired surrogates are replaced rather than failing the value
+        let lone_surrogate = [0x00, 0xD8, b'A', 0];
+        assert_eq!(
+            decode_registry_string(1, &lone_surrogate).as_deref(),
+            Some("\u{FFFD}A")
+        );
+
+        assert_eq!(
+            decode_registry_string(1, &terminated("")).as_deref(),
+            Some("")
+        );
+        assert_eq!(decode_registry_string(1, &[]), None);
+
+        // REG_NONE, REG_BINARY and REG_DWORD are not text
+        assert_eq!(decode_registry_string(0, &terminated("text")), None);
+        assert_eq!(decode_registry_string(3, &terminated("text")), None);
+        assert_eq!(decode_registry_string(4, &1u32.to_le_bytes()), None);
+    }
+
+    #[test]
+    fn test_parse_registry_flag() {
+        let wide = |text: &str| -> Vec<u8> {
+            text.encode_utf16()
+                .chain([0])
+                .flat_map(|unit| unit.to_le_bytes())
+                .collect()
+        };
+
+        assert_eq!(parse_registry_flag(4, &1u32.to_le_bytes()), Some(true));
+        assert_eq!(parse_registry_flag(4, &0u32.to_le_bytes()), Some(false));
+        assert_eq!(parse_registry_flag(4, &2u32.to_le_bytes()), Some(true));
+        assert_eq!(parse_registry_flag(11, &1u64.to_le_bytes()), Some(true));
+        assert_eq!(parse_registry_flag(4, &[1, 0]), None);
+
+        // Some installers write the number as a string
+        assert_eq!(parse_registry_flag(1, &wide("1")), Some(true));
+        assert_eq!(parse_registry_flag(1, &wide(" 0 ")), Some(false));
+        assert_eq!(parse_registry_flag(2, &wide("1")), Some(true));
+        assert_eq!(parse_registry_flag(1, &wide("yes")), None);
+        assert_eq!(parse_registry_flag(1, &wide("")), None);
+
+        // REG_BINARY and REG_NONE are not flags
+        assert_eq!(parse_registry_flag(3, &[1, 0, 0, 0]), None);
+        assert_eq!(parse_registry_flag(0, &[1, 0, 0, 0]), None);
+    }
+
+    #[test]
+    fn test_open_with_list_order() {
+        let entries: Vec<(String, String)> = [
+            ("a", "notepad.exe"),
+            ("b", "Code.exe"),
+            ("c", "notepad++.exe"),
+            ("MRUList", "cab"),
+            ("d", "wordpad.exe"),
+        ]
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+
+        assert_eq!(
+            open_with_list_order(Some("cab"), &entries),
+            ["notepad++.exe", "notepad.exe", "Code.exe", "wordpad.exe"]
+        );
+        // Letters that name nothing are ignored
+        assert_eq!(
+            open_with_list_order(Some("zb"), &entries),
+            ["Code.exe", "notepad.exe", "notepad++.exe", "wordpad.exe"]
+        );
+        assert_eq!(
+            open_with_list_order(None, &entries),
+            ["notepad.exe", "Code.exe", "notepad++.exe", "wordpad.exe"]
+        );
+        assert!(open_with_list_order(Some("ab"), &[]).is_empty());
+    }
+
+    #[test]
+    fn test_trust_status() {
+        assert_eq!(trust_status(0), Some(SignatureStatus::Valid));
+        // TRUST_E_NOSIGNATURE
+        assert_eq!(trust_status(0x800B_0100_u32 as i32), None);
+        // TRUST_E_SUBJECT_FORM_UNKNOWN, for files that cannot be signed
+        assert_eq!(trust_status(0x800B_0003_u32 as i32), None);
+        // TRUST_E_BAD_DIGEST, CERT_E_UNTRUSTEDROOT and CERT_E_EXPIRED
+        for result in [0x8009_6010_u32, 0x800B_0109, 0x800B_0101] {
+            assert_eq!(trust_status(result as i32), Some(SignatureStatus::Invalid));
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "windows")]
+    fn test_get_signature_info() {
+        let system_root = std::env::var("SystemRoot").unwrap_or_else(|_| "C:\\Windows".to_string());
+        let explorer = PathBuf::from(&system_root).join("explorer.exe");
+        let signature = get_signature_info(&explorer).expect("Failed to check signature");
+        assert_ne!(signature.status, SignatureStatus::Unsigned);
+        if signature.status == SignatureStatus::Valid {
+            assert!(signature
+                .signer
+                .as_deref()
+                .is_some_and(|signer| signer.contains("Microsoft")));
+        }
+
+        let unsigned =
+            std::env::temp_dir().join(format!("app-info-unsigned-{}.exe", std::process::id()));
+        std::fs::write(&unsigned, b"MZ not really an executable").unwrap();
+        let signature = get_signature_info(&unsigned);
+        let _ = std::fs::remove_file(&unsigned);
+        assert_eq!(
+            signature.unwrap(),
+            SignatureInfo {
+                status: SignatureStatus::Unsigned,
+                signer: None,
+            }
+        );
+
+        assert!(matches!(
+            get_signature_info(Path::new("C:\\NonExistent\\nothing.exe")),
+            Err(AppInfoError::ExecutableNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_long_path() {
+        let long = |path: &str| {
+            long_path(std::path::Path::new(path))
+                .to_string_lossy()
+                .into_owned()
+        };
+        let deep = "d".repeat(100);
+
+        // Short paths are untouched, however they are written
+        for path in [
+            r"C:\Program Files\App\app.exe",
+            "C:/Program Files/App/../App/app.exe",
+            r"\\fileserver\apps\tool.exe",
+            r"relative\app.exe",
+        ] {
+            assert_eq!(long(path), path);
+        }
+
+        let local = format!(r"C:\Apps\{deep}\{deep}\.\x\..\{deep}\app.exe");
+        assert_eq!(
+            long(&local),
+            format!(r"\\?\C:\Apps\{deep}\{deep}\{deep}\app.exe")
+        );
+        assert_eq!(
+            long(&local.replace('\\', "/")),
+            format!(r"\\?\C:\Apps\{deep}\{deep}\{deep}\app.exe")
+        );
+
+        let unc = format!(r"\\fileserver\apps\..\..\{deep}\{deep}\{deep}\tool.exe");
+        assert_eq!(
+            long(&unc),
+            format!(r"\\?\UNC\fileserver\apps\{deep}\{deep}\{deep}\tool.exe")
+        );
+
+        // Already prefixed, device and relative paths cannot be improved on
+        let prefixed = format!(r"\\?\C:\{deep}\{deep}\{deep}");
+        assert_eq!(long(&prefixed), prefixed);
+        let relative = format!(r"{deep}\{deep}\{deep}");
+        assert_eq!(long(&relative), relative);
+        let no_share = format!(r"\\{deep}{deep}{deep}");
+        assert_eq!(long(&no_share), no_share);
+    }
+
+    #[test]
+    fn test_shell_parsing_name() {
+        let name = |path: &str| shell_parsing_name(std::path::Path::new(path));
+        assert_eq!(
+            name(r"C:\Program Files\App\app.exe"),
+            r"C:\Program Files\App\app.exe"
+        );
+        assert_eq!(
+            name(r#""C:\Program Files\App\app.exe""#),
+            r"C:\Program Files\App\app.exe"
+        );
+        assert_eq!(name("C:/Tools/app.exe"), r"C:\Tools\app.exe");
+        assert_eq!(
+            name(r"\\fileserver\apps\tool.exe"),
+            r"\\fileserver\apps\tool.exe"
+        );
+        assert_eq!(
+            name(r"\\?\UNC\fileserver\apps\tool.exe"),
+            r"\\fileserver\apps\tool.exe"
+        );
+        assert_eq!(name(r"\\?\D:\Games\game.exe"), r"D:\Games\game.exe");
+        assert_eq!(
+            name(r"\\?\Volume{0b5c0a5e-1d7f-4e0e-9b5a-2f6d3c1e8a47}\app.exe"),
+            r"\\?\Volume{0b5c0a5e-1d7f-4e0e-9b5a-2f6d3c1e8a47}\app.exe"
+        );
+    }
+
+    #[test]
+    fn test_unquote_path() {
+        assert_eq!(
+            unquote_path(r#" "C:\Program Files\App\app.exe" "#),
+            r"C:\Program Files\App\app.exe"
+        );
+        assert_eq!(unquote_path(r#""C:\App\"#), r"C:\App\");
+        assert_eq!(unquote_path(r"C:\App"), r"C:\App");
+        assert_eq!(unquote_path(r#""""#), "");
+    }
+
+    #[test]
+    #[cfg(target_os = "windows")]
+    fn test_find_main_executable_long_path() {
+        let mut install_dir =
+            std::env::temp_dir().join(format!("app-info-long-{}", std::process::id()));
+        let root = install_dir.clone();
+        while install_dir.to_string_lossy().len() < 300 {
+            install_dir.push("a".repeat(60));
+        }
+        std::fs::create_dir_all(long_path(&install_dir)).unwrap();
+        std::fs::write(long_path(&install_dir.join("app.exe")), b"MZ").unwrap();
+
+        assert_eq!(
+            find_main_executable(&install_dir),
+            Some(install_dir.join("app.exe"))
+        );
+        assert!(long_path(&install_dir.join("app.exe")).exists());
+
+        let _ = std::fs::remove_dir_all(long_path(&root));
+    }
+
+    #[test]
+    fn test_strip_verbatim_prefix() {
+        let strip = |path: &str| {
+            strip_verbatim_prefix(std::path::Path::new(path))
+                .to_string_lossy()
+                .into_owned()
+        };
+        assert_eq!(
+            strip(r"\\?\C:\Scoop\apps\tool\1.2.0"),
+            r"C:\Scoop\apps\tool\1.2.0"
+        );
+        assert_eq!(
+            strip(r"\\?\UNC\fileserver\apps\tool"),
+            r"\\fileserver\apps\tool"
+        );
+        assert_eq!(strip(r"C:\Program Files\Tool"), r"C:\Program Files\Tool");
+        let volume = r"\\?\Volume{6f2a8c1e-0000-0000-0000-100000000000}\Tool";
+        assert_eq!(strip(volume), volume);
+    }
+
+    /// Saves a `.lnk` shortcut to `target`.
+    #[cfg(target_os = "windows")]
+    fn create_shortcut(link: &Path, target: &Path) {
+        let _com = initialize_com();
+        let shell_link: IShellLinkW =
+            unsafe { CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER) }.unwrap();
+        unsafe { shell_link.SetPath(&HSTRING::from(target)) }.unwrap();
+        let persist_file: IPersistFile = shell_link.cast().unwrap();
+        unsafe { persist_file.Save(&HSTRING::from(link), true) }.unwrap();
+    }
+
+    #[test]
+    #[cfg(target_os = "windows")]
+    fn test_install_location_shortcut() {
+        let root = std::env::temp_dir().join(format!("app-info-shortcut-{}", std::process::id()));
+        defer!({
+            let _ = std::fs::remove_dir_all(&root);
+        });
+        let real = root.join("1.2.0");
+        std::fs::create_dir_all(&real).unwrap();
+        std::fs::write(real.join("tool.exe"), b"MZ").unwrap();
+        let same_file = |found: Option<PathBuf>, expected: &Path| {
+            let found = found.expect("no executable found");
+            assert_eq!(
+                std::fs::canonicalize(found).unwrap(),
+                std::fs::canonicalize(expected).unwrap()
+            );
+        };
+
+        // A shortcut to the program itself, or to the directory holding it
+        let to_program = root.join("to-program");
+        std::fs::create_dir(&to_program).unwrap();
+        create_shortcut(&to_program.join("Tool.lnk"), &real.join("tool.exe"));
+        std::fs::write(to_program.join("desktop.ini"), b"").unwrap();
+        same_file(find_main_executable(&to_program), &real.join("tool.exe"));
+
+        let to_directory = root.join("to-directory");
+        std::fs::create_dir(&to_directory).unwrap();
+        create_shortcut(&to_directory.join("Tool.lnk"), &real);
+        same_file(find_main_executable(&to_directory), &real.join("tool.exe"));
+
+        // Two shortcuts are not a stand-in for the directory
+        create_shortcut(&to_directory.join("Other.lnk"), &real);
+        assert_eq!(find_main_executable(&to_directory), None);
+
+        // Shortcuts that point at each other, or nowhere
+        let (cycle_a, cycle_b) = (root.join("cycle-a"), root.join("cycle-b"));
+        std::fs::create_dir(&cycle_a).unwrap();
+        std::fs::create_dir(&cycle_b).unwrap();
+        create_shortcut(&cycle_a.join("b.lnk"), &cycle_b);
+        create_shortcut(&cycle_b.join("a.lnk"), &cycle_a);
+        assert_eq!(find_main_executable(&cycle_a), None);
+
+        let broken = root.join("broken");
+        std::fs::create_dir(&broken).unwrap();
+        create_shortcut(
+            &broken.join("Gone.lnk"),
+            &root.join("gone").join("gone.exe"),
+        );
+        assert_eq!(find_main_executable(&broken), None);
+    }
+
+    #[test]
+    #[cfg(target_os = "windows")]
+    fn test_install_location_symlink() {
+        use std::os::windows::fs::symlink_dir;
+
+        let root = std::env::temp_dir().join(format!("app-info-symlink-{}", std::process::id()));
+        defer!({
+            let _ = std::fs::remove_dir_all(&root);
+        });
+        let real = root.join("1.2.0");
+        std::fs::create_dir_all(&real).unwrap();
+        std::fs::write(real.join("tool.exe"), b"MZ").unwrap();
+
+        // Creating symbolic links needs Developer Mode or the privilege for it
+        let current = root.join("current");
+        if symlink_dir(&real, &current).is_err() {
+            eprintln!("skipping: symbolic links cannot be created here");
+            return;
+        }
+        let target = strip_verbatim_prefix(&std::fs::canonicalize(&real).unwrap());
+        assert_eq!(
+            find_main_executable(&current),
+            Some(target.join("tool.exe"))
+        );
+
+        let (loop_a, loop_b) = (root.join("loop-a"), root.join("loop-b"));
+        symlink_dir(&loop_b, &loop_a).unwrap();
+        symlink_dir(&loop_a, &loop_b).unwrap();
+        assert_eq!(find_main_executable(&loop_a), None);
+
+        let dangling = root.join("dangling");
+        symlink_dir(root.join("missing"), &dangling).unwrap();
+        assert_eq!(find_main_executable(&dangling), None);
+    }
+
+    #[test]
+    fn test_installer_cache_icon() {
+        let product_code = "{3B8F2A1C-8E4D-4C61-9A57-0D2E6F1B7C90}";
+        let root = std::env::temp_dir().join(format!("app-info-installer-{}", std::process::id()));
+        let product = root.join(product_code);
+        std::fs::create_dir_all(&product).unwrap();
+
+        assert_eq!(installer_cache_icon(&root, product_code), None);
+
+        std::fs::write(product.join("_A1B2C3.exe"), b"MZ").unwrap();
+        std::fs::write(product.join("readme.txt"), b"").unwrap();
+        assert_eq!(
+            installer_cache_icon(&root, product_code),
+            Some(product.join("_A1B2C3.exe"))
+        );
+
+        std::fs::write(product.join("product.ico"), b"").unwrap();
+        assert_eq!(
+            installer_cache_icon(&root, product_code),
+            Some(product.join("product.ico"))
+        );
+
+        std::fs::write(product.join("ARPPRODUCTICON.exe"), b"MZ").unwrap();
+        assert_eq!(
+            installer_cache_icon(&root, product_code),
+            Some(product.join("ARPPRODUCTICON.exe"))
+        );
+
+        // Only product codes can name a cache folder
+        assert_eq!(installer_cache_icon(&root, "..\\Windows"), None);
+        assert_eq!(installer_cache_icon(&root, "Mozilla Firefox"), None);
+        assert_eq!(
+            installer_cache_icon(&root, "{3B8F2A1C-8E4D-4C61-9A57-0D2E6F1B7C9}"),
+            None
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    #[cfg(all(target_os = "windows", feature = "icons"))]
+    fn test_installer_cache_icon_fallback() {
+        let Some(installer_dir) = windows_installer_dir() else {
+            return;
+        };
+
+        // Products whose registry entry gives no icon, but whose icon Windows Installer cached
+        let mut candidates = 0;
+        let mut checked = 0;
+        let mut missing = Vec::new();
+        for app in get_installed_apps(32).unwrap() {
+            let Some(product_code) = app
+                .identifier
+                .as_deref()
+                .filter(|identifier| crate::identity::is_product_code(identifier))
+            else {
+                continue;
+            };
+            if matches!(app.icon_source, Some(IconSource::Resource { .. })) {
+                continue;
+            }
+            let Some(cached) = installer_cache_icon(&installer_dir, product_code) else {
+                continue;
+            };
+            if get_file_icon(&cached, 32).is_err() {
+                continue;
+            }
+            candidates += 1;
+            if app.icon.is_some() && app.icon_source == Some(IconSource::File(cached.clone())) {
+                checked += 1;
+            } else {
+                missing.push((app.name, cached));
+            }
+        }
+        assert!(
+            candidates == 0 || checked > 0,
+            "no product gained its cached icon: {:?}",
+            missing
+        );
+        assert!(missing.is_empty(), "cached icons not used: {:?}", missing);
+    }
+
+    #[test]
+    fn test_expand_environment_strings() {
+        let lookup = |name: &str| match name {
+            "ProgramFiles" => Some(r"C:\Program Files".to_string()),
+            _ => None,
+        };
+        assert_eq!(
+            expand_environment_strings(r"%ProgramFiles%\App\app.exe", lookup),
+            r"C:\Program Files\App\app.exe"
+        );
+        assert_eq!(
+            expand_environment_strings("%Missing%\\x", lookup),
+            "%Missing%\\x"
+        );
+        assert_eq!(expand_environment_strings("100%", lookup), "100%");
+        assert_eq!(expand_environment_strings("%%", lookup), "%%");
+    }
+
+    #[test]
+    fn test_png_dimensions() {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        png.extend_from_slice(&512u32.to_be_bytes());
+        png.extend_from_slice(&384u32.to_be_bytes());
+        assert_eq!(png_dimensions(&png), Some((512, 384)));
+        assert_eq!(png_dimensions(b"BM not a png"), None);
+    }
+}
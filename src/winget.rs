@@ -0,0 +1,613 @@
+use crate::{normalize_publisher, AppInfo, InstallScope, PackageRef, RegistryView};
+
+/// One installed package that winget lists.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct WingetRow {
+    name: String,
+    id: String,
+    version: Option<String>,
+    available_version: Option<String>,
+    /// The publisher, which only `winget list --details` reports
+    publisher: Option<String>,
+    /// The Uninstall entry winget matched the package to, which only `winget list
+    /// --details` reports
+    entry: Option<UninstallEntryRef>,
+}
+
+/// An Uninstall entry as winget names it in a local identifier such as
+/// `ARP\Machine\X64\{23170F69-40C1-2702-2301-000001000000}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct UninstallEntryRef {
+    scope: Option<InstallScope>,
+    /// Whether the entry is in the 32-bit view, when the architecture says
+    wow64: Option<bool>,
+    /// The name of the entry's key: the product code of Windows Installer products
+    key: String,
+}
+
+/// Runs `winget list` and attaches the package of every matching app on Windows.
+#[cfg(target_os = "windows")]
+pub(crate) fn attach_winget_packages(apps: &mut [AppInfo]) -> crate::Result<usize> {
+    use crate::error::AppInfoError;
+    use std::os::windows::process::CommandExt;
+
+    /// `CREATE_NO_WINDOW`, so no console flashes up from GUI processes
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let winget_list = |details: bool| {
+        let mut command = std::process::Command::new("winget");
+        command.args([
+            "list",
+            "--source",
+            "winget",
+            "--accept-source-agreements",
+            "--disable-interactivity",
+        ]);
+        if details {
+            command.arg("--details");
+        }
+        command.creation_flags(CREATE_NO_WINDOW).output()
+    };
+
+    enter_span!(DEBUG, "attach_winget_packages");
+    // The details name each package's Uninstall entry and publisher, but winget only has
+    // them in recent versions; older ones reject the flag, and the table is read instead
+    let output = match winget_list(true) {
+        Ok(output) => output,
+        // No winget on this machine; nothing to attach
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(AppInfoError::PackageManagerError(err.to_string())),
+    };
+    let mut rows = Vec::new();
+    if output.status.success() {
+        rows = parse_winget_details(&String::from_utf8_lossy(&output.stdout));
+    }
+    let output = if rows.is_empty() {
+        let output =
+            winget_list(false).map_err(|err| AppInfoError::PackageManagerError(err.to_string()))?;
+        rows = parse_winget_list(&String::from_utf8_lossy(&output.stdout));
+        output
+    } else {
+        output
+    };
+
+    if rows.is_empty() && !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(AppInfoError::PackageManagerError(if message.is_empty() {
+            format!("winget exited with {}", output.status)
+        } else {
+            message
+        }));
+    }
+    debug_event!(packages = rows.len(), "listed winget packages");
+    Ok(attach_rows(apps, rows))
+}
+
+/// Splits winget's output into lines, dropping the progress spinners it draws with
+/// carriage returns.
+fn output_lines(text: &str) -> Vec<&str> {
+    text.lines()
+        .map(|line| line.rsplit('\r').next().unwrap_or(line))
+        .collect()
+}
+
+/// Parses the table `winget list` prints: a header row, a row of dashes, then one row per
+/// package, with columns aligned to the header. Returns nothing if there is no table.
+/// Rows whose ID is cut off with an ellipsis to fit the table are left out, since the
+/// ID is no use to winget then.
+fn parse_winget_list(text: &str) -> Vec<WingetRow> {
+    let lines = output_lines(text);
+    let Some(separator) = lines
+        .iter()
+        .position(|line| line.len() > 10 && line.trim_end().chars().all(|c| c == '-'))
+    else {
+        return Vec::new();
+    };
+    let Some(header) = separator.checked_sub(1).map(|index| lines[index]) else {
+        return Vec::new();
+    };
+
+    // Name, Id, Version, then Available when any package has an update, and Source
+    let header: Vec<char> = header.chars().collect();
+    let starts: Vec<usize> = (0..header.len())
+        .filter(|&i| header[i] != ' ' && (i == 0 || header[i - 1] == ' '))
+        .collect();
+    if !(3..=5).contains(&starts.len()) {
+        return Vec::new();
+    }
+    let has_available = starts.len() == 5;
+
+    lines[separator + 1..]
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| {
+            let chars: Vec<char> = line.chars().collect();
+            let column = |index: usize| -> Option<String> {
+                let start = *starts.get(index)?;
+                let end = starts.get(index + 1).copied().unwrap_or(chars.len());
+                let value: String = chars.get(start..end.min(chars.len()))?.iter().collect();
+                let value = value.trim();
+                (!value.is_empty()).then(|| value.to_string())
+            };
+            Some(WingetRow {
+                name: column(0)?,
+                id: column(1).filter(|id| !id.ends_with('…'))?,
+                version: column(2),
+                available_version: if has_available { column(3) } else { None },
+                publisher: None,
+                entry: None,
+            })
+        })
+        .collect()
+}
+
+/// Parses what `winget list --details` prints: for each package a heading such as
+/// `(1/42) Mozilla Firefox [Mozilla.Firefox]`, then `Label: value` lines and the
+/// available upgrades, indented, as in `winget [128.0]`. Labels are translated, so the
+/// local identifier and upgrades are recognized by their values; the version and
+/// publisher are only read from English output.
+fn parse_winget_details(text: &str) -> Vec<WingetRow> {
+    let mut rows: Vec<WingetRow> = Vec::new();
+    for line in output_lines(text) {
+        if let Some(row) = parse_details_heading(line) {
+            rows.push(row);
+            continue;
+        }
+        let Some(row) = rows.last_mut() else {
+            continue;
+        };
+
+        let value = line.trim();
+        if line.starts_with(char::is_whitespace) {
+            let upgrade = value
+                .strip_prefix("winget [")
+                .and_then(|rest| rest.strip_suffix(']'));
+            if let Some(version) = upgrade.filter(|version| !version.is_empty()) {
+                row.available_version
+                    .get_or_insert_with(|| version.to_string());
+            }
+            continue;
+        }
+        let Some((label, value)) = value.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if let Some(entry) = parse_local_identifier(value) {
+            row.entry = Some(entry);
+        } else if value.is_empty() {
+            continue;
+        } else if label == "Version" {
+            row.version = Some(value.to_string());
+        } else if label == "Publisher" {
+            row.publisher = Some(value.to_string());
+        }
+    }
+    rows
+}
+
+/// Reads a package heading of `winget list --details`, as in `(1/42) Name [Id]`.
+fn parse_details_heading(line: &str) -> Option<WingetRow> {
+    let (counter, rest) = line.strip_prefix('(')?.split_once(") ")?;
+    let (current, total) = counter.split_once('/')?;
+    let is_number = |text: &str| !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit());
+    if !is_number(current) || !is_number(total) {
+        return None;
+    }
+    let (name, id) = rest.trim_end().strip_suffix(']')?.rsplit_once(" [")?;
+    let (name, id) = (name.trim(), id.trim());
+    if name.is_empty() || id.is_empty() {
+        return None;
+    }
+    Some(WingetRow {
+        name: name.to_string(),
+        id: id.to_string(),
+        version: None,
+        available_version: None,
+        publisher: None,
+        entry: None,
+    })
+}
+
+/// Reads a local identifier naming an Uninstall entry, as in `ARP\User\X86\Slack`.
+fn parse_local_identifier(value: &str) -> Option<UninstallEntryRef> {
+    let mut parts = value.splitn(4, '\\');
+    if parts.next()? != "ARP" {
+        return None;
+    }
+    let scope = match parts.next()? {
+        scope if scope.eq_ignore_ascii_case("machine") => Some(InstallScope::Machine),
+        scope if scope.eq_ignore_ascii_case("user") => Some(InstallScope::User),
+        _ => None,
+    };
+    let wow64 = match parts.next()? {
+        arch if arch.eq_ignore_ascii_case("x86") => Some(true),
+        arch if arch.eq_ignore_ascii_case("x64") || arch.eq_ignore_ascii_case("arm64") => {
+            Some(false)
+        }
+        _ => None,
+    };
+    let key = parts.next()?.trim();
+    (!key.is_empty()).then(|| UninstallEntryRef {
+        scope,
+        wow64,
+        key: key.to_string(),
+    })
+}
+
+/// Lowercases a name and keeps only its letters and digits, single-spaced.
+fn normalize_name(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Attaches each row to an app and returns how many apps got a package.
+///
+/// Rows that name their Uninstall entry go to the app read from that entry, whose
+/// identifier is the entry's key name: the product code of Windows Installer products.
+/// These are matched first, so that the other rows, matched by name, cannot take an app
+/// another row names exactly.
+fn attach_rows(apps: &mut [AppInfo], rows: Vec<WingetRow>) -> usize {
+    let mut attached = 0;
+    let mut by_name = Vec::new();
+    for row in rows {
+        match row
+            .entry
+            .as_ref()
+            .and_then(|entry| entry_match(apps, entry))
+        {
+            Some(index) => {
+                attach(&mut apps[index], &row);
+                attached += 1;
+            }
+            None => by_name.push(row),
+        }
+    }
+    for row in by_name {
+        if let Some(index) = name_match(apps, &row) {
+            attach(&mut apps[index], &row);
+            attached += 1;
+        }
+    }
+    attached
+}
+
+/// Finds the app read from an Uninstall entry, preferring the registry view its
+/// architecture gives when both views have the key.
+fn entry_match(apps: &[AppInfo], entry: &UninstallEntryRef) -> Option<usize> {
+    let candidates: Vec<usize> = apps
+        .iter()
+        .enumerate()
+        .filter(|(_, app)| app.package_manager.is_none())
+        .filter(|(_, app)| {
+            app.identifier
+                .as_deref()
+                .is_some_and(|identifier| identifier.trim().eq_ignore_ascii_case(&entry.key))
+        })
+        .filter(|(_, app)| match (entry.scope, app.install_scope) {
+            (Some(expected), Some(scope)) => expected == scope,
+            _ => true,
+        })
+        .map(|(index, _)| index)
+        .collect();
+    candidates
+        .iter()
+        .copied()
+        .find(|&index| entry.wow64 == Some(apps[index].registry_view == Some(RegistryView::Wow64)))
+        .or_else(|| candidates.first().copied())
+}
+
+/// Finds the app with the row's name, ignoring case and punctuation. When both have a
+/// publisher they must match too. Of several apps with the name, the one with the row's
+/// version is chosen; without one, the row is left unmatched unless they all come from
+/// one publisher, so that a package never goes to another vendor's app of the same name.
+fn name_match(apps: &[AppInfo], row: &WingetRow) -> Option<usize> {
+    // Long names are cut off with an ellipsis
+    let (name, truncated) = match row.name.strip_suffix('…') {
+        Some(prefix) => (normalize_name(prefix), true),
+        None => (normalize_name(&row.name), false),
+    };
+    if name.is_empty() {
+        return None;
+    }
+    let publisher = row.publisher.as_deref().map(normalize_publisher);
+
+    let candidates: Vec<usize> = apps
+        .iter()
+        .enumerate()
+        .filter(|(_, app)| app.package_manager.is_none())
+        .filter(|(_, app)| {
+            let app_name = normalize_name(&app.name);
+            app_name == name || (truncated && app_name.starts_with(&name))
+        })
+        .filter(|(_, app)| match (&publisher, app.publisher.as_deref()) {
+            (Some(publisher), Some(app_publisher)) => {
+                *publisher == normalize_publisher(app_publisher)
+            }
+            _ => true,
+        })
+        .map(|(index, _)| index)
+        .collect();
+
+    let same_version = candidates
+        .iter()
+        .copied()
+        .find(|&index| row.version.is_some() && apps[index].version == row.version);
+    if same_version.is_some() {
+        return same_version;
+    }
+    let first = *candidates.first()?;
+    let publisher_of = |index: usize| apps[index].publisher.as_deref().map(normalize_publisher);
+    candidates
+        .iter()
+        .all(|&index| publisher_of(index) == publisher_of(first))
+        .then_some(first)
+}
+
+/// Records a row's package on an app.
+fn attach(app: &mut AppInfo, row: &WingetRow) {
+    app.package_manager = Some(PackageRef {
+        manager: "winget".to_string(),
+        id: row.id.clone(),
+        available_version: row.available_version.clone(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const LIST: &str = "\r   - \r   \\ \r\
+Name                                    Id                       Version        Available      Source\n\
+-------------------------------------------------------------------------------------------------------\n\
+Mozilla Firefox (x64 en-US)             Mozilla.Firefox          127.0          128.0          winget\n\
+7-Zip 23.01 (x64)                       7zip.7zip                23.01                         winget\n\
+Microsoft Visual C++ 2015-2022 Redistr… Microsoft.VCRedist.2015+ 14.38.33135.0                 winget\n\
+Python 3.12.1 (64-bit)                  Python.Python.3.12       3.12.1150.0    3.12.4         winget\n\
+Visual Studio Community 2022            Microsoft.VisualStudio.… 17.10.4                       winget\n";
+
+    const DETAILS: &str = "\r   - \r\
+(1/4) Mozilla Firefox (x64 en-US) [Mozilla.Firefox]\n\
+Version: 127.0\n\
+Publisher: Mozilla\n\
+Local Identifier: ARP\\Machine\\X64\\Mozilla Firefox 127.0 (x64 en-US)\n\
+Product Code: Mozilla Firefox 127.0 (x64 en-US)\n\
+Installer Category: exe\n\
+Installed Scope: Machine\n\
+Available Upgrades:\n  winget [128.0]\n\
+\n\
+(2/4) 7-Zip 23.01 (x64) [7zip.7zip]\n\
+Version: 23.01\n\
+Publisher: Igor Pavlov\n\
+Local Identifier: ARP\\Machine\\X86\\{23170F69-40C1-2702-2301-000001000000}\n\
+\n\
+(3/4) Setup [Contoso.Setup]\n\
+Version: 2.0\n\
+Publisher: Contoso Ltd.\n\
+\n\
+(4/4) Slack [SlackTechnologies.Slack]\n\
+Version : 4.38\n\
+Lokale ID: ARP\\User\\X64\\slack\n";
+
+    fn app(name: &str, version: &str) -> AppInfo {
+        AppInfo {
+            name: name.to_string(),
+            version: Some(version.to_string()),
+            path: PathBuf::from("C:\\Program Files").join(name),
+            ..Default::default()
+        }
+    }
+
+    fn package_id(app: &AppInfo) -> Option<&str> {
+        app.package_manager
+            .as_ref()
+            .map(|package| package.id.as_str())
+    }
+
+    #[test]
+    fn test_parse_winget_list() {
+        // The row whose ID is cut off is left out
+        let rows = parse_winget_list(LIST);
+        assert_eq!(rows.len(), 4);
+        assert_eq!(
+            rows[0],
+            WingetRow {
+                name: "Mozilla Firefox (x64 en-US)".to_string(),
+                id: "Mozilla.Firefox".to_string(),
+                version: Some("127.0".to_string()),
+                available_version: Some("128.0".to_string()),
+                publisher: None,
+                entry: None,
+            }
+        );
+        assert_eq!(rows[1].available_version, None);
+        assert_eq!(rows[2].name, "Microsoft Visual C++ 2015-2022 Redistr…");
+        assert_eq!(rows[2].id, "Microsoft.VCRedist.2015+");
+
+        // Without updates there is no Available column
+        let no_updates = "Name      Id           Version Source\n\
+-------------------------------------\n\
+7-Zip     7zip.7zip    23.01   winget\n";
+        let rows = parse_winget_list(no_updates);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].version.as_deref(), Some("23.01"));
+        assert_eq!(rows[0].available_version, None);
+
+        assert!(
+            parse_winget_list("No installed package found matching input criteria.").is_empty()
+        );
+        assert!(parse_winget_list("").is_empty());
+    }
+
+    #[test]
+    fn test_attach_rows() {
+        let mut apps = vec![
+            app("Mozilla Firefox (x64 en-US)", "127.0"),
+            app("7-Zip 23.01 (x64)", "23.01"),
+            app(
+                "Microsoft Visual C++ 2015-2022 Redistributable (x86) - 14.30.30704",
+                "14.30.30704.0",
+            ),
+            app(
+                "Microsoft Visual C++ 2015-2022 Redistributable (x64) - 14.38.33135",
+                "14.38.33135.0",
+            ),
+            app("Python 3.12.1 (64-bit)", "3.12.1150.0"),
+            app("Notepad++", "8.6"),
+        ];
+
+        let attached = attach_rows(&mut apps, parse_winget_list(LIST));
+        assert_eq!(attached, 4);
+
+        let firefox = apps[0].package_manager.as_ref().unwrap();
+        assert_eq!(firefox.manager, "winget");
+        assert_eq!(firefox.id, "Mozilla.Firefox");
+        assert_eq!(firefox.available_version.as_deref(), Some("128.0"));
+
+        // The truncated name matches the entry with the same version
+        assert!(apps[2].package_manager.is_none());
+        assert_eq!(
+            apps[3]
+                .package_manager
+                .as_ref()
+                .map(|package| package.id.as_str()),
+            Some("Microsoft.VCRedist.2015+")
+        );
+        assert!(apps[5].package_manager.is_none());
+    }
+
+    #[test]
+    fn test_parse_winget_details() {
+        let rows = parse_winget_details(DETAILS);
+        assert_eq!(rows.len(), 4);
+        assert_eq!(
+            rows[0],
+            WingetRow {
+                name: "Mozilla Firefox (x64 en-US)".to_string(),
+                id: "Mozilla.Firefox".to_string(),
+                version: Some("127.0".to_string()),
+                available_version: Some("128.0".to_string()),
+                publisher: Some("Mozilla".to_string()),
+                entry: Some(UninstallEntryRef {
+                    scope: Some(InstallScope::Machine),
+                    wow64: Some(false),
+                    key: "Mozilla Firefox 127.0 (x64 en-US)".to_string(),
+                }),
+            }
+        );
+        assert_eq!(
+            rows[1].entry,
+            Some(UninstallEntryRef {
+                scope: Some(InstallScope::Machine),
+                wow64: Some(true),
+                key: "{23170F69-40C1-2702-2301-000001000000}".to_string(),
+            })
+        );
+        assert_eq!(rows[1].available_version, None);
+        assert_eq!(rows[2].entry, None);
+        assert_eq!(rows[2].publisher.as_deref(), Some("Contoso Ltd."));
+
+        // Translated labels still give the local identifier away
+        assert_eq!(rows[3].version, None);
+        assert_eq!(
+            rows[3].entry.as_ref().map(|entry| entry.scope),
+            Some(Some(InstallScope::User))
+        );
+
+        // Older winget rejects --details, and tables are not mistaken for details
+        assert!(parse_winget_details(LIST).is_empty());
+        assert!(parse_winget_details(
+            "Argument name was not recognized for the current command: '--details'"
+        )
+        .is_empty());
+        assert!(parse_details_heading("(1/x) Name [Id]").is_none());
+        assert!(parse_local_identifier(r"MSIX\Microsoft.WindowsTerminal_1.20").is_none());
+    }
+
+    #[test]
+    fn test_attach_rows_by_entry() {
+        let entry_app = |name: &str, identifier: &str, view: RegistryView| AppInfo {
+            identifier: Some(identifier.to_string()),
+            install_scope: Some(InstallScope::Machine),
+            registry_view: Some(view),
+            ..app(name, "1.0")
+        };
+        let mut apps = vec![
+            // Matched by its entry although its name differs from winget's
+            entry_app(
+                "Firefox",
+                "Mozilla Firefox 127.0 (x64 en-US)",
+                RegistryView::Native,
+            ),
+            // Same key in both views; the X86 identifier picks the 32-bit one
+            entry_app(
+                "7-Zip",
+                "{23170F69-40C1-2702-2301-000001000000}",
+                RegistryView::Native,
+            ),
+            entry_app(
+                "7-Zip",
+                "{23170F69-40C1-2702-2301-000001000000}",
+                RegistryView::Wow64,
+            ),
+            AppInfo {
+                publisher: Some("Setup Makers Inc".to_string()),
+                ..app("Setup", "2.0")
+            },
+            AppInfo {
+                publisher: Some("CONTOSO".to_string()),
+                ..app("Setup", "1.0")
+            },
+            // Scope differs from the User entry winget names
+            entry_app("Slack", "slack", RegistryView::Native),
+        ];
+
+        let attached = attach_rows(&mut apps, parse_winget_details(DETAILS));
+        assert_eq!(attached, 4);
+        assert_eq!(package_id(&apps[0]), Some("Mozilla.Firefox"));
+        assert_eq!(package_id(&apps[1]), None);
+        assert_eq!(package_id(&apps[2]), Some("7zip.7zip"));
+        // The publisher rules out the other vendor's app, despite its matching version
+        assert_eq!(package_id(&apps[3]), None);
+        assert_eq!(package_id(&apps[4]), Some("Contoso.Setup"));
+        // Falls back to the name, the scope of the entry not matching
+        assert_eq!(package_id(&apps[5]), Some("SlackTechnologies.Slack"));
+    }
+
+    #[test]
+    fn test_attach_rows_ambiguous_name() {
+        let published = |publisher: &str, version: &str| AppInfo {
+            publisher: Some(publisher.to_string()),
+            ..app("Updater", version)
+        };
+        let row = |version: &str| WingetRow {
+            name: "Updater".to_string(),
+            id: "Contoso.Updater".to_string(),
+            version: Some(version.to_string()),
+            available_version: None,
+            publisher: None,
+            entry: None,
+        };
+
+        // Without a publisher or version to tell them apart, neither app gets the package
+        let mut apps = vec![published("Fabrikam", "1.0"), published("Contoso", "2.0")];
+        assert_eq!(attach_rows(&mut apps, vec![row("3.0")]), 0);
+
+        // The version still singles one out, and one vendor's copies are no conflict
+        assert_eq!(attach_rows(&mut apps, vec![row("2.0")]), 1);
+        assert_eq!(package_id(&apps[1]), Some("Contoso.Updater"));
+        let mut apps = vec![published("Contoso Ltd", "1.0"), published("Contoso", "1.1")];
+        assert_eq!(attach_rows(&mut apps, vec![row("3.0")]), 1);
+        assert_eq!(package_id(&apps[0]), Some("Contoso.Updater"));
+    }
+
+    #[test]
+    fn test_normalize_name() {
+        assert_eq!(normalize_name("  7-Zip 23.01 (x64) "), "7 zip 23 01 x64");
+        assert_eq!(normalize_name("Notepad++"), "notepad");
+        assert_eq!(normalize_name("—"), "");
+    }
+}
@@ -0,0 +1,321 @@
+use crate::{AppInfo, AppSource, Result, ScanContext};
+
+/// A source for the programs WMI reports on Windows, for environments whose other tooling
+/// relies on WMI's view of what is installed.
+///
+/// Programs come from `Win32_InstalledWin32Program` and `Win32_InstalledStoreProgram`.
+/// Systems without those classes fall back to `Win32_Product`, which only lists Windows
+/// Installer products and makes the installer check every one of them, so a scan can take
+/// minutes. Either way the source is far slower than the registry scan.
+///
+/// A program already in the Uninstall registry keys, by product code or by name and
+/// version, is reported as the registry has it, so it merges with the built-in source's
+/// entry when both are scanned. Other programs get their name, version, publisher,
+/// product code and install location from WMI, and no icon. A query that fails, such as
+/// when the WMI service is stopped or access is denied, is reported in
+/// [`crate::ScanReport::errors`] while the other sources' apps are kept. Other platforms
+/// yield no apps.
+///
+/// The source is not part of the built-in ones; add it to [`crate::ScanOptions::sources`].
+#[derive(Debug, Clone, Default)]
+pub struct WmiSource;
+
+impl WmiSource {
+    /// Creates a source for the programs WMI reports.
+    pub fn new() -> Self {
+        WmiSource
+    }
+}
+
+impl AppSource for WmiSource {
+    fn name(&self) -> &str {
+        "wmi"
+    }
+
+    #[cfg(target_os = "windows")]
+    fn scan(&self, ctx: &ScanContext) -> Result<Vec<AppInfo>> {
+        enter_span!(DEBUG, "wmi_scan");
+        let products = query_products()?;
+        debug_event!(products = products.len(), "queried WMI");
+        if ctx.is_cancelled() {
+            return Ok(Vec::new());
+        }
+
+        // Matched against the registry so the entries merge with the built-in source's
+        let registry = crate::window::get_installed_apps(0).unwrap_or_default();
+        let apps = wmi_apps(products, &registry);
+        ctx.report_progress(apps.len());
+        Ok(apps)
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    fn scan(&self, ctx: &ScanContext) -> Result<Vec<AppInfo>> {
+        let _ = ctx;
+        Ok(Vec::new())
+    }
+}
+
+/// The properties read from one WMI program instance.
+#[cfg(any(target_os = "windows", test))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct WmiProduct {
+    name: String,
+    version: Option<String>,
+    vendor: Option<String>,
+    /// `IdentifyingNumber`, or `MsiProductCode` for the installed-program classes
+    product_code: Option<String>,
+    install_location: Option<String>,
+}
+
+/// Queries the installed-program classes, or `Win32_Product` where neither exists.
+#[cfg(target_os = "windows")]
+fn query_products() -> Result<Vec<WmiProduct>> {
+    use crate::error::AppInfoError;
+    use windows::core::{BSTR, PCWSTR};
+    use windows::Win32::System::Com::{
+        CoCreateInstance, CoSetProxyBlanket, CLSCTX_INPROC_SERVER, EOAC_NONE,
+        RPC_C_AUTHN_LEVEL_CALL, RPC_C_IMP_LEVEL_IMPERSONATE,
+    };
+    use windows::Win32::System::Wmi::{IWbemLocator, WbemLocator, WBEM_E_INVALID_CLASS};
+
+    /// `RPC_C_AUTHN_WINNT` and `RPC_C_AUTHZ_NONE`
+    const AUTHN_WINNT: u32 = 10;
+    const AUTHZ_NONE: u32 = 0;
+
+    let failed = |err: windows::core::Error| AppInfoError::WmiError(err.message().to_string());
+    let _com = crate::window::initialize_com();
+    let services = unsafe {
+        let locator: IWbemLocator =
+            CoCreateInstance(&WbemLocator, None, CLSCTX_INPROC_SERVER).map_err(failed)?;
+        let services = locator
+            .ConnectServer(
+                &BSTR::from("ROOT\\CIMV2"),
+                &BSTR::new(),
+                &BSTR::new(),
+                &BSTR::new(),
+                0,
+                &BSTR::new(),
+                None,
+            )
+            .map_err(failed)?;
+        CoSetProxyBlanket(
+            &services,
+            AUTHN_WINNT,
+            AUTHZ_NONE,
+            PCWSTR::null(),
+            RPC_C_AUTHN_LEVEL_CALL,
+            RPC_C_IMP_LEVEL_IMPERSONATE,
+            None,
+            EOAC_NONE,
+        )
+        .map_err(failed)?;
+        services
+    };
+
+    let mut products = Vec::new();
+    let mut found_class = false;
+    for class in ["Win32_InstalledWin32Program", "Win32_InstalledStoreProgram"] {
+        match query_class(&services, class) {
+            Ok(rows) => {
+                found_class = true;
+                products.extend(rows);
+            }
+            // Added in Windows 10; older systems only have Win32_Product
+            Err(err) if err.code() == windows::core::HRESULT(WBEM_E_INVALID_CLASS.0) => {}
+            Err(err) => return Err(failed(err)),
+        }
+    }
+    if !found_class {
+        products = query_class(&services, "Win32_Product").map_err(failed)?;
+    }
+    Ok(products)
+}
+
+/// Reads every instance of `class`, skipping the ones without a name.
+#[cfg(target_os = "windows")]
+fn query_class(
+    services: &windows::Win32::System::Wmi::IWbemServices,
+    class: &str,
+) -> windows::core::Result<Vec<WmiProduct>> {
+    use windows::core::{BSTR, HSTRING, VARIANT};
+    use windows::Win32::System::Wmi::{
+        IWbemClassObject, WBEM_FLAG_FORWARD_ONLY, WBEM_FLAG_RETURN_IMMEDIATELY,
+        WBEM_GENERIC_FLAG_TYPE, WBEM_INFINITE,
+    };
+
+    let flags = WBEM_GENERIC_FLAG_TYPE(WBEM_FLAG_FORWARD_ONLY.0 | WBEM_FLAG_RETURN_IMMEDIATELY.0);
+    let enumerator = unsafe {
+        services.ExecQuery(
+            &BSTR::from("WQL"),
+            &BSTR::from(format!("SELECT * FROM {}", class)),
+            flags,
+            None,
+        )?
+    };
+
+    let mut products = Vec::new();
+    loop {
+        let mut objects: [Option<IWbemClassObject>; 1] = [None];
+        let mut returned = 0;
+        // Errors such as an unknown class only surface here with RETURN_IMMEDIATELY
+        unsafe { enumerator.Next(WBEM_INFINITE, &mut objects, &mut returned) }.ok()?;
+        let Some(object) = objects[0].take().filter(|_| returned > 0) else {
+            break;
+        };
+
+        // Properties the class lacks fail with WBEM_E_NOT_FOUND, and unset ones are null
+        let property = |name: &str| -> Option<String> {
+            let mut value = VARIANT::default();
+            unsafe { object.Get(&HSTRING::from(name), 0, &mut value, None, None) }.ok()?;
+            let value = BSTR::try_from(&value).ok()?.to_string();
+            let value = value.trim();
+            (!value.is_empty()).then(|| value.to_string())
+        };
+        let Some(name) = property("Name") else {
+            continue;
+        };
+        products.push(WmiProduct {
+            name,
+            version: property("Version"),
+            vendor: property("Vendor"),
+            product_code: property("IdentifyingNumber").or_else(|| property("MsiProductCode")),
+            install_location: property("InstallLocation"),
+        });
+    }
+    Ok(products)
+}
+
+/// Builds the apps for the WMI products, reporting each one the registry also lists as
+/// the registry's entry. A product that matches several registry entries reports each.
+#[cfg(any(target_os = "windows", test))]
+fn wmi_apps(products: Vec<WmiProduct>, registry: &[AppInfo]) -> Vec<AppInfo> {
+    use crate::glob::fold_case;
+
+    let mut apps = Vec::new();
+    for product in products {
+        let name = fold_case(product.name.trim());
+        let matches: Vec<&AppInfo> = registry
+            .iter()
+            .filter(|app| {
+                let same_code = product
+                    .product_code
+                    .as_deref()
+                    .zip(app.identifier.as_deref())
+                    .is_some_and(|(code, identifier)| code.eq_ignore_ascii_case(identifier));
+                same_code
+                    || (fold_case(app.name.trim()) == name
+                        && app.version.is_some()
+                        && app.version == product.version)
+            })
+            .collect();
+        if !matches.is_empty() {
+            apps.extend(matches.into_iter().cloned());
+            continue;
+        }
+
+        let path = product
+            .install_location
+            .as_deref()
+            .map(crate::window::unquote_path)
+            .unwrap_or_default();
+        apps.push(AppInfo {
+            name: product.name,
+            version: product.version,
+            path: std::path::PathBuf::from(path),
+            identifier: product.product_code,
+            publisher: product.vendor,
+            ..Default::default()
+        });
+    }
+    apps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn registry_app(name: &str, version: &str, identifier: &str) -> AppInfo {
+        AppInfo {
+            name: name.to_string(),
+            version: Some(version.to_string()),
+            path: PathBuf::from("C:\\Program Files").join(name),
+            identifier: Some(identifier.to_string()),
+            publisher: Some("Registry Publisher".to_string()),
+            can_uninstall: Some(true),
+            ..Default::default()
+        }
+    }
+
+    fn product(name: &str, version: &str, product_code: Option<&str>) -> WmiProduct {
+        WmiProduct {
+            name: name.to_string(),
+            version: Some(version.to_string()),
+            vendor: Some("WMI Vendor".to_string()),
+            product_code: product_code.map(str::to_string),
+            install_location: None,
+        }
+    }
+
+    #[test]
+    fn test_wmi_apps() {
+        let registry = vec![
+            registry_app("7-Zip 23.01 (x64)", "23.01", "7-Zip"),
+            registry_app(
+                "Microsoft Visual C++ 2022 X64 Minimum Runtime",
+                "14.38.33135",
+                "{A1B2C3D4-0000-1111-2222-333344445555}",
+            ),
+        ];
+        let products = vec![
+            // By name and version, ignoring case and surrounding spaces
+            product(" 7-ZIP 23.01 (X64) ", "23.01", None),
+            // By product code, even when WMI names it differently
+            product(
+                "VC++ 2022 x64 Minimum Runtime",
+                "14.38.33135",
+                Some("{a1b2c3d4-0000-1111-2222-333344445555}"),
+            ),
+            // Same name, other version: not the registry's entry
+            product("7-Zip 23.01 (x64)", "22.00", None),
+            WmiProduct {
+                install_location: Some("\"D:\\Tools\\Broken MSI\\\"".to_string()),
+                ..product("Broken MSI", "1.0", Some("{BROKEN}"))
+            },
+        ];
+
+        let apps = wmi_apps(products, &registry);
+        assert_eq!(apps.len(), 4);
+        for (app, expected) in apps.iter().zip(&registry) {
+            assert_eq!(app.name, expected.name);
+            assert_eq!(app.path, expected.path);
+            assert_eq!(app.publisher.as_deref(), Some("Registry Publisher"));
+            assert_eq!(app.can_uninstall, Some(true));
+        }
+
+        assert_eq!(apps[2].name, "7-Zip 23.01 (x64)");
+        assert_eq!(apps[2].version.as_deref(), Some("22.00"));
+        assert_eq!(apps[2].path, PathBuf::new());
+        assert_eq!(apps[2].can_uninstall, None);
+
+        assert_eq!(apps[3].name, "Broken MSI");
+        assert_eq!(apps[3].publisher.as_deref(), Some("WMI Vendor"));
+        assert_eq!(apps[3].identifier.as_deref(), Some("{BROKEN}"));
+        assert_eq!(apps[3].path, PathBuf::from("D:\\Tools\\Broken MSI\\"));
+        assert!(apps[3].icon.is_none());
+    }
+
+    #[test]
+    fn test_wmi_apps_merge_through_dedup() {
+        let registry = vec![registry_app("Git", "2.45.1", "Git_is1")];
+        let apps = wmi_apps(vec![product("Git", "2.45.1", None)], &registry);
+        assert_eq!(apps[0].stable_id(), registry[0].stable_id());
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn test_wmi_source_elsewhere() {
+        let ctx = ScanContext::new(32);
+        assert!(WmiSource::new().scan(&ctx).unwrap().is_empty());
+    }
+}
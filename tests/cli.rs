@@ -0,0 +1,57 @@
+use std::process::{Command, Output};
+
+fn app_info(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_app-info"))
+        .args(args)
+        .output()
+        .expect("Failed to run app-info")
+}
+
+#[test]
+fn test_list_json() {
+    let output = app_info(&["list", "--json"]);
+
+    if cfg!(any(target_os = "macos", target_os = "windows")) {
+        assert!(output.status.success());
+        let apps: serde_json::Value =
+            serde_json::from_slice(&output.stdout).expect("Output is not JSON");
+        let apps = apps.as_array().expect("Output is not an array");
+        assert!(!apps.is_empty());
+        assert!(apps.iter().all(|app| app["name"].is_string()));
+    } else {
+        // Unsupported platforms report an error rather than an empty list
+        assert_eq!(output.status.code(), Some(2));
+    }
+}
+
+#[test]
+fn test_find_json() {
+    let output = app_info(&["find", "--json", "No Such Application 7f3c9a"]);
+
+    if cfg!(any(target_os = "macos", target_os = "windows")) {
+        assert_eq!(output.status.code(), Some(1));
+        assert!(output.stdout.is_empty());
+    } else {
+        assert_eq!(output.status.code(), Some(2));
+    }
+}
+
+#[test]
+fn test_icon_missing_file() {
+    let out = std::env::temp_dir().join("app-info-cli-icon.png");
+    let output = app_info(&[
+        "icon",
+        "/path/to/non/existent/file",
+        "--size",
+        "32",
+        "--out",
+        out.to_str().unwrap(),
+    ]);
+
+    // A missing file is reported before the platform or feature is considered
+    assert_eq!(output.status.code(), Some(1));
+    assert!(!out.exists());
+
+    // Argument errors are not confused with a missing application
+    assert_eq!(app_info(&["icon"]).status.code(), Some(2));
+}
@@ -0,0 +1,79 @@
+//! Shows how code that consumes scan results can be tested without depending on the
+//! applications installed on the machine running the tests.
+
+use app_info::{scan_apps, AppInfo, Icon, MockSource, ScanOptions};
+use std::path::PathBuf;
+
+/// Downstream code under test: the names of the installed apps from `publisher`.
+fn apps_by_publisher(options: &ScanOptions, publisher: &str) -> Vec<String> {
+    let report = scan_apps(options).expect("Failed to scan");
+    report
+        .apps
+        .into_iter()
+        .filter(|app| app.publisher.as_deref() == Some(publisher))
+        .map(|app| app.name)
+        .collect()
+}
+
+fn app(name: &str, publisher: &str) -> AppInfo {
+    AppInfo {
+        name: name.to_string(),
+        path: PathBuf::from(format!("/Applications/{}.app", name)),
+        publisher: Some(publisher.to_string()),
+        icon: Some(Icon {
+            width: 1,
+            height: 1,
+            pixels: vec![255, 0, 0, 255],
+        }),
+        ..Default::default()
+    }
+}
+
+fn mock_options(icon_size: u16) -> ScanOptions {
+    ScanOptions {
+        icon_size,
+        include_builtin: false,
+        sources: vec![Box::new(MockSource::new(vec![
+            app("Firefox", "Mozilla"),
+            app("Thunderbird", "Mozilla"),
+            app("Safari", "Apple"),
+        ]))],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_consumer_with_mock_source() {
+    let names = apps_by_publisher(&mock_options(0), "Mozilla");
+    assert_eq!(names, ["Firefox", "Thunderbird"]);
+}
+
+#[test]
+fn test_mock_source_icons() {
+    let without = scan_apps(&mock_options(0)).unwrap();
+    assert!(without.apps.iter().all(|app| app.icon.is_none()));
+
+    let with = scan_apps(&mock_options(32)).unwrap();
+    assert!(with.apps.iter().all(|app| app.icon.is_some()));
+}
+
+#[test]
+fn test_several_mock_sources() {
+    let options = ScanOptions {
+        include_builtin: false,
+        sources: vec![
+            Box::new(MockSource::new(vec![app("Firefox", "Mozilla")]).with_name("first")),
+            Box::new(MockSource::new(vec![
+                app("Firefox", "Mozilla"),
+                app("Zed", "Zed Industries"),
+            ])),
+        ],
+        ..Default::default()
+    };
+
+    // The same app from two sources is reported once
+    let report = scan_apps(&options).unwrap();
+    let names: Vec<&str> = report.apps.iter().map(|app| app.name.as_str()).collect();
+    assert_eq!(names, ["Firefox", "Zed"]);
+    assert!(report.errors.is_empty());
+}